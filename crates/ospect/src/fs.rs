@@ -8,6 +8,8 @@
 use std::ffi::{OsStr, OsString};
 use std::path::Path;
 
+pub mod ext4;
+
 #[cfg(target_os = "linux")]
 pub mod linux;
 
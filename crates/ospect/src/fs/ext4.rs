@@ -0,0 +1,934 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Read-only parser of the [ext4] filesystem.
+//!
+//! The parser works on raw bytes of the filesystem (e.g. a block device or an
+//! image file) and does not rely on the operating system in any way. It makes
+//! it possible to inspect filesystems that are not mounted and to see what is
+//! actually stored on the disk regardless of what the kernel (or a rootkit
+//! hooking into it) reports.
+//!
+//! Filesystems created as ext2 or ext3 are supported as well since ext4 is
+//! backwards compatible with them. Note that checksums are not verified and
+//! the journal is never replayed, so on a mounted (or not cleanly unmounted)
+//! filesystem the results might not reflect the most recent changes.
+//!
+//! [ext4]: https://docs.kernel.org/filesystems/ext4/index.html
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::io::Read as _;
+//!
+//! let device = std::fs::File::open("/dev/sda1").unwrap();
+//! let mut fs = ospect::fs::ext4::Filesystem::new(device).unwrap();
+//!
+//! let inode = fs.lookup("/etc/passwd").unwrap();
+//!
+//! let mut contents = String::new();
+//! fs.open(&inode).unwrap()
+//!     .read_to_string(&mut contents).unwrap();
+//! ```
+
+use std::io::{Read, Seek};
+use std::path::Path;
+
+mod dir;
+mod extent;
+mod inode;
+mod journal;
+mod superblock;
+
+pub use self::dir::DirEntry;
+pub use self::inode::{FileType, Inode};
+pub use self::journal::{Journal, JournalBlock, Transaction};
+pub use self::superblock::Superblock;
+
+/// Number of the inode of the root directory.
+pub const ROOT_INO: u32 = 2;
+
+/// Read-only view of an ext4 filesystem.
+///
+/// The filesystem can be read from anything that implements [`Read`] and
+/// [`Seek`], e.g. a block device, an image file or an in-memory buffer.
+pub struct Filesystem<R> {
+    /// Underlying reader of the raw filesystem bytes.
+    reader: R,
+    /// Superblock of the filesystem.
+    superblock: Superblock,
+}
+
+impl<R: Read + Seek> Filesystem<R> {
+
+    /// Opens the filesystem stored in the given reader.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the superblock cannot be read, it is not a
+    /// superblock of an ext2, ext3 or ext4 filesystem or the filesystem uses
+    /// features that the parser does not support.
+    pub fn new(mut reader: R) -> std::io::Result<Filesystem<R>> {
+        let mut buf = [0; superblock::SUPERBLOCK_LEN];
+        read_exact_at(&mut reader, superblock::SUPERBLOCK_OFFSET, &mut buf)?;
+
+        let superblock = Superblock::parse(&buf)?;
+
+        Ok(Filesystem {
+            reader,
+            superblock,
+        })
+    }
+
+    /// Returns the superblock of the filesystem.
+    pub fn superblock(&self) -> &Superblock {
+        &self.superblock
+    }
+
+    /// Reads the inode with the given number.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the inode number is out of range or if the
+    /// inode cannot be read.
+    pub fn inode(&mut self, ino: u32) -> std::io::Result<Inode> {
+        if ino == 0 || ino > self.superblock.inodes_count {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("inode number out of range: {ino}"),
+            ));
+        }
+
+        let group = (ino - 1) / self.superblock.inodes_per_group;
+        let index = (ino - 1) % self.superblock.inodes_per_group;
+
+        let inode_table = self.inode_table(group)?;
+
+        let offset = inode_table * u64::from(self.superblock.block_size) +
+            u64::from(index) * u64::from(self.superblock.inode_size);
+
+        let mut buf = vec![0; usize::from(self.superblock.inode_size)];
+        read_exact_at(&mut self.reader, offset, &mut buf)?;
+
+        Inode::parse(ino, &buf)
+    }
+
+    /// Reads the inode of the root directory.
+    pub fn root(&mut self) -> std::io::Result<Inode> {
+        self.inode(ROOT_INO)
+    }
+
+    /// Finds the inode corresponding to the given absolute path.
+    ///
+    /// Symlinks are not followed, so if the path itself points to a symlink,
+    /// the inode of the symlink is returned. Symlinks in other components of
+    /// the path are considered an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the path is not absolute, contains parent
+    /// directory components, does not exist or any of the directories along
+    /// the way cannot be read.
+    pub fn lookup<P>(&mut self, path: P) -> std::io::Result<Inode>
+    where
+        P: AsRef<Path>,
+    {
+        use std::path::Component;
+
+        let path = path.as_ref();
+
+        let mut components = path.components();
+        if components.next() != Some(Component::RootDir) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("not an absolute path: {}", path.display()),
+            ));
+        }
+
+        let mut inode = self.root()?;
+        for component in components {
+            let name = match component {
+                Component::Normal(name) => name,
+                Component::CurDir => continue,
+                _ => return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("unsupported path component in: {}", path.display()),
+                )),
+            };
+
+            let name = os_str_bytes(name).ok_or_else(|| std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("non-Unicode path: {}", path.display()),
+            ))?;
+
+            let entry = self.read_dir(&inode)?
+                .into_iter()
+                .find(|entry| !entry.deleted && entry.name == name)
+                .ok_or_else(|| std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no such file or directory: {}", path.display()),
+                ))?;
+
+            inode = self.inode(entry.ino)?;
+        }
+
+        Ok(inode)
+    }
+
+    /// Lists entries of the given directory.
+    ///
+    /// Apart from the regular entries, the list contains entries recovered
+    /// from the unused space of the directory (see [`DirEntry::deleted`]).
+    /// The special `.` and `..` entries are not included.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the inode is not a directory or if the
+    /// directory contents cannot be read or are corrupted.
+    pub fn read_dir(&mut self, inode: &Inode) -> std::io::Result<Vec<DirEntry>> {
+        if !inode.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("inode {} is not a directory", inode.ino),
+            ));
+        }
+        if inode.flags & inode::INLINE_DATA_FL != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "directories with inline data are not supported",
+            ));
+        }
+
+        let has_file_type = self.superblock.has_dir_file_type();
+        let is_indexed = inode.flags & inode::INDEX_FL != 0;
+
+        let mut file = self.open(inode)?;
+
+        let block_size = file.fs.superblock.block_size as usize;
+        let block_count = inode.size / block_size as u64;
+
+        let mut block = vec![0; block_size];
+        let mut entries = Vec::new();
+
+        for index in 0..block_count {
+            file.read_exact(&mut block)?;
+
+            // The first block of an indexed (htree) directory contains the
+            // root of the index in place where the unused space of the `..`
+            // entry would be, so we cannot recover deleted entries from it.
+            let is_index_root = is_indexed && index == 0;
+
+            dir::parse_block(&block, has_file_type, is_index_root, &mut entries)?;
+        }
+
+        Ok(entries)
+    }
+
+    /// Reads the target of the given symlink.
+    ///
+    /// The target is returned as raw bytes, exactly as stored on the disk.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the inode is not a symlink or its contents
+    /// cannot be read.
+    pub fn read_link(&mut self, inode: &Inode) -> std::io::Result<Vec<u8>> {
+        /// Limit on the length of symlink targets (equal to Linux `PATH_MAX`).
+        const MAX_TARGET_LEN: u64 = 4096;
+
+        if !inode.is_symlink() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("inode {} is not a symlink", inode.ino),
+            ));
+        }
+        if inode.size > MAX_TARGET_LEN {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("symlink target too long: {}", inode.size),
+            ));
+        }
+
+        let mut target = Vec::new();
+        self.open(inode)?.read_to_end(&mut target)?;
+
+        Ok(target)
+    }
+
+    /// Opens the contents of the given inode for reading.
+    ///
+    /// The returned reader yields zeros for holes (and unwritten extents) the
+    /// same way the kernel does.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the block mapping of the inode cannot be
+    /// read or is corrupted.
+    pub fn open(&mut self, inode: &Inode) -> std::io::Result<File<'_, R>> {
+        let data = if inode.flags & inode::INLINE_DATA_FL != 0 || self.is_fast_symlink(inode) {
+            // Data of the inode is stored in place where the block mapping
+            // would normally be. Inline data can also continue in extended
+            // attributes but we do not support this case.
+            if inode.size > inode.block.len() as u64 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "inline data in extended attributes is not supported",
+                ));
+            }
+
+            Data::Inline(inode.block[..inode.size as usize].to_vec())
+        } else {
+            Data::Extents(extent::read(self, inode)?)
+        };
+
+        Ok(File {
+            fs: self,
+            len: inode.size,
+            data,
+            pos: 0,
+        })
+    }
+
+    /// Reads the journal of the filesystem.
+    ///
+    /// `None` is returned if the filesystem does not have a journal (e.g. it
+    /// is an ext2 filesystem).
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the journal cannot be read or if its
+    /// superblock is corrupted.
+    pub fn journal(&mut self) -> std::io::Result<Option<Journal>> {
+        journal::read(self)
+    }
+
+    /// Reads the contents of the given block logged in the journal.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if the block cannot be read.
+    pub fn journal_block(
+        &mut self,
+        journal: &Journal,
+        block: &JournalBlock,
+    ) -> std::io::Result<Vec<u8>> {
+        journal::read_block(self, journal, block)
+    }
+
+    /// Reads the filesystem block with the given number.
+    fn read_block(&mut self, block: u64) -> std::io::Result<Vec<u8>> {
+        if block >= self.superblock.blocks_count {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("block number out of range: {block}"),
+            ));
+        }
+
+        let block_size = self.superblock.block_size;
+
+        let mut buf = vec![0; block_size as usize];
+        read_exact_at(&mut self.reader, block * u64::from(block_size), &mut buf)?;
+
+        Ok(buf)
+    }
+
+    /// Returns the number of the first block of the inode table of a group.
+    fn inode_table(&mut self, group: u32) -> std::io::Result<u64> {
+        let desc_size = self.superblock.desc_size;
+
+        // Group descriptors are stored in the block right after the one with
+        // the superblock.
+        let offset = (u64::from(self.superblock.first_data_block) + 1) *
+            u64::from(self.superblock.block_size) +
+            u64::from(group) * u64::from(desc_size);
+
+        let mut buf = vec![0; usize::from(desc_size)];
+        read_exact_at(&mut self.reader, offset, &mut buf)?;
+
+        let mut inode_table = u64::from(le_u32(&buf, 0x08));
+        if desc_size >= 64 {
+            inode_table |= u64::from(le_u32(&buf, 0x28)) << 32;
+        }
+
+        Ok(inode_table)
+    }
+
+    /// Determines whether the given inode is a symlink stored inline.
+    fn is_fast_symlink(&self, inode: &Inode) -> bool {
+        if !inode.is_symlink() || inode.flags & inode::EXTENTS_FL != 0 {
+            return false;
+        }
+
+        // Fast symlinks do not have any data blocks, but the inode might have
+        // a block allocated for extended attributes.
+        let ext_attr_blocks = if inode.file_acl != 0 {
+            u64::from(self.superblock.block_size / 512)
+        } else {
+            0
+        };
+
+        inode.blocks == ext_attr_blocks
+    }
+}
+
+/// Reader of contents of a file stored on an ext4 filesystem.
+///
+/// The reader can be created with the [`Filesystem::open`] method.
+pub struct File<'fs, R> {
+    /// Filesystem the file is stored on.
+    fs: &'fs mut Filesystem<R>,
+    /// Size of the file in bytes.
+    len: u64,
+    /// Location of the file data.
+    data: Data,
+    /// Current position of the reader.
+    pos: u64,
+}
+
+/// Location of the file data.
+enum Data {
+    /// Data stored directly in the inode.
+    Inline(Vec<u8>),
+    /// Data stored in blocks described by the given extents.
+    Extents(Vec<extent::Extent>),
+}
+
+impl<'fs, R> File<'fs, R> {
+
+    /// Returns the size of the file in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the file has no contents.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<'fs, R: Read + Seek> Read for File<'fs, R> {
+
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let len = std::cmp::min(buf.len() as u64, self.len - self.pos);
+
+        let len = match &self.data {
+            Data::Inline(data) => {
+                let len = len as usize;
+                let pos = self.pos as usize;
+
+                buf[..len].copy_from_slice(&data[pos..pos + len]);
+                len
+            }
+            Data::Extents(extents) => {
+                let block_size = u64::from(self.fs.superblock.block_size);
+                let block = self.pos / block_size;
+
+                let index = extents.partition_point(|extent| {
+                    extent.logical + extent.len <= block
+                });
+
+                match extents.get(index) {
+                    Some(extent) if extent.logical <= block => {
+                        let end = (extent.logical + extent.len) * block_size;
+                        let len = std::cmp::min(len, end - self.pos) as usize;
+
+                        if extent.initialized {
+                            let offset = extent.physical
+                                .checked_add(block - extent.logical)
+                                .and_then(|block| block.checked_mul(block_size))
+                                .and_then(|offset| offset.checked_add(self.pos % block_size))
+                                .ok_or_else(|| std::io::Error::new(
+                                    std::io::ErrorKind::InvalidData,
+                                    format!("extent offset overflow: {extent:?}"),
+                                ))?;
+
+                            read_exact_at(&mut self.fs.reader, offset, &mut buf[..len])?;
+                        } else {
+                            buf[..len].fill(0);
+                        }
+
+                        len
+                    }
+                    // We are in a hole: it spans either until the next extent
+                    // or until the end of the file.
+                    Some(extent) => {
+                        let end = extent.logical * block_size;
+                        let len = std::cmp::min(len, end - self.pos) as usize;
+
+                        buf[..len].fill(0);
+                        len
+                    }
+                    None => {
+                        let len = len as usize;
+
+                        buf[..len].fill(0);
+                        len
+                    }
+                }
+            }
+        };
+
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<'fs, R> Seek for File<'fs, R> {
+
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        use std::io::SeekFrom;
+
+        let pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+
+        self.pos = pos.ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "invalid seek to a negative or overflowing position",
+        ))?;
+
+        Ok(self.pos)
+    }
+}
+
+/// Reads the exact number of bytes from the given offset of the reader.
+fn read_exact_at<R>(reader: &mut R, offset: u64, buf: &mut [u8]) -> std::io::Result<()>
+where
+    R: Read + Seek,
+{
+    reader.seek(std::io::SeekFrom::Start(offset))?;
+    reader.read_exact(buf)
+}
+
+/// Returns raw bytes of the given string if it is possible.
+fn os_str_bytes(string: &std::ffi::OsStr) -> Option<&[u8]> {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::ffi::OsStrExt as _;
+        Some(string.as_bytes())
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    {
+        string.to_str().map(str::as_bytes)
+    }
+}
+
+/// Decodes a little-endian 16-bit integer at the given offset of the buffer.
+fn le_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// Decodes a little-endian 32-bit integer at the given offset of the buffer.
+fn le_u32(buf: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&buf[offset..offset + 4]);
+
+    u32::from_le_bytes(bytes)
+}
+
+/// Decodes a big-endian 32-bit integer at the given offset of the buffer.
+fn be_u32(buf: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&buf[offset..offset + 4]);
+
+    u32::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Loads the filesystem image with the given name from test fixtures.
+    ///
+    /// See `testdata/ext4/generate.sh` for details on how the images were
+    /// created and what they contain.
+    fn fixture(name: &str) -> Filesystem<std::io::Cursor<Vec<u8>>> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join("ext4")
+            .join(format!("{name}.img.gz"));
+
+        let file = std::fs::File::open(path)
+            .unwrap();
+
+        let mut image = Vec::new();
+        flate2::read::GzDecoder::new(file).read_to_end(&mut image)
+            .unwrap();
+
+        Filesystem::new(std::io::Cursor::new(image))
+            .unwrap()
+    }
+
+    /// Reads the whole contents of the file at the given path.
+    fn read<R: Read + Seek>(fs: &mut Filesystem<R>, path: &str) -> Vec<u8> {
+        let inode = fs.lookup(path)
+            .unwrap();
+
+        let mut contents = Vec::new();
+        fs.open(&inode).unwrap()
+            .read_to_end(&mut contents).unwrap();
+
+        contents
+    }
+
+    /// Returns contents of the `big.txt` fixture file.
+    fn big_txt() -> Vec<u8> {
+        b"abcdefghijklmnop\n".iter()
+            .copied()
+            .cycle()
+            .take(300000)
+            .collect()
+    }
+
+    #[test]
+    fn new_invalid_magic() {
+        let image = std::io::Cursor::new(vec![0; 4096]);
+        assert!(Filesystem::new(image).is_err());
+    }
+
+    #[test]
+    fn new_too_short() {
+        let image = std::io::Cursor::new(vec![0; 1337]);
+        assert!(Filesystem::new(image).is_err());
+    }
+
+    #[test]
+    fn superblock() {
+        let fs = fixture("basic");
+
+        let superblock = fs.superblock();
+        assert_eq!(superblock.block_size, 1024);
+        assert_eq!(superblock.inode_size, 256);
+        assert_eq!(superblock.volume_name, b"basic");
+        assert_eq!(superblock.uuid, [
+            0x4c, 0x1f, 0x6b, 0x2e, 0x1c, 0x3a, 0x4d, 0x6e,
+            0x9a, 0x5b, 0x7e, 0x8f, 0x9a, 0x0b, 0x1c, 0x2d,
+        ]);
+    }
+
+    #[test]
+    fn inode_out_of_range() {
+        let mut fs = fixture("basic");
+
+        assert!(fs.inode(0).is_err());
+        assert!(fs.inode(u32::MAX).is_err());
+    }
+
+    #[test]
+    fn root() {
+        let mut fs = fixture("basic");
+
+        let root = fs.root()
+            .unwrap();
+
+        assert!(root.is_dir());
+        assert_eq!(root.ino, ROOT_INO);
+        assert_eq!(root.mode & 0o7777, 0o755);
+        assert_eq!(root.mtime, std::time::UNIX_EPOCH + std::time::Duration::from_secs(1700000000));
+    }
+
+    #[test]
+    fn read_dir_root() {
+        let mut fs = fixture("basic");
+
+        let root = fs.root()
+            .unwrap();
+
+        let mut entries = fs.read_dir(&root).unwrap()
+            .into_iter()
+            .filter(|entry| !entry.deleted)
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let names = entries.iter()
+            .map(|entry| String::from_utf8_lossy(&entry.name).into_owned())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![
+            "big.txt",
+            "dir",
+            "fragmented.bin",
+            "hello.txt",
+            "link",
+            "longlink",
+            "lost+found",
+            "many",
+            "sparse.bin",
+        ]);
+
+        assert_eq!(entries[0].file_type, Some(FileType::Regular));
+        assert_eq!(entries[1].file_type, Some(FileType::Dir));
+        assert_eq!(entries[4].file_type, Some(FileType::Symlink));
+    }
+
+    #[test]
+    fn read_dir_deleted() {
+        let mut fs = fixture("basic");
+
+        let root = fs.root()
+            .unwrap();
+
+        let entries = fs.read_dir(&root)
+            .unwrap();
+
+        let entry = entries.iter()
+            .find(|entry| entry.name == b"deleted.txt")
+            .unwrap();
+        assert!(entry.deleted);
+
+        assert!(fs.lookup("/deleted.txt").is_err());
+    }
+
+    #[test]
+    fn read_dir_many() {
+        let mut fs = fixture("basic");
+
+        let many = fs.lookup("/many")
+            .unwrap();
+
+        let entries = fs.read_dir(&many)
+            .unwrap();
+        assert_eq!(entries.len(), 200);
+
+        for entry in entries {
+            let path = format!("/many/{}", String::from_utf8(entry.name).unwrap());
+            let number = path.strip_prefix("/many/file-").unwrap();
+
+            assert_eq!(read(&mut fs, &path), format!("{number}\n").as_bytes());
+        }
+    }
+
+    #[test]
+    fn read_dir_not_dir() {
+        let mut fs = fixture("basic");
+
+        let inode = fs.lookup("/hello.txt")
+            .unwrap();
+
+        assert!(fs.read_dir(&inode).is_err());
+    }
+
+    #[test]
+    fn lookup_nested() {
+        let mut fs = fixture("basic");
+
+        assert_eq!(read(&mut fs, "/dir/nested.txt"), b"foo bar baz\n");
+        assert_eq!(read(&mut fs, "/dir/subdir/deep.txt"), b"quux\n");
+    }
+
+    #[test]
+    fn lookup_not_found() {
+        let mut fs = fixture("basic");
+
+        let error = fs.lookup("/dir/foo").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn lookup_relative() {
+        let mut fs = fixture("basic");
+
+        assert!(fs.lookup("hello.txt").is_err());
+    }
+
+    #[test]
+    fn lookup_through_file() {
+        let mut fs = fixture("basic");
+
+        assert!(fs.lookup("/hello.txt/foo").is_err());
+    }
+
+    #[test]
+    fn lookup_through_symlink() {
+        let mut fs = fixture("basic");
+
+        assert!(fs.lookup("/link/foo").is_err());
+    }
+
+    #[test]
+    fn open_small() {
+        let mut fs = fixture("basic");
+
+        let inode = fs.lookup("/hello.txt")
+            .unwrap();
+        assert!(inode.is_file());
+        assert_eq!(inode.size, 14);
+
+        assert_eq!(read(&mut fs, "/hello.txt"), b"Hello, world!\n");
+    }
+
+    #[test]
+    fn open_extent_out_of_range() {
+        let mut fs = fixture("basic");
+
+        let mut inode = fs.lookup("/hello.txt")
+            .unwrap();
+        assert!(inode.flags & inode::EXTENTS_FL != 0);
+
+        // Set the upper 16 bits of the physical block of the first extent so
+        // that it points way past the end of the filesystem.
+        inode.block[12 + 0x06..12 + 0x08].copy_from_slice(&[0xFF, 0xFF]);
+
+        assert!(fs.open(&inode).is_err());
+    }
+
+    #[test]
+    fn open_big() {
+        let mut fs = fixture("basic");
+
+        assert_eq!(read(&mut fs, "/big.txt"), big_txt());
+    }
+
+    #[test]
+    fn open_big_seek() {
+        use std::io::SeekFrom;
+
+        let mut fs = fixture("basic");
+
+        let inode = fs.lookup("/big.txt")
+            .unwrap();
+
+        let mut file = fs.open(&inode)
+            .unwrap();
+
+        let mut buf = [0; 4];
+        file.seek(SeekFrom::Start(17 * 1000 + 2)).unwrap();
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"cdef");
+
+        file.seek(SeekFrom::End(-4)).unwrap();
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, &big_txt()[300000 - 4..]);
+
+        assert!(file.seek(SeekFrom::Current(-300001)).is_err());
+    }
+
+    #[test]
+    fn open_sparse() {
+        let mut fs = fixture("basic");
+
+        let contents = read(&mut fs, "/sparse.bin");
+        assert_eq!(contents.len(), 1024 * 1024);
+
+        assert!(contents[..512 * 1024].iter().all(|byte| *byte == 0));
+        assert_eq!(&contents[512 * 1024..512 * 1024 + 6], b"sparse");
+        assert!(contents[512 * 1024 + 6..].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn open_fragmented() {
+        let mut fs = fixture("basic");
+
+        let contents = read(&mut fs, "/fragmented.bin");
+        assert_eq!(contents.len(), 64 * 1024);
+
+        for (i, chunk) in contents.chunks(4096).enumerate() {
+            let fragment = format!("fragment-{i:02}");
+
+            assert_eq!(&chunk[..fragment.len()], fragment.as_bytes());
+            assert!(chunk[fragment.len()..].iter().all(|byte| *byte == 0));
+        }
+    }
+
+    #[test]
+    fn read_link_fast() {
+        let mut fs = fixture("basic");
+
+        let inode = fs.lookup("/link")
+            .unwrap();
+        assert!(inode.is_symlink());
+
+        assert_eq!(fs.read_link(&inode).unwrap(), b"hello.txt");
+    }
+
+    #[test]
+    fn read_link_slow() {
+        let mut fs = fixture("basic");
+
+        let inode = fs.lookup("/longlink")
+            .unwrap();
+        assert!(inode.is_symlink());
+
+        let mut target = b"x".repeat(100);
+        target.extend_from_slice(b"/target");
+
+        assert_eq!(fs.read_link(&inode).unwrap(), target);
+    }
+
+    #[test]
+    fn read_link_not_symlink() {
+        let mut fs = fixture("basic");
+
+        let inode = fs.lookup("/hello.txt")
+            .unwrap();
+
+        assert!(fs.read_link(&inode).is_err());
+    }
+
+    #[test]
+    fn journal() {
+        let mut fs = fixture("basic");
+
+        let journal = fs.journal().unwrap()
+            .unwrap();
+        assert_eq!(journal.block_size, 1024);
+        assert_eq!(journal.transactions.len(), 1);
+
+        let transaction = &journal.transactions[0];
+        assert!(transaction.committed);
+        assert_eq!(transaction.blocks.len(), 1);
+        assert_eq!(transaction.blocks[0].fs_block, 333);
+
+        let block = fs.journal_block(&journal, &transaction.blocks[0])
+            .unwrap();
+        assert_eq!(block.len(), 1024);
+        assert!(block.starts_with(b"journaled block"));
+    }
+
+    #[test]
+    fn ext2_read_dir_root() {
+        let mut fs = fixture("ext2");
+
+        let root = fs.root()
+            .unwrap();
+
+        let entries = fs.read_dir(&root)
+            .unwrap();
+        assert!(entries.iter().any(|entry| entry.name == b"hello.txt"));
+        assert!(entries.iter().any(|entry| entry.name == b"many"));
+    }
+
+    #[test]
+    fn ext2_open_big() {
+        let mut fs = fixture("ext2");
+
+        // With 1 KiB blocks this file spans direct, indirect and double
+        // indirect blocks.
+        assert_eq!(read(&mut fs, "/big.txt"), big_txt());
+    }
+
+    #[test]
+    fn ext2_open_sparse() {
+        let mut fs = fixture("ext2");
+
+        let contents = read(&mut fs, "/sparse.bin");
+        assert_eq!(contents.len(), 1024 * 1024);
+        assert_eq!(&contents[512 * 1024..512 * 1024 + 6], b"sparse");
+    }
+
+    #[test]
+    fn ext2_journal() {
+        let mut fs = fixture("ext2");
+
+        assert!(fs.journal().unwrap().is_none());
+    }
+}
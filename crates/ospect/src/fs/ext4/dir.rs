@@ -0,0 +1,281 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use super::{le_u16, le_u32, FileType};
+
+/// Size of the fixed part of a directory entry.
+const DIR_ENTRY_HEADER_LEN: usize = 8;
+
+/// Entry of a directory stored on an ext4 filesystem.
+#[derive(Clone, Debug)]
+pub struct DirEntry {
+    /// Number of the inode the entry points to.
+    ///
+    /// Deleted entries might point to inode 0 (if the number was wiped) or to
+    /// an inode that has been reused for an unrelated file since.
+    pub ino: u32,
+    /// Name of the entry, exactly as stored on the disk.
+    pub name: Vec<u8>,
+    /// Type of the file as recorded in the entry.
+    ///
+    /// This is `None` if the filesystem does not record file types in the
+    /// directory entries (in which case the inode has to be consulted).
+    pub file_type: Option<FileType>,
+    /// Whether the entry has been deleted.
+    ///
+    /// Deleted entries are recovered from the unused space of directory blocks
+    /// and are reported on a best-effort basis.
+    pub deleted: bool,
+}
+
+/// Parses directory entries stored in the given directory block.
+///
+/// If `is_index_root` is set, the block is treated as a root of a directory
+/// index and the unused space after its `..` entry is not searched for deleted
+/// entries.
+pub(super) fn parse_block(
+    block: &[u8],
+    has_file_type: bool,
+    is_index_root: bool,
+    entries: &mut Vec<DirEntry>,
+) -> std::io::Result<()> {
+    let mut offset = 0;
+
+    while offset + DIR_ENTRY_HEADER_LEN <= block.len() {
+        let ino = le_u32(block, offset);
+        let rec_len = rec_len(le_u16(block, offset + 4), block.len());
+        let name_len = name_len(&block[offset..], has_file_type);
+
+        if rec_len < DIR_ENTRY_HEADER_LEN || !rec_len.is_multiple_of(4) ||
+            offset + rec_len > block.len() ||
+            DIR_ENTRY_HEADER_LEN + name_len > rec_len
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("corrupted directory entry at offset {offset}"),
+            ));
+        }
+
+        // Entries without names are used as padding or for checksums.
+        if name_len > 0 {
+            let name = &block[offset + DIR_ENTRY_HEADER_LEN..][..name_len];
+
+            if name != b"." && name != b".." {
+                entries.push(DirEntry {
+                    ino,
+                    name: name.to_vec(),
+                    file_type: file_type(block[offset + 7], has_file_type),
+                    // Entries with inode 0 are unused: this is how the first
+                    // entry of a block is deleted.
+                    deleted: ino == 0,
+                });
+            }
+
+            // When an entry is deleted, the space it occupied is merged into
+            // the preceding entry and so the deleted entry is still there.
+            if !(is_index_root && name == b"..") {
+                let slack = &block[offset + entry_len(name_len)..offset + rec_len];
+                parse_slack(slack, has_file_type, entries);
+            }
+        }
+
+        offset += rec_len;
+    }
+
+    Ok(())
+}
+
+/// Searches the unused space of a directory entry for deleted entries.
+fn parse_slack(slack: &[u8], has_file_type: bool, entries: &mut Vec<DirEntry>) {
+    let mut offset = 0;
+
+    while offset + DIR_ENTRY_HEADER_LEN <= slack.len() {
+        match parse_deleted(&slack[offset..], has_file_type) {
+            Some(entry) => {
+                offset += entry_len(entry.name.len());
+                entries.push(entry);
+            }
+            // Entries are always aligned to 4 bytes.
+            None => offset += 4,
+        }
+    }
+}
+
+/// Attempts to parse a deleted directory entry at the start of the buffer.
+///
+/// Because the unused space can contain arbitrary garbage, the entry has to
+/// pass a number of sanity checks to be considered valid.
+fn parse_deleted(buf: &[u8], has_file_type: bool) -> Option<DirEntry> {
+    let rec_len = usize::from(le_u16(buf, 4));
+    let name_len = name_len(buf, has_file_type);
+
+    if name_len == 0 || DIR_ENTRY_HEADER_LEN + name_len > buf.len() {
+        return None;
+    }
+    if !rec_len.is_multiple_of(4) || rec_len < entry_len(name_len) {
+        return None;
+    }
+
+    let raw_file_type = buf[7];
+    if has_file_type && raw_file_type > 7 {
+        return None;
+    }
+
+    let name = &buf[DIR_ENTRY_HEADER_LEN..][..name_len];
+    if name.contains(&b'\0') || name.contains(&b'/') || name == b"." || name == b".." {
+        return None;
+    }
+
+    Some(DirEntry {
+        ino: le_u32(buf, 0),
+        name: name.to_vec(),
+        file_type: file_type(raw_file_type, has_file_type),
+        deleted: true,
+    })
+}
+
+/// Decodes the length of the name of the entry at the start of the buffer.
+fn name_len(buf: &[u8], has_file_type: bool) -> usize {
+    if has_file_type {
+        usize::from(buf[6])
+    } else {
+        usize::from(le_u16(buf, 6))
+    }
+}
+
+/// Decodes the file type of a directory entry (if recorded).
+fn file_type(raw: u8, has_file_type: bool) -> Option<FileType> {
+    if has_file_type {
+        Some(FileType::from_dir_entry(raw))
+    } else {
+        None
+    }
+}
+
+/// Decodes the record length of a directory entry.
+///
+/// The length is stored as a 16-bit number, so for 64 KiB blocks a special
+/// encoding is used.
+fn rec_len(raw: u16, block_size: usize) -> usize {
+    if block_size < 65536 {
+        return usize::from(raw);
+    }
+
+    match raw {
+        0 | 65535 => 65536,
+        raw => usize::from(raw & 65532) | usize::from(raw & 3) << 16,
+    }
+}
+
+/// Returns the minimum length of a directory entry with the given name.
+fn entry_len(name_len: usize) -> usize {
+    (DIR_ENTRY_HEADER_LEN + name_len).next_multiple_of(4)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Encodes a directory entry with the given values.
+    fn raw_entry(ino: u32, rec_len: u16, name: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&ino.to_le_bytes());
+        buf.extend_from_slice(&rec_len.to_le_bytes());
+        buf.push(name.len() as u8);
+        buf.push(1);
+        buf.extend_from_slice(name);
+        buf.resize(entry_len(name.len()), 0);
+
+        buf
+    }
+
+    #[test]
+    fn parse_block_live() {
+        let mut block = Vec::new();
+        block.extend(raw_entry(2, 12, b"."));
+        block.extend(raw_entry(2, 12, b".."));
+        block.extend(raw_entry(12, 12, b"foo"));
+        block.extend(raw_entry(13, 28, b"bar"));
+        block.resize(64, 0);
+
+        let mut entries = Vec::new();
+        parse_block(&block, true, false, &mut entries)
+            .unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].ino, 12);
+        assert_eq!(entries[0].name, b"foo");
+        assert_eq!(entries[0].file_type, Some(FileType::Regular));
+        assert!(!entries[0].deleted);
+        assert_eq!(entries[1].ino, 13);
+        assert_eq!(entries[1].name, b"bar");
+        assert!(!entries[1].deleted);
+    }
+
+    #[test]
+    fn parse_block_deleted() {
+        let mut block = Vec::new();
+        // The `foo` entry spans the space of the deleted `quux` entry.
+        block.extend(raw_entry(12, 24, b"foo"));
+        block.extend(raw_entry(13, 12, b"quux"));
+        block.extend(raw_entry(14, 40, b"bar"));
+        block.resize(64, 0);
+
+        let mut entries = Vec::new();
+        parse_block(&block, true, false, &mut entries)
+            .unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].ino, 13);
+        assert_eq!(entries[1].name, b"quux");
+        assert!(entries[1].deleted);
+    }
+
+    #[test]
+    fn parse_block_deleted_first() {
+        let mut block = Vec::new();
+        block.extend(raw_entry(0, 64, b"foo"));
+        block.resize(64, 0);
+
+        let mut entries = Vec::new();
+        parse_block(&block, true, false, &mut entries)
+            .unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].deleted);
+    }
+
+    #[test]
+    fn parse_block_garbage_slack() {
+        let mut block = Vec::new();
+        block.extend(raw_entry(12, 64, b"foo"));
+        block.resize(64, 0xFF);
+
+        let mut entries = Vec::new();
+        parse_block(&block, true, false, &mut entries)
+            .unwrap();
+
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn parse_block_corrupted() {
+        let mut block = Vec::new();
+        block.extend(raw_entry(12, 128, b"foo"));
+        block.resize(64, 0);
+
+        let mut entries = Vec::new();
+        assert!(parse_block(&block, true, false, &mut entries).is_err());
+    }
+
+    #[test]
+    fn rec_len_big_block() {
+        assert_eq!(rec_len(12, 4096), 12);
+        assert_eq!(rec_len(65535, 65536), 65536);
+        assert_eq!(rec_len(0, 65536), 65536);
+        assert_eq!(rec_len(12, 65536), 12);
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::io::{Read, Seek};
+
+use super::{le_u16, le_u32, Filesystem, Inode};
+
+/// Magic number identifying extent tree nodes.
+const EXTENT_MAGIC: u16 = 0xF30A;
+
+/// Maximum depth of an extent tree.
+const MAX_EXTENT_DEPTH: u16 = 5;
+
+/// Maximum length of an initialized extent (longer ones are uninitialized).
+const MAX_INIT_EXTENT_LEN: u16 = 32768;
+
+/// Number of direct block pointers in the legacy block map.
+const DIRECT_BLOCKS: usize = 12;
+
+/// Contiguous range of file blocks mapped to contiguous filesystem blocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct Extent {
+    /// First file (logical) block covered by the extent.
+    pub logical: u64,
+    /// First filesystem (physical) block covered by the extent.
+    pub physical: u64,
+    /// Number of blocks covered by the extent.
+    pub len: u64,
+    /// Whether the extent has been written to (unwritten ones read as zeros).
+    pub initialized: bool,
+}
+
+/// Reads the block mapping of the given inode.
+///
+/// Returned extents are sorted by their logical blocks and never overlap.
+/// Blocks not covered by any of the extents are holes.
+pub(super) fn read<R>(fs: &mut Filesystem<R>, inode: &Inode) -> std::io::Result<Vec<Extent>>
+where
+    R: Read + Seek,
+{
+    let mut extents = Vec::new();
+
+    if inode.flags & super::inode::EXTENTS_FL != 0 {
+        read_tree(fs, &inode.block, MAX_EXTENT_DEPTH, &mut extents)?;
+    } else {
+        read_block_map(fs, inode, &mut extents)?;
+    }
+
+    // Extents come straight from the disk, so we make sure they point within
+    // the filesystem before anyone tries to read them.
+    let blocks_count = fs.superblock.blocks_count;
+    for extent in &extents {
+        let logical_end = extent.logical.checked_add(extent.len);
+        let physical_end = extent.physical.checked_add(extent.len);
+
+        if logical_end.is_none() || physical_end.is_none_or(|end| end > blocks_count) {
+            return Err(invalid_data(format! {
+                "extent out of range in inode {}: {extent:?}",
+                inode.ino,
+            }));
+        }
+    }
+
+    extents.sort_by_key(|extent| extent.logical);
+    for pair in extents.windows(2) {
+        if pair[0].logical + pair[0].len > pair[1].logical {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("overlapping extents in inode {}", inode.ino),
+            ));
+        }
+    }
+
+    Ok(extents)
+}
+
+/// Collects extents from the given node of an extent tree (and its children).
+fn read_tree<R>(
+    fs: &mut Filesystem<R>,
+    node: &[u8],
+    max_depth: u16,
+    extents: &mut Vec<Extent>,
+) -> std::io::Result<()>
+where
+    R: Read + Seek,
+{
+    let magic = le_u16(node, 0x00);
+    if magic != EXTENT_MAGIC {
+        return Err(invalid_data(format!("invalid extent node magic: {magic:#06x}")));
+    }
+
+    let entries = usize::from(le_u16(node, 0x02));
+    let depth = le_u16(node, 0x06);
+    // Depth of every child is strictly smaller than its parent, so this check
+    // also protects us from cycles in corrupted trees.
+    if depth > max_depth {
+        return Err(invalid_data(format!("extent tree too deep: {depth}")));
+    }
+
+    for index in 0..entries {
+        let offset = 12 + 12 * index;
+        let Some(entry) = node.get(offset..offset + 12) else {
+            return Err(invalid_data(format!("too many extent entries: {entries}")));
+        };
+
+        if depth == 0 {
+            let len = le_u16(entry, 0x04);
+            let (len, initialized) = if len > MAX_INIT_EXTENT_LEN {
+                (len - MAX_INIT_EXTENT_LEN, false)
+            } else {
+                (len, true)
+            };
+
+            extents.push(Extent {
+                logical: u64::from(le_u32(entry, 0x00)),
+                physical: u64::from(le_u32(entry, 0x08)) |
+                    u64::from(le_u16(entry, 0x06)) << 32,
+                len: u64::from(len),
+                initialized,
+            });
+        } else {
+            let leaf = u64::from(le_u32(entry, 0x04)) |
+                u64::from(le_u16(entry, 0x08)) << 32;
+
+            let child = fs.read_block(leaf)?;
+            read_tree(fs, &child, depth - 1, extents)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Collects extents from the legacy (ext2 and ext3) block map.
+///
+/// The block map consists of 12 direct block pointers followed by pointers to
+/// a single, double and triple indirect blocks.
+fn read_block_map<R>(
+    fs: &mut Filesystem<R>,
+    inode: &Inode,
+    extents: &mut Vec<Extent>,
+) -> std::io::Result<()>
+where
+    R: Read + Seek,
+{
+    let block_size = u64::from(fs.superblock.block_size);
+    // Number of blocks that the file spans. We ignore anything beyond that as
+    // it is most likely garbage.
+    let block_count = inode.size.div_ceil(block_size);
+    let pointers_per_block = block_size / 4;
+
+    for index in 0..DIRECT_BLOCKS {
+        let logical = index as u64;
+        if logical >= block_count {
+            return Ok(());
+        }
+
+        let physical = le_u32(&inode.block, 4 * index);
+        if physical != 0 {
+            push_block(extents, logical, u64::from(physical));
+        }
+    }
+
+    let mut logical = DIRECT_BLOCKS as u64;
+    for level in 1..=3 {
+        let pointer = le_u32(&inode.block, 4 * (DIRECT_BLOCKS + level as usize - 1));
+        read_indirect(fs, u64::from(pointer), level, logical, block_count, extents)?;
+
+        logical += pointers_per_block.pow(level);
+    }
+
+    Ok(())
+}
+
+/// Collects extents from the given indirect block of the legacy block map.
+///
+/// `level` is the level of indirection (1 for blocks pointing directly to data
+/// blocks) and `logical` is the first file block covered by the block.
+fn read_indirect<R>(
+    fs: &mut Filesystem<R>,
+    block: u64,
+    level: u32,
+    logical: u64,
+    block_count: u64,
+    extents: &mut Vec<Extent>,
+) -> std::io::Result<()>
+where
+    R: Read + Seek,
+{
+    if block == 0 || logical >= block_count {
+        return Ok(());
+    }
+
+    let buf = fs.read_block(block)?;
+    let pointers_per_block = buf.len() / 4;
+    // Number of file blocks covered by a single pointer of this block.
+    let span = (pointers_per_block as u64).pow(level - 1);
+
+    for index in 0..pointers_per_block {
+        let logical = logical + index as u64 * span;
+        if logical >= block_count {
+            break;
+        }
+
+        let pointer = u64::from(le_u32(&buf, 4 * index));
+        if level == 1 {
+            if pointer != 0 {
+                push_block(extents, logical, pointer);
+            }
+        } else {
+            read_indirect(fs, pointer, level - 1, logical, block_count, extents)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds a single block to the list of extents, merging it if possible.
+fn push_block(extents: &mut Vec<Extent>, logical: u64, physical: u64) {
+    if let Some(last) = extents.last_mut() {
+        if last.logical + last.len == logical && last.physical + last.len == physical {
+            last.len += 1;
+            return;
+        }
+    }
+
+    extents.push(Extent {
+        logical,
+        physical,
+        len: 1,
+        initialized: true,
+    });
+}
+
+/// Creates an error indicating that the block mapping is corrupted.
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn push_block_merge() {
+        let mut extents = Vec::new();
+        push_block(&mut extents, 0, 100);
+        push_block(&mut extents, 1, 101);
+        push_block(&mut extents, 2, 102);
+
+        assert_eq!(extents, vec![Extent {
+            logical: 0,
+            physical: 100,
+            len: 3,
+            initialized: true,
+        }]);
+    }
+
+    #[test]
+    fn push_block_gap() {
+        let mut extents = Vec::new();
+        push_block(&mut extents, 0, 100);
+        push_block(&mut extents, 1, 200);
+        push_block(&mut extents, 3, 201);
+
+        assert_eq!(extents.len(), 3);
+        assert_eq!(extents[1].physical, 200);
+        assert_eq!(extents[2].logical, 3);
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::time::SystemTime;
+
+use super::{le_u16, le_u32};
+
+/// Inode flag indicating that the directory uses hashed indexes.
+pub(super) const INDEX_FL: u32 = 0x1000;
+
+/// Inode flag indicating that the inode uses extents for block mapping.
+pub(super) const EXTENTS_FL: u32 = 0x80000;
+
+/// Inode flag indicating that the inode stores its data inline.
+pub(super) const INLINE_DATA_FL: u32 = 0x10000000;
+
+/// Size of the inode record part that is common to all revisions.
+const GOOD_OLD_INODE_SIZE: usize = 128;
+
+/// Inode of an ext4 filesystem.
+///
+/// Inodes hold metadata of files (except for their names, which are kept in
+/// directory entries) and information on where the file data is stored.
+#[derive(Clone, Debug)]
+pub struct Inode {
+    /// Number of the inode.
+    pub ino: u32,
+    /// File mode (type and permissions).
+    pub mode: u16,
+    /// Identifier of the user owning the file.
+    pub uid: u32,
+    /// Identifier of the group owning the file.
+    pub gid: u32,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Number of hard links to the inode.
+    pub links_count: u16,
+    /// Inode flags (the ones that can be changed with `chattr`).
+    pub flags: u32,
+    /// Time at which the file was last accessed.
+    pub atime: SystemTime,
+    /// Time at which the file contents were last modified.
+    pub mtime: SystemTime,
+    /// Time at which the inode was last changed.
+    pub ctime: SystemTime,
+    /// Time at which the inode was created (if available).
+    pub crtime: Option<SystemTime>,
+    /// Time at which the inode was deleted (if it was).
+    pub dtime: Option<SystemTime>,
+    /// Number of 512-byte sectors occupied by the inode.
+    pub(super) blocks: u64,
+    /// Number of the block with extended attributes (0 if none).
+    pub(super) file_acl: u64,
+    /// Raw block mapping of the inode (or inline data).
+    pub(super) block: [u8; 60],
+}
+
+/// Type of a file stored on an ext4 filesystem.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileType {
+    /// Regular file.
+    Regular,
+    /// Directory.
+    Dir,
+    /// Symbolic link.
+    Symlink,
+    /// Character device.
+    CharDevice,
+    /// Block device.
+    BlockDevice,
+    /// Named pipe.
+    Fifo,
+    /// Unix domain socket.
+    Socket,
+    /// Unknown (or unrecognized) file type.
+    Unknown,
+}
+
+impl FileType {
+
+    /// Decodes the file type from the file mode.
+    fn from_mode(mode: u16) -> FileType {
+        match mode & 0xF000 {
+            0x1000 => FileType::Fifo,
+            0x2000 => FileType::CharDevice,
+            0x4000 => FileType::Dir,
+            0x6000 => FileType::BlockDevice,
+            0x8000 => FileType::Regular,
+            0xA000 => FileType::Symlink,
+            0xC000 => FileType::Socket,
+            _ => FileType::Unknown,
+        }
+    }
+
+    /// Decodes the file type recorded in a directory entry.
+    pub(super) fn from_dir_entry(file_type: u8) -> FileType {
+        match file_type {
+            1 => FileType::Regular,
+            2 => FileType::Dir,
+            3 => FileType::CharDevice,
+            4 => FileType::BlockDevice,
+            5 => FileType::Fifo,
+            6 => FileType::Socket,
+            7 => FileType::Symlink,
+            _ => FileType::Unknown,
+        }
+    }
+}
+
+impl Inode {
+
+    /// Returns the type of the file.
+    pub fn file_type(&self) -> FileType {
+        FileType::from_mode(self.mode)
+    }
+
+    /// Returns `true` if the inode is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.file_type() == FileType::Regular
+    }
+
+    /// Returns `true` if the inode is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.file_type() == FileType::Dir
+    }
+
+    /// Returns `true` if the inode is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.file_type() == FileType::Symlink
+    }
+
+    /// Parses the inode from its raw bytes.
+    pub(super) fn parse(ino: u32, buf: &[u8]) -> std::io::Result<Inode> {
+        if buf.len() < GOOD_OLD_INODE_SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("inode record too short: {}", buf.len()),
+            ));
+        }
+
+        // Fields beyond the original 128 bytes are present only if the inode
+        // record is big enough and the inode says that they are in use.
+        let extra_size = if buf.len() > GOOD_OLD_INODE_SIZE {
+            usize::from(le_u16(buf, 0x80))
+        } else {
+            0
+        };
+        let extra_end = std::cmp::min(GOOD_OLD_INODE_SIZE + extra_size, buf.len());
+        let extra_u32 = |offset: usize| -> Option<u32> {
+            if offset + 4 <= extra_end {
+                Some(le_u32(buf, offset))
+            } else {
+                None
+            }
+        };
+
+        let time = |offset: usize, extra_offset: usize| -> SystemTime {
+            timestamp(le_u32(buf, offset), extra_u32(extra_offset).unwrap_or(0))
+        };
+
+        let crtime = extra_u32(0x90).map(|secs| {
+            timestamp(secs, extra_u32(0x94).unwrap_or(0))
+        });
+
+        let dtime = match le_u32(buf, 0x14) {
+            0 => None,
+            secs => Some(timestamp(secs, 0)),
+        };
+
+        let mut block = [0; 60];
+        block.copy_from_slice(&buf[0x28..0x64]);
+
+        Ok(Inode {
+            ino,
+            mode: le_u16(buf, 0x00),
+            uid: u32::from(le_u16(buf, 0x02)) | u32::from(le_u16(buf, 0x78)) << 16,
+            gid: u32::from(le_u16(buf, 0x18)) | u32::from(le_u16(buf, 0x7A)) << 16,
+            size: u64::from(le_u32(buf, 0x04)) | u64::from(le_u32(buf, 0x6C)) << 32,
+            links_count: le_u16(buf, 0x1A),
+            flags: le_u32(buf, 0x20),
+            atime: time(0x08, 0x8C),
+            mtime: time(0x10, 0x88),
+            ctime: time(0x0C, 0x84),
+            crtime,
+            dtime,
+            blocks: u64::from(le_u32(buf, 0x1C)) | u64::from(le_u16(buf, 0x74)) << 32,
+            file_acl: u64::from(le_u32(buf, 0x68)) | u64::from(le_u16(buf, 0x76)) << 32,
+            block,
+        })
+    }
+}
+
+/// Decodes an inode timestamp.
+///
+/// Seconds are stored as a signed 32-bit number and the extra field (if
+/// available) extends it with 2 more bits of the epoch and nanoseconds.
+fn timestamp(secs: u32, extra: u32) -> SystemTime {
+    let secs = i64::from(secs as i32) + (i64::from(extra & 0b11) << 32);
+    let nanos = std::time::Duration::from_nanos(u64::from(extra >> 2));
+
+    let time = if secs >= 0 {
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs as u64)
+    } else {
+        SystemTime::UNIX_EPOCH - std::time::Duration::from_secs(secs.unsigned_abs())
+    };
+
+    time + nanos
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_too_short() {
+        assert!(Inode::parse(1, &[0; 100]).is_err());
+    }
+
+    #[test]
+    fn parse_old_size() {
+        let mut buf = [0; 128];
+        buf[0x00..0x02].copy_from_slice(&0o100644u16.to_le_bytes());
+        buf[0x04..0x08].copy_from_slice(&1337u32.to_le_bytes());
+        buf[0x10..0x14].copy_from_slice(&1700000000u32.to_le_bytes());
+
+        let inode = Inode::parse(42, &buf)
+            .unwrap();
+
+        assert_eq!(inode.ino, 42);
+        assert!(inode.is_file());
+        assert_eq!(inode.size, 1337);
+        assert_eq!(inode.mtime, SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1700000000));
+        assert_eq!(inode.crtime, None);
+        assert_eq!(inode.dtime, None);
+    }
+
+    #[test]
+    fn parse_extra_fields() {
+        let mut buf = [0; 256];
+        buf[0x00..0x02].copy_from_slice(&0o040755u16.to_le_bytes());
+        buf[0x10..0x14].copy_from_slice(&1700000000u32.to_le_bytes());
+        buf[0x80..0x82].copy_from_slice(&32u16.to_le_bytes());
+        buf[0x88..0x8C].copy_from_slice(&(1337u32 << 2).to_le_bytes());
+        buf[0x90..0x94].copy_from_slice(&1600000000u32.to_le_bytes());
+
+        let inode = Inode::parse(42, &buf)
+            .unwrap();
+
+        assert!(inode.is_dir());
+        assert_eq!(inode.mtime, SystemTime::UNIX_EPOCH + std::time::Duration::new(1700000000, 1337));
+        assert_eq!(inode.crtime, Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1600000000)));
+    }
+
+    #[test]
+    fn timestamp_negative() {
+        let time = timestamp(-1i32 as u32, 0);
+        assert_eq!(time, SystemTime::UNIX_EPOCH - std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn timestamp_epoch_bits() {
+        let time = timestamp(0, 0b01);
+        assert_eq!(time, SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1 << 32));
+    }
+}
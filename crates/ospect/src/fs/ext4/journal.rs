@@ -0,0 +1,424 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::io::{Read, Seek};
+
+use super::{be_u32, Filesystem, Inode};
+
+/// Magic number identifying journal metadata blocks.
+const MAGIC: u32 = 0xC03B3998;
+
+/// Size of the header common to all journal metadata blocks.
+const HEADER_LEN: usize = 12;
+
+/// Size of the journal superblock that we care about.
+const SUPERBLOCK_LEN: usize = 1024;
+
+/// Type of a journal block describing blocks of a transaction.
+const BLOCK_TYPE_DESCRIPTOR: u32 = 1;
+
+/// Type of a journal block marking the end of a transaction.
+const BLOCK_TYPE_COMMIT: u32 = 2;
+
+/// Type of a journal superblock (version 1).
+const BLOCK_TYPE_SUPERBLOCK_V1: u32 = 3;
+
+/// Type of a journal superblock (version 2).
+const BLOCK_TYPE_SUPERBLOCK_V2: u32 = 4;
+
+/// Type of a journal block listing blocks revoked by a transaction.
+const BLOCK_TYPE_REVOKE: u32 = 5;
+
+/// Incompatible journal feature indicating 64-bit block numbers.
+const FEATURE_INCOMPAT_64BIT: u32 = 0x2;
+
+/// Incompatible journal feature indicating checksums (version 2).
+const FEATURE_INCOMPAT_CSUM_V2: u32 = 0x8;
+
+/// Incompatible journal feature indicating checksums (version 3).
+const FEATURE_INCOMPAT_CSUM_V3: u32 = 0x10;
+
+/// Tag flag indicating that the first 4 bytes of the block were escaped.
+const TAG_FLAG_ESCAPE: u32 = 0x1;
+
+/// Tag flag indicating that the UUID is the same as in the previous tag.
+const TAG_FLAG_SAME_UUID: u32 = 0x2;
+
+/// Tag flag indicating the last tag in a descriptor block.
+const TAG_FLAG_LAST_TAG: u32 = 0x8;
+
+/// Journal of an ext4 filesystem.
+///
+/// The journal is a circular log of filesystem metadata (and, depending on the
+/// mount options, data) blocks written before changes are applied to their
+/// final location. It might contain older versions of blocks that are long
+/// gone from the filesystem itself.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    /// Size of a single journal block in bytes.
+    pub block_size: u32,
+    /// Sequence number of the first transaction expected in the log.
+    pub sequence: u32,
+    /// Transactions found in the log, from the oldest to the newest.
+    pub transactions: Vec<Transaction>,
+    /// Inode of the journal.
+    inode: Inode,
+}
+
+/// Transaction logged in the journal.
+#[derive(Clone, Debug)]
+pub struct Transaction {
+    /// Sequence number of the transaction.
+    pub sequence: u32,
+    /// Filesystem blocks written by the transaction.
+    pub blocks: Vec<JournalBlock>,
+    /// Filesystem blocks revoked by the transaction.
+    pub revoked: Vec<u64>,
+    /// Whether the transaction has been committed.
+    ///
+    /// Uncommitted transactions are never replayed (but can still be used to
+    /// recover the data).
+    pub committed: bool,
+}
+
+/// Filesystem block logged in the journal.
+///
+/// Contents of the block can be read with [`Filesystem::journal_block`].
+#[derive(Clone, Debug)]
+pub struct JournalBlock {
+    /// Number of the filesystem block that the journal block is a copy of.
+    pub fs_block: u64,
+    /// Number of the block within the journal.
+    pub journal_block: u32,
+    /// Whether the first 4 bytes of the block were escaped.
+    escaped: bool,
+}
+
+/// Reads the journal of the given filesystem (if it has one).
+pub(super) fn read<R>(fs: &mut Filesystem<R>) -> std::io::Result<Option<Journal>>
+where
+    R: Read + Seek,
+{
+    if fs.superblock.feature_compat & super::superblock::FEATURE_COMPAT_HAS_JOURNAL == 0 {
+        return Ok(None);
+    }
+    if fs.superblock.journal_ino == 0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "external journals are not supported",
+        ));
+    }
+
+    let inode = fs.inode(fs.superblock.journal_ino)?;
+    let mut file = fs.open(&inode)?;
+
+    let mut buf = [0; SUPERBLOCK_LEN];
+    file.read_exact(&mut buf)?;
+
+    let header = Header::parse(&buf)
+        .filter(|header| {
+            header.block_type == BLOCK_TYPE_SUPERBLOCK_V1 ||
+            header.block_type == BLOCK_TYPE_SUPERBLOCK_V2
+        })
+        .ok_or_else(|| invalid_data("invalid journal superblock"))?;
+
+    let block_size = be_u32(&buf, 0x0C);
+    let max_len = be_u32(&buf, 0x10);
+    let first = be_u32(&buf, 0x14);
+    let sequence = be_u32(&buf, 0x18);
+    let start = be_u32(&buf, 0x1C);
+
+    // Features are available only in the second version of the superblock.
+    let features = if header.block_type == BLOCK_TYPE_SUPERBLOCK_V2 {
+        be_u32(&buf, 0x28)
+    } else {
+        0
+    };
+
+    if !(1024..=65536).contains(&block_size) || !block_size.is_power_of_two() {
+        return Err(invalid_data(format!("invalid journal block size: {block_size}")));
+    }
+    if first == 0 || first >= max_len {
+        return Err(invalid_data(format!("invalid journal log range: {first}..{max_len}")));
+    }
+
+    let mut scan = Scan {
+        file,
+        block_size,
+        first,
+        max_len,
+        features,
+    };
+
+    // If the journal is clean (i.e. it has nothing to replay), the start is
+    // set to 0. In such case we start from the beginning of the log to find
+    // transactions that might have been left there.
+    let (pos, expected_sequence) = if start == 0 {
+        (first, None)
+    } else {
+        (start, Some(sequence))
+    };
+    let transactions = scan.transactions(pos, expected_sequence)?;
+
+    Ok(Some(Journal {
+        block_size,
+        sequence,
+        transactions,
+        inode,
+    }))
+}
+
+/// Reads the contents of the given block logged in the journal.
+pub(super) fn read_block<R>(
+    fs: &mut Filesystem<R>,
+    journal: &Journal,
+    block: &JournalBlock,
+) -> std::io::Result<Vec<u8>>
+where
+    R: Read + Seek,
+{
+    let mut file = fs.open(&journal.inode)?;
+    file.seek(std::io::SeekFrom::Start(
+        u64::from(block.journal_block) * u64::from(journal.block_size),
+    ))?;
+
+    let mut buf = vec![0; journal.block_size as usize];
+    file.read_exact(&mut buf)?;
+
+    // Blocks that start with the magic number would be confused with journal
+    // metadata blocks, so the journal replaces it with zeros.
+    if block.escaped {
+        buf[0..4].copy_from_slice(&MAGIC.to_be_bytes());
+    }
+
+    Ok(buf)
+}
+
+/// Header of a journal metadata block.
+struct Header {
+    /// Type of the block.
+    block_type: u32,
+    /// Sequence number of the transaction the block belongs to.
+    sequence: u32,
+}
+
+impl Header {
+
+    /// Parses the header at the start of the given block.
+    ///
+    /// `None` is returned if the block is not a journal metadata block.
+    fn parse(buf: &[u8]) -> Option<Header> {
+        if buf.len() < HEADER_LEN || be_u32(buf, 0x00) != MAGIC {
+            return None;
+        }
+
+        Some(Header {
+            block_type: be_u32(buf, 0x04),
+            sequence: be_u32(buf, 0x08),
+        })
+    }
+}
+
+/// State of a scan through the journal log.
+struct Scan<'fs, R> {
+    /// Contents of the journal inode.
+    file: super::File<'fs, R>,
+    /// Size of a single journal block in bytes.
+    block_size: u32,
+    /// First block of the log.
+    first: u32,
+    /// Number of blocks in the journal (the log ends there).
+    max_len: u32,
+    /// Incompatible features of the journal.
+    features: u32,
+}
+
+impl<'fs, R: Read + Seek> Scan<'fs, R> {
+
+    /// Collects transactions from the log starting at the given position.
+    ///
+    /// The scan stops at the first block that is not a metadata block of the
+    /// expected transaction (or after going through the whole log).
+    fn transactions(
+        &mut self,
+        mut pos: u32,
+        mut expected_sequence: Option<u32>,
+    ) -> std::io::Result<Vec<Transaction>> {
+        let mut transactions = Vec::<Transaction>::new();
+
+        let log_len = self.max_len - self.first;
+        let mut visited = 0;
+
+        while visited < log_len {
+            let block = self.read_block(pos)?;
+
+            let Some(header) = Header::parse(&block) else {
+                break;
+            };
+            if expected_sequence.is_some_and(|sequence| sequence != header.sequence) {
+                break;
+            }
+            expected_sequence = Some(header.sequence);
+
+            let is_current = transactions.last().is_some_and(|transaction| {
+                transaction.sequence == header.sequence && !transaction.committed
+            });
+            if !is_current {
+                transactions.push(Transaction {
+                    sequence: header.sequence,
+                    blocks: Vec::new(),
+                    revoked: Vec::new(),
+                    committed: false,
+                });
+            }
+            let transaction = transactions.last_mut()
+                .expect("no current transaction");
+
+            match header.block_type {
+                BLOCK_TYPE_DESCRIPTOR => {
+                    for tag in self.parse_tags(&block) {
+                        pos = self.next(pos);
+                        visited += 1;
+
+                        transaction.blocks.push(JournalBlock {
+                            fs_block: tag.fs_block,
+                            journal_block: pos,
+                            escaped: tag.escaped,
+                        });
+                    }
+                }
+                BLOCK_TYPE_COMMIT => {
+                    transaction.committed = true;
+                    expected_sequence = Some(header.sequence.wrapping_add(1));
+                }
+                BLOCK_TYPE_REVOKE => {
+                    transaction.revoked.extend(self.parse_revoked(&block));
+                }
+                _ => break,
+            }
+
+            pos = self.next(pos);
+            visited += 1;
+        }
+
+        Ok(transactions)
+    }
+
+    /// Parses tags of the given descriptor block.
+    fn parse_tags(&self, block: &[u8]) -> Vec<Tag> {
+        let has_csum_v2 = self.features & FEATURE_INCOMPAT_CSUM_V2 != 0;
+        let has_csum_v3 = self.features & FEATURE_INCOMPAT_CSUM_V3 != 0;
+        let has_64bit = self.features & FEATURE_INCOMPAT_64BIT != 0;
+
+        let tag_len = if has_csum_v3 {
+            16
+        } else {
+            let mut tag_len = 8;
+            if has_csum_v2 {
+                tag_len += 2;
+            }
+            if has_64bit {
+                tag_len += 4;
+            }
+            tag_len
+        };
+
+        // With checksums enabled, descriptor blocks end with a checksum tail.
+        let end = if has_csum_v2 || has_csum_v3 {
+            block.len() - 4
+        } else {
+            block.len()
+        };
+
+        let mut tags = Vec::new();
+        let mut offset = HEADER_LEN;
+
+        while offset + tag_len <= end {
+            let mut fs_block = u64::from(be_u32(block, offset));
+
+            let flags = if has_csum_v3 {
+                be_u32(block, offset + 4)
+            } else {
+                u32::from(u16::from_be_bytes([block[offset + 6], block[offset + 7]]))
+            };
+            if has_64bit {
+                fs_block |= u64::from(be_u32(block, offset + 8)) << 32;
+            }
+
+            tags.push(Tag {
+                fs_block,
+                escaped: flags & TAG_FLAG_ESCAPE != 0,
+            });
+
+            offset += tag_len;
+            if flags & TAG_FLAG_SAME_UUID == 0 {
+                offset += 16;
+            }
+            if flags & TAG_FLAG_LAST_TAG != 0 {
+                break;
+            }
+        }
+
+        tags
+    }
+
+    /// Parses block numbers listed in the given revoke block.
+    fn parse_revoked(&self, block: &[u8]) -> Vec<u64> {
+        let record_len = if self.features & FEATURE_INCOMPAT_64BIT != 0 {
+            8
+        } else {
+            4
+        };
+
+        // The count includes the header and the count itself.
+        let count = std::cmp::min(be_u32(block, HEADER_LEN) as usize, block.len());
+
+        block[..count]
+            .get(HEADER_LEN + 4..)
+            .unwrap_or_default()
+            .chunks_exact(record_len)
+            .map(|record| {
+                record.iter().fold(0, |acc, byte| acc << 8 | u64::from(*byte))
+            })
+            .collect()
+    }
+
+    /// Reads the journal block at the given position.
+    fn read_block(&mut self, pos: u32) -> std::io::Result<Vec<u8>> {
+        self.file.seek(std::io::SeekFrom::Start(
+            u64::from(pos) * u64::from(self.block_size),
+        ))?;
+
+        let mut buf = vec![0; self.block_size as usize];
+        self.file.read_exact(&mut buf)?;
+
+        Ok(buf)
+    }
+
+    /// Returns the position following the given one (wrapping around).
+    fn next(&self, pos: u32) -> u32 {
+        if pos + 1 >= self.max_len {
+            self.first
+        } else {
+            pos + 1
+        }
+    }
+}
+
+/// Tag of a descriptor block describing a single logged block.
+struct Tag {
+    /// Number of the filesystem block that the logged block is a copy of.
+    fs_block: u64,
+    /// Whether the first 4 bytes of the logged block were escaped.
+    escaped: bool,
+}
+
+/// Creates an error indicating that the journal is corrupted.
+fn invalid_data<S>(message: S) -> std::io::Error
+where
+    S: Into<String>,
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
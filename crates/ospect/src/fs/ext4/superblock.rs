@@ -0,0 +1,231 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use super::{le_u16, le_u32};
+
+/// Offset of the superblock from the beginning of the filesystem.
+pub(super) const SUPERBLOCK_OFFSET: u64 = 1024;
+
+/// Size of the superblock in bytes.
+pub(super) const SUPERBLOCK_LEN: usize = 1024;
+
+/// Magic number identifying ext2, ext3 and ext4 superblocks.
+const MAGIC: u16 = 0xEF53;
+
+/// Compatible feature flag indicating that the filesystem has a journal.
+pub(super) const FEATURE_COMPAT_HAS_JOURNAL: u32 = 0x4;
+
+/// Incompatible feature flag indicating that directory entries record types.
+const FEATURE_INCOMPAT_FILETYPE: u32 = 0x2;
+
+/// Incompatible feature flag indicating that the filesystem can be bigger
+/// than 2^32 blocks (and that group descriptors can be bigger).
+const FEATURE_INCOMPAT_64BIT: u32 = 0x80;
+
+/// Incompatible features that the parser does not support.
+///
+/// These are compression (never finished upstream), external journal device
+/// (which is not a filesystem itself), meta block groups (which change the
+/// location of group descriptors) and storing data in directory entries.
+const FEATURE_INCOMPAT_UNSUPPORTED: u32 = 0x1 | 0x8 | 0x10 | 0x1000;
+
+/// Superblock of an ext4 filesystem.
+///
+/// The superblock contains general information about the filesystem, like its
+/// size, layout and enabled features.
+#[derive(Clone, Debug)]
+pub struct Superblock {
+    /// Total number of inodes in the filesystem.
+    pub inodes_count: u32,
+    /// Total number of blocks in the filesystem.
+    pub blocks_count: u64,
+    /// Size of a single block in bytes.
+    pub block_size: u32,
+    /// Number of the first data block (1 for 1 KiB blocks, 0 otherwise).
+    pub first_data_block: u32,
+    /// Number of blocks in a single block group.
+    pub blocks_per_group: u32,
+    /// Number of inodes in a single block group.
+    pub inodes_per_group: u32,
+    /// Size of a single inode record in bytes.
+    pub inode_size: u16,
+    /// Set of compatible features.
+    pub feature_compat: u32,
+    /// Set of incompatible features.
+    pub feature_incompat: u32,
+    /// Set of read-only compatible features.
+    pub feature_ro_compat: u32,
+    /// Unique identifier of the filesystem.
+    pub uuid: [u8; 16],
+    /// Name of the filesystem (empty if not set).
+    pub volume_name: Vec<u8>,
+    /// Path at which the filesystem was last mounted (empty if not known).
+    pub last_mounted: Vec<u8>,
+    /// Number of the inode of the journal (0 if there is no journal).
+    pub journal_ino: u32,
+    /// Size of a single group descriptor in bytes.
+    pub(super) desc_size: u16,
+}
+
+impl Superblock {
+
+    /// Parses the superblock from its raw bytes.
+    pub(super) fn parse(buf: &[u8; SUPERBLOCK_LEN]) -> std::io::Result<Superblock> {
+        let magic = le_u16(buf, 0x38);
+        if magic != MAGIC {
+            return Err(invalid_data(format!("invalid magic: {magic:#06x}")));
+        }
+
+        let feature_compat = le_u32(buf, 0x5C);
+        let feature_incompat = le_u32(buf, 0x60);
+        let feature_ro_compat = le_u32(buf, 0x64);
+
+        let unsupported = feature_incompat & FEATURE_INCOMPAT_UNSUPPORTED;
+        if unsupported != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("unsupported incompatible features: {unsupported:#x}"),
+            ));
+        }
+
+        let log_block_size = le_u32(buf, 0x18);
+        // Block sizes bigger than 64 KiB are not allowed.
+        if log_block_size > 6 {
+            return Err(invalid_data(format!("invalid block size: 2^{}", 10 + log_block_size)));
+        }
+        let block_size = 1024 << log_block_size;
+
+        let mut blocks_count = u64::from(le_u32(buf, 0x04));
+        if feature_incompat & FEATURE_INCOMPAT_64BIT != 0 {
+            blocks_count |= u64::from(le_u32(buf, 0x150)) << 32;
+        }
+
+        let blocks_per_group = le_u32(buf, 0x20);
+        if blocks_per_group == 0 {
+            return Err(invalid_data("no blocks per group"));
+        }
+        let inodes_per_group = le_u32(buf, 0x28);
+        if inodes_per_group == 0 {
+            return Err(invalid_data("no inodes per group"));
+        }
+
+        // Revision 0 of the filesystem has fixed inode size.
+        let inode_size = match le_u32(buf, 0x4C) {
+            0 => 128,
+            _ => le_u16(buf, 0x58),
+        };
+        if inode_size < 128 || !inode_size.is_power_of_two() || u32::from(inode_size) > block_size {
+            return Err(invalid_data(format!("invalid inode size: {inode_size}")));
+        }
+
+        let desc_size = if feature_incompat & FEATURE_INCOMPAT_64BIT != 0 {
+            le_u16(buf, 0xFE)
+        } else {
+            32
+        };
+        if desc_size < 32 || !desc_size.is_power_of_two() || u32::from(desc_size) > block_size {
+            return Err(invalid_data(format!("invalid group descriptor size: {desc_size}")));
+        }
+
+        let mut uuid = [0; 16];
+        uuid.copy_from_slice(&buf[0x68..0x78]);
+
+        Ok(Superblock {
+            inodes_count: le_u32(buf, 0x00),
+            blocks_count,
+            block_size,
+            first_data_block: le_u32(buf, 0x14),
+            blocks_per_group,
+            inodes_per_group,
+            inode_size,
+            feature_compat,
+            feature_incompat,
+            feature_ro_compat,
+            uuid,
+            volume_name: c_string(&buf[0x78..0x88]),
+            last_mounted: c_string(&buf[0x88..0xC8]),
+            journal_ino: le_u32(buf, 0xE0),
+            desc_size,
+        })
+    }
+
+    /// Determines whether directory entries of the filesystem record types.
+    pub(super) fn has_dir_file_type(&self) -> bool {
+        self.feature_incompat & FEATURE_INCOMPAT_FILETYPE != 0
+    }
+}
+
+/// Extracts a string from a fixed-size buffer padded with null bytes.
+fn c_string(buf: &[u8]) -> Vec<u8> {
+    let len = buf.iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(buf.len());
+
+    buf[..len].to_vec()
+}
+
+/// Creates an error indicating that the superblock is corrupted.
+fn invalid_data<S>(message: S) -> std::io::Error
+where
+    S: Into<String>,
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Returns raw bytes of a valid minimal superblock.
+    fn raw_superblock() -> [u8; SUPERBLOCK_LEN] {
+        let mut buf = [0; SUPERBLOCK_LEN];
+        buf[0x20..0x24].copy_from_slice(&8192u32.to_le_bytes());
+        buf[0x28..0x2C].copy_from_slice(&2048u32.to_le_bytes());
+        buf[0x38..0x3A].copy_from_slice(&MAGIC.to_le_bytes());
+        buf[0x78..0x7B].copy_from_slice(b"foo");
+
+        buf
+    }
+
+    #[test]
+    fn parse_minimal() {
+        let superblock = Superblock::parse(&raw_superblock())
+            .unwrap();
+
+        assert_eq!(superblock.block_size, 1024);
+        assert_eq!(superblock.inode_size, 128);
+        assert_eq!(superblock.desc_size, 32);
+        assert_eq!(superblock.volume_name, b"foo");
+    }
+
+    #[test]
+    fn parse_unsupported_feature() {
+        let mut buf = raw_superblock();
+        buf[0x60..0x64].copy_from_slice(&0x10u32.to_le_bytes());
+
+        let error = Superblock::parse(&buf).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn parse_invalid_block_size() {
+        let mut buf = raw_superblock();
+        buf[0x18..0x1C].copy_from_slice(&7u32.to_le_bytes());
+
+        let error = Superblock::parse(&buf).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_invalid_inode_size() {
+        let mut buf = raw_superblock();
+        buf[0x4C..0x50].copy_from_slice(&1u32.to_le_bytes());
+        buf[0x58..0x5A].copy_from_slice(&100u16.to_le_bytes());
+
+        let error = Superblock::parse(&buf).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
@@ -51,6 +51,64 @@ pub fn flags<P>(path: P) -> std::io::Result<u32> where
     }
 }
 
+/// Returns the next range of the file that contains data.
+///
+/// The returned range starts at or after the given `offset` and spans until
+/// the next hole in the file (or until its end). Everything between `offset`
+/// and the start of the returned range is a hole, i.e. it reads as zeros but
+/// occupies no space on the disk. `None` is returned if there is no data at
+/// or after `offset`.
+///
+/// This function uses the `SEEK_DATA` and `SEEK_HOLE` flags of [`lseek`], so
+/// on filesystems that do not support sparse files the whole file is reported
+/// as data. Note that the function moves the file cursor.
+///
+/// [`lseek`]: https://man7.org/linux/man-pages/man2/lseek.2.html
+///
+/// # Examples
+///
+/// ```no_run
+/// let file = std::fs::File::open("/tmp/foo").unwrap();
+///
+/// let data = ospect::fs::linux::next_data(&file, 0).unwrap();
+/// assert!(data.is_some());
+/// ```
+pub fn next_data(
+    file: &std::fs::File,
+    offset: u64,
+) -> std::io::Result<Option<std::ops::Range<u64>>> {
+    use std::os::unix::io::AsRawFd as _;
+
+    let offset = libc::off_t::try_from(offset)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+    // SAFETY: We pass a file descriptor that is valid until the end of the
+    // scope of this function. The result is verified below.
+    let start = unsafe {
+        libc::lseek(file.as_raw_fd(), offset, libc::SEEK_DATA)
+    };
+    if start < 0 {
+        let error = std::io::Error::last_os_error();
+        // `ENXIO` means that there is no more data after the given offset.
+        if error.raw_os_error() == Some(libc::ENXIO) {
+            return Ok(None);
+        }
+        return Err(error);
+    }
+
+    // SAFETY: Same as above. There is always an implicit hole at the end of
+    // the file, so this should always succeed if the call above did.
+    let end = unsafe {
+        libc::lseek(file.as_raw_fd(), start, libc::SEEK_HOLE)
+    };
+    if end < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // Both values are non-negative, so the casts are safe.
+    Ok(Some(start as u64..end as u64))
+}
+
 /// Collects names of all extended attributes for the specified file.
 pub fn ext_attr_names<P>(path: P) -> std::io::Result<Vec<OsString>>
 where
@@ -272,6 +330,36 @@ pub(crate) mod tests {
 
     // TODO: Write tests for symlinks.
 
+    #[test]
+    fn test_next_data_sparse() {
+        use std::os::unix::fs::FileExt as _;
+
+        const LEN: u64 = 16 * 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempfile = File::create(tempdir.path().join("foo")).unwrap();
+        tempfile.set_len(LEN).unwrap();
+        tempfile.write_at(b"foo", LEN / 2).unwrap();
+
+        let data = next_data(&tempfile, 0).unwrap().unwrap();
+        assert!(data.start <= LEN / 2);
+        assert!(data.end >= LEN / 2 + 3);
+
+        let data = next_data(&tempfile, LEN / 2 + 1).unwrap().unwrap();
+        assert_eq!(data.start, LEN / 2 + 1);
+        assert!(data.end >= LEN / 2 + 3);
+
+        assert!(next_data(&tempfile, LEN).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_next_data_empty() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempfile = File::create(tempdir.path().join("foo")).unwrap();
+
+        assert!(next_data(&tempfile, 0).unwrap().is_none());
+    }
+
     #[test]
     fn test_flags_non_existing() {
         let tempdir = tempfile::tempdir().unwrap();
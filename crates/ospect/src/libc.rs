@@ -525,6 +525,19 @@ mod macos {
         pub kp_proc: extern_proc,
         pub kp_eproc: eproc,
     }
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    pub struct mach_timebase_info {
+        /// Numerator of the absolute time to nanoseconds conversion factor.
+        pub numer: u32,
+        /// Denominator of the absolute time to nanoseconds conversion factor.
+        pub denom: u32,
+    }
+
+    extern "C" {
+        pub fn mach_timebase_info(info: *mut mach_timebase_info) -> libc::c_int;
+    }
 }
 
 #[cfg(target_os = "macos")]
@@ -113,6 +113,138 @@ pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     self::sys::fqdn()
 }
 
+/// Returns the system-wide folder for temporary files.
+///
+/// Unlike [`std::env::temp_dir`], this does not depend on the environment of
+/// the calling process (which for services can point to a private folder) but
+/// returns the folder used by the system as a whole.
+///
+/// # Errors
+///
+/// This function will return an error in case there was some issue when trying
+/// to query data from the system.
+///
+/// # Examples
+///
+/// ```no_run
+/// let temp_dir = ospect::os::temp_dir()
+///     .unwrap();
+///
+/// assert!(temp_dir.is_absolute());
+/// ```
+pub fn temp_dir() -> std::io::Result<std::path::PathBuf> {
+    self::sys::temp_dir()
+}
+
+/// Information about a user account defined on the system.
+#[derive(Debug)]
+pub struct User {
+    /// Name of the user (login).
+    pub name: std::ffi::OsString,
+    /// Identifier of the user.
+    pub uid: u32,
+    /// Identifier of the primary group of the user.
+    pub gid: u32,
+    /// Full name or other comment associated with the account (GECOS).
+    pub full_name: Option<std::ffi::OsString>,
+    /// Home directory of the user.
+    pub home_dir: Option<std::path::PathBuf>,
+    /// Login shell of the user.
+    pub shell: Option<std::path::PathBuf>,
+    /// Identifiers of supplementary groups the user is a member of.
+    pub groups: Vec<u32>,
+    /// Password metadata of the account (if available).
+    pub password: Option<Password>,
+    /// Time of the last login of the user (if available).
+    pub last_login: Option<std::time::SystemTime>,
+}
+
+/// Metadata of a user account password.
+///
+/// Note that this never includes the password hash itself.
+#[derive(Debug, Default)]
+pub struct Password {
+    /// Whether the password is locked (logging in with it is impossible).
+    pub locked: bool,
+    /// Whether the password is empty (logging in requires no password).
+    pub empty: bool,
+    /// Time at which the password was last changed.
+    pub last_change: Option<std::time::SystemTime>,
+    /// Minimum time that has to pass before the password can be changed.
+    pub min_age: Option<std::time::Duration>,
+    /// Maximum time after which the password has to be changed.
+    pub max_age: Option<std::time::Duration>,
+    /// Time before password expiration during which the user is warned.
+    pub warn_period: Option<std::time::Duration>,
+    /// Time after password expiration during which it is still accepted.
+    pub inactive_period: Option<std::time::Duration>,
+    /// Time at which the account expires.
+    pub expiration: Option<std::time::SystemTime>,
+}
+
+/// Information about a group defined on the system.
+#[derive(Debug)]
+pub struct Group {
+    /// Name of the group.
+    pub name: std::ffi::OsString,
+    /// Identifier of the group.
+    pub gid: u32,
+    /// Names of users that are members of the group.
+    ///
+    /// Note that this includes only users explicitly listed as members and
+    /// not users for which this is the primary group.
+    pub members: Vec<std::ffi::OsString>,
+}
+
+/// Returns an iterator over user accounts defined on the system.
+///
+/// Password metadata and last login times are included only if the process has
+/// sufficient permissions to read them (usually, it requires root privileges).
+///
+/// # Errors
+///
+/// This function will return an error if the user database is not available or
+/// user enumeration is not supported on the platform. Each iterator element is
+/// a result itself as individual user entries may be malformed.
+///
+/// # Examples
+///
+/// ```no_run
+/// let users = ospect::os::users()
+///     .unwrap()
+///     .map(Result::unwrap);
+///
+/// for user in users {
+///     println!("{}: {}", user.uid, user.name.to_string_lossy());
+/// }
+/// ```
+pub fn users() -> std::io::Result<impl Iterator<Item = std::io::Result<User>>> {
+    self::sys::users()
+}
+
+/// Returns an iterator over groups defined on the system.
+///
+/// # Errors
+///
+/// This function will return an error if the group database is not available
+/// or group enumeration is not supported on the platform. Each iterator element
+/// is a result itself as individual group entries may be malformed.
+///
+/// # Examples
+///
+/// ```no_run
+/// let groups = ospect::os::groups()
+///     .unwrap()
+///     .map(Result::unwrap);
+///
+/// for group in groups {
+///     println!("{}: {}", group.gid, group.name.to_string_lossy());
+/// }
+/// ```
+pub fn groups() -> std::io::Result<impl Iterator<Item = std::io::Result<Group>>> {
+    self::sys::groups()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -145,4 +277,9 @@ mod tests {
     fn fqdn_not_empty() {
         assert!(!fqdn().unwrap().is_empty());
     }
+
+    #[test]
+    fn temp_dir_is_dir() {
+        assert!(temp_dir().unwrap().is_dir());
+    }
 }
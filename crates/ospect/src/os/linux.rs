@@ -34,3 +34,389 @@ pub fn hostname() -> std::io::Result<std::ffi::OsString> {
 pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     crate::os::unix::fqdn()
 }
+
+/// Returns the system-wide folder for temporary files.
+pub fn temp_dir() -> std::io::Result<std::path::PathBuf> {
+    crate::os::unix::temp_dir()
+}
+
+/// Returns an iterator over user accounts defined on the system.
+pub fn users() -> std::io::Result<impl Iterator<Item = std::io::Result<crate::os::User>>> {
+    let passwd = std::fs::read(PASSWD_PATH)?;
+
+    // Groups are needed only to determine supplementary groups of each user,
+    // so failing to read them is not fatal. The same goes for the shadow file
+    // (which is usually readable only by root) and the login records.
+    let groups = match std::fs::read(GROUP_PATH) {
+        Ok(group) => parse_group(&group).filter_map(Result::ok).collect(),
+        Err(_) => Vec::new(),
+    };
+    let mut shadows = match std::fs::read(SHADOW_PATH) {
+        Ok(shadow) => parse_shadow(&shadow).collect(),
+        Err(_) => std::collections::HashMap::new(),
+    };
+    let mut lastlog = std::fs::File::open(LASTLOG_PATH).ok();
+
+    let users = parse_passwd(&passwd).map(|user| {
+        let mut user = user?;
+
+        user.groups = groups.iter()
+            .filter(|group| group.members.contains(&user.name))
+            .map(|group| group.gid)
+            .collect();
+        user.password = shadows.remove(&user.name);
+        user.last_login = lastlog.as_mut()
+            .and_then(|lastlog| last_login(lastlog, user.uid).ok())
+            .flatten();
+
+        Ok(user)
+    }).collect::<Vec<_>>();
+
+    Ok(users.into_iter())
+}
+
+/// Returns an iterator over groups defined on the system.
+pub fn groups() -> std::io::Result<impl Iterator<Item = std::io::Result<crate::os::Group>>> {
+    let group = std::fs::read(GROUP_PATH)?;
+
+    Ok(parse_group(&group).collect::<Vec<_>>().into_iter())
+}
+
+/// Path to the user account database.
+const PASSWD_PATH: &str = "/etc/passwd";
+
+/// Path to the group database.
+const GROUP_PATH: &str = "/etc/group";
+
+/// Path to the shadowed password database.
+const SHADOW_PATH: &str = "/etc/shadow";
+
+/// Path to the database of last logins.
+const LASTLOG_PATH: &str = "/var/log/lastlog";
+
+/// Size of the individual `lastlog` record.
+///
+/// Records consist of a 32-bit timestamp, 32-byte terminal name and 256-byte
+/// hostname (see the [`lastlog(8)`] manual page and `<lastlog.h>` header).
+///
+/// [`lastlog(8)`]: https://man7.org/linux/man-pages/man8/lastlog.8.html
+const LASTLOG_RECORD_LEN: u64 = 4 + 32 + 256;
+
+/// Parses contents of the `/etc/passwd` file.
+///
+/// See the [`passwd(5)`] manual page for the format description.
+///
+/// [`passwd(5)`]: https://man7.org/linux/man-pages/man5/passwd.5.html
+fn parse_passwd(
+    content: &[u8],
+) -> impl Iterator<Item = std::io::Result<crate::os::User>> + '_ {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    records(content).map(|fields| {
+        let [name, _, uid, gid, full_name, home_dir, shell] = fields[..] else {
+            return Err(invalid_data("unexpected number of passwd fields"));
+        };
+
+        let non_empty = |field: &[u8]| -> Option<std::ffi::OsString> {
+            if field.is_empty() {
+                None
+            } else {
+                Some(std::ffi::OsStr::from_bytes(field).to_os_string())
+            }
+        };
+
+        Ok(crate::os::User {
+            name: std::ffi::OsStr::from_bytes(name).to_os_string(),
+            uid: parse_int(uid)?,
+            gid: parse_int(gid)?,
+            full_name: non_empty(full_name),
+            home_dir: non_empty(home_dir).map(std::path::PathBuf::from),
+            shell: non_empty(shell).map(std::path::PathBuf::from),
+            groups: Vec::new(),
+            password: None,
+            last_login: None,
+        })
+    })
+}
+
+/// Parses contents of the `/etc/group` file.
+///
+/// See the [`group(5)`] manual page for the format description.
+///
+/// [`group(5)`]: https://man7.org/linux/man-pages/man5/group.5.html
+fn parse_group(
+    content: &[u8],
+) -> impl Iterator<Item = std::io::Result<crate::os::Group>> + '_ {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    records(content).map(|fields| {
+        let [name, _, gid, members] = fields[..] else {
+            return Err(invalid_data("unexpected number of group fields"));
+        };
+
+        let members = members.split(|byte| *byte == b',')
+            .filter(|member| !member.is_empty())
+            .map(|member| std::ffi::OsStr::from_bytes(member).to_os_string())
+            .collect();
+
+        Ok(crate::os::Group {
+            name: std::ffi::OsStr::from_bytes(name).to_os_string(),
+            gid: parse_int(gid)?,
+            members,
+        })
+    })
+}
+
+/// Parses contents of the `/etc/shadow` file.
+///
+/// The iterator yields pairs of user names and corresponding password metadata.
+/// Malformed entries are skipped. Password hashes are never retained.
+///
+/// See the [`shadow(5)`] manual page for the format description.
+///
+/// [`shadow(5)`]: https://man7.org/linux/man-pages/man5/shadow.5.html
+fn parse_shadow(
+    content: &[u8],
+) -> impl Iterator<Item = (std::ffi::OsString, crate::os::Password)> + '_ {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    const DAY: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+    records(content).filter_map(|fields| {
+        let [name, hash, last_change, min_age, max_age, warn, inactive, expiration, _] = fields[..] else {
+            return None;
+        };
+
+        // All the numeric fields are optional and expressed in days (dates
+        // are expressed in days since the Unix epoch).
+        let days = |field: &[u8]| -> Option<std::time::Duration> {
+            parse_int::<u32>(field).ok().map(|days| DAY * days)
+        };
+
+        let password = crate::os::Password {
+            locked: hash.starts_with(b"!") || hash.starts_with(b"*"),
+            empty: hash.is_empty(),
+            last_change: days(last_change)
+                .map(|since_epoch| std::time::UNIX_EPOCH + since_epoch),
+            min_age: days(min_age),
+            max_age: days(max_age),
+            warn_period: days(warn),
+            inactive_period: days(inactive),
+            expiration: days(expiration)
+                .map(|since_epoch| std::time::UNIX_EPOCH + since_epoch),
+        };
+
+        Some((std::ffi::OsStr::from_bytes(name).to_os_string(), password))
+    })
+}
+
+/// Reads the time of the last login of the specified user from `lastlog`.
+///
+/// Returns `None` if there is no record for the given user (which means that
+/// the user has never logged in).
+fn last_login<R>(
+    lastlog: &mut R,
+    uid: u32,
+) -> std::io::Result<Option<std::time::SystemTime>>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    // The `lastlog` file is a sparse file indexed by user identifiers.
+    lastlog.seek(std::io::SeekFrom::Start(u64::from(uid) * LASTLOG_RECORD_LEN))?;
+
+    let mut time = [0; 4];
+    match lastlog.read_exact(&mut time) {
+        Ok(()) => (),
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+            return Ok(None);
+        }
+        Err(error) => return Err(error),
+    }
+
+    // The timestamp is a 32-bit integer in the native byte order. Zero means
+    // that there is no login recorded.
+    match u32::from_ne_bytes(time) {
+        0 => Ok(None),
+        secs => {
+            let since_epoch = std::time::Duration::from_secs(u64::from(secs));
+            Ok(Some(std::time::UNIX_EPOCH + since_epoch))
+        }
+    }
+}
+
+/// Returns an iterator over records of colon-separated database files.
+///
+/// Empty lines and comments are skipped.
+fn records(content: &[u8]) -> impl Iterator<Item = Vec<&[u8]>> {
+    content.split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty() && !line.starts_with(b"#"))
+        .map(|line| line.split(|byte| *byte == b':').collect())
+}
+
+/// Parses an integer from a database file field.
+fn parse_int<T>(field: &[u8]) -> std::io::Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    let string = std::str::from_utf8(field)
+        .map_err(invalid_data)?;
+
+    string.parse().map_err(invalid_data)
+}
+
+/// Creates a new error of the [`InvalidData`] kind.
+///
+/// [`InvalidData`]: std::io::ErrorKind::InvalidData
+fn invalid_data<E>(error: E) -> std::io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_passwd_ok() {
+        let content = b"\
+root:x:0:0:root:/root:/bin/bash
+# Some comment.
+foo:x:1000:1001:Foo Bar,,,:/home/foo:/bin/zsh
+
+nobody:x:65534:65534::/nonexistent:
+";
+
+        let users = parse_passwd(content)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(users.len(), 3);
+
+        assert_eq!(users[0].name, "root");
+        assert_eq!(users[0].uid, 0);
+        assert_eq!(users[0].gid, 0);
+
+        assert_eq!(users[1].name, "foo");
+        assert_eq!(users[1].uid, 1000);
+        assert_eq!(users[1].gid, 1001);
+        assert_eq!(users[1].full_name.as_deref(), Some("Foo Bar,,,".as_ref()));
+        assert_eq!(users[1].home_dir.as_deref(), Some("/home/foo".as_ref()));
+        assert_eq!(users[1].shell.as_deref(), Some("/bin/zsh".as_ref()));
+
+        assert_eq!(users[2].name, "nobody");
+        assert!(users[2].full_name.is_none());
+        assert!(users[2].shell.is_none());
+    }
+
+    #[test]
+    fn parse_passwd_malformed() {
+        let content = b"\
+foo:x:1000
+bar:x:bar:1000:::
+baz:x:1002:1002:::
+";
+
+        let mut users = parse_passwd(content);
+        assert!(users.next().unwrap().is_err());
+        assert!(users.next().unwrap().is_err());
+        assert_eq!(users.next().unwrap().unwrap().name, "baz");
+        assert!(users.next().is_none());
+    }
+
+    #[test]
+    fn parse_group_ok() {
+        let content = b"\
+root:x:0:
+sudo:x:27:foo,bar
+foo:x:1001:
+";
+
+        let groups = parse_group(content)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        assert_eq!(groups.len(), 3);
+
+        assert_eq!(groups[0].name, "root");
+        assert_eq!(groups[0].gid, 0);
+        assert!(groups[0].members.is_empty());
+
+        assert_eq!(groups[1].name, "sudo");
+        assert_eq!(groups[1].gid, 27);
+        assert_eq!(groups[1].members, ["foo", "bar"]);
+    }
+
+    #[test]
+    fn parse_shadow_ok() {
+        let content = b"\
+root:!:19000:0:99999:7:::
+foo:$6$salt$hash:19500:1:90:14:30:20000:
+bar::0::::::
+";
+
+        let shadows = parse_shadow(content)
+            .collect::<std::collections::HashMap<_, _>>();
+        assert_eq!(shadows.len(), 3);
+
+        const DAY: std::time::Duration = std::time::Duration::from_secs(86400);
+
+        let root = &shadows[std::ffi::OsStr::new("root")];
+        assert!(root.locked);
+        assert!(!root.empty);
+        assert_eq!(root.last_change, Some(std::time::UNIX_EPOCH + DAY * 19000));
+        assert_eq!(root.max_age, Some(DAY * 99999));
+        assert!(root.inactive_period.is_none());
+        assert!(root.expiration.is_none());
+
+        let foo = &shadows[std::ffi::OsStr::new("foo")];
+        assert!(!foo.locked);
+        assert!(!foo.empty);
+        assert_eq!(foo.min_age, Some(DAY));
+        assert_eq!(foo.max_age, Some(DAY * 90));
+        assert_eq!(foo.warn_period, Some(DAY * 14));
+        assert_eq!(foo.inactive_period, Some(DAY * 30));
+        assert_eq!(foo.expiration, Some(std::time::UNIX_EPOCH + DAY * 20000));
+
+        let bar = &shadows[std::ffi::OsStr::new("bar")];
+        assert!(!bar.locked);
+        assert!(bar.empty);
+    }
+
+    #[test]
+    fn last_login_ok() {
+        let mut content = vec![0; LASTLOG_RECORD_LEN as usize * 3];
+        let offset = LASTLOG_RECORD_LEN as usize * 2;
+        content[offset..offset + 4].copy_from_slice(&1337u32.to_ne_bytes());
+
+        let mut lastlog = std::io::Cursor::new(content);
+
+        assert_eq!(last_login(&mut lastlog, 0).unwrap(), None);
+        assert_eq! {
+            last_login(&mut lastlog, 2).unwrap(),
+            Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1337))
+        };
+        assert_eq!(last_login(&mut lastlog, 42).unwrap(), None);
+    }
+
+    #[test]
+    fn users_root() {
+        let root = users().unwrap()
+            .filter_map(Result::ok)
+            .find(|user| user.uid == 0)
+            .unwrap();
+
+        assert_eq!(root.name, "root");
+    }
+
+    #[test]
+    fn groups_root() {
+        let root = groups().unwrap()
+            .filter_map(Result::ok)
+            .find(|group| group.gid == 0)
+            .unwrap();
+
+        assert_eq!(root.name, "root");
+    }
+}
@@ -34,3 +34,20 @@ pub fn hostname() -> std::io::Result<std::ffi::OsString> {
 pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     crate::os::unix::fqdn()
 }
+
+/// Returns the system-wide folder for temporary files.
+pub fn temp_dir() -> std::io::Result<std::path::PathBuf> {
+    crate::os::unix::temp_dir()
+}
+
+/// Returns an iterator over user accounts defined on the system.
+pub fn users() -> std::io::Result<std::iter::Empty<std::io::Result<crate::os::User>>> {
+    // TODO(@panhania): Add support for user enumeration.
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Returns an iterator over groups defined on the system.
+pub fn groups() -> std::io::Result<std::iter::Empty<std::io::Result<crate::os::Group>>> {
+    // TODO(@panhania): Add support for group enumeration.
+    Err(std::io::ErrorKind::Unsupported.into())
+}
@@ -117,6 +117,13 @@ pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     Ok(fqdn)
 }
 
+/// Returns the system-wide folder for temporary files.
+pub fn temp_dir() -> std::io::Result<std::path::PathBuf> {
+    // We deliberately ignore the `TMPDIR` variable: it is set per process (or
+    // per user, as on macOS) and does not describe the system as a whole.
+    Ok(std::path::PathBuf::from("/tmp"))
+}
+
 /// Returns `uname` information of the currently running operating system.
 fn uname() -> std::io::Result<libc::utsname> {
     let mut uname = std::mem::MaybeUninit::uninit();
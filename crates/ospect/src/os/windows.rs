@@ -229,6 +229,74 @@ pub fn fqdn() -> std::io::Result<std::ffi::OsString> {
     computer_name(SystemInformation::ComputerNameDnsFullyQualified)
 }
 
+/// Returns the system-wide folder for temporary files.
+pub fn temp_dir() -> std::io::Result<std::path::PathBuf> {
+    use windows_sys::{w, Win32::System::Registry::*};
+
+    // The `TEMP` variable of the system environment (as opposed to the one of
+    // the current process or user) is stored in the registry. Restricting the
+    // type to `REG_SZ` makes the function expand `REG_EXPAND_SZ` values (e.g.
+    // `%SystemRoot%\TEMP`) for us.
+    let key = w!("SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Environment");
+    let value = w!("TEMP");
+
+    let mut buf_size = 0;
+
+    // SAFETY: We call the function with no buffer to get the required size (in
+    // bytes) of it as described in the docs [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winreg/nf-winreg-reggetvaluew
+    let code = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key,
+            value,
+            RRF_RT_REG_SZ,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut buf_size,
+        )
+    };
+    if code != windows_sys::Win32::Foundation::NO_ERROR {
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    let mut buf = Vec::<u16>::with_capacity(buf_size as usize / 2);
+
+    // SAFETY: We allocated buffer of the required size. In case the value
+    // changes in the meantime and no longer fits, the function will return an
+    // error that we verify below.
+    let code = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key,
+            value,
+            RRF_RT_REG_SZ,
+            std::ptr::null_mut(),
+            buf.as_mut_ptr() as *mut std::ffi::c_void,
+            &mut buf_size,
+        )
+    };
+    if code != windows_sys::Win32::Foundation::NO_ERROR {
+        return Err(std::io::Error::from_raw_os_error(code as i32));
+    }
+
+    // SAFETY: We verified that the call succeeded. It means that the buffer has
+    // been filled with `buf_size` bytes of data and we can set its length.
+    unsafe {
+        buf.set_len(buf_size as usize / 2);
+    }
+
+    // The returned string is null-terminated, which we do not want as part of
+    // the path.
+    if buf.last() == Some(&0) {
+        buf.pop();
+    }
+
+    use std::os::windows::ffi::OsStringExt as _;
+    Ok(std::ffi::OsString::from_wide(&buf).into())
+}
+
 /// Returns the name information of the currently running operating system.
 fn computer_name(
     format: windows_sys::Win32::System::SystemInformation::COMPUTER_NAME_FORMAT,
@@ -288,3 +356,15 @@ fn computer_name(
     use std::os::windows::ffi::OsStringExt as _;
     Ok(std::ffi::OsString::from_wide(&buf))
 }
+
+/// Returns an iterator over user accounts defined on the system.
+pub fn users() -> std::io::Result<std::iter::Empty<std::io::Result<crate::os::User>>> {
+    // TODO(@panhania): Add support for user enumeration.
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Returns an iterator over groups defined on the system.
+pub fn groups() -> std::io::Result<std::iter::Empty<std::io::Result<crate::os::Group>>> {
+    // TODO(@panhania): Add support for group enumeration.
+    Err(std::io::ErrorKind::Unsupported.into())
+}
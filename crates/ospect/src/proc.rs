@@ -9,6 +9,9 @@ mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 
+#[cfg(target_family = "unix")]
+mod unix;
+
 #[cfg(target_os = "windows")]
 mod windows;
 
@@ -45,3 +48,257 @@ mod sys {
 pub fn ids() -> std::io::Result<impl Iterator<Item = std::io::Result<u32>>> {
     self::sys::ids()
 }
+
+/// Information about a process running on the system.
+///
+/// Not all the information is available on all the platforms and even on the
+/// supported ones some of it might not be accessible (e.g. because of lacking
+/// permissions). Such fields are left unset.
+#[derive(Debug)]
+pub struct Process {
+    /// Identifier of the process.
+    pub pid: u32,
+    /// Identifier of the parent process.
+    pub ppid: Option<u32>,
+    /// Name of the process (usually a possibly truncated executable name).
+    pub name: Option<std::ffi::OsString>,
+    /// Path to the executable file the process was started from.
+    pub exe: Option<std::path::PathBuf>,
+    /// Command-line arguments the process was invoked with.
+    pub cmdline: Vec<std::ffi::OsString>,
+    /// Real identifier of the user owning the process (Unix-only).
+    pub uid: Option<u32>,
+    /// Real identifier of the group owning the process (Unix-only).
+    pub gid: Option<u32>,
+    /// Time at which the process was started.
+    pub start_time: Option<std::time::SystemTime>,
+    /// State in which the process was at the moment of the inspection.
+    pub state: Option<State>,
+    /// Resident set size of the process (in bytes).
+    pub rss: Option<u64>,
+    /// Virtual memory size of the process (in bytes, not available on Windows).
+    pub vsz: Option<u64>,
+    /// Time the process spent executing in the user mode.
+    pub user_cpu_time: Option<std::time::Duration>,
+    /// Time the process spent executing in the kernel mode.
+    pub system_cpu_time: Option<std::time::Duration>,
+}
+
+/// State of a process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum State {
+    /// The process is running or is ready to run.
+    Running,
+    /// The process is in an interruptible sleep (e.g. waiting for an event).
+    Sleeping,
+    /// The process is in an uninterruptible sleep (usually waiting for I/O).
+    DiskSleep,
+    /// The process has been stopped (e.g. by a job control signal).
+    Stopped,
+    /// The process is stopped by a debugger.
+    Traced,
+    /// The process has terminated but has not been reaped by its parent.
+    Zombie,
+    /// The process is dead.
+    Dead,
+    /// The process is idle (e.g. an idle kernel thread).
+    Idle,
+}
+
+/// Returns information about the process with the specified identifier.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist (e.g. it
+/// has terminated since its identifier was obtained) or if the operating system
+/// does not allow to get the basic information about it.
+///
+/// # Examples
+///
+/// ```
+/// let process = ospect::proc::info(std::process::id())
+///     .unwrap();
+///
+/// assert_eq!(process.pid, std::process::id());
+/// ```
+pub fn info(pid: u32) -> std::io::Result<Process> {
+    self::sys::info(pid)
+}
+
+/// Returns an iterator yielding information about all processes on the system.
+///
+/// This is equivalent to combining [`ids`] with [`info`] (but can be faster on
+/// some platforms). Note that processes that terminate while being inspected
+/// are skipped silently.
+///
+/// # Errors
+///
+/// The function will return an error if the operating system does not allow
+/// to list the processes. Each iterator element is a result itself as getting
+/// information about individual processes may fail.
+///
+/// # Examples
+///
+/// ```
+/// let mut processes = ospect::proc::all()
+///     .unwrap()
+///     .filter_map(Result::ok);
+///
+/// assert! {
+///     processes.any(|process| process.pid == std::process::id())
+/// };
+/// ```
+pub fn all() -> std::io::Result<impl Iterator<Item = std::io::Result<Process>>> {
+    self::sys::all()
+}
+
+/// Returns the CPU time consumed so far by the calling thread.
+///
+/// The returned value is the sum of the time spent executing in the user and
+/// the kernel mode.
+///
+/// # Errors
+///
+/// The function will return an error if the operating system fails to report
+/// the thread CPU time.
+///
+/// # Examples
+///
+/// ```
+/// let before = ospect::proc::thread_cpu_time()
+///     .unwrap();
+///
+/// let mut sum = 0u64;
+/// for i in 0..1_000_000 {
+///     sum = sum.wrapping_add(i);
+/// }
+/// assert!(sum > 0);
+///
+/// let after = ospect::proc::thread_cpu_time()
+///     .unwrap();
+///
+/// assert!(before <= after);
+/// ```
+pub fn thread_cpu_time() -> std::io::Result<std::time::Duration> {
+    self::sys::thread_cpu_time()
+}
+
+/// A region of the virtual memory of a process.
+///
+/// Not all the information is available on all the platforms. Such fields are
+/// left unset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    /// Address at which the region starts.
+    pub start: u64,
+    /// Address at which the region ends (exclusive).
+    pub end: u64,
+    /// Whether the region can be read from.
+    pub readable: bool,
+    /// Whether the region can be written to.
+    pub writable: bool,
+    /// Whether the region can be executed.
+    pub executable: bool,
+    /// Whether the region is shared with other processes.
+    pub shared: bool,
+    /// Offset within the backing file at which the region starts.
+    pub offset: u64,
+    /// Inode of the backing file (Linux-only).
+    pub inode: Option<u64>,
+    /// Path to the backing file.
+    ///
+    /// On Linux, this can also be a pseudo-path describing the region (e.g.
+    /// `[heap]` or `[stack]`).
+    pub path: Option<std::path::PathBuf>,
+}
+
+impl MemoryRegion {
+
+    /// Returns the size of the region in bytes.
+    pub fn len(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Returns `true` if the region has zero size.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Returns regions of the virtual memory of the specified process.
+///
+/// The regions are returned in the order of their addresses.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to inspect its memory layout (e.g. in case
+/// of insufficient permissions). On platforms other than Linux the function
+/// always returns an error.
+///
+/// # Examples
+///
+/// ```
+/// # if cfg!(target_os = "linux") {
+/// let regions = ospect::proc::memory_regions(std::process::id())
+///     .unwrap();
+///
+/// assert!(regions.iter().any(|region| region.readable));
+/// # }
+/// ```
+pub fn memory_regions(pid: u32) -> std::io::Result<Vec<MemoryRegion>> {
+    self::sys::memory_regions(pid)
+}
+
+/// A handle to the virtual memory of a process.
+///
+/// The handle can be obtained using the [`open_memory`] function.
+pub struct Memory {
+    inner: self::sys::Memory,
+}
+
+impl Memory {
+
+    /// Reads the memory of the process at the given address into the buffer.
+    ///
+    /// Returns the number of bytes read. Reads can be short, e.g. if the read
+    /// crosses a boundary of a memory region.
+    ///
+    /// # Errors
+    ///
+    /// The function will return an error if the memory at the given address is
+    /// not mapped or cannot be read.
+    pub fn read_at(&self, addr: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read_at(addr, buf)
+    }
+}
+
+/// Opens the virtual memory of the specified process for reading.
+///
+/// # Errors
+///
+/// The function will return an error if the process does not exist or if the
+/// operating system does not allow to read its memory (e.g. in case of
+/// insufficient permissions). On platforms other than Linux the function
+/// always returns an error.
+///
+/// # Examples
+///
+/// ```
+/// # if cfg!(target_os = "linux") {
+/// let value = 0xC0DEu64;
+/// let addr = &value as *const u64 as u64;
+///
+/// let memory = ospect::proc::open_memory(std::process::id())
+///     .unwrap();
+///
+/// let mut buf = [0; 8];
+/// assert_eq!(memory.read_at(addr, &mut buf).unwrap(), 8);
+/// assert_eq!(u64::from_ne_bytes(buf), 0xC0DE);
+/// # }
+/// ```
+pub fn open_memory(pid: u32) -> std::io::Result<Memory> {
+    Ok(Memory {
+        inner: self::sys::open_memory(pid)?,
+    })
+}
@@ -70,3 +70,562 @@ impl Iterator for Ids {
 
     }
 }
+
+/// Returns information about the process with the specified identifier.
+pub fn info(pid: u32) -> std::io::Result<crate::proc::Process> {
+    let proc_path = std::path::PathBuf::from(format!("/proc/{pid}"));
+
+    // The `stat` file is the primary source of information about the process.
+    // If we fail to read it, we consider the whole call to be a failure (it is
+    // most likely because the process no longer exists). Other files are just
+    // supplementary and failures to read them are not fatal.
+    let stat = parse_stat(&std::fs::read(proc_path.join("stat"))?)?;
+
+    let status = std::fs::read_to_string(proc_path.join("status")).ok()
+        .map(|status| parse_status(&status));
+
+    let cmdline = match std::fs::read(proc_path.join("cmdline")) {
+        Ok(cmdline) => parse_cmdline(cmdline),
+        Err(_) => Vec::new(),
+    };
+
+    let exe = std::fs::read_link(proc_path.join("exe")).ok();
+
+    let clk_tck = sysconf(libc::_SC_CLK_TCK);
+    let page_size = sysconf(libc::_SC_PAGESIZE);
+
+    let start_time = match (boot_time(), clk_tck) {
+        (Ok(boot_time), Some(clk_tck)) => {
+            Some(boot_time + ticks_to_duration(stat.start_ticks, clk_tck))
+        }
+        _ => None,
+    };
+
+    Ok(crate::proc::Process {
+        pid,
+        ppid: Some(stat.ppid).filter(|ppid| *ppid != 0),
+        name: Some(stat.name),
+        exe,
+        cmdline,
+        uid: status.as_ref().and_then(|status| status.uid),
+        gid: status.as_ref().and_then(|status| status.gid),
+        start_time,
+        state: stat.state,
+        rss: page_size.map(|page_size| stat.rss_pages * page_size),
+        vsz: Some(stat.vsize),
+        user_cpu_time: clk_tck.map(|clk_tck| {
+            ticks_to_duration(stat.user_ticks, clk_tck)
+        }),
+        system_cpu_time: clk_tck.map(|clk_tck| {
+            ticks_to_duration(stat.system_ticks, clk_tck)
+        }),
+    })
+}
+
+/// Returns an iterator yielding information about all processes on the system.
+pub fn all() -> std::io::Result<impl Iterator<Item = std::io::Result<crate::proc::Process>>> {
+    crate::proc::unix::all()
+}
+
+/// Returns the CPU time consumed so far by the calling thread.
+pub fn thread_cpu_time() -> std::io::Result<std::time::Duration> {
+    crate::proc::unix::thread_cpu_time()
+}
+
+/// Returns regions of the virtual memory of the specified process.
+pub fn memory_regions(pid: u32) -> std::io::Result<Vec<crate::proc::MemoryRegion>> {
+    let content = std::fs::read(format!("/proc/{pid}/maps"))?;
+
+    content.split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(parse_maps_line)
+        .collect()
+}
+
+/// A Linux-specific handle to the virtual memory of a process.
+pub struct Memory {
+    /// Handle to the `/proc/<pid>/mem` file of the process.
+    file: std::fs::File,
+}
+
+impl Memory {
+
+    /// Reads the memory of the process at the given address into the buffer.
+    pub fn read_at(&self, addr: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        use std::os::unix::fs::FileExt as _;
+
+        self.file.read_at(buf, addr)
+    }
+}
+
+/// Opens the virtual memory of the specified process for reading.
+pub fn open_memory(pid: u32) -> std::io::Result<Memory> {
+    Ok(Memory {
+        file: std::fs::File::open(format!("/proc/{pid}/mem"))?,
+    })
+}
+
+/// Information about a process parsed from the `/proc/<pid>/stat` file.
+#[derive(Debug)]
+struct Stat {
+    /// Name of the process executable (the `comm` field).
+    name: std::ffi::OsString,
+    /// State of the process (the `state` field).
+    state: Option<crate::proc::State>,
+    /// Identifier of the parent process (the `ppid` field).
+    ppid: u32,
+    /// Time spent in the user mode in clock ticks (the `utime` field).
+    user_ticks: u64,
+    /// Time spent in the kernel mode in clock ticks (the `stime` field).
+    system_ticks: u64,
+    /// Time the process started after boot in clock ticks (the `starttime`
+    /// field).
+    start_ticks: u64,
+    /// Virtual memory size in bytes (the `vsize` field).
+    vsize: u64,
+    /// Resident set size in pages (the `rss` field).
+    rss_pages: u64,
+}
+
+/// Parses contents of the `/proc/<pid>/stat` file.
+///
+/// See the [`proc_pid_stat(5)`] manual page for the format description.
+///
+/// [`proc_pid_stat(5)`]: https://man7.org/linux/man-pages/man5/proc_pid_stat.5.html
+fn parse_stat(content: &[u8]) -> std::io::Result<Stat> {
+    use std::os::unix::ffi::OsStrExt as _;
+    use std::str::FromStr as _;
+
+    fn invalid_data<E>(error: E) -> std::io::Error
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
+
+    // The process name is enclosed in parentheses and it can contain arbitrary
+    // characters, including spaces and parentheses themselves. Thus, we look
+    // for the first opening and the last closing parenthesis.
+    let name_start = content.iter().position(|byte| *byte == b'(')
+        .ok_or_else(|| invalid_data("no process name start"))?;
+    let name_end = content.iter().rposition(|byte| *byte == b')')
+        .ok_or_else(|| invalid_data("no process name end"))?;
+    if name_end < name_start {
+        return Err(invalid_data("malformed process name"));
+    }
+
+    let name = std::ffi::OsStr::from_bytes(&content[name_start + 1..name_end]);
+
+    // Everything after the name should be just space-separated ASCII values.
+    let rest = std::str::from_utf8(&content[name_end + 1..])
+        .map_err(invalid_data)?;
+    let fields = rest.split_ascii_whitespace().collect::<Vec<_>>();
+
+    // The first field after the name is `state` (third field in the file) and
+    // we need everything up to `rss` (24th field in the file).
+    if fields.len() < 22 {
+        return Err(invalid_data("not enough fields"));
+    }
+
+    let parse_u64 = |idx: usize| {
+        u64::from_str(fields[idx]).map_err(invalid_data)
+    };
+
+    let state = match fields[0] {
+        "R" => Some(crate::proc::State::Running),
+        "S" => Some(crate::proc::State::Sleeping),
+        "D" => Some(crate::proc::State::DiskSleep),
+        "T" => Some(crate::proc::State::Stopped),
+        "t" => Some(crate::proc::State::Traced),
+        "Z" => Some(crate::proc::State::Zombie),
+        "X" | "x" => Some(crate::proc::State::Dead),
+        "I" => Some(crate::proc::State::Idle),
+        _ => None,
+    };
+
+    Ok(Stat {
+        name: name.to_os_string(),
+        state,
+        ppid: u32::from_str(fields[1]).map_err(invalid_data)?,
+        user_ticks: parse_u64(11)?,
+        system_ticks: parse_u64(12)?,
+        start_ticks: parse_u64(19)?,
+        vsize: parse_u64(20)?,
+        // The `rss` field is signed but in practice it should never be
+        // negative. If it is, we just clamp it to zero.
+        rss_pages: i64::from_str(fields[21]).map_err(invalid_data)?
+            .max(0) as u64,
+    })
+}
+
+/// Information about a process parsed from the `/proc/<pid>/status` file.
+#[derive(Debug, Default)]
+struct Status {
+    /// Real identifier of the user owning the process.
+    uid: Option<u32>,
+    /// Real identifier of the group owning the process.
+    gid: Option<u32>,
+}
+
+/// Parses contents of the `/proc/<pid>/status` file.
+///
+/// See the [`proc_pid_status(5)`] manual page for the format description.
+///
+/// [`proc_pid_status(5)`]: https://man7.org/linux/man-pages/man5/proc_pid_status.5.html
+fn parse_status(content: &str) -> Status {
+    use std::str::FromStr as _;
+
+    let mut status = Status::default();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        // Both `Uid` and `Gid` lines contain four values: real, effective,
+        // saved set and filesystem identifier. We are interested only in the
+        // first one.
+        let real_id = || {
+            value.split_ascii_whitespace().next()
+                .and_then(|id| u32::from_str(id).ok())
+        };
+
+        match key {
+            "Uid" => status.uid = real_id(),
+            "Gid" => status.gid = real_id(),
+            _ => continue,
+        }
+    }
+
+    status
+}
+
+/// Parses contents of the `/proc/<pid>/cmdline` file.
+///
+/// The arguments in this file are separated (and terminated) by null bytes.
+fn parse_cmdline(content: Vec<u8>) -> Vec<std::ffi::OsString> {
+    use std::os::unix::ffi::OsStringExt as _;
+
+    let mut args = content.split(|byte| *byte == b'\0')
+        .map(|arg| std::ffi::OsString::from_vec(arg.to_vec()))
+        .collect::<Vec<_>>();
+
+    // Because the last argument is also terminated by a null byte, splitting
+    // yields one excessive empty element at the end that we need to remove.
+    if args.last().is_some_and(|arg| arg.is_empty()) {
+        args.pop();
+    }
+
+    args
+}
+
+/// Parses a single line of the `/proc/<pid>/maps` file.
+///
+/// See the [`proc_pid_maps(5)`] manual page for the format description.
+///
+/// [`proc_pid_maps(5)`]: https://man7.org/linux/man-pages/man5/proc_pid_maps.5.html
+fn parse_maps_line(line: &[u8]) -> std::io::Result<crate::proc::MemoryRegion> {
+    use std::os::unix::ffi::OsStrExt as _;
+    use std::str::FromStr as _;
+
+    fn invalid_data<E>(error: E) -> std::io::Error
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+    }
+
+    // The path is the last field and it can contain spaces, so we split only
+    // the fields that precede it. The path is padded with spaces that we have
+    // to trim.
+    let mut fields = line.splitn(6, |byte| *byte == b' ');
+    let mut next_field = || -> std::io::Result<&str> {
+        let field = fields.next()
+            .ok_or_else(|| invalid_data("not enough fields"))?;
+
+        std::str::from_utf8(field).map_err(invalid_data)
+    };
+
+    let (start, end) = next_field()?.split_once('-')
+        .ok_or_else(|| invalid_data("malformed address range"))?;
+    let start = u64::from_str_radix(start, 16).map_err(invalid_data)?;
+    let end = u64::from_str_radix(end, 16).map_err(invalid_data)?;
+    if end < start {
+        return Err(invalid_data("inverted address range"));
+    }
+
+    let perms = next_field()?.as_bytes();
+    if perms.len() != 4 {
+        return Err(invalid_data("malformed permissions"));
+    }
+
+    let offset = u64::from_str_radix(next_field()?, 16).map_err(invalid_data)?;
+    let _dev = next_field()?;
+    let inode = u64::from_str(next_field()?).map_err(invalid_data)?;
+
+    let path = fields.next()
+        .map(|path| {
+            let start = path.iter().position(|byte| *byte != b' ')
+                .unwrap_or(path.len());
+            &path[start..]
+        })
+        .filter(|path| !path.is_empty())
+        .map(|path| std::path::PathBuf::from(std::ffi::OsStr::from_bytes(path)));
+
+    Ok(crate::proc::MemoryRegion {
+        start,
+        end,
+        readable: perms[0] == b'r',
+        writable: perms[1] == b'w',
+        executable: perms[2] == b'x',
+        shared: perms[3] == b's',
+        offset,
+        inode: Some(inode).filter(|inode| *inode != 0),
+        path,
+    })
+}
+
+/// Returns the time at which the system was booted.
+///
+/// The time is read from the `btime` entry of the `/proc/stat` file.
+fn boot_time() -> std::io::Result<std::time::SystemTime> {
+    use std::str::FromStr as _;
+
+    let content = std::fs::read_to_string("/proc/stat")?;
+
+    for line in content.lines() {
+        let Some(btime) = line.strip_prefix("btime ") else {
+            continue;
+        };
+
+        let btime = u64::from_str(btime.trim()).map_err(|error| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+        })?;
+
+        return Ok(std::time::UNIX_EPOCH + std::time::Duration::from_secs(btime));
+    }
+
+    Err(std::io::ErrorKind::NotFound.into())
+}
+
+/// Returns the value of the specified configuration option (if available).
+fn sysconf(name: libc::c_int) -> Option<u64> {
+    // SAFETY: `sysconf` is always safe to call. In case the specified option
+    // is not supported, it returns -1 which we discard below.
+    let value = unsafe {
+        libc::sysconf(name)
+    };
+
+    u64::try_from(value).ok().filter(|value| *value > 0)
+}
+
+/// Converts the number of clock ticks to a duration.
+fn ticks_to_duration(ticks: u64, clk_tck: u64) -> std::time::Duration {
+    let secs = ticks / clk_tck;
+    let nanos = (ticks % clk_tck) * 1_000_000_000 / clk_tck;
+
+    std::time::Duration::from_secs(secs) + std::time::Duration::from_nanos(nanos)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_stat_ok() {
+        let content = b"\
+1337 (foo) S 42 1337 1337 0 -1 4194560 1184 0 0 0 \
+150 25 0 0 20 0 1 0 4200 12345678 321 18446744073709551615 \
+1 1 0 0 0 0 0 4096 65536 0 0 0 17 3 0 0 0 0 0 0 0 0 0 0 0 0 0\n";
+
+        let stat = parse_stat(content).unwrap();
+        assert_eq!(stat.name, "foo");
+        assert_eq!(stat.state, Some(crate::proc::State::Sleeping));
+        assert_eq!(stat.ppid, 42);
+        assert_eq!(stat.user_ticks, 150);
+        assert_eq!(stat.system_ticks, 25);
+        assert_eq!(stat.start_ticks, 4200);
+        assert_eq!(stat.vsize, 12345678);
+        assert_eq!(stat.rss_pages, 321);
+    }
+
+    #[test]
+    fn parse_stat_name_with_parens_and_spaces() {
+        let content = b"\
+1337 (foo) (bar) Z 1 1337 1337 0 -1 4194560 1184 0 0 0 \
+0 0 0 0 20 0 1 0 4200 0 0 18446744073709551615\n";
+
+        let stat = parse_stat(content).unwrap();
+        assert_eq!(stat.name, "foo) (bar");
+        assert_eq!(stat.state, Some(crate::proc::State::Zombie));
+        assert_eq!(stat.ppid, 1);
+    }
+
+    #[test]
+    fn parse_stat_not_enough_fields() {
+        let error = parse_stat(b"1337 (foo) S 42 1337\n").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_status_ok() {
+        let content = "\
+Name:\tfoo
+Umask:\t0022
+State:\tS (sleeping)
+Pid:\t1337
+PPid:\t42
+Uid:\t1000\t1001\t1002\t1003
+Gid:\t2000\t2001\t2002\t2003
+";
+
+        let status = parse_status(content);
+        assert_eq!(status.uid, Some(1000));
+        assert_eq!(status.gid, Some(2000));
+    }
+
+    #[test]
+    fn parse_cmdline_ok() {
+        let args = parse_cmdline(b"/bin/foo\0--bar\0\0baz\0".to_vec());
+        assert_eq!(args, vec!["/bin/foo", "--bar", "", "baz"]);
+    }
+
+    #[test]
+    fn parse_cmdline_empty() {
+        assert!(parse_cmdline(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn parse_maps_line_file() {
+        let line = b"7f1c2a000000-7f1c2a021000 r-xp 00002000 fd:01 1337          /usr/lib/foo bar.so";
+
+        let region = parse_maps_line(line).unwrap();
+        assert_eq!(region.start, 0x7f1c2a000000);
+        assert_eq!(region.end, 0x7f1c2a021000);
+        assert!(region.readable);
+        assert!(!region.writable);
+        assert!(region.executable);
+        assert!(!region.shared);
+        assert_eq!(region.offset, 0x2000);
+        assert_eq!(region.inode, Some(1337));
+        assert_eq!(region.path, Some("/usr/lib/foo bar.so".into()));
+    }
+
+    #[test]
+    fn parse_maps_line_pseudo_path() {
+        let line = b"55d4f9a3c000-55d4f9a5d000 rw-s 00000000 00:00 0                          [heap]";
+
+        let region = parse_maps_line(line).unwrap();
+        assert!(region.writable);
+        assert!(region.shared);
+        assert_eq!(region.inode, None);
+        assert_eq!(region.path, Some("[heap]".into()));
+    }
+
+    #[test]
+    fn parse_maps_line_anonymous() {
+        let line = b"7ffd1e9f0000-7ffd1ea11000 ---p 00000000 00:00 0 ";
+
+        let region = parse_maps_line(line).unwrap();
+        assert!(!region.readable);
+        assert!(!region.writable);
+        assert!(!region.executable);
+        assert_eq!(region.path, None);
+    }
+
+    #[test]
+    fn parse_maps_line_inverted_range() {
+        let line = b"7ffd1ea12000-7ffd1e9f0000 rw-p 00000000 00:00 0                          [stack]";
+
+        let error = parse_maps_line(line).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn parse_maps_line_malformed() {
+        let error = parse_maps_line(b"7ffd1e9f0000 rw-p").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn memory_regions_self() {
+        let value = 0xC0DEu64;
+        let addr = &value as *const u64 as u64;
+
+        let regions = memory_regions(std::process::id())
+            .unwrap();
+
+        assert! {
+            regions.iter().any(|region| {
+                region.readable && region.writable &&
+                region.start <= addr && addr < region.end
+            })
+        };
+    }
+
+    #[test]
+    fn open_memory_self() {
+        let value = *b"foobarbaz";
+        let addr = value.as_ptr() as u64;
+
+        let memory = open_memory(std::process::id())
+            .unwrap();
+
+        let mut buf = [0; 9];
+        assert_eq!(memory.read_at(addr, &mut buf).unwrap(), 9);
+        assert_eq!(&buf, b"foobarbaz");
+    }
+
+    #[test]
+    fn ticks_to_duration_exact() {
+        assert_eq! {
+            ticks_to_duration(300, 100),
+            std::time::Duration::from_secs(3)
+        };
+    }
+
+    #[test]
+    fn ticks_to_duration_fractional() {
+        assert_eq! {
+            ticks_to_duration(150, 100),
+            std::time::Duration::from_millis(1500)
+        };
+    }
+
+    #[test]
+    fn info_self() {
+        let process = info(std::process::id())
+            .unwrap();
+
+        assert_eq!(process.pid, std::process::id());
+        assert_eq!(process.exe, Some(std::env::current_exe().unwrap()));
+        assert_eq!(process.cmdline.len(), std::env::args_os().count());
+
+        // SAFETY: Both functions are always safe to call.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        assert_eq!(process.uid, Some(uid));
+        assert_eq!(process.gid, Some(gid));
+
+        assert!(process.start_time.unwrap() <= std::time::SystemTime::now());
+        assert!(process.rss.unwrap() > 0);
+        assert!(process.vsz.unwrap() > 0);
+    }
+
+    #[test]
+    fn info_child() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("3600")
+            .spawn()
+            .unwrap();
+
+        let process = info(child.id());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let process = process.unwrap();
+        assert_eq!(process.ppid, Some(std::process::id()));
+        assert_eq!(process.name, Some("sleep".into()));
+    }
+}
@@ -118,3 +118,243 @@ impl Iterator for Ids {
         Some(Ok(pid))
     }
 }
+
+/// Returns information about the process with the specified identifier.
+pub fn info(pid: u32) -> std::io::Result<crate::proc::Process> {
+    let pid = libc::c_int::try_from(pid).map_err(|error| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, error)
+    })?;
+
+    // Basic information about the process is the only thing that we require
+    // to succeed. Everything else is supplementary.
+    let bsd_info = pid_info::<libc::proc_bsdinfo>(pid, libc::PROC_PIDTBSDINFO)?;
+    let task_info = pid_info::<libc::proc_taskinfo>(pid, libc::PROC_PIDTASKINFO)
+        .ok();
+
+    let state = match bsd_info.pbi_status {
+        libc::SIDL | libc::SRUN => Some(crate::proc::State::Running),
+        libc::SSLEEP => Some(crate::proc::State::Sleeping),
+        libc::SSTOP => Some(crate::proc::State::Stopped),
+        libc::SZOMB => Some(crate::proc::State::Zombie),
+        _ => None,
+    };
+
+    let start_time = std::time::UNIX_EPOCH
+        + std::time::Duration::from_secs(bsd_info.pbi_start_tvsec)
+        + std::time::Duration::from_micros(bsd_info.pbi_start_tvusec);
+
+    // CPU times are reported in Mach absolute time units that need to be
+    // converted to nanoseconds using the timebase information.
+    let timebase = mach_timebase().ok();
+    let cpu_time = |time: u64| -> Option<std::time::Duration> {
+        let (numer, denom) = timebase?;
+        let nanos = u128::from(time) * u128::from(numer) / u128::from(denom);
+        Some(std::time::Duration::from_nanos(u64::try_from(nanos).ok()?))
+    };
+
+    Ok(crate::proc::Process {
+        pid: bsd_info.pbi_pid,
+        ppid: Some(bsd_info.pbi_ppid).filter(|ppid| *ppid != 0),
+        name: Some(c_chars_to_os_string(&bsd_info.pbi_comm)),
+        exe: path(pid).ok(),
+        cmdline: cmdline(pid).unwrap_or_default(),
+        uid: Some(bsd_info.pbi_ruid),
+        gid: Some(bsd_info.pbi_rgid),
+        start_time: Some(start_time),
+        state,
+        rss: task_info.as_ref().map(|info| info.pti_resident_size),
+        vsz: task_info.as_ref().map(|info| info.pti_virtual_size),
+        user_cpu_time: task_info.as_ref()
+            .and_then(|info| cpu_time(info.pti_total_user)),
+        system_cpu_time: task_info.as_ref()
+            .and_then(|info| cpu_time(info.pti_total_system)),
+    })
+}
+
+/// Returns an iterator yielding information about all processes on the system.
+pub fn all() -> std::io::Result<impl Iterator<Item = std::io::Result<crate::proc::Process>>> {
+    crate::proc::unix::all()
+}
+
+/// Returns the CPU time consumed so far by the calling thread.
+pub fn thread_cpu_time() -> std::io::Result<std::time::Duration> {
+    crate::proc::unix::thread_cpu_time()
+}
+
+/// Returns regions of the virtual memory of the specified process.
+pub fn memory_regions(_: u32) -> std::io::Result<Vec<crate::proc::MemoryRegion>> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// A handle to the virtual memory of a process.
+///
+/// Memory handles are not supported on this platform, so this type cannot be
+/// instantiated.
+pub enum Memory {
+}
+
+impl Memory {
+
+    /// Reads the memory of the process at the given address into the buffer.
+    pub fn read_at(&self, _: u64, _: &mut [u8]) -> std::io::Result<usize> {
+        match *self {
+        }
+    }
+}
+
+/// Opens the virtual memory of the specified process for reading.
+pub fn open_memory(_: u32) -> std::io::Result<Memory> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Retrieves the process information of the specified `flavor`.
+///
+/// The type parameter `T` must be the type corresponding to the `flavor`.
+fn pid_info<T>(pid: libc::c_int, flavor: libc::c_int) -> std::io::Result<T> {
+    let mut info = std::mem::MaybeUninit::<T>::uninit();
+    let info_size = std::mem::size_of::<T>() as libc::c_int;
+
+    // SAFETY: We pass a buffer big enough to hold the structure of type `T`
+    // along with its size. The function returns the number of bytes written
+    // which we verify below.
+    let code = unsafe {
+        libc::proc_pidinfo(pid, flavor, 0, info.as_mut_ptr().cast(), info_size)
+    };
+    if code <= 0 {
+        let error = std::io::Error::last_os_error();
+        // If the process does not exist, `ESRCH` is returned. We translate it
+        // to the more standard "not found" error kind.
+        if error.raw_os_error() == Some(libc::ESRCH) {
+            return Err(std::io::ErrorKind::NotFound.into());
+        }
+        return Err(error);
+    }
+    if code != info_size {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    // SAFETY: The call succeeded and filled the whole structure.
+    Ok(unsafe { info.assume_init() })
+}
+
+/// Returns the path to the executable of the specified process.
+fn path(pid: libc::c_int) -> std::io::Result<std::path::PathBuf> {
+    use std::os::unix::ffi::OsStringExt as _;
+
+    let mut buf = vec![0u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+
+    // SAFETY: We pass a buffer of the maximum size that the function can ever
+    // need. The function returns the length of the path or non-positive value
+    // in case of an error.
+    let len = unsafe {
+        libc::proc_pidpath(pid, buf.as_mut_ptr().cast(), buf.len() as u32)
+    };
+    if len <= 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    buf.truncate(len as usize);
+    Ok(std::ffi::OsString::from_vec(buf).into())
+}
+
+/// Returns command-line arguments of the specified process.
+fn cmdline(pid: libc::c_int) -> std::io::Result<Vec<std::ffi::OsString>> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let mut arg_max = std::mem::MaybeUninit::<libc::c_int>::uninit();
+    let mut arg_max_size = std::mem::size_of::<libc::c_int>();
+    let mut mib = [libc::CTL_KERN, libc::KERN_ARGMAX];
+
+    // SAFETY: We query the maximum size of the arguments buffer which is an
+    // integer value. We check for errors afterwards.
+    let code = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(), mib.len() as libc::c_uint,
+            arg_max.as_mut_ptr().cast(), &mut arg_max_size,
+            std::ptr::null_mut(), 0,
+        )
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the value is initialized.
+    let arg_max = unsafe { arg_max.assume_init() } as usize;
+
+    let mut buf = vec![0u8; arg_max];
+    let mut buf_size = buf.len();
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+
+    // SAFETY: We pass a buffer of the maximum size required to hold arguments
+    // of any process. We check for errors afterwards.
+    let code = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(), mib.len() as libc::c_uint,
+            buf.as_mut_ptr().cast(), &mut buf_size,
+            std::ptr::null_mut(), 0,
+        )
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    buf.truncate(buf_size);
+
+    // The buffer starts with the number of arguments followed by the path of
+    // the executable, a null padding and then the null-terminated arguments.
+    let argc_size = std::mem::size_of::<libc::c_int>();
+    if buf.len() < argc_size {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+    let argc = libc::c_int::from_ne_bytes(buf[..argc_size].try_into().unwrap());
+
+    let mut rest = &buf[argc_size..];
+    let exe_len = rest.iter().position(|byte| *byte == b'\0')
+        .unwrap_or(rest.len());
+    rest = &rest[exe_len..];
+    let padding_len = rest.iter().position(|byte| *byte != b'\0')
+        .unwrap_or(rest.len());
+    rest = &rest[padding_len..];
+
+    let args = rest.split(|byte| *byte == b'\0')
+        .take(usize::try_from(argc).unwrap_or(0))
+        .map(|arg| std::ffi::OsStr::from_bytes(arg).to_os_string())
+        .collect();
+
+    Ok(args)
+}
+
+/// Returns the numerator and denominator of the Mach timebase.
+fn mach_timebase() -> std::io::Result<(u32, u32)> {
+    let mut info = std::mem::MaybeUninit::<crate::libc::mach_timebase_info>::uninit();
+
+    // SAFETY: We pass a valid pointer to the structure to fill. We check for
+    // errors afterwards.
+    let code = unsafe {
+        crate::libc::mach_timebase_info(info.as_mut_ptr())
+    };
+    if code != 0 {
+        return Err(std::io::ErrorKind::Other.into());
+    }
+
+    // SAFETY: The call succeeded, so the structure is initialized.
+    let info = unsafe { info.assume_init() };
+    if info.denom == 0 {
+        return Err(std::io::ErrorKind::InvalidData.into());
+    }
+
+    Ok((info.numer, info.denom))
+}
+
+/// Converts a null-terminated array of C characters to an [`OsString`].
+///
+/// [`OsString`]: std::ffi::OsString
+fn c_chars_to_os_string(chars: &[libc::c_char]) -> std::ffi::OsString {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let bytes = chars.iter()
+        .take_while(|char| **char != 0)
+        .map(|char| *char as u8)
+        .collect::<Vec<_>>();
+
+    std::ffi::OsStr::from_bytes(&bytes).to_os_string()
+}
@@ -0,0 +1,91 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Returns an iterator yielding information about all processes on the system.
+pub fn all() -> std::io::Result<impl Iterator<Item = std::io::Result<crate::proc::Process>>> {
+    let iter = crate::proc::ids()?.filter_map(|pid| {
+        let pid = match pid {
+            Ok(pid) => pid,
+            Err(error) => return Some(Err(error)),
+        };
+
+        match crate::proc::info(pid) {
+            Ok(process) => Some(Ok(process)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+            Err(error) => Some(Err(error)),
+        }
+    });
+
+    Ok(iter)
+}
+
+/// Returns the CPU time consumed so far by the calling thread.
+pub fn thread_cpu_time() -> std::io::Result<std::time::Duration> {
+    let mut timespec = std::mem::MaybeUninit::<libc::timespec>::uninit();
+
+    // SAFETY: We pass a valid clock identifier and a pointer to a buffer of
+    // the right size. We verify the result of the call below.
+    let code = unsafe {
+        libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, timespec.as_mut_ptr())
+    };
+    if code != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the structure is initialized.
+    let timespec = unsafe { timespec.assume_init() };
+
+    // Both values should never be negative for a CPU clock, so conversion
+    // failures indicate a bogus result.
+    let secs = u64::try_from(timespec.tv_sec)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+    let nanos = u32::try_from(timespec.tv_nsec)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+
+    Ok(std::time::Duration::new(secs, nanos))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn thread_cpu_time_monotonic() {
+        let before = thread_cpu_time()
+            .unwrap();
+
+        // We burn some CPU cycles to make sure the clock actually advances.
+        let mut sum = 0u64;
+        for i in 0..10_000_000u64 {
+            sum = std::hint::black_box(sum.wrapping_add(i));
+        }
+        assert!(sum > 0);
+
+        let after = thread_cpu_time()
+            .unwrap();
+
+        assert!(before < after);
+    }
+
+    #[test]
+    fn thread_cpu_time_per_thread() {
+        // A freshly spawned thread that did nothing should have consumed much
+        // less CPU time than the process-wide budget we are going to burn.
+        let mut sum = 0u64;
+        for i in 0..10_000_000u64 {
+            sum = std::hint::black_box(sum.wrapping_add(i));
+        }
+        assert!(sum > 0);
+
+        let this_thread = thread_cpu_time()
+            .unwrap();
+        let other_thread = std::thread::spawn(|| thread_cpu_time().unwrap())
+            .join()
+            .unwrap();
+
+        assert!(other_thread < this_thread);
+    }
+}
@@ -91,3 +91,350 @@ const DEFAULT_PID_BUF_CAP: usize = 1024;
 
 /// The maximum capacity of the process identifiers buffer.
 const MAX_PID_BUF_CAP: usize = 16384;
+
+/// Returns information about the process with the specified identifier.
+pub fn info(pid: u32) -> std::io::Result<crate::proc::Process> {
+    // Information about the parent process and the executable name is not
+    // available through the process handle and we need to obtain it from the
+    // system snapshot. This is also what we require to succeed to consider the
+    // process as existing.
+    let entry = snapshot()?.into_iter()
+        .find(|entry| entry.th32ProcessID == pid)
+        .ok_or(std::io::ErrorKind::NotFound)?;
+
+    Ok(process(entry))
+}
+
+/// Returns an iterator yielding information about all processes on the system.
+pub fn all() -> std::io::Result<impl Iterator<Item = std::io::Result<crate::proc::Process>>> {
+    // A single snapshot has entries of all the processes, so there is no need
+    // to take one per process (as `info` does).
+    let entries = snapshot()?;
+
+    Ok(entries.into_iter().map(|entry| Ok(process(entry))))
+}
+
+/// Returns information about the process described by the snapshot entry.
+fn process(
+    entry: windows_sys::Win32::System::Diagnostics::ToolHelp::PROCESSENTRY32W,
+) -> crate::proc::Process {
+    use std::os::windows::io::AsRawHandle as _;
+
+    let name = {
+        let len = entry.szExeFile.iter().position(|char| *char == 0)
+            .unwrap_or(entry.szExeFile.len());
+        std::os::windows::ffi::OsStringExt::from_wide(&entry.szExeFile[..len])
+    };
+
+    let mut process = crate::proc::Process {
+        pid: entry.th32ProcessID,
+        ppid: Some(entry.th32ParentProcessID).filter(|ppid| *ppid != 0),
+        name: Some(name),
+        exe: None,
+        // TODO: Add support for command-line arguments. This requires reading
+        // the memory of the process environment block.
+        cmdline: Vec::new(),
+        uid: None,
+        gid: None,
+        start_time: None,
+        state: None,
+        rss: None,
+        // Virtual size is not exposed by any of the documented APIs (memory
+        // counters have only the commit charge, which is something else).
+        vsz: None,
+        user_cpu_time: None,
+        system_cpu_time: None,
+    };
+
+    // Opening the process may fail because of insufficient permissions (e.g.
+    // for protected system processes). In such cases we just return the basic
+    // information collected so far.
+    let handle = match open(process.pid) {
+        Ok(handle) => handle,
+        Err(_) => return process,
+    };
+    let handle = handle.as_raw_handle();
+
+    process.exe = exe(handle).ok();
+
+    if let Ok(times) = times(handle) {
+        process.start_time = Some(times.creation_time);
+        process.user_cpu_time = Some(times.user_time);
+        process.system_cpu_time = Some(times.kernel_time);
+    }
+
+    if let Ok(counters) = memory_counters(handle) {
+        process.rss = Some(counters.WorkingSetSize as u64);
+    }
+
+    process
+}
+
+/// Returns the CPU time consumed so far by the calling thread.
+pub fn thread_cpu_time() -> std::io::Result<std::time::Duration> {
+    use windows_sys::Win32::Foundation::FILETIME;
+
+    let mut creation_time = std::mem::MaybeUninit::<FILETIME>::uninit();
+    let mut exit_time = std::mem::MaybeUninit::<FILETIME>::uninit();
+    let mut kernel_time = std::mem::MaybeUninit::<FILETIME>::uninit();
+    let mut user_time = std::mem::MaybeUninit::<FILETIME>::uninit();
+
+    // SAFETY: This is just a call to the unsafe function as described in the
+    // documentation [1]. The pseudo-handle of the current thread is always
+    // valid and we verify the result of the call below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getthreadtimes
+    let status = unsafe {
+        windows_sys::Win32::System::Threading::GetThreadTimes(
+            windows_sys::Win32::System::Threading::GetCurrentThread(),
+            creation_time.as_mut_ptr(),
+            exit_time.as_mut_ptr(),
+            kernel_time.as_mut_ptr(),
+            user_time.as_mut_ptr(),
+        )
+    };
+    if status == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the time structures are initialized.
+    let (kernel_time, user_time) = unsafe {(
+        kernel_time.assume_init(),
+        user_time.assume_init(),
+    )};
+
+    Ok(filetime_to_duration(kernel_time) + filetime_to_duration(user_time))
+}
+
+/// Returns regions of the virtual memory of the specified process.
+pub fn memory_regions(_: u32) -> std::io::Result<Vec<crate::proc::MemoryRegion>> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// A handle to the virtual memory of a process.
+///
+/// Memory handles are not supported on this platform, so this type cannot be
+/// instantiated.
+pub enum Memory {
+}
+
+impl Memory {
+
+    /// Reads the memory of the process at the given address into the buffer.
+    pub fn read_at(&self, _: u64, _: &mut [u8]) -> std::io::Result<usize> {
+        match *self {
+        }
+    }
+}
+
+/// Opens the virtual memory of the specified process for reading.
+pub fn open_memory(_: u32) -> std::io::Result<Memory> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+/// Returns entries of all processes from a system snapshot.
+fn snapshot() -> std::io::Result<Vec<windows_sys::Win32::System::Diagnostics::ToolHelp::PROCESSENTRY32W>> {
+    use std::os::windows::io::{AsRawHandle as _, FromRawHandle as _};
+    use windows_sys::Win32::System::Diagnostics::ToolHelp::*;
+
+    // SAFETY: This is just a call to the unsafe function as described in the
+    // documentation [1]. We verify the result of the call below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-createtoolhelp32snapshot
+    let snapshot = unsafe {
+        CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0)
+    };
+    if snapshot == windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The snapshot handle is valid and we are its sole owner. Wrapping
+    // it ensures that it gets closed once we are done.
+    let snapshot = unsafe {
+        std::os::windows::io::OwnedHandle::from_raw_handle(snapshot)
+    };
+
+    // SAFETY: The structure consists only of integers and arrays of integers,
+    // so zeroed memory is a valid value.
+    let mut entry = unsafe {
+        std::mem::zeroed::<PROCESSENTRY32W>()
+    };
+    entry.dwSize = std::mem::size_of::<PROCESSENTRY32W>() as u32;
+
+    // SAFETY: We pass a valid snapshot handle and properly initialized entry
+    // structure as described in the documentation [1]. We verify the result
+    // of the call below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/tlhelp32/nf-tlhelp32-process32firstw
+    let mut status = unsafe {
+        Process32FirstW(snapshot.as_raw_handle(), &mut entry)
+    };
+
+    let mut entries = Vec::new();
+    while status != 0 {
+        entries.push(entry);
+
+        // SAFETY: Same as with the call to `Process32FirstW` above.
+        status = unsafe {
+            Process32NextW(snapshot.as_raw_handle(), &mut entry)
+        };
+    }
+
+    Ok(entries)
+}
+
+/// Opens the specified process for querying limited information.
+fn open(pid: u32) -> std::io::Result<std::os::windows::io::OwnedHandle> {
+    use std::os::windows::io::FromRawHandle as _;
+    use windows_sys::Win32::System::Threading::*;
+
+    // SAFETY: This is just a call to the unsafe function as described in the
+    // documentation [1]. We verify the result of the call below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-openprocess
+    let handle = unsafe {
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid)
+    };
+    if handle.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The handle is valid and we are its sole owner.
+    Ok(unsafe {
+        std::os::windows::io::OwnedHandle::from_raw_handle(handle)
+    })
+}
+
+/// Returns the path to the executable of the process.
+fn exe(
+    handle: std::os::windows::io::RawHandle,
+) -> std::io::Result<std::path::PathBuf> {
+    use std::os::windows::ffi::OsStringExt as _;
+
+    let mut buf = vec![0u16; MAX_EXE_PATH_LEN];
+    let mut buf_len = buf.len() as u32;
+
+    // SAFETY: This is just a call to the unsafe function as described in the
+    // documentation [1]. We pass the buffer along with its length and verify
+    // the result of the call below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/winbase/nf-winbase-queryfullprocessimagenamew
+    let status = unsafe {
+        windows_sys::Win32::System::Threading::QueryFullProcessImageNameW(
+            handle,
+            windows_sys::Win32::System::Threading::PROCESS_NAME_WIN32,
+            buf.as_mut_ptr(),
+            &mut buf_len,
+        )
+    };
+    if status == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    buf.truncate(buf_len as usize);
+    Ok(std::ffi::OsString::from_wide(&buf).into())
+}
+
+/// Timing information about a process.
+struct Times {
+    /// Time at which the process was created.
+    creation_time: std::time::SystemTime,
+    /// Time the process spent executing in the kernel mode.
+    kernel_time: std::time::Duration,
+    /// Time the process spent executing in the user mode.
+    user_time: std::time::Duration,
+}
+
+/// Returns timing information about the process.
+fn times(handle: std::os::windows::io::RawHandle) -> std::io::Result<Times> {
+    use windows_sys::Win32::Foundation::FILETIME;
+
+    let mut creation_time = std::mem::MaybeUninit::<FILETIME>::uninit();
+    let mut exit_time = std::mem::MaybeUninit::<FILETIME>::uninit();
+    let mut kernel_time = std::mem::MaybeUninit::<FILETIME>::uninit();
+    let mut user_time = std::mem::MaybeUninit::<FILETIME>::uninit();
+
+    // SAFETY: This is just a call to the unsafe function as described in the
+    // documentation [1]. We verify the result of the call below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/processthreadsapi/nf-processthreadsapi-getprocesstimes
+    let status = unsafe {
+        windows_sys::Win32::System::Threading::GetProcessTimes(
+            handle,
+            creation_time.as_mut_ptr(),
+            exit_time.as_mut_ptr(),
+            kernel_time.as_mut_ptr(),
+            user_time.as_mut_ptr(),
+        )
+    };
+    if status == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so all the structures are initialized.
+    let (creation_time, kernel_time, user_time) = unsafe {(
+        creation_time.assume_init(),
+        kernel_time.assume_init(),
+        user_time.assume_init(),
+    )};
+
+    // Creation time is expressed as the number of 100-nanosecond intervals
+    // since January 1, 1601 [1].
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/minwinbase/ns-minwinbase-filetime
+    let creation_time = std::time::UNIX_EPOCH
+        + filetime_to_duration(creation_time)
+        - std::time::Duration::from_secs(WINDOWS_TO_UNIX_EPOCH_SECS);
+
+    Ok(Times {
+        creation_time,
+        kernel_time: filetime_to_duration(kernel_time),
+        user_time: filetime_to_duration(user_time),
+    })
+}
+
+/// Returns memory usage counters of the process.
+fn memory_counters(
+    handle: std::os::windows::io::RawHandle,
+) -> std::io::Result<windows_sys::Win32::System::ProcessStatus::PROCESS_MEMORY_COUNTERS> {
+    use windows_sys::Win32::System::ProcessStatus::*;
+
+    let mut counters = std::mem::MaybeUninit::<PROCESS_MEMORY_COUNTERS>::uninit();
+
+    // SAFETY: This is just a call to the unsafe function as described in the
+    // documentation [1]. We pass the buffer along with its size and verify the
+    // result of the call below.
+    //
+    // [1]: https://learn.microsoft.com/en-us/windows/win32/api/psapi/nf-psapi-getprocessmemoryinfo
+    let status = unsafe {
+        K32GetProcessMemoryInfo(
+            handle,
+            counters.as_mut_ptr(),
+            std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        )
+    };
+    if status == 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // SAFETY: The call succeeded, so the structure is initialized.
+    Ok(unsafe { counters.assume_init() })
+}
+
+/// Converts a [`FILETIME`] value to a duration.
+///
+/// [`FILETIME`]: windows_sys::Win32::Foundation::FILETIME
+fn filetime_to_duration(
+    filetime: windows_sys::Win32::Foundation::FILETIME,
+) -> std::time::Duration {
+    let ticks = u64::from(filetime.dwHighDateTime) << 32
+        | u64::from(filetime.dwLowDateTime);
+
+    std::time::Duration::from_nanos(ticks.saturating_mul(100))
+}
+
+/// The maximum length of the executable path (in UTF-16 units).
+const MAX_EXE_PATH_LEN: usize = 32768;
+
+/// Number of seconds between the Windows epoch (1601) and the Unix epoch.
+const WINDOWS_TO_UNIX_EPOCH_SECS: u64 = 11_644_473_600;
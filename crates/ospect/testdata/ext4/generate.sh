@@ -0,0 +1,67 @@
+#!/usr/bin/env bash
+# Copyright 2024 Google LLC
+#
+# Use of this source code is governed by an MIT-style license that can be found
+# in the LICENSE file or at https://opensource.org/licenses/MIT.
+#
+# Generates ext4 (and ext2) filesystem images used in tests of the `ext4`
+# module. Requires `mke2fs` and `debugfs` from e2fsprogs (1.47 or newer) but
+# does not require root privileges as nothing is mounted.
+#
+# Images are stored gzip-compressed as they consist mostly of zeros.
+
+set -euo pipefail
+
+cd "$(dirname "$0")"
+
+ROOT="$(mktemp -d)"
+trap 'rm -rf "$ROOT"' EXIT
+
+# Fixed timestamps and hash seeds make the images (mostly) reproducible.
+export E2FSPROGS_FAKE_TIME=1700000000
+UUID="4c1f6b2e-1c3a-4d6e-9a5b-7e8f9a0b1c2d"
+
+mkdir -p "$ROOT/dir/subdir" "$ROOT/many"
+printf 'Hello, world!\n' > "$ROOT/hello.txt"
+printf 'foo bar baz\n' > "$ROOT/dir/nested.txt"
+printf 'quux\n' > "$ROOT/dir/subdir/deep.txt"
+printf 'I will be gone soon.\n' > "$ROOT/deleted.txt"
+# A file spanning multiple blocks (and double indirect blocks on ext2). The
+# length of the line is coprime with the block size, so misplaced blocks do
+# not go unnoticed.
+head -c 300000 < <(yes 'abcdefghijklmnop') > "$ROOT/big.txt"
+# A sparse file with some data in the middle.
+truncate -s 1M "$ROOT/sparse.bin"
+printf 'sparse' | dd of="$ROOT/sparse.bin" bs=1 seek=524288 conv=notrunc status=none
+# A file with many small data fragments (so that its extent tree is deeper).
+truncate -s 64K "$ROOT/fragmented.bin"
+for i in $(seq 0 15); do
+    printf 'fragment-%02d' "$i" | dd of="$ROOT/fragmented.bin" bs=1 seek=$((i * 4096)) conv=notrunc status=none
+done
+for i in $(seq 1 200); do
+    printf '%s\n' "$i" > "$ROOT/many/file-$i"
+done
+ln -s hello.txt "$ROOT/link"
+ln -s "$(printf 'x%.0s' $(seq 1 100))/target" "$ROOT/longlink"
+touch -h -d @1700000000 "$ROOT"/* "$ROOT"/*/* "$ROOT"/dir/subdir/*
+
+rm -f basic.img ext2.img basic.img.gz ext2.img.gz
+
+mke2fs -q -t ext4 -b 1024 -I 256 -U "$UUID" -E hash_seed="$UUID" \
+    -L basic -d "$ROOT" basic.img 4M > /dev/null
+# Removing the file leaves a deleted directory entry (and the inode) behind.
+debugfs -w -R "rm /deleted.txt" basic.img > /dev/null 2>&1
+# Writes a single transaction (overwriting block 333) to the journal. The
+# journal is not replayed, so the filesystem itself stays intact.
+printf 'journaled block' > "$ROOT/journaled"
+truncate -s 1024 "$ROOT/journaled"
+debugfs -w -f - basic.img > /dev/null 2>&1 <<SCRIPT
+jo
+jw -b 333 $ROOT/journaled
+jc
+SCRIPT
+
+mke2fs -q -t ext2 -b 1024 -I 128 -U "$UUID" -E hash_seed="$UUID" \
+    -L ext2 -d "$ROOT" ext2.img 2M > /dev/null 2>&1
+
+gzip -9 -n basic.img ext2.img
@@ -11,22 +11,34 @@ const PROTOS: &'static [&'static str] = &[
     "../../proto/rrg/fs.proto",
     "../../proto/rrg/net.proto",
     "../../proto/rrg/os.proto",
+    "../../proto/rrg/process.proto",
     "../../proto/rrg/startup.proto",
     "../../proto/rrg/winreg.proto",
     "../../proto/rrg/action/execute_signed_command.proto",
+    "../../proto/rrg/action/find_files.proto",
     "../../proto/rrg/action/get_file_contents.proto",
     "../../proto/rrg/action/get_file_hash.proto",
     "../../proto/rrg/action/get_file_metadata.proto",
     "../../proto/rrg/action/get_filesystem_timeline.proto",
+    "../../proto/rrg/action/get_memory_contents.proto",
+    "../../proto/rrg/action/get_raw_file_contents.proto",
     "../../proto/rrg/action/get_system_metadata.proto",
     "../../proto/rrg/action/get_winreg_value.proto",
     "../../proto/rrg/action/grep_file_contents.proto",
+    "../../proto/rrg/action/grep_files.proto",
+    "../../proto/rrg/action/grep_memory.proto",
     "../../proto/rrg/action/list_connections.proto",
+    "../../proto/rrg/action/list_directory.proto",
     "../../proto/rrg/action/list_interfaces.proto",
+    "../../proto/rrg/action/list_memory_regions.proto",
     "../../proto/rrg/action/list_mounts.proto",
+    "../../proto/rrg/action/list_processes.proto",
+    "../../proto/rrg/action/list_raw_directory.proto",
+    "../../proto/rrg/action/list_users.proto",
     "../../proto/rrg/action/list_winreg_keys.proto",
     "../../proto/rrg/action/list_winreg_values.proto",
     "../../proto/rrg/action/query_wmi.proto",
+    "../../proto/rrg/action/scan_files.proto",
 ];
 
 fn main() {
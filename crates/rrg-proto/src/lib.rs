@@ -76,6 +76,48 @@ impl From<std::fs::Metadata> for fs::FileMetadata {
     }
 }
 
+impl From<ospect::fs::ext4::FileType> for fs::file_metadata::Type {
+
+    fn from(file_type: ospect::fs::ext4::FileType) -> fs::file_metadata::Type {
+        use ospect::fs::ext4::FileType::*;
+
+        match file_type {
+            Regular => fs::file_metadata::Type::FILE,
+            Dir => fs::file_metadata::Type::DIR,
+            Symlink => fs::file_metadata::Type::SYMLINK,
+            _ => fs::file_metadata::Type::UNKNOWN,
+        }
+    }
+}
+
+impl From<ospect::fs::ext4::Inode> for fs::FileMetadata {
+
+    fn from(inode: ospect::fs::ext4::Inode) -> fs::FileMetadata {
+        // Timestamps stored on the disk can be arbitrary (including ones from
+        // before the epoch which we cannot represent), so we skip such.
+        let into_timestamp = |time: std::time::SystemTime| {
+            time.duration_since(std::time::UNIX_EPOCH).ok()
+                .map(|_| into_timestamp(time))
+        };
+
+        let mut proto = fs::FileMetadata::default();
+        proto.set_type(inode.file_type().into());
+        proto.set_size(inode.size);
+
+        if let Some(time) = into_timestamp(inode.atime) {
+            proto.set_access_time(time);
+        }
+        if let Some(time) = into_timestamp(inode.mtime) {
+            proto.set_modification_time(time);
+        }
+        if let Some(time) = inode.crtime.and_then(into_timestamp) {
+            proto.set_creation_time(time);
+        }
+
+        proto
+    }
+}
+
 impl From<ospect::fs::ExtAttr> for fs::FileExtAttr {
 
     fn from(ext_attr: ospect::fs::ExtAttr) -> fs::FileExtAttr {
@@ -114,6 +156,28 @@ impl From<ospect::fs::Mount> for fs::Mount {
     }
 }
 
+impl From<ospect::proc::MemoryRegion> for process::MemoryRegion {
+
+    fn from(region: ospect::proc::MemoryRegion) -> process::MemoryRegion {
+        let mut proto = process::MemoryRegion::default();
+        proto.set_start(region.start);
+        proto.set_end(region.end);
+        proto.set_readable(region.readable);
+        proto.set_writable(region.writable);
+        proto.set_executable(region.executable);
+        proto.set_shared(region.shared);
+        proto.set_offset(region.offset);
+        if let Some(inode) = region.inode {
+            proto.set_inode(inode);
+        }
+        if let Some(path) = region.path {
+            proto.set_path(path.into());
+        }
+
+        proto
+    }
+}
+
 impl From<std::net::Ipv4Addr> for net::IpAddress {
 
     fn from(addr: std::net::Ipv4Addr) -> net::IpAddress {
@@ -24,6 +24,9 @@ pub mod get_file_metadata;
 #[cfg(feature = "action-get_file_contents")]
 pub mod get_file_contents;
 
+#[cfg(feature = "action-get_file_hash")]
+pub mod get_file_hash;
+
 #[cfg(feature = "action-grep_file_contents")]
 pub mod grep_file_contents;
 
@@ -33,12 +36,21 @@ pub mod get_filesystem_timeline;
 #[cfg(feature = "action-list_connections")]
 pub mod list_connections;
 
+#[cfg(feature = "action-list_directory")]
+pub mod list_directory;
+
 #[cfg(feature = "action-list_interfaces")]
 pub mod list_interfaces;
 
 #[cfg(feature = "action-list_mounts")]
 pub mod list_mounts;
 
+#[cfg(feature = "action-list_processes")]
+pub mod list_processes;
+
+#[cfg(feature = "action-list_users")]
+pub mod list_users;
+
 #[cfg(feature = "action-get_winreg_value")]
 pub mod get_winreg_value;
 
@@ -51,6 +63,33 @@ pub mod list_winreg_keys;
 #[cfg(feature = "action-query_wmi")]
 pub mod query_wmi;
 
+#[cfg(feature = "action-execute_signed_command")]
+pub mod execute_signed_command;
+
+#[cfg(feature = "action-grep_files")]
+pub mod grep_files;
+
+#[cfg(feature = "action-scan_files")]
+pub mod scan_files;
+
+#[cfg(feature = "action-list_memory_regions")]
+pub mod list_memory_regions;
+
+#[cfg(feature = "action-get_memory_contents")]
+pub mod get_memory_contents;
+
+#[cfg(feature = "action-grep_memory")]
+pub mod grep_memory;
+
+#[cfg(feature = "action-list_raw_directory")]
+pub mod list_raw_directory;
+
+#[cfg(feature = "action-get_raw_file_contents")]
+pub mod get_raw_file_contents;
+
+#[cfg(feature = "action-find_files")]
+pub mod find_files;
+
 use log::info;
 
 /// Dispatches the given `request` to an appropriate action handler.
@@ -89,6 +128,10 @@ where
         GetFileContents => {
             handle(session, request, self::get_file_contents::handle)
         }
+        #[cfg(feature = "action-get_file_hash")]
+        GetFileHash => {
+            handle(session, request, self::get_file_hash::handle)
+        }
         #[cfg(feature = "action-grep_file_contents")]
         GrepFileContents => {
             handle(session, request, self::grep_file_contents::handle)
@@ -101,6 +144,10 @@ where
         ListConnections => {
             handle(session, request, self::list_connections::handle)
         }
+        #[cfg(feature = "action-list_directory")]
+        ListDirectory => {
+            handle(session, request, self::list_directory::handle)
+        }
         #[cfg(feature = "action-list_interfaces")]
         ListInterfaces => {
             handle(session, request, self::list_interfaces::handle)
@@ -109,6 +156,14 @@ where
         ListMounts => {
             handle(session, request, self::list_mounts::handle)
         }
+        #[cfg(feature = "action-list_processes")]
+        ListProcesses => {
+            handle(session, request, self::list_processes::handle)
+        }
+        #[cfg(feature = "action-list_users")]
+        ListUsers => {
+            handle(session, request, self::list_users::handle)
+        }
         #[cfg(feature = "action-get_winreg_value")]
         GetWinregValue => {
             handle(session, request, self::get_winreg_value::handle)
@@ -125,6 +180,42 @@ where
         QueryWmi => {
             handle(session, request, self::query_wmi::handle)
         }
+        #[cfg(feature = "action-execute_signed_command")]
+        ExecuteSignedCommand => {
+            handle(session, request, self::execute_signed_command::handle)
+        }
+        #[cfg(feature = "action-grep_files")]
+        GrepFiles => {
+            handle(session, request, self::grep_files::handle)
+        }
+        #[cfg(feature = "action-scan_files")]
+        ScanFiles => {
+            handle(session, request, self::scan_files::handle)
+        }
+        #[cfg(feature = "action-list_memory_regions")]
+        ListMemoryRegions => {
+            handle(session, request, self::list_memory_regions::handle)
+        }
+        #[cfg(feature = "action-get_memory_contents")]
+        GetMemoryContents => {
+            handle(session, request, self::get_memory_contents::handle)
+        }
+        #[cfg(feature = "action-grep_memory")]
+        GrepMemory => {
+            handle(session, request, self::grep_memory::handle)
+        }
+        #[cfg(feature = "action-list_raw_directory")]
+        ListRawDirectory => {
+            handle(session, request, self::list_raw_directory::handle)
+        }
+        #[cfg(feature = "action-get_raw_file_contents")]
+        GetRawFileContents => {
+            handle(session, request, self::get_raw_file_contents::handle)
+        }
+        #[cfg(feature = "action-find_files")]
+        FindFiles => {
+            handle(session, request, self::find_files::handle)
+        }
         // We allow `unreachable_patterns` because otherwise we get a warning if
         // we compile with all the actions enabled.
         #[allow(unreachable_patterns)]
@@ -0,0 +1,727 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+use std::path::PathBuf;
+
+/// Limit on the size of standard output and standard error sent back.
+///
+/// Both of them have to fit (together with the rest of the result) within the
+/// size limit of a single Fleetspeak message.
+const MAX_OUTPUT_LEN: usize = 512 * 1024; // 512 KiB.
+
+/// Interval at which we check whether the command has finished.
+const WAIT_POLL_INTERVAL: std::time::Duration = {
+    std::time::Duration::from_millis(50)
+};
+
+/// Arguments of the `execute_signed_command` action.
+pub struct Args {
+    /// Serialized command exactly as it was signed.
+    raw_command: Vec<u8>,
+    /// Command to execute.
+    command: Command,
+    /// Signature of the serialized command.
+    signature: Option<ed25519_dalek::Signature>,
+    /// Standard input to pass to the command.
+    stdin: Vec<u8>,
+    /// Time after which the command execution is aborted.
+    timeout: std::time::Duration,
+}
+
+/// Command to execute (as specified in the signed message).
+struct Command {
+    /// Path to the executable file to execute.
+    path: PathBuf,
+    /// Arguments to pass to the command.
+    args: Vec<String>,
+    /// Environment in which to invoke the command.
+    env: std::collections::HashMap<String, String>,
+}
+
+/// Result of the `execute_signed_command` action.
+struct Item {
+    /// Exit status of the command subprocess.
+    exit_status: std::process::ExitStatus,
+    /// Standard output of the command (possibly truncated).
+    stdout: Vec<u8>,
+    /// Whether the standard output was truncated.
+    stdout_truncated: bool,
+    /// Standard error of the command (possibly truncated).
+    stderr: Vec<u8>,
+    /// Whether the standard error was truncated.
+    stderr_truncated: bool,
+}
+
+/// Handles invocations of the `execute_signed_command` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::Write as _;
+
+    let verifying_key = session.args().command_verification_key
+        .ok_or(crate::session::Error::action(MissingVerificationKeyError))?;
+
+    let signature = args.signature
+        .ok_or(crate::session::Error::invalid_command_signature(MissingSignatureError))?;
+
+    verifying_key.verify_strict(&args.raw_command, &signature)
+        .map_err(crate::session::Error::invalid_command_signature)?;
+
+    let mut command = std::process::Command::new(&args.command.path);
+    command
+        .args(&args.command.args)
+        .env_clear()
+        .envs(&args.command.env)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+
+    // The command gets its own process group, so that on timeout we can kill
+    // all processes it spawned (that might hold its output pipes open).
+    #[cfg(target_family = "unix")]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let mut child = command.spawn()
+        .map_err(crate::session::Error::action)?;
+
+    // We need to feed the standard input and consume the standard output and
+    // error concurrently. Otherwise, the command might block on a full pipe
+    // and we would wait for it forever.
+    let mut child_stdin = child.stdin.take()
+        .expect("no stdin pipe");
+    let stdin = args.stdin;
+    let stdin_thread = std::thread::spawn(move || {
+        // The command is allowed not to consume its whole input, so failures
+        // (e.g. broken pipe) here are not errors.
+        if let Err(error) = child_stdin.write_all(&stdin) {
+            log::warn!("failed to write command standard input: {error}");
+        }
+    });
+
+    let child_stdout = child.stdout.take()
+        .expect("no stdout pipe");
+    let stdout_thread = std::thread::spawn(move || {
+        read_truncated(child_stdout, MAX_OUTPUT_LEN)
+    });
+
+    let child_stderr = child.stderr.take()
+        .expect("no stderr pipe");
+    let stderr_thread = std::thread::spawn(move || {
+        read_truncated(child_stderr, MAX_OUTPUT_LEN)
+    });
+
+    let wait_start = std::time::Instant::now();
+    let exit_status = loop {
+        match child.try_wait() {
+            Ok(Some(exit_status)) => break exit_status,
+            Ok(None) => (),
+            Err(error) => return Err(crate::session::Error::action(error)),
+        }
+
+        let wait_time = wait_start.elapsed();
+        if wait_time >= args.timeout {
+            if let Err(error) = kill(&mut child) {
+                log::error!("failed to kill timed out command: {error}");
+            }
+            if let Err(error) = child.wait() {
+                log::error!("failed to wait for killed command: {error}");
+            }
+
+            // Once the command is dead, its pipes are closed and the threads
+            // finish promptly. We do not care about their results anymore but
+            // we do not want to leave them running in the background.
+            //
+            // Note that on Windows we kill only the command process itself, so
+            // if it spawned processes that outlive it, we wait for them too.
+            let _ = stdin_thread.join();
+            let _ = stdout_thread.join();
+            let _ = stderr_thread.join();
+
+            return Err(crate::session::Error::action(TimeoutError {
+                timeout: args.timeout,
+            }));
+        }
+
+        session.heartbeat();
+        std::thread::sleep(std::cmp::min(args.timeout - wait_time, WAIT_POLL_INTERVAL));
+    };
+
+    // The threads should never panic but if they do, there is not much we can
+    // do other than to propagate the panic.
+    stdin_thread.join()
+        .expect("stdin thread panicked");
+    let (stdout, stdout_truncated) = stdout_thread.join()
+        .expect("stdout thread panicked")
+        .map_err(crate::session::Error::action)?;
+    let (stderr, stderr_truncated) = stderr_thread.join()
+        .expect("stderr thread panicked")
+        .map_err(crate::session::Error::action)?;
+
+    session.reply(Item {
+        exit_status,
+        stdout,
+        stdout_truncated,
+        stderr,
+        stderr_truncated,
+    })?;
+
+    Ok(())
+}
+
+/// Kills the command process (and on Unix all processes in its group).
+fn kill(child: &mut std::process::Child) -> std::io::Result<()> {
+    #[cfg(target_family = "unix")]
+    {
+        let pgid = libc::pid_t::try_from(child.id())
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+
+        // SAFETY: This is just a call to the unsafe function as described in
+        // the documentation [1]. The command was spawned as a leader of its own
+        // process group, so the group identifier is the same as its pid.
+        //
+        // [1]: https://man7.org/linux/man-pages/man2/kill.2.html
+        let code = unsafe {
+            libc::kill(-pgid, libc::SIGKILL)
+        };
+        if code != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    {
+        child.kill()
+    }
+}
+
+/// Reads the whole stream but keeps only up to `max_len` first bytes of it.
+///
+/// The returned flag specifies whether the output was truncated. Note that the
+/// stream is always read until the very end to avoid blocking the writer.
+fn read_truncated<R>(mut stream: R, max_len: usize) -> std::io::Result<(Vec<u8>, bool)>
+where
+    R: std::io::Read,
+{
+    use std::io::Read as _;
+
+    let mut buf = Vec::new();
+    (&mut stream).take(max_len as u64).read_to_end(&mut buf)?;
+
+    let len_rest = std::io::copy(&mut stream, &mut std::io::sink())?;
+
+    Ok((buf, len_rest > 0))
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::execute_signed_command::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+        use protobuf::Message as _;
+        use rrg_proto::execute_signed_command::signed_command::Stdin;
+
+        let raw_command = proto.take_command();
+
+        let mut command_proto = rrg_proto::execute_signed_command::SignedCommand::parse_from_bytes(&raw_command)
+            .map_err(|error| ParseArgsError::invalid_field("command", error))?;
+
+        let path = PathBuf::try_from(command_proto.take_path())
+            .map_err(|error| ParseArgsError::invalid_field("command", error))?;
+
+        let signature = match proto.command_ed25519_signature() {
+            [] => None,
+            signature => Some({
+                ed25519_dalek::Signature::from_slice(signature)
+                    .map_err(|error| {
+                        ParseArgsError::invalid_field("command_ed25519_signature", error)
+                    })?
+            }),
+        };
+
+        let unsigned_stdin = proto.take_unsigned_stdin();
+        let stdin = match command_proto.stdin.take() {
+            Some(Stdin::SignedStdin(signed_stdin)) if unsigned_stdin.is_empty() => {
+                signed_stdin
+            }
+            Some(Stdin::UnsignedStdin(true)) => unsigned_stdin,
+            _ if unsigned_stdin.is_empty() => Vec::new(),
+            _ => {
+                return Err(ParseArgsError::invalid_field("unsigned_stdin", {
+                    UnsignedStdinNotAllowedError
+                }));
+            }
+        };
+
+        let timeout = if proto.has_timeout() {
+            rrg_proto::try_from_duration(proto.take_timeout())
+                .map_err(|error| ParseArgsError::invalid_field("timeout", error))?
+        } else {
+            std::time::Duration::ZERO
+        };
+
+        Ok(Args {
+            raw_command,
+            command: Command {
+                path,
+                args: command_proto.take_args(),
+                env: command_proto.take_env(),
+            },
+            signature,
+            stdin,
+            timeout,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::execute_signed_command::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::execute_signed_command::Result::default();
+
+        if let Some(exit_code) = self.exit_status.code() {
+            proto.set_exit_code(exit_code);
+        }
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::process::ExitStatusExt as _;
+            if let Some(exit_signal) = self.exit_status.signal() {
+                proto.set_exit_signal(exit_signal);
+            }
+        }
+
+        proto.set_stdout(self.stdout);
+        proto.set_stdout_truncated(self.stdout_truncated);
+        proto.set_stderr(self.stderr);
+        proto.set_stderr_truncated(self.stderr_truncated);
+
+        proto
+    }
+}
+
+/// An error indicating that no command verification key was configured.
+#[derive(Debug)]
+struct MissingVerificationKeyError;
+
+impl std::fmt::Display for MissingVerificationKeyError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "no command verification key configured")
+    }
+}
+
+impl std::error::Error for MissingVerificationKeyError {
+}
+
+/// An error indicating that the command to execute was not signed.
+#[derive(Debug)]
+struct MissingSignatureError;
+
+impl std::fmt::Display for MissingSignatureError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "missing command signature")
+    }
+}
+
+impl std::error::Error for MissingSignatureError {
+}
+
+/// An error indicating that unsigned standard input is not allowed.
+#[derive(Debug)]
+struct UnsignedStdinNotAllowedError;
+
+impl std::fmt::Display for UnsignedStdinNotAllowedError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "signed command does not allow unsigned standard input")
+    }
+}
+
+impl std::error::Error for UnsignedStdinNotAllowedError {
+}
+
+/// An error indicating that the command did not finish before the timeout.
+#[derive(Debug)]
+struct TimeoutError {
+    /// Time after which the command execution was aborted.
+    timeout: std::time::Duration,
+}
+
+impl std::fmt::Display for TimeoutError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "command execution timed out after {}",
+            humantime::format_duration(self.timeout)
+        }
+    }
+}
+
+impl std::error::Error for TimeoutError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use crate::request::Args as _;
+
+    /// Signing key used for signing commands in tests.
+    fn signing_key() -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::from_bytes(&[0x42; 32])
+    }
+
+    /// Creates a session that verifies commands with the test key.
+    fn session() -> crate::session::FakeSession {
+        session_with_key(signing_key().verifying_key())
+    }
+
+    /// Creates a session that verifies commands with the given key.
+    fn session_with_key(
+        key: ed25519_dalek::VerifyingKey,
+    ) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            heartbeat_rate: std::time::Duration::from_secs(0),
+            verbosity: log::LevelFilter::Debug,
+            log_to_stdout: false,
+            log_to_file: None,
+            offline_input: None,
+            offline_output_file: None,
+            offline_output_dir: None,
+            command_verification_key: Some(key),
+            #[cfg(feature = "action-get_filesystem_timeline")]
+            timeline_snapshot_dir: None,
+        })
+    }
+
+    /// Creates signed action arguments for the given command.
+    fn signed_args(
+        command: rrg_proto::execute_signed_command::SignedCommand,
+        timeout: std::time::Duration,
+    ) -> rrg_proto::execute_signed_command::Args {
+        use ed25519_dalek::Signer as _;
+        use protobuf::Message as _;
+
+        let raw_command = command.write_to_bytes()
+            .unwrap();
+
+        let mut proto = rrg_proto::execute_signed_command::Args::default();
+        proto.set_command_ed25519_signature({
+            signing_key().sign(&raw_command).to_vec()
+        });
+        proto.set_command(raw_command);
+        proto.set_timeout(timeout.into());
+
+        proto
+    }
+
+    /// Creates a command executing the given shell script.
+    fn sh_command(script: &str) -> rrg_proto::execute_signed_command::SignedCommand {
+        let mut command = rrg_proto::execute_signed_command::SignedCommand::default();
+        command.set_path(PathBuf::from("/bin/sh").into());
+        command.mut_args().push(String::from("-c"));
+        command.mut_args().push(String::from(script));
+
+        command
+    }
+
+    const TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_stdout() {
+        let args = Args::from_proto(signed_args(sh_command("echo hello"), TIMEOUT))
+            .unwrap();
+
+        let mut session = session();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert!(item.exit_status.success());
+        assert_eq!(item.stdout, b"hello\n");
+        assert!(!item.stdout_truncated);
+        assert!(item.stderr.is_empty());
+        assert!(!item.stderr_truncated);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_stderr() {
+        let args = Args::from_proto(signed_args(sh_command("echo hello >&2"), TIMEOUT))
+            .unwrap();
+
+        let mut session = session();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert!(item.stdout.is_empty());
+        assert_eq!(item.stderr, b"hello\n");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_exit_code() {
+        let args = Args::from_proto(signed_args(sh_command("exit 42"), TIMEOUT))
+            .unwrap();
+
+        let mut session = session();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.exit_status.code(), Some(42));
+
+        let proto = crate::response::Item::into_proto(Item {
+            exit_status: item.exit_status,
+            stdout: Vec::new(),
+            stdout_truncated: false,
+            stderr: Vec::new(),
+            stderr_truncated: false,
+        });
+        assert_eq!(proto.exit_code(), 42);
+        assert_eq!(proto.exit_signal(), 0);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_exit_signal() {
+        let args = Args::from_proto(signed_args(sh_command("kill -9 $$"), TIMEOUT))
+            .unwrap();
+
+        let mut session = session();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+
+        use std::os::unix::process::ExitStatusExt as _;
+        assert_eq!(item.exit_status.signal(), Some(libc::SIGKILL));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_env() {
+        let mut command = sh_command("echo $FOO; echo $HOME");
+        command.mut_env().insert(String::from("FOO"), String::from("bar"));
+
+        let args = Args::from_proto(signed_args(command, TIMEOUT))
+            .unwrap();
+
+        let mut session = session();
+        handle(&mut session, args)
+            .unwrap();
+
+        // Environment should not be inherited, so `$HOME` should be empty.
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.stdout, b"bar\n\n");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_signed_stdin() {
+        let mut command = sh_command("cat");
+        command.set_signed_stdin(b"hello".to_vec());
+
+        let args = Args::from_proto(signed_args(command, TIMEOUT))
+            .unwrap();
+
+        let mut session = session();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.stdout, b"hello");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_unsigned_stdin() {
+        let mut command = sh_command("cat");
+        command.set_unsigned_stdin(true);
+
+        let mut proto = signed_args(command, TIMEOUT);
+        proto.set_unsigned_stdin(b"hello".to_vec());
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+
+        let mut session = session();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.stdout, b"hello");
+    }
+
+    #[test]
+    fn from_proto_unsigned_stdin_not_allowed() {
+        let mut proto = signed_args(sh_command("cat"), TIMEOUT);
+        proto.set_unsigned_stdin(b"hello".to_vec());
+
+        let error = Args::from_proto(proto).err().unwrap();
+        assert_eq! {
+            error.kind(),
+            crate::request::ParseArgsErrorKind::InvalidField("unsigned_stdin")
+        };
+    }
+
+    #[test]
+    fn from_proto_unsigned_stdin_with_signed_stdin() {
+        let mut command = sh_command("cat");
+        command.set_signed_stdin(b"hello".to_vec());
+
+        let mut proto = signed_args(command, TIMEOUT);
+        proto.set_unsigned_stdin(b"world".to_vec());
+
+        let error = Args::from_proto(proto).err().unwrap();
+        assert_eq! {
+            error.kind(),
+            crate::request::ParseArgsErrorKind::InvalidField("unsigned_stdin")
+        };
+    }
+
+    #[test]
+    fn from_proto_malformed_signature() {
+        let mut proto = signed_args(sh_command("true"), TIMEOUT);
+        proto.set_command_ed25519_signature(b"foo".to_vec());
+
+        let error = Args::from_proto(proto).err().unwrap();
+        assert_eq! {
+            error.kind(),
+            crate::request::ParseArgsErrorKind::InvalidField("command_ed25519_signature")
+        };
+    }
+
+    #[test]
+    fn handle_missing_signature() {
+        let mut proto = signed_args(sh_command("true"), TIMEOUT);
+        proto.clear_command_ed25519_signature();
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+
+        let mut session = session();
+        let error = handle(&mut session, args).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::InvalidCommandSignature);
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_invalid_signature() {
+        let mut proto = signed_args(sh_command("true"), TIMEOUT);
+
+        // We replace the signed command with a different one, keeping the
+        // signature of the original.
+        proto.set_command({
+            use protobuf::Message as _;
+            sh_command("false").write_to_bytes().unwrap()
+        });
+
+        let args = Args::from_proto(proto)
+            .unwrap();
+
+        let mut session = session();
+        let error = handle(&mut session, args).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::InvalidCommandSignature);
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_different_key() {
+        let args = Args::from_proto(signed_args(sh_command("true"), TIMEOUT))
+            .unwrap();
+
+        let mut session = session_with_key({
+            ed25519_dalek::SigningKey::from_bytes(&[0x13; 32]).verifying_key()
+        });
+        let error = handle(&mut session, args).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::InvalidCommandSignature);
+    }
+
+    #[test]
+    fn handle_no_verification_key() {
+        let args = Args::from_proto(signed_args(sh_command("true"), TIMEOUT))
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        let error = handle(&mut session, args).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::ActionFailure);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_timeout() {
+        let timeout = std::time::Duration::from_millis(100);
+        let args = Args::from_proto(signed_args(sh_command("sleep 60"), timeout))
+            .unwrap();
+
+        let start = std::time::Instant::now();
+
+        let mut session = session();
+        let error = handle(&mut session, args).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::ActionFailure);
+        assert_eq!(session.reply_count(), 0);
+
+        // The shell can run `sleep` as a subprocess that would keep the output
+        // pipes open, we verify that we did not wait for it to finish.
+        assert!(start.elapsed() < std::time::Duration::from_secs(30));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_truncated() {
+        let script = format!("head -c {} /dev/zero", MAX_OUTPUT_LEN + 1337);
+        let args = Args::from_proto(signed_args(sh_command(&script), TIMEOUT))
+            .unwrap();
+
+        let mut session = session();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.stdout.len(), MAX_OUTPUT_LEN);
+        assert!(item.stdout_truncated);
+        assert!(!item.stderr_truncated);
+    }
+
+    #[test]
+    fn read_truncated_short() {
+        let (buf, truncated) = read_truncated(&b"foo"[..], 5)
+            .unwrap();
+
+        assert_eq!(buf, b"foo");
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn read_truncated_exact() {
+        let (buf, truncated) = read_truncated(&b"foo"[..], 3)
+            .unwrap();
+
+        assert_eq!(buf, b"foo");
+        assert!(!truncated);
+    }
+
+    #[test]
+    fn read_truncated_long() {
+        let (buf, truncated) = read_truncated(&b"foobar"[..], 3)
+            .unwrap();
+
+        assert_eq!(buf, b"foo");
+        assert!(truncated);
+    }
+}
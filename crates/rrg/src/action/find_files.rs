@@ -0,0 +1,1709 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+mod glob;
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Limit on the size of individual file part blob sent to the blob sink.
+const MAX_BLOB_LEN: usize = 2 * 1024 * 1024; // 2 MiB.
+
+/// Default limit on the number of matches of a contents condition per file.
+const DEFAULT_MAX_MATCHES: u64 = 1;
+
+/// Arguments of the `find_files` action.
+pub struct Args {
+    /// Patterns that paths of the files to find have to match (any of).
+    ///
+    /// Patterns are interpolated only once the action runs, as gathering facts
+    /// about the system requires reading system files.
+    templates: Vec<crate::interpolate::Template>,
+    /// Limit on the number of nested folders matched by `**` wildcards.
+    recursion_depth: u32,
+    /// Whether to follow symlinks when expanding wildcards.
+    follow_symlinks: bool,
+    /// Conditions that the files have to satisfy to be reported.
+    conditions: Vec<Condition>,
+    /// Action to perform on every file found.
+    action: FileAction,
+    /// Whether to collect MD5 digest of the file contents.
+    md5: bool,
+    /// Whether to collect SHA-1 digest of the file contents.
+    sha1: bool,
+    /// Whether to collect SHA-256 digest of the file contents.
+    sha256: bool,
+    /// Limit on the size of files to hash or download.
+    max_file_size: Option<u64>,
+    /// Limit on the number of matches of every contents condition per file.
+    max_matches: u64,
+    /// Whether to compress blobs sent to the blob sink.
+    compress_blobs: bool,
+}
+
+/// Path pattern (after interpolation) of the files to find.
+struct Pattern {
+    /// Interpolation of knowledge-base variables that produced the pattern.
+    interpolation: crate::interpolate::Interpolation,
+    /// Globs that the pattern expands to.
+    globs: Vec<glob::Glob>,
+}
+
+/// Condition that files have to satisfy to be reported.
+enum Condition {
+    /// Size of the file has to be within the range.
+    Size(std::ops::RangeInclusive<u64>),
+    /// Time of the last modification of the file has to be within the range.
+    ModificationTime(TimeRange),
+    /// Time of the last access to the file has to be within the range.
+    AccessTime(TimeRange),
+    /// Time of the last status change of the file has to be within the range.
+    InodeChangeTime(TimeRange),
+    /// Mode bits of the file under the mask have to be equal to the bits.
+    Mode {
+        mask: u32,
+        bits: u32,
+    },
+    /// Contents of the file have to contain a match of the regex.
+    Contents(regex::bytes::Regex),
+}
+
+/// Range of time (with inclusive bounds) a timestamp of a file has to be in.
+struct TimeRange {
+    /// Earliest time the timestamp can be at (if any).
+    min: Option<SystemTime>,
+    /// Latest time the timestamp can be at (if any).
+    max: Option<SystemTime>,
+}
+
+/// Action to perform on files found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileAction {
+    /// Report metadata of the files.
+    Stat,
+    /// Report metadata and digests of the files.
+    Hash,
+    /// Report metadata and digests of the files and send their contents.
+    Download,
+}
+
+/// Result of the `find_files` action.
+pub struct Item {
+    /// Path of the file found.
+    path: PathBuf,
+    /// Metadata of the file found.
+    metadata: std::fs::Metadata,
+    /// Occurrences of patterns of the contents conditions within the file.
+    matches: Vec<Match>,
+    /// Digest of the file contents.
+    #[cfg(any(
+        feature = "action-get_file_metadata-md5",
+        feature = "action-get_file_metadata-sha1",
+        feature = "action-get_file_metadata-sha256",
+    ))]
+    digest: crate::action::get_file_metadata::Digest,
+    /// Parts of the file contents sent to the blob sink.
+    parts: Vec<Part>,
+    /// Interpolation of the path pattern that the file matched.
+    interpolation: crate::interpolate::Interpolation,
+}
+
+/// Occurrence of a pattern of a contents condition within a file.
+struct Match {
+    /// Index of the condition the pattern of which was found.
+    condition_index: usize,
+    /// Byte offset within the file at which the match starts.
+    offset: u64,
+    /// Bytes that matched the pattern.
+    content: Vec<u8>,
+}
+
+/// Part of the file contents sent to the blob sink.
+struct Part {
+    /// Byte offset of the file part.
+    offset: u64,
+    /// Number of bytes of the file part.
+    len: u64,
+    /// SHA-256 digest of the file part sent to the blob sink.
+    blob_sha256: [u8; 32],
+}
+
+/// Handles invocations of the `find_files` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    if args.action != FileAction::Stat {
+        if args.md5 && !cfg!(feature = "action-get_file_metadata-md5") {
+            log::warn!("MD5 digest requested but not supported");
+        }
+        if args.sha1 && !cfg!(feature = "action-get_file_metadata-sha1") {
+            log::warn!("SHA-1 digest requested but not supported");
+        }
+        if args.sha256 && !cfg!(feature = "action-get_file_metadata-sha256") {
+            log::warn!("SHA-256 digest requested but not supported");
+        }
+    }
+
+    let patterns = patterns(&args)?;
+
+    // The same file can match more than one glob (e.g. if they overlap) but we
+    // want to report it only once.
+    let mut paths_found = std::collections::HashSet::new();
+
+    for pattern in &patterns {
+        for glob in &pattern.globs {
+            for entry in entries(glob, args.follow_symlinks) {
+                // Walks can take a long time without finding anything to
+                // report, so we need to let Fleetspeak know that we are still
+                // alive and stop once we run out of time.
+                session.heartbeat();
+                session.check_time_limits()?;
+
+                if !glob.is_match(&entry.path) {
+                    continue;
+                }
+                if !paths_found.insert(entry.path.clone()) {
+                    continue;
+                }
+
+                find(session, &args, &pattern.interpolation, entry)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Interpolates path templates of the given arguments into patterns.
+fn patterns(args: &Args) -> crate::session::Result<Vec<Pattern>> {
+    // Gathering facts about the system (listing users in particular) is not
+    // free, so we do it only if there are any variables to interpolate.
+    let facts = if args.templates.iter().any(|template| template.has_vars()) {
+        crate::interpolate::Facts::collect()
+    } else {
+        crate::interpolate::Facts::default()
+    };
+
+    let mut patterns = Vec::new();
+    for template in &args.templates {
+        // Values of variables are matched literally, as otherwise paths with
+        // wildcard characters (e.g. `[` in home folders) would turn into
+        // different (or malformed) globs.
+        let interpolations = template.interpolate(&facts, glob::escape)
+            .map_err(crate::session::Error::action)?;
+
+        for interpolation in interpolations {
+            let globs = glob::parse(&interpolation.path, args.recursion_depth)
+                .map_err(crate::session::Error::action)?;
+
+            patterns.push(Pattern {
+                interpolation,
+                globs,
+            });
+        }
+    }
+
+    Ok(patterns)
+}
+
+/// Returns all filesystem entries visited when expanding the given glob.
+///
+/// Entries are not checked against the glob, only the walk is limited to the
+/// subtrees where matches can be found.
+fn entries(
+    glob: &glob::Glob,
+    follow_symlinks: bool,
+) -> Box<dyn Iterator<Item = crate::fs::Entry> + '_> {
+    if glob.max_depth() == 0 {
+        let metadata = if follow_symlinks {
+            glob.root().metadata()
+        } else {
+            glob.root().symlink_metadata()
+        };
+
+        return match metadata {
+            Ok(metadata) => {
+                Box::new(std::iter::once(crate::fs::Entry {
+                    path: glob.root().to_path_buf(),
+                    metadata,
+                    symlink_chain: Vec::new(),
+                }))
+            }
+            Err(error) => {
+                if error.kind() != std::io::ErrorKind::NotFound {
+                    log::error! {
+                        "failed to collect metadata of '{}': {error}",
+                        glob.root().display()
+                    };
+                }
+                Box::new(std::iter::empty())
+            }
+        };
+    }
+
+    let mut entries = match crate::fs::walk_dir(glob.root()) {
+        Ok(entries) => {
+            // Components after the first wildcard do not affect the root, so
+            // without this we would walk over everything below it.
+            let prefix_glob = glob.clone();
+            entries
+                .with_max_depth(glob.max_depth())
+                .with_descend_filter(move |path| prefix_glob.is_prefix_match(path))
+        }
+        Err(error) => {
+            if error.kind() != std::io::ErrorKind::NotFound {
+                log::error! {
+                    "failed to list '{}': {error}",
+                    glob.root().display()
+                };
+            }
+            return Box::new(std::iter::empty());
+        }
+    };
+    if follow_symlinks {
+        entries = entries.with_follow_symlinks();
+    }
+
+    Box::new(entries.filter_map(|entry| match entry {
+        Ok(entry) => Some(entry),
+        Err(error) => {
+            log::error!("failed to read directory entry: {error}");
+            None
+        }
+    }))
+}
+
+/// Checks the conditions against the given entry and performs the action.
+fn find<S>(
+    session: &mut S,
+    args: &Args,
+    interpolation: &crate::interpolate::Interpolation,
+    entry: crate::fs::Entry,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    // We check conditions on metadata first as they are cheap, contents of the
+    // file are searched only if there is a chance for it to be reported.
+    let is_satisfied = args.conditions.iter()
+        .all(|condition| condition.is_satisfied_by(&entry.metadata));
+    if !is_satisfied {
+        return Ok(());
+    }
+
+    let regexes = args.conditions.iter()
+        .enumerate()
+        .filter_map(|(condition_index, condition)| match condition {
+            Condition::Contents(regex) => Some((condition_index, regex)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let matches = if regexes.is_empty() {
+        Vec::new()
+    } else {
+        match search(session, &entry, &regexes, args.max_matches)? {
+            Some(matches) => matches,
+            None => return Ok(()),
+        }
+    };
+
+    // Non-regular files (like devices or named pipes) can block or yield
+    // infinite content, so we never collect them.
+    let is_collectable = entry.metadata.is_file() && match args.max_file_size {
+        Some(max_file_size) => entry.metadata.len() <= max_file_size,
+        None => true,
+    };
+
+    #[cfg(any(
+        feature = "action-get_file_metadata-md5",
+        feature = "action-get_file_metadata-sha1",
+        feature = "action-get_file_metadata-sha256",
+    ))]
+    let digest = match args.action {
+        FileAction::Hash | FileAction::Download if is_collectable => {
+            crate::action::get_file_metadata::digest(
+                &entry.path,
+                args.md5,
+                args.sha1,
+                args.sha256,
+            )
+        }
+        _ => crate::action::get_file_metadata::Digest::default(),
+    };
+
+    let parts = match args.action {
+        FileAction::Download if is_collectable => {
+            download(session, &entry.path, args.max_file_size, args.compress_blobs)?
+        }
+        _ => Vec::new(),
+    };
+
+    session.reply(Item {
+        path: entry.path,
+        metadata: entry.metadata,
+        matches,
+        #[cfg(any(
+            feature = "action-get_file_metadata-md5",
+            feature = "action-get_file_metadata-sha1",
+            feature = "action-get_file_metadata-sha256",
+        ))]
+        digest,
+        parts,
+        interpolation: interpolation.clone(),
+    })?;
+
+    Ok(())
+}
+
+/// Searches contents of the given entry for matches of the given regexes.
+///
+/// Regexes are given along with indices of the conditions they come from. All
+/// of them are searched for in a single pass over the file, which stops once
+/// every regex is matched `max_matches` times.
+///
+/// `None` is returned if any of the regexes is not found.
+fn search<S>(
+    session: &mut S,
+    entry: &crate::fs::Entry,
+    regexes: &[(usize, &regex::bytes::Regex)],
+    max_matches: u64,
+) -> crate::session::Result<Option<Vec<Match>>>
+where
+    S: crate::session::Session,
+{
+    // Non-regular files (like devices or named pipes) can block or yield
+    // infinite content, so we never search them.
+    if !entry.metadata.is_file() {
+        return Ok(None);
+    }
+
+    let file = match std::fs::File::open(&entry.path) {
+        Ok(file) => file,
+        Err(error) => {
+            log::error! {
+                "failed to open '{}': {error}",
+                entry.path.display()
+            };
+            return Ok(None);
+        }
+    };
+
+    let mut matches = Vec::new();
+    let mut match_counts = vec![0; regexes.len()];
+
+    let mut search = crate::io::RegexSearch::new_many(file, {
+        regexes.iter()
+            .map(|(_, regex)| *regex)
+            .collect()
+    });
+
+    loop {
+        use crate::io::RegexSearchStep::*;
+        match search.step() {
+            Ok(Match(matcz)) => {
+                let match_count = &mut match_counts[matcz.regex_index];
+                if *match_count < max_matches {
+                    *match_count += 1;
+
+                    matches.push(self::Match {
+                        condition_index: regexes[matcz.regex_index].0,
+                        offset: matcz.offset,
+                        content: matcz.content.to_vec(),
+                    });
+                }
+
+                if match_counts.iter().all(|match_count| *match_count >= max_matches) {
+                    break;
+                }
+            }
+            Ok(Progress) => (),
+            Ok(Done) => break,
+            Err(error) => {
+                log::error! {
+                    "failed to read '{}': {error}",
+                    entry.path.display()
+                };
+                break;
+            }
+        }
+
+        // Searching big files can take a while, so we need to let Fleetspeak
+        // know that we are still alive and not stuck and stop once we run out
+        // of time.
+        session.heartbeat();
+        session.check_time_limits()?;
+    }
+
+    if match_counts.contains(&0) {
+        return Ok(None);
+    }
+
+    // Matches of different regexes are found in no particular order.
+    matches.sort_by_key(|matcz| (matcz.condition_index, matcz.offset));
+
+    Ok(Some(matches))
+}
+
+/// Sends contents of the given file to the blob sink.
+///
+/// At most `max_file_size` bytes are sent (if specified), even if the file has
+/// grown since its size was checked.
+fn download<S>(
+    session: &mut S,
+    path: &Path,
+    max_file_size: Option<u64>,
+    compress_blobs: bool,
+) -> crate::session::Result<Vec<Part>>
+where
+    S: crate::session::Session,
+{
+    use std::io::Read as _;
+    use sha2::Digest as _;
+
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) => {
+            log::error!("failed to open '{}': {error}", path.display());
+            return Ok(Vec::new());
+        }
+    };
+    let mut file = file.take(max_file_size.unwrap_or(u64::MAX));
+
+    let mut parts = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let mut buf = Vec::with_capacity(MAX_BLOB_LEN);
+
+        // We read until the buffer is full (rather than doing a single `read`
+        // call) to have file parts always aligned the same way.
+        let len_read = match (&mut file).take(MAX_BLOB_LEN as u64).read_to_end(&mut buf) {
+            Ok(0) => break,
+            Ok(len_read) => len_read,
+            Err(error) => {
+                log::error!("failed to read '{}': {error}", path.display());
+                break;
+            }
+        };
+
+        let blob_sha256 = sha2::Sha256::digest(&buf).into();
+
+        let mut blob = crate::blob::Blob::from(buf);
+        if compress_blobs {
+            blob = blob.compress();
+        }
+
+        session.send(crate::Sink::Blob, blob)?;
+        parts.push(Part {
+            offset,
+            len: len_read as u64,
+            blob_sha256,
+        });
+
+        offset += len_read as u64;
+
+        // Sending big files can take a while, so we need to let Fleetspeak
+        // know that we are still alive and not stuck and stop once we run out
+        // of time.
+        session.heartbeat();
+        session.check_time_limits()?;
+    }
+
+    Ok(parts)
+}
+
+impl Condition {
+
+    /// Checks whether the given metadata satisfies the condition.
+    ///
+    /// Conditions on file contents cannot be checked against metadata only,
+    /// so they are always considered satisfied.
+    fn is_satisfied_by(&self, metadata: &std::fs::Metadata) -> bool {
+        match self {
+            Condition::Size(range) => range.contains(&metadata.len()),
+            Condition::ModificationTime(range) => range.contains(metadata.modified()),
+            Condition::AccessTime(range) => range.contains(metadata.accessed()),
+            Condition::InodeChangeTime(range) => range.contains(inode_change_time(metadata)),
+            #[cfg(target_family = "unix")]
+            Condition::Mode { mask, bits } => {
+                std::os::unix::fs::MetadataExt::mode(metadata) & mask == *bits
+            }
+            #[cfg(not(target_family = "unix"))]
+            Condition::Mode { mask, bits } => {
+                let _ = (mask, bits);
+                false
+            }
+            Condition::Contents(_) => true,
+        }
+    }
+
+    /// Parses the condition from its Protocol Buffers representation.
+    fn from_proto(
+        mut proto: rrg_proto::find_files::Condition,
+    ) -> Result<Condition, ConditionError> {
+        let condition = match () {
+            () if proto.has_size() => {
+                let size = proto.take_size();
+                let max_size = match size.max_size() {
+                    0 => u64::MAX,
+                    max_size => max_size,
+                };
+
+                Condition::Size(size.min_size()..=max_size)
+            }
+            () if proto.has_modification_time() => {
+                Condition::ModificationTime(TimeRange::from_proto(proto.take_modification_time())?)
+            }
+            () if proto.has_access_time() => {
+                Condition::AccessTime(TimeRange::from_proto(proto.take_access_time())?)
+            }
+            () if proto.has_inode_change_time() => {
+                Condition::InodeChangeTime(TimeRange::from_proto(proto.take_inode_change_time())?)
+            }
+            () if proto.has_mode() && cfg!(not(target_family = "unix")) => {
+                return Err(ConditionError::UnsupportedMode);
+            }
+            () if proto.has_mode() => {
+                let mode = proto.take_mode();
+
+                Condition::Mode {
+                    mask: mode.mask(),
+                    bits: mode.bits(),
+                }
+            }
+            () if proto.has_contents() => {
+                let mut contents = proto.take_contents();
+
+                let regex = match () {
+                    () if contents.has_regex() => {
+                        regex::bytes::Regex::new(contents.regex())
+                            .map_err(ConditionError::Regex)?
+                    }
+                    () if contents.has_literal() => {
+                        let mut pattern = String::from("(?-u)");
+                        for byte in contents.take_literal() {
+                            pattern.push_str(&format!("\\x{byte:02X}"));
+                        }
+
+                        regex::bytes::Regex::new(&pattern)
+                            .map_err(ConditionError::Regex)?
+                    }
+                    _ => return Err(ConditionError::NoPattern),
+                };
+
+                Condition::Contents(regex)
+            }
+            _ => return Err(ConditionError::NoCondition),
+        };
+
+        Ok(condition)
+    }
+}
+
+impl TimeRange {
+
+    /// Checks whether the given time is within the range.
+    ///
+    /// Times that could not be determined are never within the range.
+    fn contains(&self, time: std::io::Result<SystemTime>) -> bool {
+        let Ok(time) = time else {
+            return false;
+        };
+
+        self.min.is_none_or(|min| min <= time) && self.max.is_none_or(|max| time <= max)
+    }
+
+    /// Parses the range from its Protocol Buffers representation.
+    fn from_proto(
+        mut proto: rrg_proto::find_files::TimeCondition,
+    ) -> Result<TimeRange, ConditionError> {
+        let min = if proto.has_min_time() {
+            Some(time_from_proto(proto.take_min_time())?)
+        } else {
+            None
+        };
+
+        let max = if proto.has_max_time() {
+            Some(time_from_proto(proto.take_max_time())?)
+        } else {
+            None
+        };
+
+        Ok(TimeRange {
+            min,
+            max,
+        })
+    }
+}
+
+/// Returns the time of the last status change of the file.
+///
+/// On Windows, where there is no such time, the creation time is returned.
+fn inode_change_time(metadata: &std::fs::Metadata) -> std::io::Result<SystemTime> {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::MetadataExt as _;
+
+        u32::try_from(metadata.ctime_nsec()).ok()
+            .and_then(|nanos| time_since_epoch(metadata.ctime(), nanos))
+            .ok_or_else(|| std::io::Error::other("invalid status change time"))
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    {
+        metadata.created()
+    }
+}
+
+/// Converts a Protocol Buffers `Timestamp` message to [`SystemTime`].
+fn time_from_proto(
+    proto: protobuf::well_known_types::timestamp::Timestamp,
+) -> Result<SystemTime, ConditionError> {
+    u32::try_from(proto.nanos).ok()
+        .filter(|nanos| *nanos < 1_000_000_000)
+        .and_then(|nanos| time_since_epoch(proto.seconds, nanos))
+        .ok_or(ConditionError::InvalidTime)
+}
+
+/// Returns the time the given number of seconds and nanoseconds since epoch.
+///
+/// `None` is returned if the time is not representable.
+fn time_since_epoch(secs: i64, nanos: u32) -> Option<SystemTime> {
+    let time = if secs >= 0 {
+        SystemTime::UNIX_EPOCH.checked_add(std::time::Duration::from_secs(secs.unsigned_abs()))
+    } else {
+        SystemTime::UNIX_EPOCH.checked_sub(std::time::Duration::from_secs(secs.unsigned_abs()))
+    };
+
+    time?.checked_add(std::time::Duration::from_nanos(u64::from(nanos)))
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::find_files::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        if proto.paths().is_empty() {
+            return Err(ParseArgsError::invalid_field("paths", NoPathsError));
+        }
+
+        let recursion_depth = match proto.max_recursion_depth() {
+            0 => glob::DEFAULT_RECURSION_DEPTH,
+            recursion_depth => recursion_depth,
+        };
+
+        let templates = proto.paths().iter()
+            .map(|path| path.parse::<crate::interpolate::Template>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("paths", error))?;
+
+        // Templates with variables can be turned into globs only once they
+        // are interpolated, but the other ones can be validated upfront.
+        for template in templates.iter().filter(|template| !template.has_vars()) {
+            glob::parse(template.raw(), recursion_depth)
+                .map_err(|error| ParseArgsError::invalid_field("paths", error))?;
+        }
+
+        let conditions = proto.take_conditions().into_iter()
+            .map(Condition::from_proto)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("conditions", error))?;
+
+        let action = match proto.action() {
+            rrg_proto::find_files::FileAction::STAT => FileAction::Stat,
+            rrg_proto::find_files::FileAction::HASH => FileAction::Hash,
+            rrg_proto::find_files::FileAction::DOWNLOAD => FileAction::Download,
+        };
+
+        let max_file_size = match proto.max_file_size() {
+            0 => None,
+            max_file_size => Some(max_file_size),
+        };
+
+        let max_matches = match proto.max_matches() {
+            0 => DEFAULT_MAX_MATCHES,
+            max_matches => max_matches,
+        };
+
+        Ok(Args {
+            templates,
+            recursion_depth,
+            follow_symlinks: proto.follow_symlinks(),
+            conditions,
+            action,
+            md5: proto.md5(),
+            sha1: proto.sha1(),
+            sha256: proto.sha256(),
+            max_file_size,
+            max_matches,
+            compress_blobs: proto.compress_blobs(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::find_files::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::find_files::Result::default();
+        proto.set_path(self.path.into());
+        proto.set_metadata(self.metadata.into());
+
+        for matcz in self.matches {
+            let mut matcz_proto = rrg_proto::find_files::ContentsMatch::default();
+            // The number of conditions comes from a protobuf repeated field, so
+            // it is impossible for an index to overflow `u32`.
+            matcz_proto.set_condition_index(matcz.condition_index as u32);
+            matcz_proto.set_offset(matcz.offset);
+            matcz_proto.set_content(matcz.content);
+
+            proto.mut_matches().push(matcz_proto);
+        }
+
+        #[cfg(feature = "action-get_file_metadata-md5")]
+        if let Some(md5) = self.digest.md5 {
+            proto.set_md5(md5.to_vec());
+        }
+        #[cfg(feature = "action-get_file_metadata-sha1")]
+        if let Some(sha1) = self.digest.sha1 {
+            proto.set_sha1(sha1.to_vec());
+        }
+        #[cfg(feature = "action-get_file_metadata-sha256")]
+        if let Some(sha256) = self.digest.sha256 {
+            proto.set_sha256(sha256.to_vec());
+        }
+
+        for part in self.parts {
+            let mut part_proto = rrg_proto::find_files::FilePart::default();
+            part_proto.set_offset(part.offset);
+            part_proto.set_length(part.len);
+            part_proto.set_blob_sha256(part.blob_sha256.to_vec());
+
+            proto.mut_parts().push(part_proto);
+        }
+
+        proto.set_interpolation(self.interpolation.into());
+
+        proto
+    }
+}
+
+/// An error type for situations where no paths to find were specified.
+#[derive(Debug)]
+struct NoPathsError;
+
+impl std::fmt::Display for NoPathsError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "no paths to find")
+    }
+}
+
+impl std::error::Error for NoPathsError {
+}
+
+/// An error that can occur when parsing a condition.
+#[derive(Debug)]
+enum ConditionError {
+    /// No condition was specified.
+    NoCondition,
+    /// No pattern to search for was specified in a contents condition.
+    NoPattern,
+    /// A time in a time condition is not valid.
+    InvalidTime,
+    /// A mode condition was specified on a platform without mode bits.
+    UnsupportedMode,
+    /// A regex in a contents condition is not valid.
+    Regex(regex::Error),
+}
+
+impl std::fmt::Display for ConditionError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConditionError::NoCondition => write!(fmt, "no condition"),
+            ConditionError::NoPattern => write!(fmt, "no pattern to search for"),
+            ConditionError::InvalidTime => write!(fmt, "invalid time"),
+            ConditionError::UnsupportedMode => {
+                write!(fmt, "mode conditions are not supported on this platform")
+            }
+            ConditionError::Regex(error) => write!(fmt, "invalid regex: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ConditionError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConditionError::Regex(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Constructs arguments finding files matching the given patterns.
+    fn args(patterns: &[String]) -> Args {
+        let templates = patterns.iter()
+            .map(|pattern| pattern.parse::<crate::interpolate::Template>().unwrap())
+            .collect();
+
+        Args {
+            templates,
+            recursion_depth: glob::DEFAULT_RECURSION_DEPTH,
+            follow_symlinks: false,
+            conditions: vec![],
+            action: FileAction::Stat,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            max_file_size: None,
+            max_matches: DEFAULT_MAX_MATCHES,
+            compress_blobs: false,
+        }
+    }
+
+    /// Returns paths of all the files reported by the session in sorted order.
+    fn paths(session: &crate::session::FakeSession) -> Vec<PathBuf> {
+        let mut paths = session.replies::<Item>()
+            .map(|item| item.path.clone())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        paths
+    }
+
+    /// Returns the pattern of the given path in the given directory.
+    fn pattern(dir: &Path, path: &str) -> String {
+        format!("{}/{path}", dir.display())
+    }
+
+    #[test]
+    fn handle_literal() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar"), b"")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "foo")]))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![tempdir.path().join("foo")]);
+    }
+
+    #[test]
+    fn handle_non_existent() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let patterns = [
+            pattern(tempdir.path(), "foo"),
+            pattern(tempdir.path(), "foo/*"),
+        ];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&patterns))
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_wildcard() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("dir.txt"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo.txt"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar.txt"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("baz.log"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("dir.txt").join("quux.txt"), b"")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "*.txt")]))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![
+            tempdir.path().join("bar.txt"),
+            tempdir.path().join("dir.txt"),
+            tempdir.path().join("foo.txt"),
+        ]);
+    }
+
+    #[test]
+    fn handle_wildcard_nested() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir_all(tempdir.path().join("foo").join(".ssh"))
+            .unwrap();
+        std::fs::create_dir_all(tempdir.path().join("bar").join(".ssh"))
+            .unwrap();
+        std::fs::create_dir_all(tempdir.path().join("baz"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo").join(".ssh").join("id_rsa"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar").join(".ssh").join("id_ed25519"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("baz").join("id_rsa"), b"")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "*/.ssh/id_*")]))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![
+            tempdir.path().join("bar").join(".ssh").join("id_ed25519"),
+            tempdir.path().join("foo").join(".ssh").join("id_rsa"),
+        ]);
+    }
+
+    #[test]
+    fn handle_recursive() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut dir = tempdir.path().to_path_buf();
+        for name in ["a", "b", "c", "d"] {
+            std::fs::write(dir.join("foo"), b"")
+                .unwrap();
+
+            dir.push(name);
+            std::fs::create_dir(&dir)
+                .unwrap();
+        }
+
+        // This one is nested too deep to match.
+        std::fs::write(dir.join("foo"), b"")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "**/foo")]))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![
+            tempdir.path().join("a").join("b").join("c").join("foo"),
+            tempdir.path().join("a").join("b").join("foo"),
+            tempdir.path().join("a").join("foo"),
+            tempdir.path().join("foo"),
+        ]);
+    }
+
+    #[test]
+    fn handle_recursive_custom_depth() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir_all(tempdir.path().join("a").join("b"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("a").join("foo"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("a").join("b").join("foo"), b"")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "**1/foo")]))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![
+            tempdir.path().join("a").join("foo"),
+        ]);
+    }
+
+    #[test]
+    fn handle_wildcard_recursive_pruned() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir_all(tempdir.path().join("a").join("bar"))
+            .unwrap();
+        std::fs::create_dir_all(tempdir.path().join("b").join("c").join("bar"))
+            .unwrap();
+        std::fs::create_dir_all(tempdir.path().join("d").join("e").join("f").join("bar"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("a").join("bar").join("foo"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("b").join("c").join("bar").join("foo"), b"")
+            .unwrap();
+        // This one is nested too deep to match.
+        std::fs::write(tempdir.path().join("d").join("e").join("f").join("bar").join("foo"), b"")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "*/**1/bar/*")]))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![
+            tempdir.path().join("a").join("bar").join("foo"),
+            tempdir.path().join("b").join("c").join("bar").join("foo"),
+        ]);
+    }
+
+    #[test]
+    fn handle_alternation() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("bar"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar").join("baz"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("quux"), b"")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "{foo,bar/*,norf}")]))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![
+            tempdir.path().join("bar").join("baz"),
+            tempdir.path().join("foo"),
+        ]);
+    }
+
+    #[test]
+    fn handle_class() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("log1"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("log2"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("logx"), b"")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "log[0-9]")]))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![
+            tempdir.path().join("log1"),
+            tempdir.path().join("log2"),
+        ]);
+    }
+
+    #[test]
+    fn handle_duplicates() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+
+        let patterns = [
+            pattern(tempdir.path(), "foo"),
+            pattern(tempdir.path(), "*"),
+            pattern(tempdir.path(), "{f*,*o}"),
+        ];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&patterns))
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![tempdir.path().join("foo")]);
+    }
+
+    #[test]
+    fn handle_size_condition() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("small"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("medium"), b"foobar")
+            .unwrap();
+        std::fs::write(tempdir.path().join("big"), b"foobarbaz")
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "*")]);
+        args.conditions = vec![Condition::Size(4..=6)];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![tempdir.path().join("medium")]);
+    }
+
+    #[test]
+    fn handle_modification_time_condition() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+
+        let old = std::fs::File::create(tempdir.path().join("old"))
+            .unwrap();
+        old.set_modified(time - std::time::Duration::from_secs(3600))
+            .unwrap();
+
+        let new = std::fs::File::create(tempdir.path().join("new"))
+            .unwrap();
+        new.set_modified(time + std::time::Duration::from_secs(3600))
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "*")]);
+        args.conditions = vec![Condition::ModificationTime(TimeRange {
+            min: Some(time),
+            max: None,
+        })];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![tempdir.path().join("new")]);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_mode_condition() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("exec"), b"")
+            .unwrap();
+        std::fs::set_permissions(tempdir.path().join("exec"), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("noexec"), b"")
+            .unwrap();
+        std::fs::set_permissions(tempdir.path().join("noexec"), std::fs::Permissions::from_mode(0o644))
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "*")]);
+        args.conditions = vec![Condition::Mode {
+            mask: 0o111,
+            bits: 0o111,
+        }];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![tempdir.path().join("exec")]);
+    }
+
+    #[test]
+    fn handle_contents_conditions() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("dir"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"foo bar\xffbaz foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar"), b"foo")
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "*")]);
+        args.conditions = vec![
+            Condition::Size(1..=u64::MAX),
+            Condition::Contents(regex::bytes::Regex::new("fo+").unwrap()),
+            Condition::Contents(regex::bytes::Regex::new(r"(?-u)\xFFbaz").unwrap()),
+        ];
+        args.max_matches = u64::MAX;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, tempdir.path().join("foo"));
+
+        let matches = item.matches.iter()
+            .map(|matcz| (matcz.condition_index, matcz.offset, matcz.content.as_slice()))
+            .collect::<Vec<_>>();
+        assert_eq!(matches, vec![
+            (1, 0, &b"foo"[..]),
+            (1, 12, &b"foo"[..]),
+            (2, 7, &b"\xffbaz"[..]),
+        ]);
+    }
+
+    #[test]
+    fn handle_contents_max_matches() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"foo bar foo bar foo")
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "foo")]);
+        args.conditions = vec![
+            Condition::Contents(regex::bytes::Regex::new("foo").unwrap()),
+            Condition::Contents(regex::bytes::Regex::new("bar").unwrap()),
+        ];
+        args.max_matches = 2;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        let matches = item.matches.iter()
+            .map(|matcz| (matcz.condition_index, matcz.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(matches, vec![
+            (0, 0),
+            (0, 8),
+            (1, 4),
+            (1, 12),
+        ]);
+    }
+
+    #[test]
+    fn handle_contents_not_all_matched() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"foo foo foo")
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "foo")]);
+        args.conditions = vec![
+            Condition::Contents(regex::bytes::Regex::new("foo").unwrap()),
+            Condition::Contents(regex::bytes::Regex::new("bar").unwrap()),
+        ];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[cfg(feature = "action-get_file_metadata-sha256")]
+    #[test]
+    fn handle_hash() {
+        use sha2::Digest as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("dir"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("file"), b"foobar")
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "*")]);
+        args.action = FileAction::Hash;
+        args.sha256 = true;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 2);
+
+        let dir = session.replies::<Item>()
+            .find(|item| item.path == tempdir.path().join("dir"))
+            .unwrap();
+        assert_eq!(dir.digest.sha256, None);
+
+        let file = session.replies::<Item>()
+            .find(|item| item.path == tempdir.path().join("file"))
+            .unwrap();
+        assert_eq!(file.digest.sha256, Some(sha2::Sha256::digest(b"foobar").into()));
+        assert!(file.parts.is_empty());
+
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+    }
+
+    #[test]
+    fn handle_download() {
+        use sha2::Digest as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let contents = b"foobar".repeat(MAX_BLOB_LEN / 3 + 1);
+        std::fs::write(tempdir.path().join("file"), &contents)
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "file")]);
+        args.action = FileAction::Download;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.parts.len(), 3);
+
+        let blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .collect::<Vec<_>>();
+        assert_eq!(blobs.len(), 3);
+
+        for (part, blob) in item.parts.iter().zip(blobs.iter()) {
+            assert_eq!(part.len, blob.as_bytes().len() as u64);
+            assert_eq!(part.blob_sha256, <[u8; 32]>::from(sha2::Sha256::digest(blob.as_bytes())));
+        }
+        assert_eq!(item.parts[0].offset, 0);
+        assert_eq!(item.parts[1].offset, MAX_BLOB_LEN as u64);
+        assert_eq!(item.parts[2].offset, 2 * MAX_BLOB_LEN as u64);
+
+        let downloaded = blobs.iter()
+            .flat_map(|blob| blob.as_bytes().to_vec())
+            .collect::<Vec<_>>();
+        assert!(downloaded == contents);
+    }
+
+    #[test]
+    fn handle_download_max_file_size() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("small"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("big"), b"foobar")
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "*")]);
+        args.action = FileAction::Download;
+        args.max_file_size = Some(3);
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        // Both files are reported, but only the small one is downloaded.
+        assert_eq!(session.reply_count(), 2);
+
+        let blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .collect::<Vec<_>>();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].as_bytes(), b"foo");
+    }
+
+    #[test]
+    fn handle_download_compress_blobs() {
+        use sha2::Digest as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let contents = b"foo bar baz\n".repeat(1024);
+        std::fs::write(tempdir.path().join("file"), &contents)
+            .unwrap();
+
+        let mut args = args(&[pattern(tempdir.path(), "file")]);
+        args.action = FileAction::Download;
+        args.compress_blobs = true;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        // The digest should be the one of the uncompressed content.
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.parts.len(), 1);
+        assert_eq!(item.parts[0].len, contents.len() as u64);
+        assert_eq!(item.parts[0].blob_sha256, <[u8; 32]>::from(sha2::Sha256::digest(&contents)));
+
+        // The content is very repetitive, so it should have been compressed.
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert!(blob.as_bytes().len() < contents.len());
+    }
+
+    #[test]
+    fn download_max_file_size() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        // The file might grow after its size is checked, so we simulate it by
+        // downloading file bigger than the limit.
+        std::fs::write(tempdir.path().join("foo"), b"foobar")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        let parts = download(&mut session, &tempdir.path().join("foo"), Some(3), false)
+            .unwrap();
+
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].len, 3);
+
+        let blobs = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .collect::<Vec<_>>();
+        assert_eq!(blobs.len(), 1);
+        assert_eq!(blobs[0].as_bytes(), b"foo");
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_follow_symlinks() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("dir"))
+            .unwrap();
+        std::fs::create_dir(tempdir.path().join("outside"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("outside").join("foo"), b"")
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.path().join("outside"), tempdir.path().join("dir").join("link"))
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(&[pattern(tempdir.path(), "dir/*/foo")]))
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 0);
+
+        let mut args = args(&[pattern(tempdir.path(), "dir/*/foo")]);
+        args.follow_symlinks = true;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(paths(&session), vec![
+            tempdir.path().join("dir").join("link").join("foo"),
+        ]);
+    }
+
+    #[test]
+    fn handle_real_time_limit() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+
+        // Nothing satisfies the condition, the limit should be enforced
+        // nonetheless.
+        let mut args = args(&[pattern(tempdir.path(), "**")]);
+        args.conditions = vec![Condition::Size(1..=u64::MAX)];
+
+        let mut session = crate::session::FakeSession::new()
+            .with_real_time_limit(std::time::Duration::ZERO);
+
+        let error = handle(&mut session, args)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), crate::session::ErrorKind::RealTimeLimitExceeded);
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_interpolation() {
+        let temp_dir = ospect::os::temp_dir()
+            .unwrap();
+
+        // We create the temporary folder in the system folder for temporary
+        // files, so we can refer to it using a variable.
+        let tempdir = tempfile::tempdir_in(&temp_dir)
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str()
+            .unwrap();
+
+        let mut proto = rrg_proto::find_files::Args::default();
+        proto.mut_paths().push(format!("%%environ_temp%%/{tempdir_name}/foo"));
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, temp_dir.join(tempdir_name).join("foo"));
+
+        assert_eq!(item.interpolation.raw, format!("%%environ_temp%%/{tempdir_name}/foo"));
+        assert_eq!(item.interpolation.bindings.len(), 1);
+        assert!(item.interpolation.bindings.contains_key(&crate::interpolate::Var::EnvironTemp));
+    }
+
+    #[test]
+    fn args_from_proto_no_paths() {
+        let proto = rrg_proto::find_files::Args::default();
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_relative_path() {
+        let mut proto = rrg_proto::find_files::Args::default();
+        proto.mut_paths().push(String::from("foo/*"));
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_unknown_var() {
+        let mut proto = rrg_proto::find_files::Args::default();
+        proto.mut_paths().push(String::from("%%users.foo%%/*"));
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    #[test]
+    fn handle_users_unavailable() {
+        let mut session = crate::session::FakeSession::new();
+
+        let error = handle(&mut session, args(&[String::from("%%users.homedir%%/*")]))
+            .unwrap_err();
+
+        assert_eq!(error.kind(), crate::session::ErrorKind::ActionFailure);
+    }
+
+    #[test]
+    fn args_from_proto_environ_temp() {
+        let mut proto = rrg_proto::find_files::Args::default();
+        proto.mut_paths().push(String::from("%%environ_temp%%/*"));
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+
+        let temp_dir = ospect::os::temp_dir().unwrap()
+            .into_os_string().into_string()
+            .unwrap();
+
+        let patterns = patterns(&args)
+            .unwrap();
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].interpolation.path, format!("{temp_dir}/*"));
+        assert_eq!(patterns[0].interpolation.bindings, [
+            (crate::interpolate::Var::EnvironTemp, temp_dir),
+        ].into());
+    }
+
+    #[test]
+    fn args_from_proto_empty_condition() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut proto = rrg_proto::find_files::Args::default();
+        proto.mut_paths().push(pattern(tempdir.path(), "*"));
+        proto.mut_conditions().push(rrg_proto::find_files::Condition::default());
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_conditions() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut proto = rrg_proto::find_files::Args::default();
+        proto.mut_paths().push(pattern(tempdir.path(), "{foo,bar}"));
+        proto.set_action(rrg_proto::find_files::FileAction::DOWNLOAD);
+
+        let mut size = rrg_proto::find_files::Condition::default();
+        size.mut_size().set_min_size(42);
+        proto.mut_conditions().push(size);
+
+        let mut time = rrg_proto::find_files::Condition::default();
+        time.mut_access_time().mut_max_time().seconds = 1337;
+        proto.mut_conditions().push(time);
+
+        let mut literal = rrg_proto::find_files::Condition::default();
+        literal.mut_contents().set_literal(b"\x00.*".to_vec());
+        proto.mut_conditions().push(literal);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+
+        let patterns = patterns(&args)
+            .unwrap();
+
+        assert_eq!(patterns.len(), 1);
+        assert_eq!(patterns[0].globs.len(), 2);
+        assert_eq!(args.action, FileAction::Download);
+        assert_eq!(args.conditions.len(), 3);
+
+        assert!(matches!(&args.conditions[0], Condition::Size(size) if *size == (42..=u64::MAX)));
+
+        match &args.conditions[1] {
+            Condition::AccessTime(range) => {
+                assert_eq!(range.min, None);
+                assert_eq!(range.max, Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1337)));
+            }
+            _ => panic!("unexpected condition"),
+        }
+
+        match &args.conditions[2] {
+            Condition::Contents(regex) => {
+                assert!(regex.is_match(b"foo\x00.*bar"));
+                assert!(!regex.is_match(b"foo\x00bar"));
+            }
+            _ => panic!("unexpected condition"),
+        }
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    #[test]
+    fn args_from_proto_mode_unsupported() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut proto = rrg_proto::find_files::Args::default();
+        proto.mut_paths().push(pattern(tempdir.path(), "*"));
+
+        let mut mode = rrg_proto::find_files::Condition::default();
+        mode.mut_mode().set_mask(0o777);
+        proto.mut_conditions().push(mode);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_invalid_time() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut proto = rrg_proto::find_files::Args::default();
+        proto.mut_paths().push(pattern(tempdir.path(), "*"));
+
+        let mut time = rrg_proto::find_files::Condition::default();
+        time.mut_modification_time().mut_min_time().nanos = -1;
+        proto.mut_conditions().push(time);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+}
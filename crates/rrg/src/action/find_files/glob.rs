@@ -0,0 +1,874 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Glob patterns of filesystem paths.
+//!
+//! Alternations (`{foo,bar}`) are expanded upfront, so every pattern turns into
+//! one or more globs without them. A glob is matched by walking the filesystem
+//! from its longest prefix without wildcards (the root) up to the depth that
+//! the wildcards can reach and testing every path encountered along the way
+//! against a regex that the rest of the glob is translated to.
+//!
+//! There is no escape character (as backslashes are path separators on Windows),
+//! special characters can be matched literally by enclosing them in a class
+//! instead (e.g. `[*]`).
+
+use std::path::{Component, Path, PathBuf};
+
+/// Default limit on the number of nested folders matched by `**` wildcards.
+pub const DEFAULT_RECURSION_DEPTH: u32 = 3;
+
+/// Limit on the number of nested folders a `**` wildcard can match.
+const MAX_RECURSION_DEPTH: u32 = 255;
+
+/// Limit on the number of globs a single pattern can expand to.
+const MAX_EXPANSION_LEN: usize = 1024;
+
+/// Glob pattern of a filesystem path.
+#[derive(Clone)]
+pub struct Glob {
+    /// Longest prefix of the pattern without any wildcards.
+    root: PathBuf,
+    /// Regex that paths relative to the root have to match.
+    ///
+    /// Relative paths are matched with every component preceded by a slash.
+    /// The regex is `None` if the pattern has no wildcards at all.
+    regex: Option<regex::bytes::Regex>,
+    /// Limit on the depth (relative to the root) of matching paths.
+    max_depth: u32,
+    /// Components of the pattern following the root.
+    ///
+    /// These are used to tell whether paths can lead to matches (see
+    /// [`Glob::is_prefix_match`]).
+    segments: Vec<Segment>,
+}
+
+/// Single component of a glob pattern following its root.
+#[derive(Clone)]
+enum Segment {
+    /// Component matching names of a single path component.
+    Name(regex::bytes::Regex),
+    /// `**` wildcard matching up to the given number of path components.
+    Recursion(u32),
+}
+
+impl Glob {
+
+    /// Returns the longest prefix of the pattern without any wildcards.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns the limit on the depth (relative to the root) of matching paths.
+    ///
+    /// The limit of zero means that the root is the only path that can match.
+    pub fn max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Checks whether the given path matches the glob.
+    pub fn is_match(&self, path: &Path) -> bool {
+        let Some(regex) = &self.regex else {
+            return path == self.root;
+        };
+
+        let Ok(path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        let mut bytes = Vec::new();
+        for component in path.components() {
+            bytes.push(b'/');
+            bytes.extend_from_slice(&os_str_bytes(component.as_os_str()));
+        }
+
+        regex.is_match(&bytes)
+    }
+
+    /// Checks whether paths below the given one can match the glob.
+    ///
+    /// This is useful to avoid descending into folders that cannot contain any
+    /// matches, e.g. for `/*/foo/bar` there is no point in descending into
+    /// `/baz/quux` as it is not a `foo` folder.
+    pub fn is_prefix_match(&self, path: &Path) -> bool {
+        let Ok(path) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+
+        // We simulate a non-deterministic automaton in which every state is
+        // the index of the segment to match next along with the number of
+        // components matched by it so far (if it is a `**` wildcard).
+        let mut states = Vec::new();
+        self.push_state(&mut states, 0, 0);
+
+        for component in path.components() {
+            let name = os_str_bytes(component.as_os_str());
+
+            let mut next_states = Vec::new();
+            for &(index, count) in &states {
+                match self.segments.get(index) {
+                    Some(Segment::Name(regex)) if regex.is_match(&name) => {
+                        self.push_state(&mut next_states, index + 1, 0);
+                    }
+                    Some(Segment::Recursion(depth)) if count < *depth => {
+                        self.push_state(&mut next_states, index, count + 1);
+                    }
+                    _ => (),
+                }
+            }
+
+            if next_states.is_empty() {
+                return false;
+            }
+            states = next_states;
+        }
+
+        states.iter().any(|&(index, count)| match self.segments.get(index) {
+            Some(Segment::Name(_)) => true,
+            Some(Segment::Recursion(depth)) => count < *depth,
+            None => false,
+        })
+    }
+
+    /// Adds the given state (along with states reachable from it without
+    /// matching any component) to the list of states.
+    fn push_state(&self, states: &mut Vec<(usize, u32)>, index: usize, count: u32) {
+        if states.contains(&(index, count)) {
+            return;
+        }
+        states.push((index, count));
+
+        // `**` wildcards can match no components at all, so the next segment
+        // is always reachable from them.
+        if let Some(Segment::Recursion(_)) = self.segments.get(index) {
+            self.push_state(states, index + 1, 0);
+        }
+    }
+}
+
+/// Escapes the given text so that it is matched literally when used in a glob.
+///
+/// Every special character is enclosed in a character class of its own (e.g.
+/// `*` becomes `[*]`), as there are no special characters within classes other
+/// than the closing bracket (which is not special outside of them).
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        if matches!(char, '*' | '?' | '[' | '{' | '}' | ',') {
+            escaped.push('[');
+            escaped.push(char);
+            escaped.push(']');
+        } else {
+            escaped.push(char);
+        }
+    }
+
+    escaped
+}
+
+/// Parses the given pattern into globs.
+///
+/// Every alternation in the pattern is expanded into a separate glob, so the
+/// path matches the pattern if it matches any of the returned globs. Bare `**`
+/// wildcards are limited to `recursion_depth` nested folders.
+///
+/// # Errors
+///
+/// This function will return an error if the pattern is malformed, is not
+/// absolute or expands to too many globs.
+pub fn parse(pattern: &str, recursion_depth: u32) -> Result<Vec<Glob>, ParseError> {
+    expand(pattern)?.iter()
+        .map(|pattern| parse_glob(pattern, recursion_depth))
+        .collect()
+}
+
+/// Parses a single pattern without alternations into a glob.
+fn parse_glob(pattern: &str, recursion_depth: u32) -> Result<Glob, ParseError> {
+    let pattern = Path::new(pattern);
+    if !pattern.is_absolute() {
+        return Err(ParseError::Relative);
+    }
+
+    let mut root = PathBuf::new();
+    let mut regex = String::new();
+    let mut max_depth = 0u32;
+    let mut segments = Vec::new();
+    let mut is_literal = true;
+
+    // Paths on Windows are case-insensitive, so should be the globs.
+    let flags = if cfg!(target_family = "windows") { "(?i)" } else { "" };
+
+    for component in pattern.components() {
+        let name = match component {
+            Component::Prefix(_) | Component::RootDir => {
+                root.push(component);
+                continue;
+            }
+            Component::CurDir => continue,
+            Component::ParentDir if is_literal => {
+                root.push(component);
+                continue;
+            }
+            Component::ParentDir => return Err(ParseError::ParentDir),
+            // The pattern is a string, so all its components are strings too
+            // and the conversion is never lossy.
+            Component::Normal(name) => name.to_string_lossy(),
+        };
+
+        if is_literal {
+            if let Some(name) = unescape(&name) {
+                root.push(name);
+                continue;
+            }
+        }
+        is_literal = false;
+
+        if let Some(depth) = recursion(&name, recursion_depth)? {
+            regex.push_str(&format!("(?:/(?-u:[^/])+){{0,{depth}}}"));
+            max_depth = max_depth.saturating_add(depth);
+            segments.push(Segment::Recursion(depth));
+        } else {
+            let mut name_regex = String::new();
+            translate(&name, &mut name_regex)?;
+
+            regex.push('/');
+            regex.push_str(&name_regex);
+            max_depth = max_depth.saturating_add(1);
+
+            let name_regex = regex::bytes::Regex::new(&format!("^{flags}{name_regex}$"))
+                .map_err(ParseError::Regex)?;
+            segments.push(Segment::Name(name_regex));
+        }
+    }
+
+    let regex = if is_literal {
+        None
+    } else {
+        let regex = regex::bytes::Regex::new(&format!("^{flags}{regex}$"))
+            .map_err(ParseError::Regex)?;
+
+        Some(regex)
+    };
+
+    Ok(Glob {
+        root,
+        regex,
+        max_depth,
+        segments,
+    })
+}
+
+/// Expands all alternations in the given pattern.
+fn expand(pattern: &str) -> Result<Vec<String>, ParseError> {
+    let mut expanded = Vec::new();
+    let mut pending = vec![String::from(pattern)];
+
+    while let Some(pattern) = pending.pop() {
+        match alternation(&pattern)? {
+            None => expanded.push(pattern),
+            Some(alternation) => {
+                let prefix = &pattern[..alternation.range.start];
+                let suffix = &pattern[alternation.range.end..];
+
+                // Pending patterns are processed from the back, so we push the
+                // alternatives in reverse to keep them in the original order.
+                for alternative in alternation.alternatives.iter().rev() {
+                    pending.push(format!("{prefix}{alternative}{suffix}"));
+                }
+            }
+        }
+
+        if expanded.len() + pending.len() > MAX_EXPANSION_LEN {
+            return Err(ParseError::TooManyAlternatives);
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Alternation (`{foo,bar}`) within a pattern.
+struct Alternation<'p> {
+    /// Byte range of the alternation (including the braces) in the pattern.
+    range: std::ops::Range<usize>,
+    /// Alternatives of the alternation.
+    alternatives: Vec<&'p str>,
+}
+
+/// Finds the first outermost alternation in the given pattern.
+///
+/// `None` is returned if there are no alternations in the pattern.
+fn alternation(pattern: &str) -> Result<Option<Alternation<'_>>, ParseError> {
+    // All the special characters are ASCII, so we can safely look at the bytes
+    // and slice the pattern at their positions.
+    let bytes = pattern.as_bytes();
+
+    let mut start = 0;
+    let mut depth = 0;
+    let mut alternatives = Vec::new();
+    let mut alternative_start = 0;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // Braces and commas within character classes have no special
+            // meaning, so we skip the classes altogether.
+            b'[' => if let Some(len) = class_len(&bytes[i..]) {
+                i += len;
+                continue;
+            },
+            b'{' => {
+                if depth == 0 {
+                    start = i;
+                    alternative_start = i + 1;
+                }
+                depth += 1;
+            }
+            b',' if depth == 1 => {
+                alternatives.push(&pattern[alternative_start..i]);
+                alternative_start = i + 1;
+            }
+            b'}' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    alternatives.push(&pattern[alternative_start..i]);
+                    return Ok(Some(Alternation {
+                        range: start..i + 1,
+                        alternatives,
+                    }));
+                }
+            }
+            _ => (),
+        }
+
+        i += 1;
+    }
+
+    if depth > 0 {
+        return Err(ParseError::UnclosedAlternation);
+    }
+
+    Ok(None)
+}
+
+/// Returns the depth limit of the given component if it is a `**` wildcard.
+fn recursion(name: &str, recursion_depth: u32) -> Result<Option<u32>, ParseError> {
+    let Some(depth) = name.strip_prefix("**") else {
+        return Ok(None);
+    };
+
+    let depth = if depth.is_empty() {
+        recursion_depth
+    } else if depth.bytes().all(|byte| byte.is_ascii_digit()) {
+        // The depth consists of digits only, so the parsing can fail only
+        // because of an overflow.
+        depth.parse().unwrap_or(u32::MAX)
+    } else {
+        return Ok(None);
+    };
+
+    if depth > MAX_RECURSION_DEPTH {
+        return Err(ParseError::RecursionDepth(depth));
+    }
+
+    Ok(Some(depth))
+}
+
+/// Returns the name the given component matches if it has no wildcards.
+///
+/// Character classes with a single character (like the ones produced by
+/// [`escape`]) are not considered wildcards, as they match only one name too.
+fn unescape(name: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut rest = name;
+
+    while let Some(char) = rest.chars().next() {
+        match char {
+            '*' | '?' => return None,
+            '[' => {
+                let len = class_len(rest.as_bytes())?;
+
+                let class = &rest[1..len - 1];
+                let mut class_chars = class.chars();
+                match (class_chars.next(), class_chars.next()) {
+                    (Some(char), None) if char != '!' && char != '^' => {
+                        unescaped.push(char);
+                    }
+                    _ => return None,
+                }
+
+                rest = &rest[len..];
+                continue;
+            }
+            _ => unescaped.push(char),
+        }
+
+        rest = &rest[char.len_utf8()..];
+    }
+
+    Some(unescaped)
+}
+
+/// Translates a single path component of a glob into a regex.
+fn translate(name: &str, regex: &mut String) -> Result<(), ParseError> {
+    let mut rest = name;
+
+    while let Some(char) = rest.chars().next() {
+        match char {
+            // Unicode mode is disabled for wildcards to match file names that
+            // are not valid UTF-8 as well.
+            '*' => regex.push_str("(?-u:[^/])*"),
+            '?' => regex.push_str("(?:[^/]|(?-u:[^/]))"),
+            '[' => {
+                let len = class_len(rest.as_bytes())
+                    .ok_or(ParseError::UnclosedClass)?;
+
+                translate_class(&rest[1..len - 1], regex);
+
+                rest = &rest[len..];
+                continue;
+            }
+            _ => regex.push_str(&regex::escape(char.encode_utf8(&mut [0; 4]))),
+        }
+
+        rest = &rest[char.len_utf8()..];
+    }
+
+    Ok(())
+}
+
+/// Translates contents of a character class of a glob into a regex.
+fn translate_class(class: &str, regex: &mut String) {
+    regex.push('[');
+    let class = match class.strip_prefix(['!', '^']) {
+        Some(class) => {
+            // Negated classes must not match the separator of components.
+            regex.push_str("^/");
+            class
+        }
+        None => class,
+    };
+
+    let chars = class.chars().collect::<Vec<_>>();
+    for (i, char) in chars.iter().enumerate() {
+        // Hyphens denote ranges unless they are at the beginning or the end
+        // of the class, in which case they are matched literally.
+        if *char == '-' && i > 0 && i + 1 < chars.len() {
+            regex.push('-');
+        } else {
+            regex.push_str(&regex::escape(char.encode_utf8(&mut [0; 4])));
+        }
+    }
+    regex.push(']');
+}
+
+/// Returns the length of the character class at the beginning of the pattern.
+///
+/// `None` is returned if the class is not closed. Note that a closing bracket
+/// right after the opening one (or after the negation) is matched literally.
+fn class_len(pattern: &[u8]) -> Option<usize> {
+    let mut len = 1;
+    if matches!(pattern.get(len), Some(b'!' | b'^')) {
+        len += 1;
+    }
+    if pattern.get(len) == Some(&b']') {
+        len += 1;
+    }
+
+    let pos = pattern.get(len..)?.iter().position(|byte| *byte == b']')?;
+    Some(len + pos + 1)
+}
+
+/// Returns the bytes of the given OS string.
+///
+/// On platforms where OS strings are not arbitrary bytes, the string is lossily
+/// converted to UTF-8 first.
+fn os_str_bytes(string: &std::ffi::OsStr) -> std::borrow::Cow<'_, [u8]> {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::ffi::OsStrExt as _;
+        std::borrow::Cow::Borrowed(string.as_bytes())
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    {
+        std::borrow::Cow::Owned(string.to_string_lossy().into_owned().into_bytes())
+    }
+}
+
+/// An error that can occur when parsing a glob pattern.
+#[derive(Debug)]
+pub enum ParseError {
+    /// The pattern is not absolute.
+    Relative,
+    /// An alternation in the pattern is not closed.
+    UnclosedAlternation,
+    /// A character class in the pattern is not closed.
+    UnclosedClass,
+    /// The pattern expands to too many alternatives.
+    TooManyAlternatives,
+    /// The pattern refers to a parent folder after a wildcard.
+    ParentDir,
+    /// The pattern has a `**` wildcard with too big depth limit.
+    RecursionDepth(u32),
+    /// The pattern could not be translated into a valid regex.
+    Regex(regex::Error),
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::Relative => {
+                write!(fmt, "relative glob")
+            }
+            ParseError::UnclosedAlternation => {
+                write!(fmt, "unclosed alternation in glob")
+            }
+            ParseError::UnclosedClass => {
+                write!(fmt, "unclosed character class in glob")
+            }
+            ParseError::TooManyAlternatives => {
+                write!(fmt, "glob expands to more than {MAX_EXPANSION_LEN} alternatives")
+            }
+            ParseError::ParentDir => {
+                write!(fmt, "parent folder after a wildcard in glob")
+            }
+            ParseError::RecursionDepth(depth) => {
+                write! {
+                    fmt,
+                    "recursion depth ({depth}) is bigger than allowed ({MAX_RECURSION_DEPTH})"
+                }
+            }
+            ParseError::Regex(error) => {
+                write!(fmt, "invalid glob: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Regex(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Prefix that makes slash-separated paths absolute on all platforms.
+    #[cfg(target_family = "unix")]
+    const ROOT: &str = "";
+    #[cfg(target_family = "windows")]
+    const ROOT: &str = "C:";
+
+    /// Parses the given pattern expecting exactly one glob.
+    fn glob(pattern: &str) -> Glob {
+        let mut globs = parse(&format!("{ROOT}{pattern}"), DEFAULT_RECURSION_DEPTH)
+            .unwrap();
+        assert_eq!(globs.len(), 1);
+
+        globs.remove(0)
+    }
+
+    /// Checks whether the given path matches the given glob.
+    fn is_match(glob: &Glob, path: &str) -> bool {
+        glob.is_match(&PathBuf::from(format!("{ROOT}{path}")))
+    }
+
+    #[test]
+    fn parse_relative() {
+        assert!(matches!(parse("foo/*", 3), Err(ParseError::Relative)));
+    }
+
+    #[test]
+    fn parse_unclosed_alternation() {
+        let result = parse(&format!("{ROOT}/foo/{{bar,baz"), 3);
+        assert!(matches!(result, Err(ParseError::UnclosedAlternation)));
+    }
+
+    #[test]
+    fn parse_unclosed_class() {
+        let result = parse(&format!("{ROOT}/foo/[bar"), 3);
+        assert!(matches!(result, Err(ParseError::UnclosedClass)));
+    }
+
+    #[test]
+    fn parse_too_many_alternatives() {
+        let pattern = format!("{ROOT}/{}", "{a,b}".repeat(16));
+
+        let result = parse(&pattern, 3);
+        assert!(matches!(result, Err(ParseError::TooManyAlternatives)));
+    }
+
+    #[test]
+    fn parse_parent_dir_after_wildcard() {
+        let result = parse(&format!("{ROOT}/foo/*/../bar"), 3);
+        assert!(matches!(result, Err(ParseError::ParentDir)));
+    }
+
+    #[test]
+    fn parse_literal() {
+        let glob = glob("/foo/bar");
+
+        assert_eq!(glob.root(), Path::new(&format!("{ROOT}/foo/bar")));
+        assert_eq!(glob.max_depth(), 0);
+        assert!(is_match(&glob, "/foo/bar"));
+        assert!(!is_match(&glob, "/foo/bar/baz"));
+    }
+
+    #[test]
+    fn parse_root_and_max_depth() {
+        let glob = glob("/foo/bar/*/baz/*.txt");
+
+        assert_eq!(glob.root(), Path::new(&format!("{ROOT}/foo/bar")));
+        assert_eq!(glob.max_depth(), 3);
+    }
+
+    #[test]
+    fn parse_recursion_max_depth() {
+        assert_eq!(glob("/foo/**/bar").max_depth(), DEFAULT_RECURSION_DEPTH + 1);
+        assert_eq!(glob("/foo/**5/bar").max_depth(), 6);
+    }
+
+    #[test]
+    fn parse_recursion_too_deep() {
+        let result = parse(&format!("{ROOT}/foo/**256"), 3);
+        assert!(matches!(result, Err(ParseError::RecursionDepth(256))));
+
+        let result = parse(&format!("{ROOT}/foo/**99999999999"), 3);
+        assert!(matches!(result, Err(ParseError::RecursionDepth(u32::MAX))));
+
+        let result = parse(&format!("{ROOT}/foo/**"), 256);
+        assert!(matches!(result, Err(ParseError::RecursionDepth(256))));
+    }
+
+    #[test]
+    fn parse_alternations() {
+        let globs = parse(&format!("{ROOT}/{{foo,bar/{{baz,quux}}}}/{{a,b}}"), 3)
+            .unwrap();
+
+        let roots = globs.iter()
+            .map(|glob| glob.root().to_path_buf())
+            .collect::<Vec<_>>();
+
+        assert_eq! {
+            roots,
+            [
+                "/foo/a", "/foo/b",
+                "/bar/baz/a", "/bar/baz/b",
+                "/bar/quux/a", "/bar/quux/b",
+            ].iter().map(|path| PathBuf::from(format!("{ROOT}{path}"))).collect::<Vec<_>>()
+        };
+    }
+
+    #[test]
+    fn parse_alternation_empty() {
+        let globs = parse(&format!("{ROOT}/foo{{,.txt}}"), 3)
+            .unwrap();
+
+        assert_eq!(globs.len(), 2);
+        assert_eq!(globs[0].root(), Path::new(&format!("{ROOT}/foo")));
+        assert_eq!(globs[1].root(), Path::new(&format!("{ROOT}/foo.txt")));
+    }
+
+    #[test]
+    fn parse_alternation_in_class() {
+        let glob = glob("/foo/[{,]");
+
+        assert!(is_match(&glob, "/foo/{"));
+        assert!(is_match(&glob, "/foo/,"));
+    }
+
+    #[test]
+    fn is_match_star() {
+        let glob = glob("/foo/*.txt");
+
+        assert!(is_match(&glob, "/foo/bar.txt"));
+        assert!(is_match(&glob, "/foo/.txt"));
+        assert!(!is_match(&glob, "/foo/bar.log"));
+        assert!(!is_match(&glob, "/foo/bar/baz.txt"));
+        assert!(!is_match(&glob, "/bar/baz.txt"));
+    }
+
+    #[test]
+    fn is_match_question_mark() {
+        let glob = glob("/foo/ba?");
+
+        assert!(is_match(&glob, "/foo/bar"));
+        assert!(is_match(&glob, "/foo/baż"));
+        assert!(!is_match(&glob, "/foo/ba"));
+        assert!(!is_match(&glob, "/foo/barr"));
+    }
+
+    #[test]
+    fn is_match_class() {
+        let glob = glob("/foo/[a-c]x[-_]");
+
+        assert!(is_match(&glob, "/foo/ax-"));
+        assert!(is_match(&glob, "/foo/cx_"));
+        assert!(!is_match(&glob, "/foo/dx-"));
+        assert!(!is_match(&glob, "/foo/ax+"));
+    }
+
+    #[test]
+    fn is_match_class_negated() {
+        let glob = glob("/foo/[!ab]");
+
+        assert!(is_match(&glob, "/foo/c"));
+        assert!(!is_match(&glob, "/foo/a"));
+        assert!(!is_match(&glob, "/foo/b"));
+    }
+
+    #[test]
+    fn is_match_class_bracket() {
+        let glob = glob("/foo/[]]");
+
+        assert!(is_match(&glob, "/foo/]"));
+        assert!(!is_match(&glob, "/foo/a"));
+    }
+
+    #[test]
+    fn is_match_special_chars() {
+        let glob = glob("/foo/(bar)+.$*");
+
+        assert!(is_match(&glob, "/foo/(bar)+.$"));
+        assert!(is_match(&glob, "/foo/(bar)+.$baz"));
+        assert!(!is_match(&glob, "/foo/barr.$"));
+    }
+
+    #[test]
+    fn is_match_recursion() {
+        let glob = glob("/foo/**/bar");
+
+        assert!(is_match(&glob, "/foo/bar"));
+        assert!(is_match(&glob, "/foo/a/bar"));
+        assert!(is_match(&glob, "/foo/a/b/c/bar"));
+        assert!(!is_match(&glob, "/foo/a/b/c/d/bar"));
+        assert!(!is_match(&glob, "/foo/a/baz"));
+    }
+
+    #[test]
+    fn is_match_recursion_custom_depth() {
+        let glob = glob("/foo/**1/*");
+
+        assert!(is_match(&glob, "/foo/a"));
+        assert!(is_match(&glob, "/foo/a/b"));
+        assert!(!is_match(&glob, "/foo/a/b/c"));
+    }
+
+    #[test]
+    fn is_match_alternation_with_separator() {
+        let globs = parse(&format!("{ROOT}/{{foo/bar,baz}}/*"), 3)
+            .unwrap();
+
+        let is_match_any = |path: &str| {
+            globs.iter().any(|glob| is_match(glob, path))
+        };
+
+        assert!(is_match_any("/foo/bar/quux"));
+        assert!(is_match_any("/baz/quux"));
+        assert!(!is_match_any("/foo/quux"));
+    }
+
+    /// Checks whether paths below the given one can match the given glob.
+    fn is_prefix_match(glob: &Glob, path: &str) -> bool {
+        glob.is_prefix_match(&PathBuf::from(format!("{ROOT}{path}")))
+    }
+
+    #[test]
+    fn is_prefix_match_literal() {
+        let glob = glob("/foo/bar");
+
+        assert!(!is_prefix_match(&glob, "/foo/bar"));
+        assert!(!is_prefix_match(&glob, "/foo/bar/baz"));
+    }
+
+    #[test]
+    fn is_prefix_match_star() {
+        let glob = glob("/foo/*/bar/baz");
+
+        assert!(is_prefix_match(&glob, "/foo"));
+        assert!(is_prefix_match(&glob, "/foo/a"));
+        assert!(is_prefix_match(&glob, "/foo/a/bar"));
+        assert!(!is_prefix_match(&glob, "/foo/a/quux"));
+        assert!(!is_prefix_match(&glob, "/foo/a/bar/baz"));
+        assert!(!is_prefix_match(&glob, "/quux"));
+    }
+
+    #[test]
+    fn is_prefix_match_recursion() {
+        let glob = glob("/foo/**2/bar/*");
+
+        assert!(is_prefix_match(&glob, "/foo/a"));
+        assert!(is_prefix_match(&glob, "/foo/a/b"));
+        assert!(is_prefix_match(&glob, "/foo/a/b/bar"));
+        assert!(is_prefix_match(&glob, "/foo/bar"));
+        assert!(!is_prefix_match(&glob, "/foo/a/b/c"));
+        assert!(!is_prefix_match(&glob, "/foo/bar/a/b"));
+    }
+
+    #[test]
+    fn is_prefix_match_recursion_last() {
+        let glob = glob("/foo/*/**1");
+
+        assert!(is_prefix_match(&glob, "/foo/a"));
+        assert!(!is_prefix_match(&glob, "/foo/a/b"));
+    }
+
+    #[test]
+    fn escape_special_chars() {
+        assert_eq!(escape("foo"), "foo");
+        assert_eq!(escape("f*o?"), "f[*]o[?]");
+        assert_eq!(escape("[foo]"), "[[]foo]");
+        assert_eq!(escape("{foo,bar}"), "[{]foo[,]bar[}]");
+    }
+
+    #[test]
+    fn parse_escaped() {
+        let globs = parse(&format!("{ROOT}/{}/*", escape("{f*o,[bar]}")), 3)
+            .unwrap();
+        assert_eq!(globs.len(), 1);
+
+        let glob = &globs[0];
+        assert_eq!(glob.root(), Path::new(&format!("{ROOT}/{{f*o,[bar]}}")));
+        assert_eq!(glob.max_depth(), 1);
+        assert!(is_match(glob, "/{f*o,[bar]}/quux"));
+        assert!(!is_match(glob, "/{fooo,[bar]}/quux"));
+    }
+
+    #[test]
+    fn parse_escaped_after_wildcard() {
+        let glob = glob(&format!("/*/{}", escape("f?o")));
+
+        assert!(is_match(&glob, "/foo/f?o"));
+        assert!(!is_match(&glob, "/foo/foo"));
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn is_match_non_utf8() {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        let glob = glob("/foo/*bar");
+
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"/foo/\xff\xfebar"));
+        assert!(glob.is_match(path));
+    }
+
+    #[cfg(target_family = "windows")]
+    #[test]
+    fn is_match_case_insensitive() {
+        let glob = glob("/foo/*.TXT");
+
+        assert!(is_match(&glob, "/foo/bar.txt"));
+        assert!(is_match(&glob, "/foo/BAR.txt"));
+    }
+}
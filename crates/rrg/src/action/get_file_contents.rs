@@ -2,7 +2,7 @@
 //
 // Use of this source code is governed by an MIT-style license that can be found
 // in the LICENSE file or at https://opensource.org/licenses/MIT.
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Limit on the size of individual file part blob sent to the blob sink.
 const MAX_BLOB_LEN: usize = 2 * 1024 * 1024; // 2 MiB.
@@ -11,68 +11,224 @@ const MAX_BLOB_LEN: usize = 2 * 1024 * 1024; // 2 MiB.
 pub struct Args {
     /// Path to the file to get the contents of.
     path: PathBuf,
+    /// Template of the path (if variables in it are to be interpolated).
+    path_template: Option<crate::interpolate::Template>,
+    /// Ranges of the file to get the contents of.
+    ranges: Vec<Range>,
+    /// Whether to skip sending blobs altogether and only report digests.
+    hashes_only: bool,
+    /// SHA-256 digests of file parts that should not be sent to the blob sink.
+    known_blob_sha256s: std::collections::HashSet<[u8; 32]>,
+    /// Whether to compress blobs sent to the blob sink.
+    compress_blobs: bool,
+    /// Whether to report holes of sparse files instead of sending them.
+    detect_holes: bool,
+}
+
+/// A range of the file to get the contents of.
+struct Range {
     /// Offset from which to read the file contents.
     offset: u64,
-    /// Number of bytes to read from the file.
+    /// Number of bytes to read from the file (`usize::MAX` to read to the end).
     len: usize,
 }
 
 /// Result of the `get_file_contents` action.
 pub struct Item {
-    /// Byte offset of the file part sent to the blob sink.
+    /// Byte offset of the file part.
     offset: u64,
-    /// Number of bytes of the file part sent to the blob sink.
-    len: usize,
+    /// Number of bytes of the file part.
+    len: u64,
     /// SHA-256 digest of the file part sent to the blob sink.
-    blob_sha256: [u8; 32],
+    ///
+    /// This is `None` if the file part is a hole (which is never sent).
+    blob_sha256: Option<[u8; 32]>,
+    /// Whether sending the file part to the blob sink was skipped.
+    blob_skipped: bool,
+    /// Interpolation of the path that produced the file (if any).
+    interpolation: Option<crate::interpolate::Interpolation>,
 }
 
 /// Handle invocations of the `get_file_contents` action.
 pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let path_template = args.path_template.as_ref();
+    crate::interpolate::for_each_path(&args.path, path_template, |path, interpolation| {
+        handle_path(session, &args, path, interpolation)
+    })
+}
+
+/// Handles a single file of the `get_file_contents` action invocation.
+fn handle_path<S>(
+    session: &mut S,
+    args: &Args,
+    path: &Path,
+    interpolation: Option<&crate::interpolate::Interpolation>,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut file = std::fs::File::open(path)
+        .map_err(crate::session::Error::action)?;
+    let metadata = file.metadata()
+        .map_err(crate::session::Error::action)?;
+
+    for range in &args.ranges {
+        handle_range(session, args, &mut file, &metadata, range, interpolation)?;
+    }
+
+    Ok(())
+}
+
+/// Handles a single range of the `get_file_contents` action invocation.
+fn handle_range<S>(
+    session: &mut S,
+    args: &Args,
+    file: &mut std::fs::File,
+    metadata: &std::fs::Metadata,
+    range: &Range,
+    interpolation: Option<&crate::interpolate::Interpolation>,
+) -> crate::session::Result<()>
 where
     S: crate::session::Session,
 {
     use std::io::{Read as _, Seek as _};
     use sha2::Digest as _;
 
-    let mut file = std::fs::File::open(args.path)
-        .map_err(crate::session::Error::action)?;
+    let end = range.offset.saturating_add(range.len as u64);
+    let mut offset = range.offset;
 
-    let mut offset = args.offset;
-    let mut len_left = args.len;
+    while offset < end {
+        // Without hole detection we consider the whole file to be data, so
+        // holes are read (as zeros) and sent as any other file part.
+        let data = if args.detect_holes {
+            next_data(file, metadata, offset)
+        } else {
+            Some(offset..u64::MAX)
+        };
 
-    file.seek(std::io::SeekFrom::Start(offset))
-        .map_err(crate::session::Error::action)?;
+        let data = match data {
+            Some(data) => data,
+            None => {
+                // There is no more data in the file, but there might be a hole
+                // between the current offset and the end of the file.
+                let hole_end = std::cmp::min(metadata.len(), end);
+                if offset < hole_end {
+                    session.reply(Item::hole(offset..hole_end, interpolation))?;
+                }
+                break;
+            }
+        };
+
+        if data.start > offset {
+            let hole_end = std::cmp::min(data.start, end);
+            session.reply(Item::hole(offset..hole_end, interpolation))?;
+
+            offset = hole_end;
+            continue;
+        }
+
+        let buf_len = std::cmp::min(data.end, end) - offset;
+        let buf_len = std::cmp::min(buf_len, MAX_BLOB_LEN as u64);
+        let mut buf = Vec::with_capacity(buf_len as usize);
 
-    loop {
-        let mut buf = vec![0; std::cmp::min(len_left, MAX_BLOB_LEN)];
+        file.seek(std::io::SeekFrom::Start(offset))
+            .map_err(crate::session::Error::action)?;
 
-        let len_read = file.read(&mut buf[..])
+        // We read until the buffer is full (rather than doing a single `read`
+        // call) to have file parts always aligned the same way. Otherwise the
+        // digests would not match between requests, making it impossible to
+        // skip parts the server already has.
+        let len_read = (&mut *file).take(buf_len).read_to_end(&mut buf)
             .map_err(crate::session::Error::action)?;
 
         if len_read == 0 {
             break;
         }
 
-        buf.truncate(len_read);
+        let blob_sha256 = sha2::Sha256::digest(&buf).into();
+        let blob_skipped = args.hashes_only ||
+            args.known_blob_sha256s.contains(&blob_sha256);
 
-        let blob = crate::blob::Blob::from(buf);
-        let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
+        if !blob_skipped {
+            let mut blob = crate::blob::Blob::from(buf);
+            if args.compress_blobs {
+                blob = blob.compress();
+            }
 
-        session.send(crate::Sink::Blob, blob)?;
+            session.send(crate::Sink::Blob, blob)?;
+        }
         session.reply(Item {
             offset,
-            len: len_read,
-            blob_sha256,
+            len: len_read as u64,
+            blob_sha256: Some(blob_sha256),
+            blob_skipped,
+            interpolation: interpolation.cloned(),
         })?;
 
         offset += len_read as u64;
-        len_left -= len_read;
     }
 
     Ok(())
 }
 
+/// Returns the next range of the file that contains data.
+///
+/// See [`ospect::fs::linux::next_data`] for more details. On platforms that
+/// do not support hole detection, the whole file is considered to be data.
+#[cfg(target_os = "linux")]
+fn next_data(
+    file: &std::fs::File,
+    metadata: &std::fs::Metadata,
+    offset: u64,
+) -> Option<std::ops::Range<u64>> {
+    // Non-regular files (e.g. `/dev/zero`) do not support hole detection in
+    // a meaningful way, so we treat them as consisting of data only.
+    if !metadata.is_file() {
+        return Some(offset..u64::MAX);
+    }
+
+    match ospect::fs::linux::next_data(file, offset) {
+        Ok(data) => data,
+        Err(error) => {
+            log::warn!("failed to find data at offset {offset}: {error}");
+            Some(offset..u64::MAX)
+        }
+    }
+}
+
+/// Returns the next range of the file that contains data.
+///
+/// On platforms that do not support hole detection, the whole file is
+/// considered to be data.
+#[cfg(not(target_os = "linux"))]
+fn next_data(
+    _file: &std::fs::File,
+    _metadata: &std::fs::Metadata,
+    offset: u64,
+) -> Option<std::ops::Range<u64>> {
+    Some(offset..u64::MAX)
+}
+
+impl Item {
+
+    /// Creates an item corresponding to a hole in the given range of the file.
+    fn hole(
+        range: std::ops::Range<u64>,
+        interpolation: Option<&crate::interpolate::Interpolation>,
+    ) -> Item {
+        Item {
+            offset: range.start,
+            len: range.end - range.start,
+            blob_sha256: None,
+            blob_skipped: false,
+            interpolation: interpolation.cloned(),
+        }
+    }
+}
+
 impl crate::request::Args for Args {
 
     type Proto = rrg_proto::get_file_contents::Args;
@@ -83,24 +239,66 @@ impl crate::request::Args for Args {
         let path = PathBuf::try_from(proto.take_path())
             .map_err(|error| ParseArgsError::invalid_field("path", error))?;
 
-        let len = match proto.length() {
-            0 => usize::MAX,
-            len if len > MAX_BLOB_LEN as u64 => {
-                return Err(ParseArgsError::invalid_field("length", LenError {
-                    len,
-                }));
+        let path_template = if proto.interpolate_path() {
+            let path_template = crate::interpolate::Template::from_path(&path)
+                .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+            Some(path_template)
+        } else {
+            None
+        };
+
+        let ranges = if proto.ranges().is_empty() {
+            let len = parse_len(proto.length())
+                .map_err(|error| ParseArgsError::invalid_field("length", error))?;
+
+            vec![Range {
+                offset: proto.offset(),
+                len,
+            }]
+        } else {
+            if proto.offset() != 0 || proto.length() != 0 {
+                return Err(ParseArgsError::invalid_field("ranges", RangesConflictError));
             }
-            len => len as usize,
+
+            proto.take_ranges().into_iter()
+                .map(|range| Ok(Range {
+                    offset: range.offset(),
+                    len: parse_len(range.length())
+                        .map_err(|error| ParseArgsError::invalid_field("ranges", error))?,
+                }))
+                .collect::<Result<_, _>>()?
         };
 
+        let known_blob_sha256s = proto.take_known_blob_sha256s().into_iter()
+            .map(|sha256| <[u8; 32]>::try_from(sha256).map_err(|sha256| {
+                ParseArgsError::invalid_field("known_blob_sha256s", Sha256LenError {
+                    len: sha256.len(),
+                })
+            }))
+            .collect::<Result<_, _>>()?;
+
         Ok(Args {
             path,
-            offset: proto.offset(),
-            len,
+            path_template,
+            ranges,
+            hashes_only: proto.hashes_only(),
+            known_blob_sha256s,
+            compress_blobs: proto.compress_blobs(),
+            detect_holes: proto.detect_holes(),
         })
     }
 }
 
+/// Parses the length of a range as specified in the protobuf message.
+fn parse_len(len: u64) -> Result<usize, LenError> {
+    match len {
+        0 => Ok(usize::MAX),
+        len if len > MAX_BLOB_LEN as u64 => Err(LenError { len }),
+        len => Ok(len as usize),
+    }
+}
+
 impl crate::response::Item for Item {
 
     type Proto = rrg_proto::get_file_contents::Result;
@@ -108,8 +306,15 @@ impl crate::response::Item for Item {
     fn into_proto(self) -> Self::Proto {
         let mut proto = Self::Proto::default();
         proto.set_offset(self.offset);
-        proto.set_length(self.len as u64);
-        proto.set_blob_sha256(self.blob_sha256.into());
+        proto.set_length(self.len);
+        match self.blob_sha256 {
+            Some(blob_sha256) => proto.set_blob_sha256(blob_sha256.into()),
+            None => proto.set_hole(true),
+        }
+        proto.set_blob_skipped(self.blob_skipped);
+        if let Some(interpolation) = self.interpolation {
+            proto.set_interpolation(interpolation.into());
+        }
 
         proto
     }
@@ -135,6 +340,37 @@ impl std::fmt::Display for LenError {
 impl std::error::Error for LenError {
 }
 
+/// An error indicating that the action was invoked with an invalid digest.
+#[derive(Debug)]
+struct Sha256LenError {
+    len: usize,
+}
+
+impl std::fmt::Display for Sha256LenError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "invalid SHA-256 digest length: {}", self.len)
+    }
+}
+
+impl std::error::Error for Sha256LenError {
+}
+
+/// An error indicating that the action was invoked with both the ranges and
+/// the legacy offset and length.
+#[derive(Debug)]
+struct RangesConflictError;
+
+impl std::fmt::Display for RangesConflictError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "ranges specified together with offset or length")
+    }
+}
+
+impl std::error::Error for RangesConflictError {
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -150,8 +386,15 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("foo"),
-            offset: 0,
-            len: usize::MAX,
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: usize::MAX,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -172,8 +415,15 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("foo"),
-            offset: 0,
-            len: usize::MAX,
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: usize::MAX,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -202,8 +452,15 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("foo"),
-            offset: 5,
-            len: usize::MAX,
+            path_template: None,
+            ranges: vec![Range {
+                offset: 5,
+                len: usize::MAX,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -232,8 +489,15 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("foo"),
-            offset: 0,
-            len: 5,
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: 5,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -258,8 +522,15 @@ mod tests {
     fn handle_big_file_to_len() {
         let args = Args {
             path: PathBuf::from("/dev/zero"),
-            offset: 0,
-            len: MAX_BLOB_LEN * 2 + 1337,
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: MAX_BLOB_LEN * 2 + 1337,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -270,11 +541,11 @@ mod tests {
 
         let item = session.reply::<Item>(0);
         assert_eq!(item.offset, 0);
-        assert_eq!(item.len, MAX_BLOB_LEN);
+        assert_eq!(item.len, MAX_BLOB_LEN as u64);
 
         let item = session.reply::<Item>(1);
         assert_eq!(item.offset, MAX_BLOB_LEN as u64);
-        assert_eq!(item.len, MAX_BLOB_LEN);
+        assert_eq!(item.len, MAX_BLOB_LEN as u64);
 
         let item = session.reply::<Item>(2);
         assert_eq!(item.offset, MAX_BLOB_LEN as u64 * 2);
@@ -298,8 +569,15 @@ mod tests {
     fn handle_big_file_from_offset_to_len() {
         let args = Args {
             path: PathBuf::from("/dev/zero"),
-            offset: 0xb33f,
-            len: MAX_BLOB_LEN + 1337,
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0xb33f,
+                len: MAX_BLOB_LEN + 1337,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -310,10 +588,426 @@ mod tests {
 
         let item = session.reply::<Item>(0);
         assert_eq!(item.offset, 0xb33f);
-        assert_eq!(item.len, MAX_BLOB_LEN);
+        assert_eq!(item.len, MAX_BLOB_LEN as u64);
 
         let item = session.reply::<Item>(1);
         assert_eq!(item.offset, 0xb33f + MAX_BLOB_LEN as u64);
         assert_eq!(item.len, 1337);
     }
+
+    #[test]
+    fn handle_hashes_only() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: usize::MAX,
+            }],
+            hashes_only: true,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 0);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.len, 10);
+        assert!(item.blob_skipped);
+
+        use sha2::Digest as _;
+        assert_eq!(item.blob_sha256, Some(sha2::Sha256::digest(b"0123456789").into()));
+    }
+
+    #[test]
+    // `/dev/zero` is not available on Windows (nor there is an equivalent).
+    #[cfg_attr(target_family = "windows", ignore)]
+    fn handle_known_blob_sha256s() {
+        use sha2::Digest as _;
+
+        let known_blob_sha256 = sha2::Sha256::digest(vec![0; MAX_BLOB_LEN]).into();
+
+        let args = Args {
+            path: PathBuf::from("/dev/zero"),
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: MAX_BLOB_LEN * 2 + 1337,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: [known_blob_sha256].into(),
+            compress_blobs: false,
+            detect_holes: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 3);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.blob_sha256, Some(known_blob_sha256));
+        assert!(item.blob_skipped);
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.blob_sha256, Some(known_blob_sha256));
+        assert!(item.blob_skipped);
+
+        let item = session.reply::<Item>(2);
+        assert_eq!(item.len, 1337);
+        assert!(!item.blob_skipped);
+
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 1);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes().len(), 1337);
+    }
+
+    #[test]
+    fn args_from_proto_invalid_known_blob_sha256() {
+        let mut proto = rrg_proto::get_file_contents::Args::default();
+        proto.set_path(PathBuf::from("/foo").into());
+        proto.mut_known_blob_sha256s().push(vec![0; 31]);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn handle_compress_blobs() {
+        use sha2::Digest as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let content = b"foo bar baz\n".repeat(1024);
+        std::fs::write(tempdir.path().join("foo"), &content)
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: usize::MAX,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: true,
+            detect_holes: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        // The digest should be the one of the uncompressed content.
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.len, content.len() as u64);
+        assert_eq!(item.blob_sha256, Some(sha2::Sha256::digest(&content).into()));
+
+        // The content is very repetitive, so it should have been compressed.
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert!(blob.as_bytes().len() < content.len());
+    }
+
+    #[test]
+    fn handle_multiple_ranges() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"0123456789")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            path_template: None,
+            ranges: vec![
+                Range {
+                    offset: 7,
+                    len: 2,
+                },
+                Range {
+                    offset: 1,
+                    len: 3,
+                },
+            ],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 2);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 7);
+        assert_eq!(item.len, 2);
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.offset, 1);
+        assert_eq!(item.len, 3);
+
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 2);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), b"78");
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 1);
+        assert_eq!(blob.as_bytes(), b"123");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_sparse_file() {
+        use std::os::unix::fs::FileExt as _;
+
+        const LEN: u64 = 16 * 1024 * 1024;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let file = std::fs::File::create(tempdir.path().join("foo"))
+            .unwrap();
+        file.set_len(LEN)
+            .unwrap();
+        file.write_at(b"foo", LEN / 2)
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: usize::MAX,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let items = session.replies::<Item>().collect::<Vec<_>>();
+
+        // Parts should cover the whole file without gaps.
+        let mut offset = 0;
+        for item in &items {
+            assert_eq!(item.offset, offset);
+            offset += item.len;
+        }
+        assert_eq!(offset, LEN);
+
+        // The filesystem might not support sparse files, in which case there
+        // is nothing more to verify.
+        let holes = items.iter()
+            .filter(|item| item.blob_sha256.is_none())
+            .collect::<Vec<_>>();
+        if holes.is_empty() {
+            return;
+        }
+
+        // Holes are never sent, so we should send much less than the whole file.
+        let blobs_len = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(|blob| blob.as_bytes().len() as u64)
+            .sum::<u64>();
+        assert!(blobs_len < LEN);
+        assert!(blobs_len >= 3);
+
+        for hole in holes {
+            assert!(!hole.blob_skipped);
+            assert!(hole.offset + hole.len <= LEN / 2 || hole.offset >= LEN / 2 + 3);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_sparse_file_no_detect_holes() {
+        use std::os::unix::fs::FileExt as _;
+
+        const LEN: u64 = 4 * 1024 * 1024;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let file = std::fs::File::create(tempdir.path().join("foo"))
+            .unwrap();
+        file.set_len(LEN)
+            .unwrap();
+        file.write_at(b"foo", LEN / 2)
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            path_template: None,
+            ranges: vec![Range {
+                offset: 0,
+                len: usize::MAX,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        // Holes should be sent as regular file parts.
+        assert!(session.replies::<Item>().all(|item| item.blob_sha256.is_some()));
+
+        let blobs_len = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(|blob| blob.as_bytes().len() as u64)
+            .sum::<u64>();
+        assert_eq!(blobs_len, LEN);
+    }
+
+    #[test]
+    fn handle_interpolation() {
+        let temp_dir = ospect::os::temp_dir()
+            .unwrap();
+
+        // We create the temporary folder in the system folder for temporary
+        // files, so we can refer to it using a variable.
+        let tempdir = tempfile::tempdir_in(&temp_dir)
+            .unwrap();
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"hello\n")
+            .unwrap();
+
+        let template = format!("%%environ_temp%%/{tempdir_name}/foo");
+
+        let args = Args {
+            path: PathBuf::from(&template),
+            path_template: Some(template.parse().unwrap()),
+            ranges: vec![Range {
+                offset: 0,
+                len: usize::MAX,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        let interpolation = item.interpolation.as_ref()
+            .unwrap();
+        assert_eq!(interpolation.raw, template);
+        assert!(interpolation.bindings.contains_key(&crate::interpolate::Var::EnvironTemp));
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), b"hello\n");
+    }
+
+    #[test]
+    fn handle_interpolation_non_existent() {
+        let tempdir = tempfile::tempdir_in(ospect::os::temp_dir().unwrap())
+            .unwrap();
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str()
+            .unwrap();
+
+        let template = format!("%%environ_temp%%/{tempdir_name}/foo");
+
+        let args = Args {
+            path: PathBuf::from(&template),
+            path_template: Some(template.parse().unwrap()),
+            ranges: vec![Range {
+                offset: 0,
+                len: usize::MAX,
+            }],
+            hashes_only: false,
+            known_blob_sha256s: std::collections::HashSet::new(),
+            compress_blobs: false,
+            detect_holes: false,
+        };
+
+        // Interpolated paths are not expected to exist, so instead of failing
+        // the file should be just skipped.
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_path() {
+        let mut proto = rrg_proto::get_file_contents::Args::default();
+        proto.set_path(PathBuf::from("%%users.homedir%%/.bashrc").into());
+        proto.set_interpolate_path(true);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+        assert!(args.path_template.is_some());
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_path_unknown_var() {
+        let mut proto = rrg_proto::get_file_contents::Args::default();
+        proto.set_path(PathBuf::from("%%foo.bar%%/.bashrc").into());
+        proto.set_interpolate_path(true);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_ranges() {
+        let mut range = rrg_proto::get_file_contents::Range::default();
+        range.set_offset(42);
+
+        let mut proto = rrg_proto::get_file_contents::Args::default();
+        proto.set_path(PathBuf::from("/foo").into());
+        proto.mut_ranges().push(range);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+
+        assert_eq!(args.ranges.len(), 1);
+        assert_eq!(args.ranges[0].offset, 42);
+        assert_eq!(args.ranges[0].len, usize::MAX);
+    }
+
+    #[test]
+    fn args_from_proto_ranges_and_offset() {
+        let mut proto = rrg_proto::get_file_contents::Args::default();
+        proto.set_path(PathBuf::from("/foo").into());
+        proto.set_offset(42);
+        proto.mut_ranges().push(rrg_proto::get_file_contents::Range::default());
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
 }
@@ -0,0 +1,574 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+use std::path::{Path, PathBuf};
+
+/// Size of the buffer used for reading the file contents.
+const BUF_LEN: usize = 1024 * 1024; // 1 MiB.
+
+/// Arguments of the `get_file_hash` action.
+pub struct Args {
+    /// Absolute path to the file to get the hash of.
+    path: PathBuf,
+    /// Template of the path (if variables in it are to be interpolated).
+    path_template: Option<crate::interpolate::Template>,
+    /// Offset from which to hash the file contents.
+    offset: u64,
+    /// Number of bytes to hash from the file.
+    len: u64,
+    /// Whether to collect MD5 digest of the file contents.
+    md5: bool,
+    /// Whether to collect SHA-1 digest of the file contents.
+    sha1: bool,
+    /// Whether to collect SHA-256 digest of the file contents.
+    sha256: bool,
+}
+
+/// Result of the `get_file_hash` action.
+struct Item {
+    /// Canonical path of the hashed file.
+    path: PathBuf,
+    /// Offset from which the file contents were hashed.
+    offset: u64,
+    /// Number of bytes of the file that were hashed.
+    len: u64,
+    /// MD5 digest of the file contents.
+    #[cfg(feature = "action-get_file_hash-md5")]
+    md5: Option<[u8; 16]>,
+    /// SHA-1 digest of the file contents.
+    #[cfg(feature = "action-get_file_hash-sha1")]
+    sha1: Option<[u8; 20]>,
+    /// SHA-256 digest of the file contents.
+    #[cfg(feature = "action-get_file_hash-sha256")]
+    sha256: Option<[u8; 32]>,
+    /// Interpolation of the path that produced the hashed file (if any).
+    interpolation: Option<crate::interpolate::Interpolation>,
+}
+
+/// Handles invocations of the `get_file_hash` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    if args.md5 && !cfg!(feature = "action-get_file_hash-md5") {
+        log::warn!("MD5 digest requested but not supported");
+    }
+    if args.sha1 && !cfg!(feature = "action-get_file_hash-sha1") {
+        log::warn!("SHA-1 digest requested but not supported");
+    }
+    if args.sha256 && !cfg!(feature = "action-get_file_hash-sha256") {
+        log::warn!("SHA-256 digest requested but not supported");
+    }
+
+    let path_template = args.path_template.as_ref();
+    crate::interpolate::for_each_path(&args.path, path_template, |path, interpolation| {
+        handle_path(session, &args, path, interpolation)
+    })
+}
+
+/// Handles the `get_file_hash` action for a single path.
+fn handle_path<S>(
+    session: &mut S,
+    args: &Args,
+    path: &Path,
+    interpolation: Option<&crate::interpolate::Interpolation>,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Read as _, Seek as _};
+
+    if path.is_relative() {
+        use std::io::{Error, ErrorKind};
+
+        let error = Error::new(ErrorKind::InvalidInput, "relative path");
+        return Err(crate::session::Error::action(error));
+    }
+
+    let path = path.canonicalize()
+        .map_err(crate::session::Error::action)?;
+
+    let mut file = std::fs::File::open(&path)
+        .map_err(crate::session::Error::action)?;
+
+    file.seek(std::io::SeekFrom::Start(args.offset))
+        .map_err(crate::session::Error::action)?;
+
+    let mut file = file.take(args.len);
+
+    #[cfg(feature = "action-get_file_hash-md5")]
+    let mut md5_hasher = if args.md5 {
+        Some(<md5::Md5 as md5::Digest>::new())
+    } else {
+        None
+    };
+
+    #[cfg(feature = "action-get_file_hash-sha1")]
+    let mut sha1_hasher = if args.sha1 {
+        Some(<sha1::Sha1 as sha1::Digest>::new())
+    } else {
+        None
+    };
+
+    #[cfg(feature = "action-get_file_hash-sha256")]
+    let mut sha256_hasher = if args.sha256 {
+        Some(<sha2::Sha256 as sha2::Digest>::new())
+    } else {
+        None
+    };
+
+    let mut buf = vec![0; BUF_LEN];
+    let mut len = 0;
+
+    loop {
+        let len_read = match file.read(&mut buf[..]) {
+            Ok(0) => break,
+            Ok(len_read) => len_read,
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {
+                continue
+            }
+            Err(error) => return Err(crate::session::Error::action(error)),
+        };
+
+        #[allow(unused_variables)] // Unused if all digest features are off.
+        let chunk = &buf[..len_read];
+
+        #[cfg(feature = "action-get_file_hash-md5")]
+        if let Some(ref mut md5_hasher) = md5_hasher {
+            <_ as md5::Digest>::update(md5_hasher, chunk);
+        }
+
+        #[cfg(feature = "action-get_file_hash-sha1")]
+        if let Some(ref mut sha1_hasher) = sha1_hasher {
+            <_ as sha1::Digest>::update(sha1_hasher, chunk);
+        }
+
+        #[cfg(feature = "action-get_file_hash-sha256")]
+        if let Some(ref mut sha256_hasher) = sha256_hasher {
+            <_ as sha2::Digest>::update(sha256_hasher, chunk);
+        }
+
+        len += len_read as u64;
+
+        // Hashing big files can take a while, so we need to let Fleetspeak
+        // know that we are still alive and not stuck.
+        session.heartbeat();
+    }
+
+    session.reply(Item {
+        path,
+        offset: args.offset,
+        len,
+        #[cfg(feature = "action-get_file_hash-md5")]
+        md5: md5_hasher.map(<_ as md5::Digest>::finalize).map(<[u8; 16]>::from),
+        #[cfg(feature = "action-get_file_hash-sha1")]
+        sha1: sha1_hasher.map(<_ as sha1::Digest>::finalize).map(<[u8; 20]>::from),
+        #[cfg(feature = "action-get_file_hash-sha256")]
+        sha256: sha256_hasher.map(<_ as sha2::Digest>::finalize).map(<[u8; 32]>::from),
+        interpolation: interpolation.cloned(),
+    })?;
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_file_hash::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let path = PathBuf::try_from(proto.take_path())
+            .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+        let path_template = if proto.interpolate_path() {
+            let path_template = crate::interpolate::Template::from_path(&path)
+                .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+            Some(path_template)
+        } else {
+            None
+        };
+
+        let len = match proto.length() {
+            0 => u64::MAX,
+            len => len,
+        };
+
+        Ok(Args {
+            path,
+            path_template,
+            offset: proto.offset(),
+            len,
+            md5: proto.md5(),
+            sha1: proto.sha1(),
+            sha256: proto.sha256(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_file_hash::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::get_file_hash::Result::default();
+        proto.set_path(self.path.into());
+        proto.set_offset(self.offset);
+        proto.set_length(self.len);
+
+        #[cfg(feature = "action-get_file_hash-md5")]
+        if let Some(md5) = self.md5 {
+            proto.set_md5(md5.to_vec());
+        }
+        #[cfg(feature = "action-get_file_hash-sha1")]
+        if let Some(sha1) = self.sha1 {
+            proto.set_sha1(sha1.to_vec());
+        }
+        #[cfg(feature = "action-get_file_hash-sha256")]
+        if let Some(sha256) = self.sha256 {
+            proto.set_sha256(sha256.to_vec());
+        }
+
+        if let Some(interpolation) = self.interpolation {
+            proto.set_interpolation(interpolation.into());
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_non_existent() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            path_template: None,
+            offset: 0,
+            len: u64::MAX,
+            md5: true,
+            sha1: true,
+            sha256: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_relative_path() {
+        let args = Args {
+            path: PathBuf::from("foo").join("bar"),
+            path_template: None,
+            offset: 0,
+            len: u64::MAX,
+            md5: true,
+            sha1: true,
+            sha256: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_canonical_path() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.join("dir"))
+            .unwrap();
+        std::fs::write(tempdir.join("file"), "hello\n")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.join("dir").join("..").join("file"),
+            path_template: None,
+            offset: 0,
+            len: u64::MAX,
+            md5: false,
+            sha1: false,
+            sha256: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, tempdir.join("file"));
+        assert_eq!(item.offset, 0);
+        assert_eq!(item.len, 6);
+    }
+
+    #[cfg(all(
+        feature = "action-get_file_hash-md5",
+        feature = "action-get_file_hash-sha1",
+        feature = "action-get_file_hash-sha256",
+    ))]
+    #[test]
+    fn handle_all_digests() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), "hello\n")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("file"),
+            path_template: None,
+            offset: 0,
+            len: u64::MAX,
+            md5: true,
+            sha1: true,
+            sha256: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        // All digests below are pre-computed by the `md5sum`, `sha1sum` and
+        // `sha256sum` tools.
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.md5, Some([
+            0xb1, 0x94, 0x6a, 0xc9, 0x24, 0x92, 0xd2, 0x34,
+            0x7c, 0x62, 0x35, 0xb4, 0xd2, 0x61, 0x11, 0x84,
+        ]));
+        assert_eq!(item.sha1, Some([
+            0xf5, 0x72, 0xd3, 0x96, 0xfa, 0xe9, 0x20, 0x66, 0x28, 0x71,
+            0x4f, 0xb2, 0xce, 0x00, 0xf7, 0x2e, 0x94, 0xf2, 0x25, 0x8f,
+        ]));
+        assert_eq!(item.sha256, Some([
+            0x58, 0x91, 0xb5, 0xb5, 0x22, 0xd5, 0xdf, 0x08,
+            0x6d, 0x0f, 0xf0, 0xb1, 0x10, 0xfb, 0xd9, 0xd2,
+            0x1b, 0xb4, 0xfc, 0x71, 0x63, 0xaf, 0x34, 0xd0,
+            0x82, 0x86, 0xa2, 0xe8, 0x46, 0xf6, 0xbe, 0x03,
+        ]));
+    }
+
+    #[cfg(feature = "action-get_file_hash-sha256")]
+    #[test]
+    fn handle_only_sha256() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), "hello\n")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("file"),
+            path_template: None,
+            offset: 0,
+            len: u64::MAX,
+            md5: false,
+            sha1: false,
+            sha256: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.reply::<Item>(0);
+        #[cfg(feature = "action-get_file_hash-md5")]
+        assert!(item.md5.is_none());
+        #[cfg(feature = "action-get_file_hash-sha1")]
+        assert!(item.sha1.is_none());
+        assert!(item.sha256.is_some());
+    }
+
+    #[cfg(all(
+        feature = "action-get_file_hash-md5",
+        feature = "action-get_file_hash-sha1",
+        feature = "action-get_file_hash-sha256",
+    ))]
+    #[test]
+    fn handle_range() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), "hello\n")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("file"),
+            path_template: None,
+            offset: 2,
+            len: 3,
+            md5: true,
+            sha1: true,
+            sha256: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        // All digests below are pre-computed (for the `llo` string) by the
+        // `md5sum`, `sha1sum` and `sha256sum` tools.
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 2);
+        assert_eq!(item.len, 3);
+        assert_eq!(item.md5, Some([
+            0x70, 0x62, 0xda, 0x73, 0x93, 0xec, 0xc3, 0x1c,
+            0x3c, 0x05, 0x64, 0x02, 0x0f, 0x85, 0xef, 0xd1,
+        ]));
+        assert_eq!(item.sha1, Some([
+            0x15, 0xeb, 0x52, 0x15, 0xaa, 0xd6, 0x73, 0x48, 0x61, 0xa5,
+            0xa5, 0xbb, 0x99, 0x72, 0x39, 0x8f, 0xde, 0xa9, 0xb8, 0xd0,
+        ]));
+        assert_eq!(item.sha256, Some([
+            0x13, 0xd8, 0x96, 0x35, 0x35, 0x57, 0xf2, 0x9e,
+            0x6c, 0x8a, 0xac, 0x4b, 0xde, 0x65, 0xc7, 0x43,
+            0xf4, 0x20, 0x6d, 0xf8, 0x20, 0xff, 0x83, 0x28,
+            0xae, 0x56, 0x7f, 0x92, 0x41, 0x89, 0xd3, 0x39,
+        ]));
+    }
+
+    #[test]
+    fn handle_offset_past_end() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), "hello\n")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("file"),
+            path_template: None,
+            offset: 1337,
+            len: u64::MAX,
+            md5: false,
+            sha1: false,
+            sha256: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 1337);
+        assert_eq!(item.len, 0);
+    }
+
+    #[test]
+    fn handle_interpolation() {
+        let temp_dir = ospect::os::temp_dir()
+            .unwrap();
+
+        // We create the temporary folder in the system folder for temporary
+        // files, so we can refer to it using a variable.
+        let tempdir = tempfile::tempdir_in(&temp_dir)
+            .unwrap();
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"hello\n")
+            .unwrap();
+
+        let template = format!("%%environ_temp%%/{tempdir_name}/file");
+
+        let args = Args {
+            path: PathBuf::from(&template),
+            path_template: Some(template.parse().unwrap()),
+            offset: 0,
+            len: u64::MAX,
+            md5: false,
+            sha1: false,
+            sha256: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, tempdir.path().join("file").canonicalize().unwrap());
+        assert_eq!(item.len, 6);
+
+        let interpolation = item.interpolation.as_ref()
+            .unwrap();
+        assert_eq!(interpolation.raw, template);
+        assert!(interpolation.bindings.contains_key(&crate::interpolate::Var::EnvironTemp));
+    }
+
+    #[test]
+    fn handle_interpolation_non_existent() {
+        let tempdir = tempfile::tempdir_in(ospect::os::temp_dir().unwrap())
+            .unwrap();
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str()
+            .unwrap();
+
+        let template = format!("%%environ_temp%%/{tempdir_name}/file");
+
+        let args = Args {
+            path: PathBuf::from(&template),
+            path_template: Some(template.parse().unwrap()),
+            offset: 0,
+            len: u64::MAX,
+            md5: false,
+            sha1: false,
+            sha256: true,
+        };
+
+        // Interpolated paths are not expected to exist, so instead of failing
+        // the file should be just skipped.
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_path() {
+        let mut proto = rrg_proto::get_file_hash::Args::default();
+        proto.set_path(PathBuf::from("%%users.homedir%%/.bashrc").into());
+        proto.set_interpolate_path(true);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+        assert!(args.path_template.is_some());
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_path_unknown_var() {
+        let mut proto = rrg_proto::get_file_hash::Args::default();
+        proto.set_path(PathBuf::from("%%foo.bar%%/.bashrc").into());
+        proto.set_interpolate_path(true);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    // `/dev/zero` is not available on Windows (nor there is an equivalent).
+    #[cfg_attr(target_family = "windows", ignore)]
+    fn handle_big_file_to_len() {
+        let args = Args {
+            path: PathBuf::from("/dev/zero"),
+            path_template: None,
+            offset: 0,
+            len: BUF_LEN as u64 * 3 + 1337,
+            md5: false,
+            sha1: false,
+            sha256: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.len, BUF_LEN as u64 * 3 + 1337);
+    }
+}
@@ -8,6 +8,8 @@ use std::path::{Path, PathBuf};
 pub struct Args {
     /// Root path to the file to get the metadata of.
     path: PathBuf,
+    /// Template of the root path (if variables in it are to be interpolated).
+    path_template: Option<crate::interpolate::Template>,
     /// Limit on the depth of recursion when visiting subfolders.
     max_depth: u32,
     /// Whether to collect MD5 digest of the file contents.
@@ -16,6 +18,8 @@ pub struct Args {
     sha1: bool,
     /// Whether to collect SHA-256 digest of the file contents.
     sha256: bool,
+    /// Whether to follow symlinks when visiting subfolders.
+    follow_symlinks: bool,
 }
 
 /// Result of the `get_file_metadata` action.
@@ -39,8 +43,17 @@ struct Item {
     // attributes!) and on macOS it is called "flags".
     /// Path to the file pointed by a symlink (if available).
     symlink: Option<PathBuf>,
+    /// Paths the symlink was resolved through (if it was followed).
+    symlink_chain: Vec<PathBuf>,
     /// Digest of the file contents.
+    #[cfg(any(
+        feature = "action-get_file_metadata-md5",
+        feature = "action-get_file_metadata-sha1",
+        feature = "action-get_file_metadata-sha256",
+    ))]
     digest: Digest,
+    /// Interpolation of the root path that the file was found under (if any).
+    interpolation: Option<crate::interpolate::Interpolation>,
 }
 
 /// Handles invocations of the `get_file_metadata` action.
@@ -48,19 +61,47 @@ pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
 where
     S: crate::session::Session,
 {
-    if args.path.is_relative() {
+    // We log warnings here instead of the `digest` method to avoid repeated
+    // messages for (potential) child files.
+    if args.md5 && !cfg!(feature = "action-get_file_metadata-md5") {
+        log::warn!("MD5 digest requested but not supported");
+    }
+    if args.sha1 && !cfg!(feature = "action-get_file_metadata-sha1") {
+        log::warn!("SHA-1 digest requested but not supported");
+    }
+    if args.sha256 && !cfg!(feature = "action-get_file_metadata-sha256") {
+        log::warn!("SHA-256 digest requested but not supported");
+    }
+
+    let path_template = args.path_template.as_ref();
+    crate::interpolate::for_each_path(&args.path, path_template, |root, interpolation| {
+        handle_root(session, &args, root, interpolation)
+    })
+}
+
+/// Handles the `get_file_metadata` action for a single root path.
+fn handle_root<S>(
+    session: &mut S,
+    args: &Args,
+    root: &Path,
+    interpolation: Option<&crate::interpolate::Interpolation>,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    if root.is_relative() {
         use std::io::{Error, ErrorKind};
 
         let error = Error::new(ErrorKind::InvalidInput, "relative path");
         return Err(crate::session::Error::action(error));
     }
 
-    let metadata = args.path.symlink_metadata()
+    let metadata = root.symlink_metadata()
         .map_err(crate::session::Error::action)?;
 
     #[cfg(target_family = "unix")]
     let ext_attrs = || -> std::io::Result<Vec<ospect::fs::ExtAttr>> {
-        ospect::fs::ext_attrs(args.path.as_ref())?
+        ospect::fs::ext_attrs(root)?
             .collect()
     }().map_err(crate::session::Error::action)?;
 
@@ -72,42 +113,41 @@ where
     let symlink;
 
     if metadata.is_symlink() {
-        path = canonicalize_parent(&args.path);
-        symlink = Some(std::fs::read_link(&args.path));
+        path = canonicalize_parent(root);
+        symlink = Some(std::fs::read_link(root));
     } else {
-        path = args.path.canonicalize();
+        path = root.canonicalize();
         symlink = None;
     };
 
     let path = path.map_err(crate::session::Error::action)?;
     let symlink = symlink.transpose().map_err(crate::session::Error::action)?;
 
-    // We log warnings here instead of the `digest` method to avoid repeated
-    // messages for (potential) child files.
-    if args.md5 && !cfg!(feature = "action-get_file_metadata-md5") {
-        log::warn!("MD5 digest requested but not supported");
-    }
-    if args.sha1 && !cfg!(feature = "action-get_file_metadata-sha1") {
-        log::warn!("SHA-1 digest requested but not supported");
-    }
-    if args.sha256 && !cfg!(feature = "action-get_file_metadata-sha256") {
-        log::warn!("SHA-256 digest requested but not supported");
-    }
-
     session.reply(Item {
         path: path.clone(),
         metadata,
         #[cfg(target_family = "unix")]
         ext_attrs,
         symlink,
-        digest: digest(&args.path, &args),
+        symlink_chain: Vec::new(),
+        #[cfg(any(
+            feature = "action-get_file_metadata-md5",
+            feature = "action-get_file_metadata-sha1",
+            feature = "action-get_file_metadata-sha256",
+        ))]
+        digest: digest(root, args.md5, args.sha1, args.sha256),
+        interpolation: interpolation.cloned(),
     })?;
 
     if args.max_depth > 0 {
-        for entry in crate::fs::walk_dir(&path)
+        let mut entries = crate::fs::walk_dir(&path)
             .map_err(crate::session::Error::action)?
-            .with_max_depth(args.max_depth)
-        {
+            .with_max_depth(args.max_depth);
+        if args.follow_symlinks {
+            entries = entries.with_follow_symlinks();
+        }
+
+        for entry in entries {
             let entry = match entry {
                 Ok(entry) => entry,
                 Err(error) => {
@@ -139,7 +179,9 @@ where
                 }
             };
 
-            let symlink = if entry.metadata.is_symlink() {
+            // Followed symlinks have metadata of their targets but we still want
+            // to report what they point to.
+            let symlink = if entry.metadata.is_symlink() || !entry.symlink_chain.is_empty() {
                 match std::fs::read_link(&entry.path) {
                     Ok(symlink) => Some(symlink),
                     Err(error) => {
@@ -155,7 +197,12 @@ where
                 None
             };
 
-            let digest = digest(&entry.path, &args);
+            #[cfg(any(
+                feature = "action-get_file_metadata-md5",
+                feature = "action-get_file_metadata-sha1",
+                feature = "action-get_file_metadata-sha256",
+            ))]
+            let digest = digest(&entry.path, args.md5, args.sha1, args.sha256);
 
             session.reply(Item {
                 path: entry.path,
@@ -163,7 +210,14 @@ where
                 #[cfg(target_family = "unix")]
                 ext_attrs,
                 symlink,
+                symlink_chain: entry.symlink_chain,
+                #[cfg(any(
+                    feature = "action-get_file_metadata-md5",
+                    feature = "action-get_file_metadata-sha1",
+                    feature = "action-get_file_metadata-sha256",
+                ))]
                 digest,
+                interpolation: interpolation.cloned(),
             })?;
         }
     }
@@ -172,22 +226,36 @@ where
 }
 
 /// Record with digest information of the file contents.
+#[cfg(any(
+    feature = "action-get_file_metadata-md5",
+    feature = "action-get_file_metadata-sha1",
+    feature = "action-get_file_metadata-sha256",
+))]
 #[derive(Default)]
-struct Digest {
+pub struct Digest {
     /// MD5 digest of the file contents.
     #[cfg(feature = "action-get_file_metadata-md5")]
-    md5: Option<[u8; 16]>,
+    pub md5: Option<[u8; 16]>,
     /// SHA-1 digest of the file contents.
     #[cfg(feature = "action-get_file_metadata-sha1")]
-    sha1: Option<[u8; 20]>,
+    pub sha1: Option<[u8; 20]>,
     /// SHA-256 digest of the file contents.
     #[cfg(feature = "action-get_file_metadata-sha256")]
-    sha256: Option<[u8; 32]>,
+    pub sha256: Option<[u8; 32]>,
 }
 
 /// Computes the digest record of the file contents using requested algorithms.
-fn digest(path: &Path, args: &Args) -> Digest {
-    if !(args.md5 || args.sha1 || args.sha256) {
+///
+/// Algorithms that are requested but not supported (because the corresponding
+/// features are disabled) are silently ignored. Errors are logged and yield
+/// an empty record.
+#[cfg(any(
+    feature = "action-get_file_metadata-md5",
+    feature = "action-get_file_metadata-sha1",
+    feature = "action-get_file_metadata-sha256",
+))]
+pub fn digest(path: &Path, md5: bool, sha1: bool, sha256: bool) -> Digest {
+    if !(md5 || sha1 || sha256) {
         // If no digests were requested, we do not need to read the file.
         return Digest::default();
     }
@@ -201,21 +269,21 @@ fn digest(path: &Path, args: &Args) -> Digest {
     };
 
     #[cfg(feature = "action-get_file_metadata-md5")]
-    let mut md5_hasher = if args.md5 {
+    let mut md5_hasher = if md5 {
         Some(<md5::Md5 as md5::Digest>::new())
     } else {
         None
     };
 
     #[cfg(feature = "action-get_file_metadata-sha1")]
-    let mut sha1_hasher = if args.sha1 {
+    let mut sha1_hasher = if sha1 {
         Some(<sha1::Sha1 as sha1::Digest>::new())
     } else {
         None
     };
 
     #[cfg(feature = "action-get_file_metadata-sha256")]
-    let mut sha256_hasher = if args.sha256 {
+    let mut sha256_hasher = if sha256 {
         Some(<sha2::Sha256 as sha2::Digest>::new())
     } else {
         None
@@ -272,12 +340,23 @@ impl crate::request::Args for Args {
         let path = PathBuf::try_from(proto.take_path())
             .map_err(|error| ParseArgsError::invalid_field("path", error))?;
 
+        let path_template = if proto.interpolate_path() {
+            let path_template = crate::interpolate::Template::from_path(&path)
+                .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+            Some(path_template)
+        } else {
+            None
+        };
+
         Ok(Args {
             path,
+            path_template,
             max_depth: proto.max_depth(),
             md5: proto.md5(),
             sha1: proto.sha1(),
             sha256: proto.sha256(),
+            follow_symlinks: proto.follow_symlinks(),
         })
     }
 }
@@ -301,6 +380,9 @@ impl crate::response::Item for Item {
         if let Some(symlink) = self.symlink {
             proto.set_symlink(symlink.into());
         }
+        for path in self.symlink_chain {
+            proto.mut_symlink_chain().push(path.into());
+        }
 
         #[cfg(feature = "action-get_file_metadata-md5")]
         if let Some(md5) = self.digest.md5 {
@@ -315,6 +397,10 @@ impl crate::response::Item for Item {
             proto.set_sha256(sha256.to_vec());
         }
 
+        if let Some(interpolation) = self.interpolation {
+            proto.set_interpolation(interpolation.into());
+        }
+
         proto
     }
 }
@@ -367,10 +453,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("foo"),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -381,10 +469,12 @@ mod tests {
     fn handle_relative() {
         let args = Args {
             path: PathBuf::from("foo/bar/baz"),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -403,10 +493,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.join("foo").to_path_buf(),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -434,10 +526,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.join("link"),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -470,10 +564,12 @@ mod tests {
 
         let args = Args {
             path: tempfile.path().to_path_buf(),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -506,10 +602,12 @@ mod tests {
 
         let args = Args {
             path: tempfile.path().to_owned(),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -538,10 +636,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.to_path_buf(),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -578,10 +678,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.to_path_buf(),
+            path_template: None,
             max_depth: 1,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -623,10 +725,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.to_path_buf(),
+            path_template: None,
             max_depth: 1,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -645,6 +749,62 @@ mod tests {
         assert_eq!(item_link.symlink, Some(tempdir.join("file")));
     }
 
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_dir_follow_symlinks() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let tempdir = tempdir.path().canonicalize()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.join("dir"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("dir").join("file"))
+            .unwrap();
+
+        std::os::unix::fs::symlink("dir", tempdir.join("link"))
+            .unwrap();
+        // Symlink cycles should not be a problem.
+        std::os::unix::fs::symlink(&tempdir, tempdir.join("dir").join("root"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.to_path_buf(),
+            path_template: None,
+            max_depth: 8,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            follow_symlinks: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        let items_by_path = session.replies::<Item>()
+            .map(|item| (item.path.clone(), item))
+            .collect::<std::collections::HashMap::<_, _>>();
+
+        // The directory is reachable both directly and through the symlink but
+        // its contents should be reported only once (through whichever path is
+        // visited first).
+        assert_eq!(items_by_path.len(), 5);
+        assert!(items_by_path.contains_key(&tempdir));
+        assert!(items_by_path.contains_key(&tempdir.join("dir")));
+        assert!(items_by_path.contains_key(&tempdir.join("link")));
+
+        let item_link = items_by_path[&tempdir.join("link")];
+        assert!(item_link.metadata.is_dir());
+        assert_eq!(item_link.symlink, Some(PathBuf::from("dir")));
+        assert_eq!(item_link.symlink_chain, vec![tempdir.join("dir")]);
+
+        let item_root = items_by_path.values()
+            .find(|item| item.path.ends_with("root"))
+            .unwrap();
+        assert!(item_root.metadata.is_dir());
+        assert_eq!(item_root.symlink_chain, vec![tempdir.clone()]);
+    }
+
     #[cfg(feature = "test-setfattr")]
     #[cfg(target_os = "linux")]
     #[test]
@@ -680,10 +840,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.to_path_buf(),
+            path_template: None,
             max_depth: 1,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -742,10 +904,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.to_path_buf(),
+            path_template: None,
             max_depth: 1,
             md5: false,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -783,10 +947,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.join("file"),
+            path_template: None,
             max_depth: 0,
             md5: true,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -817,10 +983,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.clone(),
+            path_template: None,
             max_depth: 1,
             md5: true,
             sha1: false,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -861,10 +1029,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.join("file"),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: true,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -895,10 +1065,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.clone(),
+            path_template: None,
             max_depth: 1,
             md5: false,
             sha1: true,
             sha256: false,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -939,10 +1111,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.join("file"),
+            path_template: None,
             max_depth: 0,
             md5: false,
             sha1: false,
             sha256: true,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -975,10 +1149,12 @@ mod tests {
 
         let args = Args {
             path: tempdir.clone(),
+            path_template: None,
             max_depth: 1,
             md5: false,
             sha1: false,
             sha256: true,
+            follow_symlinks: false,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -1022,6 +1198,111 @@ mod tests {
         }};
     }
 
+    #[test]
+    fn handle_interpolation() {
+        let temp_dir = ospect::os::temp_dir()
+            .unwrap();
+
+        // We create the temporary folder in the system folder for temporary
+        // files, so we can refer to it using a variable.
+        let tempdir = tempfile::tempdir_in(&temp_dir)
+            .unwrap();
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+
+        let template = format!("%%environ_temp%%/{tempdir_name}/foo");
+
+        let args = Args {
+            path: PathBuf::from(&template),
+            path_template: Some(template.parse().unwrap()),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            follow_symlinks: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, tempdir.path().join("foo").canonicalize().unwrap());
+
+        let interpolation = item.interpolation.as_ref()
+            .unwrap();
+        assert_eq!(interpolation.raw, template);
+        assert_eq!(interpolation.bindings.len(), 1);
+        assert!(interpolation.bindings.contains_key(&crate::interpolate::Var::EnvironTemp));
+    }
+
+    #[test]
+    fn handle_interpolation_non_existent() {
+        let tempdir = tempfile::tempdir_in(ospect::os::temp_dir().unwrap())
+            .unwrap();
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str()
+            .unwrap();
+
+        let template = format!("%%environ_temp%%/{tempdir_name}/foo");
+
+        let args = Args {
+            path: PathBuf::from(&template),
+            path_template: Some(template.parse().unwrap()),
+            max_depth: 0,
+            md5: false,
+            sha1: false,
+            sha256: false,
+            follow_symlinks: false,
+        };
+
+        // Interpolated paths are not expected to exist, so instead of failing
+        // the file should be just skipped.
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_path() {
+        let mut proto = rrg_proto::get_file_metadata::Args::default();
+        proto.set_path(PathBuf::from("%%users.homedir%%/.bashrc").into());
+        proto.set_interpolate_path(true);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+
+        let path_template = args.path_template
+            .unwrap();
+        assert!(path_template.has_vars());
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_path_unknown_var() {
+        let mut proto = rrg_proto::get_file_metadata::Args::default();
+        proto.set_path(PathBuf::from("%%foo.bar%%/.bashrc").into());
+        proto.set_interpolate_path(true);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_no_interpolate_path() {
+        let mut proto = rrg_proto::get_file_metadata::Args::default();
+        proto.set_path(PathBuf::from("%%foo.bar%%/.bashrc").into());
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+
+        // Paths are literal unless interpolation is requested.
+        assert!(args.path_template.is_none());
+        assert_eq!(args.path, PathBuf::from("%%foo.bar%%/.bashrc"));
+    }
+
     #[test]
     fn canonicalize_parent_empty() {
         let canonical = canonicalize_parent("")
@@ -5,13 +5,51 @@
 
 //! A handler and associated types for the timeline action.
 
-use std::path::PathBuf;
+mod snapshot;
+mod text;
+
+use std::path::{Path, PathBuf};
 
 use rrg_proto::convert::FromLossy;
 
+/// Limit on the number of threads used to traverse the filesystem.
+const MAX_WALK_THREADS: usize = 64;
+
+/// Default limit on the size of files to collect SHA-256 digests of.
+const DEFAULT_SHA256_MAX_SIZE: u64 = 64 * 1024 * 1024;
+
 /// Arguments of the `get_filesystem_timeline` action.
 pub struct Args {
+    /// Root directory to get the timeline of.
     root: PathBuf,
+    /// Template of the root (if variables in it are to be interpolated).
+    root_template: Option<crate::interpolate::Template>,
+    /// Whether to collect Linux inode flags.
+    linux_flags: bool,
+    /// Whether to collect names of extended attributes.
+    ext_attr_names: bool,
+    /// Whether to collect targets of symlinks.
+    symlinks: bool,
+    /// Limit on the size of files to collect SHA-256 digests of (if any).
+    sha256_max_size: Option<u64>,
+    /// Types of filesystems to descend into when crossing device boundaries.
+    cross_dev_fs_types: Vec<String>,
+    /// Regexes of paths to exclude from the timeline.
+    excluded_paths: Vec<regex::Regex>,
+    /// Limit on the depth of recursion when visiting subfolders.
+    max_depth: Option<u32>,
+    /// Identifier under which to save a snapshot of the timeline.
+    snapshot_id: Option<snapshot::Id>,
+    /// Identifier of a snapshot to compare the timeline against.
+    base_snapshot_id: Option<snapshot::Id>,
+    /// Text format to return the timeline in (gzchunked protos if not set).
+    text_format: Option<text::Format>,
+    /// Number of threads to traverse the filesystem with (if parallel).
+    walk_threads: Option<std::num::NonZeroUsize>,
+    /// Order of entries yielded by the parallel traversal.
+    walk_order: crate::fs::Order,
+    /// Whether to follow symlinks.
+    follow_symlinks: bool,
 }
 
 /// Result of the `get_filesystem_timeline` action.
@@ -20,6 +58,8 @@ pub struct Item {
     blob_sha256: [u8; 32],
     // Number of entries in the batch sent to the blob sink.
     entry_count: usize,
+    /// Interpolation of the root that produced the batch (if any).
+    interpolation: Option<crate::interpolate::Interpolation>,
 }
 
 /// Handles requests for the timeline action.
@@ -27,6 +67,32 @@ pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
 where
     S: crate::session::Session,
 {
+    // We log warnings here instead of when processing individual entries to
+    // avoid repeated messages.
+    if args.linux_flags && !cfg!(target_os = "linux") {
+        log::warn!("Linux flags requested but not supported");
+    }
+    if args.ext_attr_names && !cfg!(target_family = "unix") {
+        log::warn!("extended attribute names requested but not supported");
+    }
+
+    let root_template = args.root_template.as_ref();
+    crate::interpolate::for_each_path(&args.root, root_template, |root, interpolation| {
+        handle_root(session, &args, root, interpolation)
+    })
+}
+
+/// Handles the timeline action for a single root directory.
+fn handle_root<S>(
+    session: &mut S,
+    args: &Args,
+    root: &Path,
+    interpolation: Option<&crate::interpolate::Interpolation>,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use protobuf::Message as _;
     use sha2::Digest as _;
 
     // `entry_count` keeps track of the number of entries that are included in
@@ -40,36 +106,219 @@ where
     // when we process batches.
     let entry_count = std::cell::Cell::new(0);
 
-    let entries = crate::fs::walk_dir(&args.root)
+    let snapshot_dir = if args.snapshot_id.is_some() || args.base_snapshot_id.is_some() {
+        let snapshot_dir = session.args().timeline_snapshot_dir.clone()
+            .ok_or(crate::session::Error::action(MissingSnapshotDirError))?;
+
+        Some(snapshot_dir)
+    } else {
+        None
+    };
+
+    let diff = match (&snapshot_dir, &args.base_snapshot_id) {
+        (Some(snapshot_dir), Some(base_snapshot_id)) => {
+            let diff = snapshot::Diff::load(snapshot_dir, base_snapshot_id)
+                .map_err(crate::session::Error::action)?;
+
+            Some(diff)
+        }
+        _ => None,
+    };
+
+    let mut entries = crate::fs::walk_dir(root)
         .map_err(crate::session::Error::action)?
+        .with_cross_dev_fs_types(&args.cross_dev_fs_types)
+        .with_excluded_paths(args.excluded_paths.clone());
+    if let Some(max_depth) = args.max_depth {
+        entries = entries.with_max_depth(max_depth);
+    }
+    if args.follow_symlinks {
+        entries = entries.with_follow_symlinks();
+    }
+
+    type Entries = Box<dyn Iterator<Item = std::io::Result<crate::fs::Entry>>>;
+
+    let entries: Entries = match args.walk_threads {
+        Some(walk_threads) => {
+            let entries = entries.into_par(walk_threads, args.walk_order)
+                .map_err(crate::session::Error::action)?;
+
+            Box::new(entries)
+        }
+        None => Box::new(entries),
+    };
+
+    let entries = entries
         .filter_map(|entry| match entry {
             Ok(entry) => Some(entry),
             Err(error) => {
                 log::warn!("failed to obtain directory entry: {}", error);
                 None
             }
-        })
+        });
+
+    // Snapshot records have to be sorted by path (and so do the records to
+    // compare against the base snapshot), which for big filesystems cannot be
+    // done in memory, so the sorter spills them to the snapshot directory.
+    let sorter = match &snapshot_dir {
+        Some(snapshot_dir) => {
+            let sorter = snapshot::Sorter::new(snapshot_dir)
+                .map_err(crate::session::Error::action)?;
+
+            Some(sorter)
+        }
+        None => None,
+    };
+
+    // These are cells for the same reason as `entry_count`: they are used by
+    // the `entries` iterator but we also need them afterwards. Errors of the
+    // snapshot files are stored and returned once all batches are sent.
+    let snapshot_sorter = std::cell::RefCell::new(None);
+    let snapshot_writer = std::cell::RefCell::new(None);
+    let snapshot_error = std::cell::RefCell::new(None);
+
+    // Collecting digests can take arbitrarily long, so the session has to be
+    // available to the `entries` iterator as well (and so do errors raised by
+    // it, which are returned before sending the next batch).
+    let session = std::cell::RefCell::new(session);
+    let session_error = std::cell::RefCell::new(None);
+
+    type TimelineEntries<'a> = Box<dyn Iterator<Item = rrg_proto::get_filesystem_timeline::Entry> + 'a>;
+
+    let entries: TimelineEntries = match (diff, sorter) {
+        (Some(diff), Some(mut sorter)) => {
+            // Changes can be determined only once all the records are sorted,
+            // so we need to go through the whole filesystem first. Timeline
+            // entries are spilled to disk together with their records. Nothing
+            // is sent until the whole filesystem is traversed, so the session
+            // has to be kept alive here.
+            for entry in entries {
+                {
+                    let mut session = session.borrow_mut();
+                    session.heartbeat();
+                    session.check_time_limits()?;
+                }
+
+                let record = snapshot::Record::new(&entry);
+                let entry = into_timeline_entry(entry, args, &mut **session.borrow_mut())?
+                    .write_to_bytes()
+                    .map_err(crate::session::Error::action)?;
+
+                sorter.push(record, entry)
+                    .map_err(crate::session::Error::action)?;
+            }
+
+            let records = sorter.finish()
+                .map_err(crate::session::Error::action)?;
+
+            if let (Some(snapshot_dir), Some(snapshot_id)) = (&snapshot_dir, &args.snapshot_id) {
+                let writer = snapshot::Writer::create(snapshot_dir, snapshot_id)
+                    .map_err(crate::session::Error::action)?;
+
+                snapshot_writer.replace(Some(writer));
+            }
+
+            Box::new(diff.compare(records).map_while(|compared| {
+                let entry = compared.and_then(|compared| {
+                    // Unchanged files are not reported but they still have to
+                    // be part of the new snapshot.
+                    if let (snapshot::Compared::Current(record, ..), Some(writer)) =
+                        (&compared, snapshot_writer.borrow_mut().as_mut())
+                    {
+                        writer.write(record)?;
+                    }
+
+                    into_diff_entry(compared)
+                });
+
+                match entry {
+                    Ok(entry) => Some(entry),
+                    Err(error) => {
+                        snapshot_error.replace(Some(error));
+                        None
+                    }
+                }
+            }).flatten())
+        }
+        (_, sorter) => {
+            snapshot_sorter.replace(sorter);
+
+            Box::new(entries.map_while(|entry| {
+                if let Some(sorter) = snapshot_sorter.borrow_mut().as_mut() {
+                    let record = snapshot::Record::new(&entry);
+
+                    if let Err(error) = sorter.push(record, Vec::new()) {
+                        snapshot_error.replace(Some(error));
+                        return None;
+                    }
+                }
+
+                match into_timeline_entry(entry, args, &mut **session.borrow_mut()) {
+                    Ok(entry) => Some(entry),
+                    Err(error) => {
+                        session_error.replace(Some(error));
+                        None
+                    }
+                }
+            }))
+        }
+    };
+
+    let entries = entries
         .inspect(|_| {
             entry_count.set(entry_count.get() + 1);
-        })
-        .map(rrg_proto::get_filesystem_timeline::Entry::from_lossy);
+        });
+
+    let batches: Box<dyn Iterator<Item = std::io::Result<Vec<u8>>>> = match args.text_format {
+        Some(text_format) => Box::new(text::encode(entries, text_format)),
+        None => Box::new(crate::gzchunked::encode(entries)),
+    };
+
+    for batch in batches {
+        if let Some(error) = session_error.take() {
+            return Err(error);
+        }
 
-    for batch in crate::gzchunked::encode(entries) {
         let batch = batch
             .map_err(crate::session::Error::action)?;
 
         let blob = crate::blob::Blob::from(batch);
         let blob_sha256 = sha2::Sha256::digest(blob.as_bytes()).into();
 
+        let mut session = session.borrow_mut();
         session.send(crate::Sink::Blob, blob)?;
         session.reply(Item {
             blob_sha256,
             entry_count: entry_count.get(),
+            interpolation: interpolation.cloned(),
         })?;
 
         entry_count.set(0);
     }
 
+    if let Some(error) = session_error.take() {
+        return Err(error);
+    }
+
+    if let Some(error) = snapshot_error.take() {
+        return Err(crate::session::Error::action(error));
+    }
+
+    if let Some(writer) = snapshot_writer.take() {
+        writer.finish()
+            .map_err(crate::session::Error::action)?;
+    }
+
+    if let (Some(sorter), Some(snapshot_dir), Some(snapshot_id)) =
+        (snapshot_sorter.take(), &snapshot_dir, &args.snapshot_id)
+    {
+        let records = sorter.finish()
+            .map_err(crate::session::Error::action)?;
+
+        snapshot::save(snapshot_dir, snapshot_id, records)
+            .map_err(crate::session::Error::action)?;
+    }
+
     Ok(())
 }
 
@@ -83,12 +332,83 @@ impl crate::request::Args for Args {
         let root = PathBuf::try_from(proto.take_root())
             .map_err(|error| ParseArgsError::invalid_field("root", error))?;
 
+        let root_template = if proto.interpolate_root() {
+            let root_template = crate::interpolate::Template::from_path(&root)
+                .map_err(|error| ParseArgsError::invalid_field("root", error))?;
+
+            // A snapshot is saved under a single identifier, so it cannot be
+            // taken of (or compared against) many roots at once.
+            let has_snapshot = !proto.snapshot_id().is_empty() || !proto.base_snapshot_id().is_empty();
+            if root_template.has_user_vars() && has_snapshot {
+                return Err(ParseArgsError::invalid_field("root", SnapshotFanOutError));
+            }
+
+            Some(root_template)
+        } else {
+            None
+        };
+
+        let sha256_max_size = match (proto.sha256(), proto.sha256_max_size()) {
+            (false, _) => None,
+            (true, 0) => Some(DEFAULT_SHA256_MAX_SIZE),
+            (true, max_size) => Some(max_size),
+        };
+
+        let excluded_paths = proto.excluded_path_regexes().iter()
+            .map(|regex| regex::Regex::new(regex))
+            .collect::<Result<_, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("excluded_path_regexes", error))?;
+
+        let max_depth = match proto.max_depth() {
+            0 => None,
+            max_depth => Some(max_depth),
+        };
+
+        let walk_threads = match proto.walk_threads() as usize {
+            threads if threads > MAX_WALK_THREADS => {
+                let error = WalkThreadsError { threads };
+                return Err(ParseArgsError::invalid_field("walk_threads", error));
+            }
+            threads => std::num::NonZeroUsize::new(threads),
+        };
+
+        let walk_order = if proto.walk_unordered() {
+            crate::fs::Order::Unordered
+        } else {
+            crate::fs::Order::Deterministic
+        };
+
         Ok(Args {
             root: root,
+            root_template,
+            linux_flags: proto.linux_flags(),
+            ext_attr_names: proto.ext_attr_names(),
+            symlinks: proto.symlinks(),
+            sha256_max_size,
+            cross_dev_fs_types: proto.take_cross_device_fs_types(),
+            excluded_paths,
+            max_depth,
+            snapshot_id: parse_snapshot_id(proto.snapshot_id())
+                .map_err(|error| ParseArgsError::invalid_field("snapshot_id", error))?,
+            base_snapshot_id: parse_snapshot_id(proto.base_snapshot_id())
+                .map_err(|error| ParseArgsError::invalid_field("base_snapshot_id", error))?,
+            text_format: text::Format::from_proto(proto.output_format()),
+            walk_threads,
+            walk_order,
+            follow_symlinks: proto.follow_symlinks(),
         })
     }
 }
 
+/// Parses a snapshot identifier as specified in the protobuf message.
+fn parse_snapshot_id(id: &str) -> Result<Option<snapshot::Id>, snapshot::ParseIdError> {
+    if id.is_empty() {
+        Ok(None)
+    } else {
+        id.parse().map(Some)
+    }
+}
+
 impl crate::response::Item for Item {
 
     type Proto = rrg_proto::get_filesystem_timeline::Result;
@@ -97,11 +417,185 @@ impl crate::response::Item for Item {
         let mut proto = Self::Proto::default();
         proto.set_blob_sha256(self.blob_sha256.into());
         proto.set_entry_count(self.entry_count as u64);
+        if let Some(interpolation) = self.interpolation {
+            proto.set_interpolation(interpolation.into());
+        }
 
         proto
     }
 }
 
+/// Converts the result of comparing a file against the base snapshot into a
+/// timeline entry.
+///
+/// `None` is returned for files that did not change since the base snapshot.
+fn into_diff_entry(
+    compared: snapshot::Compared,
+) -> std::io::Result<Option<rrg_proto::get_filesystem_timeline::Entry>> {
+    use protobuf::Message as _;
+
+    match compared {
+        snapshot::Compared::Current(_, _, None) => Ok(None),
+        snapshot::Compared::Current(_, entry, Some(change)) => {
+            let mut entry = rrg_proto::get_filesystem_timeline::Entry::parse_from_bytes(&entry)
+                .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+            entry.set_change_type(change.into());
+
+            Ok(Some(entry))
+        }
+        snapshot::Compared::Deleted(record) => Ok(Some(record.into_deleted_entry())),
+    }
+}
+
+/// Converts the given filesystem entry into a timeline entry.
+///
+/// Apart from the basic metadata (see the [`FromLossy`] implementation), the
+/// timeline entry is enriched with extra information requested in `args`. If
+/// collecting any of it fails, the error is logged and the field is left unset.
+///
+/// An error is returned only if the session exceeds its time limits.
+fn into_timeline_entry<S>(
+    mut entry: crate::fs::Entry,
+    args: &Args,
+    session: &mut S,
+) -> crate::session::Result<rrg_proto::get_filesystem_timeline::Entry>
+where
+    S: crate::session::Session,
+{
+    #[cfg(target_os = "linux")]
+    let linux_flags = if args.linux_flags {
+        linux_flags(&entry)
+    } else {
+        None
+    };
+
+    #[cfg(target_family = "unix")]
+    let ext_attr_names = if args.ext_attr_names {
+        ext_attr_names(&entry)
+    } else {
+        Vec::new()
+    };
+
+    let symlink_chain = std::mem::take(&mut entry.symlink_chain);
+
+    // Followed symlinks have metadata of their targets but we still want to
+    // report what they point to.
+    let is_symlink = entry.metadata.is_symlink() || !symlink_chain.is_empty();
+
+    let symlink = if args.symlinks && is_symlink {
+        match std::fs::read_link(&entry.path) {
+            Ok(symlink) => Some(symlink),
+            Err(error) => {
+                log::warn! {
+                    "failed to read symlink target of '{}': {error}",
+                    entry.path.display()
+                };
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let sha256 = match args.sha256_max_size {
+        Some(max_size) if entry.metadata.is_file() && entry.metadata.len() <= max_size => {
+            // Reading contents of big files takes a while, so we make sure we
+            // are still allowed to run before each of them.
+            session.heartbeat();
+            session.check_time_limits()?;
+
+            match sha256(&entry.path) {
+                Ok(sha256) => Some(sha256),
+                Err(error) => {
+                    log::warn! {
+                        "failed to compute SHA-256 of '{}': {error}",
+                        entry.path.display()
+                    };
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    let mut proto = rrg_proto::get_filesystem_timeline::Entry::from_lossy(entry);
+
+    #[cfg(target_os = "linux")]
+    if let Some(linux_flags) = linux_flags {
+        proto.set_linux_flags(linux_flags);
+    }
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::ffi::OsStringExt as _;
+
+        for ext_attr_name in ext_attr_names {
+            proto.mut_ext_attr_names().push(ext_attr_name.into_vec());
+        }
+    }
+
+    if let Some(symlink) = symlink {
+        proto.set_symlink(rrg_proto::path::into_bytes(symlink));
+    }
+    for path in symlink_chain {
+        proto.mut_symlink_chain().push(rrg_proto::path::into_bytes(path));
+    }
+    if let Some(sha256) = sha256 {
+        proto.set_sha256(sha256.to_vec());
+    }
+
+    Ok(proto)
+}
+
+/// Collects Linux inode flags of the given entry.
+///
+/// Flags are collected only for regular files and directories: other files
+/// do not support them and opening some of them (e.g. FIFOs) could block.
+#[cfg(target_os = "linux")]
+fn linux_flags(entry: &crate::fs::Entry) -> Option<u32> {
+    if !entry.metadata.is_file() && !entry.metadata.is_dir() {
+        return None;
+    }
+
+    match ospect::fs::linux::flags(&entry.path) {
+        Ok(flags) => Some(flags),
+        Err(error) => {
+            log::warn! {
+                "failed to collect flags of '{}': {error}",
+                entry.path.display()
+            };
+            None
+        }
+    }
+}
+
+/// Collects names of extended attributes of the given entry.
+#[cfg(target_family = "unix")]
+fn ext_attr_names(entry: &crate::fs::Entry) -> Vec<std::ffi::OsString> {
+    match ospect::fs::ext_attr_names(&entry.path) {
+        Ok(names) => names,
+        Err(error) => {
+            log::warn! {
+                "failed to list extended attributes of '{}': {error}",
+                entry.path.display()
+            };
+            Vec::new()
+        }
+    }
+}
+
+/// Computes SHA-256 digest of the contents of the file at the given path.
+fn sha256(path: &std::path::Path) -> std::io::Result<[u8; 32]> {
+    use sha2::Digest as _;
+
+    let mut file = std::fs::File::open(path)?;
+
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+
+    Ok(hasher.finalize().into())
+}
+
 impl FromLossy<crate::fs::Entry> for rrg_proto::get_filesystem_timeline::Entry {
 
     fn from_lossy(entry: crate::fs::Entry) -> Self {
@@ -158,6 +652,55 @@ impl FromLossy<crate::fs::Entry> for rrg_proto::get_filesystem_timeline::Entry {
     }
 }
 
+/// An error indicating that no timeline snapshot directory was configured.
+#[derive(Debug)]
+struct MissingSnapshotDirError;
+
+impl std::fmt::Display for MissingSnapshotDirError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "no timeline snapshot directory configured")
+    }
+}
+
+impl std::error::Error for MissingSnapshotDirError {
+}
+
+/// An error indicating that snapshots were requested for a root interpolated
+/// for every user.
+#[derive(Debug)]
+struct SnapshotFanOutError;
+
+impl std::fmt::Display for SnapshotFanOutError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "snapshots cannot be used with roots interpolated for every user")
+    }
+}
+
+impl std::error::Error for SnapshotFanOutError {
+}
+
+/// An error indicating that too many traversal threads were requested.
+#[derive(Debug)]
+struct WalkThreadsError {
+    threads: usize,
+}
+
+impl std::fmt::Display for WalkThreadsError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "requested number of threads ({}) is bigger than allowed ({})",
+            self.threads, MAX_WALK_THREADS
+        }
+    }
+}
+
+impl std::error::Error for WalkThreadsError {
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -167,9 +710,7 @@ mod tests {
     fn handle_non_existent_path() {
         let tempdir = tempfile::tempdir().unwrap();
 
-        let request = Args {
-            root: tempdir.path().join("foo")
-        };
+        let request = args(tempdir.path().join("foo"));
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_err());
@@ -180,9 +721,7 @@ mod tests {
         let tempdir = tempfile::tempdir().unwrap();
         let tempdir_path = tempdir.path().to_path_buf();
 
-        let request = Args {
-            root: tempdir_path.clone(),
-        };
+        let request = args(tempdir_path.clone());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -198,9 +737,7 @@ mod tests {
         std::fs::File::create(tempdir.path().join("b")).unwrap();
         std::fs::File::create(tempdir.path().join("c")).unwrap();
 
-        let request = Args {
-            root: tempdir.path().to_path_buf(),
-        };
+        let request = args(tempdir.path().to_path_buf());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -221,9 +758,7 @@ mod tests {
 
         std::fs::create_dir_all(tempdir_path.join("a").join("b")).unwrap();
 
-        let request = Args {
-            root: tempdir_path.clone(),
-        };
+        let request = args(tempdir_path.clone());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -249,9 +784,7 @@ mod tests {
         std::fs::create_dir(&dir_path).unwrap();
         std::os::unix::fs::symlink(&dir_path, &symlink_path).unwrap();
 
-        let request = Args {
-            root: root_path.clone(),
-        };
+        let request = args(root_path.clone());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -275,9 +808,7 @@ mod tests {
         std::fs::File::create(&file_path_1).unwrap();
         std::fs::File::create(&file_path_2).unwrap();
 
-        let request = Args {
-            root: root_path.clone(),
-        };
+        let request = args(root_path.clone());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -300,9 +831,7 @@ mod tests {
         let tempdir = tempfile::tempdir().unwrap();
         std::fs::write(tempdir.path().join("foo"), b"123456789").unwrap();
 
-        let request = Args {
-            root: tempdir.path().to_path_buf(),
-        };
+        let request = args(tempdir.path().to_path_buf());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -340,9 +869,7 @@ mod tests {
         std::fs::File::create(&file_path).unwrap();
         std::fs::hard_link(&file_path, &hardlink_path).unwrap();
 
-        let request = Args {
-            root: root_path.clone(),
-        };
+        let request = args(root_path.clone());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -386,9 +913,7 @@ mod tests {
         };
         assert!(status > 0);
 
-        let request = Args {
-            root: temp_dir.path().to_path_buf(),
-        };
+        let request = args(temp_dir.path().to_path_buf());
 
         let mut session = crate::session::FakeSession::new();
         assert!(handle(&mut session, request).is_ok());
@@ -401,28 +926,826 @@ mod tests {
         assert_eq!(attributes & FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_HIDDEN);
     }
 
-    /// Retrieves timeline entries from the given session object.
-    fn entries(
-        session: &crate::session::FakeSession,
-    ) -> Vec<rrg_proto::get_filesystem_timeline::Entry> {
-        let blob_count = session.parcel_count(crate::Sink::Blob);
-        let reply_count = session.reply_count();
-        assert_eq!(blob_count, reply_count);
+    #[test]
+    fn handle_no_extras() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"123456789").unwrap();
 
-        let chunks = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
-            .map(crate::blob::Blob::as_bytes);
+        let request = args(tempdir.path().to_path_buf());
 
-        let entries = crate::gzchunked::decode(chunks)
-            .map(Result::unwrap)
-            .collect::<Vec<_>>();
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
 
-        let total_entry_count = session.replies::<Item>()
-            .map(|item| item.entry_count)
-            .sum();
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert!(!entries[0].has_linux_flags());
+        assert!(entries[0].ext_attr_names.is_empty());
+        assert!(!entries[0].has_symlink());
+        assert!(!entries[0].has_sha256());
+    }
 
-        assert_eq!(entries.len(), total_entry_count);
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlinks() {
+        let tempdir = tempfile::tempdir().unwrap();
 
-        entries
+        let file_path = tempdir.path().join("file");
+        let symlink_path = tempdir.path().join("symlink");
+
+        std::fs::File::create(&file_path).unwrap();
+        std::os::unix::fs::symlink(&file_path, &symlink_path).unwrap();
+
+        let request = Args {
+            symlinks: true,
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.path().to_owned());
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(path(&entries[0]), Some(file_path.clone()));
+        assert!(!entries[0].has_symlink());
+        assert_eq!(path(&entries[1]), Some(symlink_path));
+
+        let symlink = rrg_proto::path::from_bytes(entries[1].symlink().to_owned()).unwrap();
+        assert_eq!(symlink, file_path);
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_follow_symlinks() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+
+        std::fs::write(outside.path().join("file"), b"foo").unwrap();
+        std::os::unix::fs::symlink(outside.path(), tempdir.path().join("dir"))
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.path(), outside.path().join("root"))
+            .unwrap();
+
+        let request = Args {
+            symlinks: true,
+            follow_symlinks: true,
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.path().to_owned());
+
+        let dir_path = tempdir.path().join("dir");
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(path(&entries[0]), Some(dir_path.clone()));
+        assert_eq!(path(&entries[1]), Some(dir_path.join("file")));
+        assert_eq!(path(&entries[2]), Some(dir_path.join("root")));
+
+        use std::os::unix::fs::MetadataExt as _;
+        let dir_mode = std::fs::metadata(outside.path()).unwrap().mode();
+        assert_eq!(entries[0].unix_mode(), i64::from(dir_mode));
+
+        let symlink = rrg_proto::path::from_bytes(entries[0].symlink().to_owned()).unwrap();
+        assert_eq!(symlink, outside.path());
+        assert_eq!(entries[0].symlink_chain, vec![
+            rrg_proto::path::into_bytes(outside.path().to_path_buf()),
+        ]);
+
+        assert_eq!(entries[1].size(), 3);
+        assert!(entries[1].symlink_chain.is_empty());
+    }
+
+    #[test]
+    fn handle_sha256() {
+        use sha2::Digest as _;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"123456789").unwrap();
+        std::fs::create_dir(tempdir.path().join("bar")).unwrap();
+
+        let request = Args {
+            sha256_max_size: Some(u64::MAX),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.path().to_owned());
+
+        assert_eq!(entries.len(), 2);
+
+        // Directories have no contents and so no digest.
+        assert_eq!(path(&entries[0]), Some(tempdir.path().join("bar")));
+        assert!(!entries[0].has_sha256());
+
+        assert_eq!(path(&entries[1]), Some(tempdir.path().join("foo")));
+        assert_eq!(entries[1].sha256(), &sha2::Sha256::digest(b"123456789")[..]);
+    }
+
+    #[test]
+    fn handle_sha256_max_size() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"123").unwrap();
+        std::fs::write(tempdir.path().join("bar"), b"123456789").unwrap();
+
+        let request = Args {
+            sha256_max_size: Some(4),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.path().to_owned());
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(path(&entries[0]), Some(tempdir.path().join("bar")));
+        assert!(!entries[0].has_sha256());
+        assert_eq!(path(&entries[1]), Some(tempdir.path().join("foo")));
+        assert!(entries[1].has_sha256());
+    }
+
+    #[cfg(feature = "test-chattr")]
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_linux_flags() {
+        // https://elixir.bootlin.com/linux/v5.8.14/source/include/uapi/linux/fs.h#L242
+        const FS_NODUMP_FL: u32 = 0x00000040;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::File::create(tempdir.path().join("foo")).unwrap();
+
+        assert! {
+            std::process::Command::new("chattr")
+                .arg("+d")
+                .arg(tempdir.path().join("foo"))
+                .status().unwrap()
+                .success()
+        };
+
+        let request = Args {
+            linux_flags: true,
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].linux_flags() & FS_NODUMP_FL, FS_NODUMP_FL);
+    }
+
+    #[cfg(feature = "test-setfattr")]
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_ext_attr_names() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::File::create(tempdir.path().join("foo")).unwrap();
+
+        assert! {
+            std::process::Command::new("setfattr")
+                .arg("--no-dereference")
+                .arg("--name").arg("user.foo")
+                .arg("--value").arg("bar")
+                .arg(tempdir.path().join("foo"))
+                .status().unwrap()
+                .success()
+        };
+
+        let request = Args {
+            ext_attr_names: true,
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].ext_attr_names, vec![b"user.foo".to_vec()]);
+    }
+
+    #[test]
+    fn handle_sha256_real_time_limit() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"123456789").unwrap();
+
+        let request = Args {
+            sha256_max_size: Some(u64::MAX),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new()
+            .with_real_time_limit(std::time::Duration::ZERO);
+
+        let error = handle(&mut session, request).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::RealTimeLimitExceeded);
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn args_from_proto_sha256() {
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("/foo").into());
+        proto.set_sha256(true);
+
+        let args = <Args as crate::request::Args>::from_proto(proto.clone())
+            .unwrap();
+        assert_eq!(args.sha256_max_size, Some(DEFAULT_SHA256_MAX_SIZE));
+
+        proto.set_sha256_max_size(1024);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+        assert_eq!(args.sha256_max_size, Some(1024));
+    }
+
+    #[test]
+    fn handle_excluded_paths() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(tempdir.path().join("cache")).unwrap();
+        std::fs::File::create(tempdir.path().join("cache").join("foo")).unwrap();
+        std::fs::File::create(tempdir.path().join("bar")).unwrap();
+
+        let request = Args {
+            excluded_paths: vec![regex::Regex::new("cache$").unwrap()],
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(path(&entries[0]), Some(tempdir.path().join("bar")));
+    }
+
+    #[test]
+    fn handle_max_depth() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(tempdir.path().join("a").join("b")).unwrap();
+
+        let request = Args {
+            max_depth: Some(1),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(path(&entries[0]), Some(tempdir.path().join("a")));
+    }
+
+    #[test]
+    fn args_from_proto_invalid_excluded_path_regex() {
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("/foo").into());
+        proto.mut_excluded_path_regexes().push(String::from("(foo"));
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn handle_snapshot_without_dir() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let request = Args {
+            snapshot_id: Some("foo".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_err());
+    }
+
+    #[test]
+    fn handle_base_snapshot_non_existent() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let snapshot_dir = tempfile::tempdir().unwrap();
+
+        let request = Args {
+            base_snapshot_id: Some("foo".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = session_with_snapshot_dir(snapshot_dir.path());
+        assert!(handle(&mut session, request).is_err());
+    }
+
+    #[test]
+    fn handle_incremental() {
+        use rrg_proto::get_filesystem_timeline::ChangeType;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let snapshot_dir = tempfile::tempdir().unwrap();
+
+        std::fs::write(tempdir.path().join("a"), b"").unwrap();
+        std::fs::write(tempdir.path().join("b"), b"").unwrap();
+        std::fs::write(tempdir.path().join("c"), b"").unwrap();
+
+        let request = Args {
+            snapshot_id: Some("base".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = session_with_snapshot_dir(snapshot_dir.path());
+        assert!(handle(&mut session, request).is_ok());
+
+        // Saving a snapshot should not affect the timeline itself.
+        let base_entries = entries(&session);
+        assert_eq!(base_entries.len(), 3);
+        assert!(base_entries.iter().all(|entry| !entry.has_change_type()));
+
+        std::fs::write(tempdir.path().join("b"), b"foo").unwrap();
+        std::fs::remove_file(tempdir.path().join("c")).unwrap();
+        std::fs::write(tempdir.path().join("d"), b"").unwrap();
+
+        let request = Args {
+            base_snapshot_id: Some("base".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = session_with_snapshot_dir(snapshot_dir.path());
+        assert!(handle(&mut session, request).is_ok());
+
+        let mut entries = entries(&session);
+        entries.sort_by_key(|entry| entry.path().to_owned());
+
+        assert_eq!(entries.len(), 3);
+
+        assert_eq!(path(&entries[0]), Some(tempdir.path().join("b")));
+        assert_eq!(entries[0].change_type(), ChangeType::MODIFIED);
+        assert_eq!(entries[0].size(), 3);
+
+        assert_eq!(path(&entries[1]), Some(tempdir.path().join("c")));
+        assert_eq!(entries[1].change_type(), ChangeType::DELETED);
+
+        assert_eq!(path(&entries[2]), Some(tempdir.path().join("d")));
+        assert_eq!(entries[2].change_type(), ChangeType::CREATED);
+    }
+
+    #[test]
+    fn handle_incremental_real_time_limit() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let snapshot_dir = tempfile::tempdir().unwrap();
+
+        std::fs::write(tempdir.path().join("a"), b"").unwrap();
+
+        let request = Args {
+            snapshot_id: Some("base".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = session_with_snapshot_dir(snapshot_dir.path());
+        assert!(handle(&mut session, request).is_ok());
+
+        let request = Args {
+            base_snapshot_id: Some("base".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = session_with_snapshot_dir(snapshot_dir.path())
+            .with_real_time_limit(std::time::Duration::ZERO);
+
+        let error = handle(&mut session, request).unwrap_err();
+        assert_eq!(error.kind(), crate::session::ErrorKind::RealTimeLimitExceeded);
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_incremental_with_snapshot() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let snapshot_dir = tempfile::tempdir().unwrap();
+
+        std::fs::write(tempdir.path().join("a"), b"").unwrap();
+
+        let request = Args {
+            snapshot_id: Some("first".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = session_with_snapshot_dir(snapshot_dir.path());
+        assert!(handle(&mut session, request).is_ok());
+
+        std::fs::write(tempdir.path().join("b"), b"").unwrap();
+
+        let request = Args {
+            snapshot_id: Some("second".parse().unwrap()),
+            base_snapshot_id: Some("first".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = session_with_snapshot_dir(snapshot_dir.path());
+        assert!(handle(&mut session, request).is_ok());
+        assert_eq!(entries(&session).len(), 1);
+
+        // The second snapshot should include unchanged files as well, so there
+        // should be no changes reported relative to it.
+        let request = Args {
+            base_snapshot_id: Some("second".parse().unwrap()),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = session_with_snapshot_dir(snapshot_dir.path());
+        assert!(handle(&mut session, request).is_ok());
+        assert_eq!(entries(&session).len(), 0);
+
+        // No temporary files should be left behind in the snapshot directory.
+        let mut snapshot_files = std::fs::read_dir(snapshot_dir.path()).unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        snapshot_files.sort();
+
+        assert_eq!(snapshot_files, vec!["first.snapshot", "second.snapshot"]);
+    }
+
+    #[test]
+    fn handle_bodyfile() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"quux").unwrap();
+
+        let request = Args {
+            text_format: Some(text::Format::Bodyfile),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let text = text(&session);
+        let fields = text.trim_end().split('|').collect::<Vec<_>>();
+
+        // Backslashes (path separators on Windows) are escaped.
+        let path = tempdir.path().join("foo").to_str().unwrap()
+            .replace('\\', "\\\\");
+
+        assert_eq!(fields.len(), 11);
+        assert_eq!(fields[0], "0");
+        assert_eq!(fields[1], path);
+        assert_eq!(fields[6], "4");
+
+        #[cfg(target_family = "unix")]
+        assert!(fields[3].starts_with("-rw"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_bodyfile_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let name = std::ffi::OsStr::from_bytes(b"foo|\xff\nbar");
+        std::fs::write(tempdir.path().join(name), b"").unwrap();
+
+        let request = Args {
+            text_format: Some(text::Format::Bodyfile),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let text = text(&session);
+        assert_eq!(text.lines().count(), 1);
+        assert!(text.contains("/foo\\|\\xff\\x0abar|"));
+    }
+
+    #[test]
+    fn handle_csv() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::write(tempdir.path().join("foo,bar"), b"quux").unwrap();
+        std::fs::write(tempdir.path().join("baz"), b"").unwrap();
+
+        let request = Args {
+            sha256_max_size: Some(u64::MAX),
+            text_format: Some(text::Format::Csv),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let text = text(&session);
+
+        let mut lines = text.lines();
+        assert!(lines.next().unwrap().starts_with("path,"));
+
+        let mut lines = lines.collect::<Vec<_>>();
+        lines.sort();
+        assert_eq!(lines.len(), 2);
+
+        // `sha256("")` and `sha256("quux")`.
+        assert!(lines[0].contains("baz\","));
+        assert!(lines[0].contains(",e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855,"));
+        assert!(lines[1].contains("foo,bar\","));
+        assert!(lines[1].contains(",053057fda9a935f2d4fa8c7bc62a411a26926e00b491c07c1b2ec1909078a0a2,"));
+    }
+
+    #[test]
+    fn args_from_proto_output_format() {
+        use rrg_proto::get_filesystem_timeline::OutputFormat;
+
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("/foo").into());
+
+        let args = <Args as crate::request::Args>::from_proto(proto.clone())
+            .unwrap();
+        assert_eq!(args.text_format, None);
+
+        proto.set_output_format(OutputFormat::CSV);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+        assert_eq!(args.text_format, Some(text::Format::Csv));
+    }
+
+    #[test]
+    fn handle_walk_threads() {
+        let tempdir = tempfile::tempdir().unwrap();
+        for i in 0..4 {
+            let dir = tempdir.path().join(format!("dir{i}"));
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("file"), b"").unwrap();
+        }
+
+        let request = args(tempdir.path().to_path_buf());
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let paths = entries(&session).iter()
+            .map(path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), 8);
+
+        let request = Args {
+            walk_threads: std::num::NonZeroUsize::new(4),
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let par_paths = entries(&session).iter()
+            .map(path)
+            .collect::<Vec<_>>();
+        assert_eq!(par_paths, paths);
+    }
+
+    #[test]
+    fn handle_walk_threads_unordered() {
+        let tempdir = tempfile::tempdir().unwrap();
+        for i in 0..4 {
+            let dir = tempdir.path().join(format!("dir{i}"));
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join("file"), b"").unwrap();
+        }
+
+        let request = Args {
+            walk_threads: std::num::NonZeroUsize::new(4),
+            walk_order: crate::fs::Order::Unordered,
+            ..args(tempdir.path().to_path_buf())
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let mut paths = entries(&session).iter()
+            .map(|entry| path(entry).unwrap())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(paths, vec![
+            tempdir.path().join("dir0"),
+            tempdir.path().join("dir0").join("file"),
+            tempdir.path().join("dir1"),
+            tempdir.path().join("dir1").join("file"),
+            tempdir.path().join("dir2"),
+            tempdir.path().join("dir2").join("file"),
+            tempdir.path().join("dir3"),
+            tempdir.path().join("dir3").join("file"),
+        ]);
+    }
+
+    #[test]
+    fn args_from_proto_walk_threads() {
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("/foo").into());
+        proto.set_walk_threads(8);
+        proto.set_walk_unordered(true);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+        assert_eq!(args.walk_threads, std::num::NonZeroUsize::new(8));
+        assert_eq!(args.walk_order, crate::fs::Order::Unordered);
+    }
+
+    #[test]
+    fn args_from_proto_too_many_walk_threads() {
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("/foo").into());
+        proto.set_walk_threads(1024);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_invalid_snapshot_id() {
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("/foo").into());
+        proto.set_snapshot_id(String::from("../foo"));
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn handle_interpolation() {
+        let temp_dir = ospect::os::temp_dir().unwrap();
+
+        // We create the temporary folder in the system folder for temporary
+        // files, so we can refer to it using a variable.
+        let tempdir = tempfile::tempdir_in(&temp_dir).unwrap();
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str().unwrap();
+        std::fs::File::create(tempdir.path().join("a")).unwrap();
+
+        let template = format!("%%environ_temp%%/{tempdir_name}");
+
+        let request = Args {
+            root_template: Some(template.parse().unwrap()),
+            ..args(PathBuf::from(&template))
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+
+        let entries = entries(&session);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(path(&entries[0]), Some(temp_dir.join(tempdir_name).join("a")));
+
+        let item = session.reply::<Item>(0);
+        let interpolation = item.interpolation.as_ref().unwrap();
+        assert_eq!(interpolation.raw, template);
+        assert!(interpolation.bindings.contains_key(&crate::interpolate::Var::EnvironTemp));
+    }
+
+    #[test]
+    fn handle_interpolation_non_existent() {
+        let tempdir = tempfile::tempdir_in(ospect::os::temp_dir().unwrap()).unwrap();
+        let tempdir_name = tempdir.path().file_name().unwrap().to_str().unwrap();
+
+        let template = format!("%%environ_temp%%/{tempdir_name}/foo");
+
+        let request = Args {
+            root_template: Some(template.parse().unwrap()),
+            ..args(PathBuf::from(&template))
+        };
+
+        // Interpolated roots are not expected to exist, so instead of failing
+        // the root should be just skipped.
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, request).is_ok());
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_root() {
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("%%users.homedir%%").into());
+        proto.set_interpolate_root(true);
+
+        let args = <Args as crate::request::Args>::from_proto(proto).unwrap();
+        assert!(args.root_template.is_some());
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_root_users_snapshot() {
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("%%users.homedir%%").into());
+        proto.set_interpolate_root(true);
+        proto.set_snapshot_id(String::from("foo"));
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_interpolate_root_snapshot() {
+        let mut proto = rrg_proto::get_filesystem_timeline::Args::default();
+        proto.set_root(PathBuf::from("%%environ_temp%%").into());
+        proto.set_interpolate_root(true);
+        proto.set_snapshot_id(String::from("foo"));
+
+        // Roots without `users.` variables are interpolated into at most one
+        // path, so taking snapshots of them is fine.
+        let args = <Args as crate::request::Args>::from_proto(proto).unwrap();
+        assert!(args.root_template.is_some());
+    }
+
+    /// Returns default arguments for getting the timeline of the given root.
+    fn args(root: PathBuf) -> Args {
+        Args {
+            root,
+            root_template: None,
+            linux_flags: false,
+            ext_attr_names: false,
+            symlinks: false,
+            sha256_max_size: None,
+            cross_dev_fs_types: vec![],
+            excluded_paths: vec![],
+            max_depth: None,
+            snapshot_id: None,
+            base_snapshot_id: None,
+            text_format: None,
+            walk_threads: None,
+            walk_order: crate::fs::Order::Deterministic,
+            follow_symlinks: false,
+        }
+    }
+
+    /// Creates a session storing timeline snapshots in the given directory.
+    fn session_with_snapshot_dir(
+        snapshot_dir: &std::path::Path,
+    ) -> crate::session::FakeSession {
+        crate::session::FakeSession::with_args(crate::args::Args {
+            heartbeat_rate: std::time::Duration::from_secs(0),
+            verbosity: log::LevelFilter::Debug,
+            log_to_stdout: false,
+            log_to_file: None,
+            offline_input: None,
+            offline_output_file: None,
+            offline_output_dir: None,
+            #[cfg(feature = "action-execute_signed_command")]
+            command_verification_key: None,
+            timeline_snapshot_dir: Some(snapshot_dir.to_path_buf()),
+        })
+    }
+
+    /// Retrieves timeline entries from the given session object.
+    fn entries(
+        session: &crate::session::FakeSession,
+    ) -> Vec<rrg_proto::get_filesystem_timeline::Entry> {
+        let blob_count = session.parcel_count(crate::Sink::Blob);
+        let reply_count = session.reply_count();
+        assert_eq!(blob_count, reply_count);
+
+        let chunks = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .map(crate::blob::Blob::as_bytes);
+
+        let entries = crate::gzchunked::decode(chunks)
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+
+        let total_entry_count = session.replies::<Item>()
+            .map(|item| item.entry_count)
+            .sum();
+
+        assert_eq!(entries.len(), total_entry_count);
+
+        entries
+    }
+
+    /// Retrieves the timeline in a text format from the given session object.
+    fn text(session: &crate::session::FakeSession) -> String {
+        use std::io::Read as _;
+
+        let blob_count = session.parcel_count(crate::Sink::Blob);
+        let reply_count = session.reply_count();
+        assert_eq!(blob_count, reply_count);
+
+        let mut text = String::new();
+        for blob in session.parcels::<crate::blob::Blob>(crate::Sink::Blob) {
+            flate2::read::GzDecoder::new(blob.as_bytes())
+                .read_to_string(&mut text)
+                .unwrap();
+        }
+
+        let total_entry_count = session.replies::<Item>()
+            .map(|item| item.entry_count)
+            .sum::<usize>();
+
+        // The CSV format has an extra header line.
+        let line_count = text.lines()
+            .filter(|line| !line.starts_with("path,"))
+            .count();
+        assert_eq!(line_count, total_entry_count);
+
+        text
     }
 
     /// Constructs a path for the given timeline entry.
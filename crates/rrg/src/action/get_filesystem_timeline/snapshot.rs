@@ -0,0 +1,895 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Persistent snapshots of filesystem timelines.
+//!
+//! A snapshot is a compact index of a timeline that records just enough to
+//! tell whether a file changed since the snapshot was taken. Snapshots are
+//! stored as gzipped sequences of records sorted by path in a directory
+//! configured with the `--timeline-snapshot-dir` flag.
+//!
+//! Because records are sorted, a timeline can be compared against a snapshot
+//! by merging the two, so neither of them has to be kept in memory. Records of
+//! the timeline itself are sorted externally: they are spilled to temporary
+//! files (in the snapshot directory) in sorted runs which are merged at the end.
+
+use std::collections::BinaryHeap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{LittleEndian, ReadBytesExt as _, WriteBytesExt as _};
+
+/// Magic bytes (with format version) at the beginning of every snapshot.
+const MAGIC: &[u8; 8] = b"RRGTLS02";
+
+/// Limit on the size of records buffered in memory before they are spilled.
+const MAX_RUN_SIZE: usize = 16 * 1024 * 1024;
+
+/// Counter used to give temporary run files unique names.
+static RUN_COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+/// Identifier of a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Id(String);
+
+impl std::str::FromStr for Id {
+
+    type Err = ParseIdError;
+
+    fn from_str(string: &str) -> Result<Id, ParseIdError> {
+        // Identifiers are used as file names, so we need to be strict about
+        // what we accept (e.g. to not allow escaping the snapshot directory).
+        let is_valid = !string.is_empty() && string.chars().all(|char| {
+            char.is_ascii_alphanumeric() || char == '-' || char == '_'
+        });
+
+        if is_valid {
+            Ok(Id(String::from(string)))
+        } else {
+            Err(ParseIdError {
+                id: String::from(string),
+            })
+        }
+    }
+}
+
+impl Id {
+
+    /// Returns the path of the snapshot file within the given directory.
+    fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.snapshot", self.0))
+    }
+}
+
+/// Metadata of a single file recorded in a snapshot.
+///
+/// Records are ordered by their paths first.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Record {
+    /// Path to the file (encoded the same way as in timeline entries).
+    pub path: Vec<u8>,
+    /// Serial number of the file (0 where not available).
+    pub ino: u64,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Time of the last data change of the file in nanoseconds since epoch.
+    pub mtime_nanos: i64,
+    /// Time of the last status change of the file in nanoseconds since epoch
+    /// (0 where not available).
+    pub ctime_nanos: i64,
+}
+
+impl Record {
+
+    /// Creates a snapshot record for the given filesystem entry.
+    pub fn new(entry: &crate::fs::Entry) -> Record {
+        let mtime_nanos = entry.metadata.modified().ok()
+            .and_then(|time| rrg_proto::nanos(time).ok())
+            .and_then(|nanos| i64::try_from(nanos).ok())
+            .unwrap_or(0);
+
+        #[cfg(target_family = "unix")]
+        let (ino, ctime_nanos) = {
+            use std::os::unix::fs::MetadataExt as _;
+
+            let ctime_nanos = entry.metadata.ctime()
+                .saturating_mul(1_000_000_000)
+                .saturating_add(entry.metadata.ctime_nsec());
+
+            (entry.metadata.ino(), ctime_nanos)
+        };
+
+        #[cfg(not(target_family = "unix"))]
+        let (ino, ctime_nanos) = (0, 0);
+
+        Record {
+            path: rrg_proto::path::into_bytes(entry.path.clone()),
+            ino,
+            size: entry.metadata.len(),
+            mtime_nanos,
+            ctime_nanos,
+        }
+    }
+
+    /// Creates a timeline entry of a file that has been deleted.
+    pub fn into_deleted_entry(self) -> rrg_proto::get_filesystem_timeline::Entry {
+        let mut proto = rrg_proto::get_filesystem_timeline::Entry::default();
+        proto.set_path(self.path);
+        proto.set_size(self.size);
+        proto.set_mtime_nanos(self.mtime_nanos);
+
+        #[cfg(target_family = "unix")]
+        {
+            proto.set_unix_ino(self.ino);
+            proto.set_ctime_nanos(self.ctime_nanos);
+        }
+
+        proto.set_change_type(rrg_proto::get_filesystem_timeline::ChangeType::DELETED);
+
+        proto
+    }
+
+    /// Writes the record to the given writer.
+    fn write<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let path_len = u32::try_from(self.path.len())
+            .map_err(|_| std::io::ErrorKind::InvalidInput)?;
+
+        writer.write_u32::<LittleEndian>(path_len)?;
+        writer.write_all(&self.path)?;
+        writer.write_u64::<LittleEndian>(self.ino)?;
+        writer.write_u64::<LittleEndian>(self.size)?;
+        writer.write_i64::<LittleEndian>(self.mtime_nanos)?;
+        writer.write_i64::<LittleEndian>(self.ctime_nanos)?;
+
+        Ok(())
+    }
+
+    /// Reads a record from the given reader.
+    ///
+    /// `None` is returned if there are no more records to read.
+    fn read<R: Read>(reader: &mut R) -> std::io::Result<Option<Record>> {
+        let path_len = match reader.read_u32::<LittleEndian>() {
+            Ok(path_len) => path_len,
+            Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => {
+                return Ok(None);
+            }
+            Err(error) => return Err(error),
+        };
+
+        let mut path = Vec::new();
+        reader.take(u64::from(path_len)).read_to_end(&mut path)?;
+        if path.len() != path_len as usize {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(Some(Record {
+            path,
+            ino: reader.read_u64::<LittleEndian>()?,
+            size: reader.read_u64::<LittleEndian>()?,
+            mtime_nanos: reader.read_i64::<LittleEndian>()?,
+            ctime_nanos: reader.read_i64::<LittleEndian>()?,
+        }))
+    }
+}
+
+/// Type of change of a file since the base snapshot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// The file did not exist in the base snapshot.
+    Created,
+    /// The file existed in the base snapshot but its metadata changed.
+    Modified,
+}
+
+impl From<Change> for rrg_proto::get_filesystem_timeline::ChangeType {
+
+    fn from(change: Change) -> rrg_proto::get_filesystem_timeline::ChangeType {
+        match change {
+            Change::Created => Self::CREATED,
+            Change::Modified => Self::MODIFIED,
+        }
+    }
+}
+
+/// External sorter of records (with arbitrary payloads) by their paths.
+///
+/// Records are buffered in memory and once the buffer gets too big, they are
+/// sorted and spilled to a temporary file (a run). Once all the records are
+/// pushed, the runs are merged back into a single sorted stream.
+pub struct Sorter {
+    /// Directory to create run files in.
+    dir: PathBuf,
+    /// Records (with payloads) not spilled to disk yet.
+    buf: Vec<(Record, Vec<u8>)>,
+    /// Approximate size of the buffered records in bytes.
+    buf_size: usize,
+    /// Size of the buffered records at which they are spilled to disk.
+    max_run_size: usize,
+    /// Run files spilled so far.
+    runs: Runs,
+}
+
+impl Sorter {
+
+    /// Creates a new sorter spilling records to the given directory.
+    pub fn new(dir: &Path) -> std::io::Result<Sorter> {
+        create_dir(dir)?;
+
+        Ok(Sorter {
+            dir: dir.to_path_buf(),
+            buf: Vec::new(),
+            buf_size: 0,
+            max_run_size: MAX_RUN_SIZE,
+            runs: Runs {
+                paths: Vec::new(),
+            },
+        })
+    }
+
+    /// Adds the given record (and its payload) to the sorter.
+    pub fn push(&mut self, record: Record, payload: Vec<u8>) -> std::io::Result<()> {
+        // Fixed-size fields of the record take 32 bytes.
+        self.buf_size += 32 + record.path.len() + payload.len();
+        self.buf.push((record, payload));
+
+        if self.buf_size >= self.max_run_size {
+            self.spill()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sorts the buffered records and writes them to a new run file.
+    fn spill(&mut self) -> std::io::Result<()> {
+        self.buf.sort_unstable();
+
+        let path = self.dir.join(format! {
+            ".run-{}-{}.tmp",
+            std::process::id(),
+            RUN_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+        });
+
+        let file = create_file(&path)?;
+        self.runs.paths.push(path);
+
+        let mut writer = std::io::BufWriter::new(file);
+        for (record, payload) in self.buf.drain(..) {
+            let payload_len = u32::try_from(payload.len())
+                .map_err(|_| std::io::ErrorKind::InvalidInput)?;
+
+            record.write(&mut writer)?;
+            writer.write_u32::<LittleEndian>(payload_len)?;
+            writer.write_all(&payload)?;
+        }
+        writer.flush()?;
+
+        self.buf_size = 0;
+
+        Ok(())
+    }
+
+    /// Finishes sorting and returns all the pushed records ordered by path.
+    pub fn finish(mut self) -> std::io::Result<Sorted> {
+        self.buf.sort_unstable();
+
+        let mut sources = Vec::<Source>::new();
+        for path in &self.runs.paths {
+            let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+
+            sources.push(Box::new(std::iter::from_fn(move || {
+                read_run_entry(&mut reader).transpose()
+            })));
+        }
+        sources.push(Box::new(self.buf.into_iter().map(Ok)));
+
+        Sorted::new(sources, self.runs)
+    }
+}
+
+/// Source of sorted records (with payloads) to merge.
+type Source = Box<dyn Iterator<Item = std::io::Result<(Record, Vec<u8>)>>>;
+
+/// Reads a single record (and its payload) from a run file.
+fn read_run_entry<R: Read>(reader: &mut R) -> std::io::Result<Option<(Record, Vec<u8>)>> {
+    let Some(record) = Record::read(reader)? else {
+        return Ok(None);
+    };
+
+    let payload_len = reader.read_u32::<LittleEndian>()?;
+
+    let mut payload = Vec::new();
+    reader.take(u64::from(payload_len)).read_to_end(&mut payload)?;
+    if payload.len() != payload_len as usize {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(Some((record, payload)))
+}
+
+/// Temporary run files that are deleted once no longer needed.
+struct Runs {
+    paths: Vec<PathBuf>,
+}
+
+impl Drop for Runs {
+
+    fn drop(&mut self) {
+        for path in &self.paths {
+            if let Err(error) = std::fs::remove_file(path) {
+                log::warn!("failed to remove '{}': {error}", path.display());
+            }
+        }
+    }
+}
+
+/// Iterator over records (with payloads) ordered by path.
+///
+/// This iterator is created by the [`Sorter::finish`] method.
+pub struct Sorted {
+    /// Sorted sources to merge.
+    sources: Vec<Source>,
+    /// Next record of every source that is not exhausted yet.
+    heads: BinaryHeap<std::cmp::Reverse<(Record, usize, Vec<u8>)>>,
+    /// Run files backing the sources (kept until the iterator is dropped).
+    _runs: Runs,
+}
+
+impl Sorted {
+
+    /// Creates an iterator merging the given sorted sources.
+    fn new(mut sources: Vec<Source>, runs: Runs) -> std::io::Result<Sorted> {
+        let mut heads = BinaryHeap::new();
+        for (index, source) in sources.iter_mut().enumerate() {
+            if let Some((record, payload)) = source.next().transpose()? {
+                heads.push(std::cmp::Reverse((record, index, payload)));
+            }
+        }
+
+        Ok(Sorted {
+            sources,
+            heads,
+            _runs: runs,
+        })
+    }
+}
+
+impl Iterator for Sorted {
+
+    type Item = std::io::Result<(Record, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<std::io::Result<(Record, Vec<u8>)>> {
+        let std::cmp::Reverse((record, index, payload)) = self.heads.pop()?;
+
+        match self.sources[index].next() {
+            Some(Ok((next_record, next_payload))) => {
+                self.heads.push(std::cmp::Reverse((next_record, index, next_payload)));
+            }
+            Some(Err(error)) => return Some(Err(error)),
+            None => (),
+        }
+
+        Some(Ok((record, payload)))
+    }
+}
+
+/// Result of comparing a file against the base snapshot.
+pub enum Compared {
+    /// A file that currently exists (with its payload and the change since
+    /// the base snapshot, if any).
+    Current(Record, Vec<u8>, Option<Change>),
+    /// A file that existed in the base snapshot but no longer exists.
+    Deleted(Record),
+}
+
+/// Comparison of the current state of the filesystem with a base snapshot.
+///
+/// The base snapshot is read from the disk as the comparison progresses and
+/// only one record of it is kept in memory at a time.
+pub struct Diff {
+    /// Reader of the remaining records of the base snapshot.
+    reader: std::io::BufReader<flate2::read::GzDecoder<std::fs::File>>,
+    /// Next record of the base snapshot to compare against (if any).
+    base: Option<Record>,
+}
+
+impl Diff {
+
+    /// Loads the snapshot with the given identifier to compare against.
+    pub fn load(dir: &Path, id: &Id) -> std::io::Result<Diff> {
+        let file = std::fs::File::open(id.path(dir))?;
+        let mut reader = std::io::BufReader::new(flate2::read::GzDecoder::new(file));
+
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "invalid timeline snapshot magic",
+            ));
+        }
+
+        let base = Record::read(&mut reader)?;
+
+        Ok(Diff {
+            reader,
+            base,
+        })
+    }
+
+    /// Compares the given records (ordered by path) against the base snapshot.
+    pub fn compare<I>(self, records: I) -> Compare<I>
+    where
+        I: Iterator<Item = std::io::Result<(Record, Vec<u8>)>>,
+    {
+        Compare {
+            diff: self,
+            records,
+            current: None,
+        }
+    }
+
+    /// Advances to the next record of the base snapshot.
+    ///
+    /// The current record of the base snapshot is returned.
+    fn next_base(&mut self) -> std::io::Result<Option<Record>> {
+        let next = Record::read(&mut self.reader)?;
+
+        if let (Some(base), Some(next)) = (&self.base, &next) {
+            if next.path <= base.path {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "unsorted timeline snapshot records",
+                ));
+            }
+        }
+
+        Ok(std::mem::replace(&mut self.base, next))
+    }
+}
+
+/// Iterator over results of comparing files against the base snapshot.
+///
+/// This iterator is created by the [`Diff::compare`] method.
+pub struct Compare<I> {
+    /// Base snapshot to compare against.
+    diff: Diff,
+    /// Remaining records (ordered by path) of the current files.
+    records: I,
+    /// Next record of the current files to compare.
+    current: Option<(Record, Vec<u8>)>,
+}
+
+impl<I> Compare<I>
+where
+    I: Iterator<Item = std::io::Result<(Record, Vec<u8>)>>,
+{
+    /// Compares the next file against the base snapshot.
+    fn next_compared(&mut self) -> std::io::Result<Option<Compared>> {
+        if self.current.is_none() {
+            self.current = self.records.next().transpose()?;
+        }
+
+        use std::cmp::Ordering;
+
+        let ordering = match (&self.current, &self.diff.base) {
+            (None, None) => return Ok(None),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((record, _)), Some(base)) => record.path.cmp(&base.path),
+        };
+
+        let current = match ordering {
+            Ordering::Greater => None,
+            Ordering::Less | Ordering::Equal => self.current.take(),
+        };
+        let base = match ordering {
+            Ordering::Less => None,
+            Ordering::Greater | Ordering::Equal => self.diff.next_base()?,
+        };
+
+        Ok(match (current, base) {
+            (Some((record, payload)), None) => {
+                Some(Compared::Current(record, payload, Some(Change::Created)))
+            }
+            (Some((record, payload)), Some(base)) => {
+                let change = (record != base).then_some(Change::Modified);
+                Some(Compared::Current(record, payload, change))
+            }
+            (None, Some(base)) => Some(Compared::Deleted(base)),
+            (None, None) => None,
+        })
+    }
+}
+
+impl<I> Iterator for Compare<I>
+where
+    I: Iterator<Item = std::io::Result<(Record, Vec<u8>)>>,
+{
+    type Item = std::io::Result<Compared>;
+
+    fn next(&mut self) -> Option<std::io::Result<Compared>> {
+        self.next_compared().transpose()
+    }
+}
+
+/// Writer of a new snapshot.
+///
+/// Records are written to a temporary file which is renamed once the writer
+/// is finished, so a failure midway never leaves a corrupted snapshot behind.
+pub struct Writer {
+    /// Writer of the temporary snapshot file.
+    writer: std::io::BufWriter<flate2::write::GzEncoder<std::fs::File>>,
+    /// Path to the snapshot file.
+    path: PathBuf,
+    /// Path to the temporary snapshot file.
+    temp_path: PathBuf,
+    /// Path of the last written record (to verify the ordering).
+    last_path: Option<Vec<u8>>,
+}
+
+impl Writer {
+
+    /// Creates a writer of a snapshot with the given identifier.
+    pub fn create(dir: &Path, id: &Id) -> std::io::Result<Writer> {
+        create_dir(dir)?;
+
+        let path = id.path(dir);
+        let temp_path = path.with_extension("snapshot.tmp");
+
+        let file = create_file(&temp_path)?;
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut writer = std::io::BufWriter::new(encoder);
+
+        writer.write_all(MAGIC)?;
+
+        Ok(Writer {
+            writer,
+            path,
+            temp_path,
+            last_path: None,
+        })
+    }
+
+    /// Writes the given record to the snapshot.
+    ///
+    /// Records have to be written in order of their paths.
+    pub fn write(&mut self, record: &Record) -> std::io::Result<()> {
+        if self.last_path.as_ref().is_some_and(|last_path| *last_path >= record.path) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "unsorted timeline snapshot records",
+            ));
+        }
+
+        record.write(&mut self.writer)?;
+        self.last_path = Some(record.path.clone());
+
+        Ok(())
+    }
+
+    /// Finishes writing the snapshot and moves it to its final location.
+    pub fn finish(self) -> std::io::Result<()> {
+        let encoder = self.writer.into_inner()
+            .map_err(std::io::IntoInnerError::into_error)?;
+        encoder.finish()?.sync_all()?;
+
+        std::fs::rename(&self.temp_path, &self.path)
+    }
+}
+
+/// Saves a snapshot consisting of the given records (ordered by path).
+pub fn save<I>(dir: &Path, id: &Id, records: I) -> std::io::Result<()>
+where
+    I: Iterator<Item = std::io::Result<(Record, Vec<u8>)>>,
+{
+    let mut writer = Writer::create(dir, id)?;
+    for record in records {
+        let (record, _) = record?;
+        writer.write(&record)?;
+    }
+
+    writer.finish()
+}
+
+/// Creates the snapshot directory (accessible only to the owner).
+fn create_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::DirBuilderExt as _;
+        builder.mode(0o700);
+    }
+
+    builder.create(dir)
+}
+
+/// Creates a new file (accessible only to the owner) at the given path.
+///
+/// If there is a file at the given path already (e.g. left behind by a failed
+/// run), it is replaced.
+fn create_file(path: &Path) -> std::io::Result<std::fs::File> {
+    match std::fs::remove_file(path) {
+        Ok(()) => (),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => (),
+        Err(error) => return Err(error),
+    }
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::fs::OpenOptionsExt as _;
+        options.mode(0o600);
+    }
+
+    options.open(path)
+}
+
+/// An error indicating that a snapshot identifier is invalid.
+#[derive(Debug)]
+pub struct ParseIdError {
+    id: String,
+}
+
+impl std::fmt::Display for ParseIdError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "invalid snapshot identifier: {:?}", self.id)
+    }
+}
+
+impl std::error::Error for ParseIdError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Creates a record with the given path and size.
+    fn record(path: &str, size: u64) -> Record {
+        Record {
+            path: Vec::from(path),
+            ino: 42,
+            size,
+            mtime_nanos: 1337,
+            ctime_nanos: -1,
+        }
+    }
+
+    /// Sorts the given records using a sorter spilling to the given directory.
+    fn sort(dir: &Path, records: Vec<Record>) -> Sorted {
+        let mut sorter = Sorter::new(dir)
+            .unwrap();
+
+        for record in records {
+            sorter.push(record, Vec::new())
+                .unwrap();
+        }
+
+        sorter.finish()
+            .unwrap()
+    }
+
+    /// Saves a snapshot consisting of the given (not necessarily sorted) records.
+    fn save_records(dir: &Path, id: &Id, records: Vec<Record>) {
+        save(dir, id, sort(dir, records))
+            .unwrap();
+    }
+
+    /// Compares the given records against the given snapshot.
+    fn compare(dir: &Path, id: &Id, records: Vec<Record>) -> Vec<Compared> {
+        let diff = Diff::load(dir, id)
+            .unwrap();
+
+        diff.compare(sort(dir, records))
+            .collect::<std::io::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn id_valid() {
+        assert!("foo".parse::<Id>().is_ok());
+        assert!("daily-2024_01_01".parse::<Id>().is_ok());
+    }
+
+    #[test]
+    fn id_invalid() {
+        assert!("".parse::<Id>().is_err());
+        assert!("../foo".parse::<Id>().is_err());
+        assert!("foo/bar".parse::<Id>().is_err());
+        assert!("foo.bar".parse::<Id>().is_err());
+    }
+
+    #[test]
+    fn sorter_in_memory() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let records = sort(tempdir.path(), vec![
+            record("/foo", 1),
+            record("/bar", 2),
+            record("/baz", 3),
+        ]).map(|record| record.unwrap().0).collect::<Vec<_>>();
+
+        assert_eq!(records, vec![
+            record("/bar", 2),
+            record("/baz", 3),
+            record("/foo", 1),
+        ]);
+    }
+
+    #[test]
+    fn sorter_spilled() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut sorter = Sorter::new(tempdir.path())
+            .unwrap();
+        // Make every record end up in its own run.
+        sorter.max_run_size = 1;
+
+        for i in [5, 3, 8, 1, 9, 2, 7, 4, 6, 0] {
+            sorter.push(record(&format!("/{i}"), i), vec![i as u8; i as usize])
+                .unwrap();
+        }
+
+        let sorted = sorter.finish()
+            .unwrap();
+        assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 10);
+
+        let entries = sorted
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(entries.len(), 10);
+        for (i, (record, payload)) in entries.into_iter().enumerate() {
+            assert_eq!(record, self::record(&format!("/{i}"), i as u64));
+            assert_eq!(payload, vec![i as u8; i]);
+        }
+
+        // Once the sorted records are consumed, run files should be removed.
+        assert_eq!(std::fs::read_dir(tempdir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let id = "foo".parse::<Id>()
+            .unwrap();
+
+        save_records(tempdir.path(), &id, vec![record("/foo", 1), record("/bar", 2)]);
+
+        let compared = compare(tempdir.path(), &id, vec![]);
+        let deleted = compared.into_iter()
+            .map(|compared| match compared {
+                Compared::Deleted(record) => record,
+                Compared::Current(..) => panic!("unexpected current record"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(deleted, vec![record("/bar", 2), record("/foo", 1)]);
+    }
+
+    #[test]
+    fn save_and_load_empty() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let id = "foo".parse::<Id>()
+            .unwrap();
+
+        save_records(tempdir.path(), &id, vec![]);
+
+        assert!(compare(tempdir.path(), &id, vec![]).is_empty());
+    }
+
+    #[test]
+    fn save_unsorted() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let id = "foo".parse::<Id>()
+            .unwrap();
+
+        let records = vec![
+            Ok((record("/foo", 1), Vec::new())),
+            Ok((record("/bar", 2), Vec::new())),
+        ];
+
+        assert!(save(tempdir.path(), &id, records.into_iter()).is_err());
+        assert!(Diff::load(tempdir.path(), &id).is_err());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn save_permissions() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let snapshot_dir = tempdir.path().join("snapshots");
+
+        let id = "foo".parse::<Id>()
+            .unwrap();
+
+        save_records(&snapshot_dir, &id, vec![record("/foo", 1)]);
+
+        let dir_mode = std::fs::metadata(&snapshot_dir).unwrap()
+            .permissions().mode();
+        assert_eq!(dir_mode & 0o777, 0o700);
+
+        let file_mode = std::fs::metadata(id.path(&snapshot_dir)).unwrap()
+            .permissions().mode();
+        assert_eq!(file_mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn load_non_existent() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let id = "foo".parse::<Id>()
+            .unwrap();
+
+        assert!(Diff::load(tempdir.path(), &id).is_err());
+    }
+
+    #[test]
+    fn load_corrupted() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let id = "foo".parse::<Id>()
+            .unwrap();
+
+        std::fs::write(id.path(tempdir.path()), b"garbage")
+            .unwrap();
+
+        assert!(Diff::load(tempdir.path(), &id).is_err());
+    }
+
+    #[test]
+    fn diff_compare() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let id = "foo".parse::<Id>()
+            .unwrap();
+
+        save_records(tempdir.path(), &id, vec![
+            record("/same", 1),
+            record("/modified", 2),
+            record("/deleted", 3),
+        ]);
+
+        let compared = compare(tempdir.path(), &id, vec![
+            record("/same", 1),
+            record("/modified", 1337),
+            record("/created", 4),
+        ]);
+
+        let compared = compared.into_iter()
+            .map(|compared| match compared {
+                Compared::Current(record, _, Some(Change::Created)) => (record, "created"),
+                Compared::Current(record, _, Some(Change::Modified)) => (record, "modified"),
+                Compared::Current(record, _, None) => (record, "unchanged"),
+                Compared::Deleted(record) => (record, "deleted"),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(compared, vec![
+            (record("/created", 4), "created"),
+            (record("/deleted", 3), "deleted"),
+            (record("/modified", 1337), "modified"),
+            (record("/same", 1), "unchanged"),
+        ]);
+    }
+}
@@ -0,0 +1,490 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Text formats of filesystem timelines.
+//!
+//! Apart from the default gzchunked stream of Protocol Buffers messages, the
+//! timeline can be returned as gzipped text in formats that existing forensic
+//! tools understand directly (the bodyfile format of The Sleuth Kit and CSV).
+
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+use rrg_proto::get_filesystem_timeline::{ChangeType, Entry};
+
+/// A rough size limit for gzipped parts of the output.
+const PART_SIZE: u64 = 1024 * 1024; // 1 MiB.
+
+/// Names of the columns of the CSV format.
+const CSV_HEADER: &str = concat! {
+    "path,unix_mode,size,unix_dev,unix_ino,unix_uid,unix_gid,",
+    "atime_nanos,mtime_nanos,ctime_nanos,btime_nanos,windows_attributes,",
+    "linux_flags,symlink,sha256,change_type\n",
+};
+
+/// Text format of the timeline.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The [bodyfile] format as used by The Sleuth Kit.
+    ///
+    /// [bodyfile]: https://wiki.sleuthkit.org/index.php?title=Body_file
+    Bodyfile,
+    /// The [CSV] format with a header line.
+    ///
+    /// [CSV]: https://www.rfc-editor.org/rfc/rfc4180
+    Csv,
+}
+
+impl Format {
+
+    /// Returns the text format corresponding to the given protobuf enum.
+    ///
+    /// `None` is returned for the Protocol Buffers format.
+    pub fn from_proto(
+        proto: rrg_proto::get_filesystem_timeline::OutputFormat,
+    ) -> Option<Format> {
+        use rrg_proto::get_filesystem_timeline::OutputFormat;
+
+        match proto {
+            OutputFormat::PROTO => None,
+            OutputFormat::BODYFILE => Some(Format::Bodyfile),
+            OutputFormat::CSV => Some(Format::Csv),
+        }
+    }
+}
+
+/// Encodes timeline entries into gzipped parts of text in the given format.
+pub fn encode<I>(iter: I, format: Format) -> Encode<I>
+where
+    I: Iterator<Item = Entry>,
+{
+    Encode {
+        iter,
+        format,
+        part_size: PART_SIZE,
+        is_first: true,
+    }
+}
+
+/// Streaming encoder of timeline entries into gzipped text.
+///
+/// It implements the `Iterator` trait, lazily polling the underlying iterator
+/// over timeline entries as more parts are needed. Each part is a standalone
+/// gzip stream consisting of whole lines, so concatenated parts form a valid
+/// gzip file of the whole timeline.
+///
+/// Instances of this type can be constructed using the [`encode`] function.
+pub struct Encode<I> {
+    iter: I,
+    format: Format,
+    part_size: u64,
+    /// Whether the next line is the first one of the whole output.
+    is_first: bool,
+}
+
+impl<I> Encode<I>
+where
+    I: Iterator<Item = Entry>,
+{
+    /// Obtains the next part of the output (if available).
+    fn next_part(&mut self) -> std::io::Result<Option<Vec<u8>>> {
+        let compression = flate2::Compression::default();
+        let mut encoder = flate2::write::GzEncoder::new(vec!(), compression);
+
+        let mut is_empty = true;
+        while (encoder.get_ref().len() as u64) < self.part_size {
+            let entry = match self.iter.next() {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            let mut line = String::new();
+            match self.format {
+                Format::Bodyfile => write_bodyfile_line(&mut line, &entry),
+                Format::Csv => {
+                    if self.is_first {
+                        line.push_str(CSV_HEADER);
+                    }
+                    write_csv_line(&mut line, &entry);
+                }
+            }
+
+            encoder.write_all(line.as_bytes())?;
+            self.is_first = false;
+            is_empty = false;
+        }
+
+        if is_empty {
+            Ok(None)
+        } else {
+            Ok(Some(encoder.finish()?))
+        }
+    }
+}
+
+impl<I> Iterator for Encode<I>
+where
+    I: Iterator<Item = Entry>,
+{
+    type Item = std::io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<std::io::Result<Vec<u8>>> {
+        self.next_part().transpose()
+    }
+}
+
+/// Writes a bodyfile line (with the trailing newline) of the given entry.
+fn write_bodyfile_line(line: &mut String, entry: &Entry) {
+    /// Converts the given timestamp to seconds since epoch.
+    fn secs(nanos: i64) -> i64 {
+        nanos.div_euclid(1_000_000_000)
+    }
+
+    // We do not compute MD5 digests of files, so the first field is always 0
+    // (as it is done by The Sleuth Kit when it is not available).
+    line.push_str("0|");
+    write_escaped(line, entry.path(), &['|']);
+    line.push('|');
+
+    let mode = if entry.has_unix_mode() {
+        mode_string(entry.unix_mode())
+    } else {
+        String::from("0")
+    };
+
+    // Writing to strings never fails, so we can safely ignore the result.
+    let _ = writeln! {
+        line,
+        "{}|{}|{}|{}|{}|{}|{}|{}|{}",
+        entry.unix_ino(),
+        mode,
+        entry.unix_uid(),
+        entry.unix_gid(),
+        entry.size(),
+        secs(entry.atime_nanos()),
+        secs(entry.mtime_nanos()),
+        secs(entry.ctime_nanos()),
+        secs(entry.btime_nanos()),
+    };
+}
+
+/// Writes a CSV line (with the trailing newline) of the given entry.
+fn write_csv_line(line: &mut String, entry: &Entry) {
+    /// Writes the given value (if available) followed by a comma.
+    fn field<T: std::fmt::Display>(line: &mut String, value: Option<T>) {
+        if let Some(value) = value {
+            let _ = write!(line, "{value}");
+        }
+        line.push(',');
+    }
+
+    /// Writes the given path (if available) followed by a comma.
+    fn path_field(line: &mut String, path: Option<&[u8]>) {
+        if let Some(path) = path {
+            // Because of escaping, paths never contain newlines and the only
+            // character that needs special treatment are quotes.
+            let mut escaped = String::new();
+            write_escaped(&mut escaped, path, &[]);
+
+            line.push('"');
+            line.push_str(&escaped.replace('"', "\"\""));
+            line.push('"');
+        }
+        line.push(',');
+    }
+
+    path_field(line, entry.has_path().then(|| entry.path()));
+    field(line, entry.has_unix_mode().then(|| entry.unix_mode()));
+    field(line, entry.has_size().then(|| entry.size()));
+    field(line, entry.has_unix_dev().then(|| entry.unix_dev()));
+    field(line, entry.has_unix_ino().then(|| entry.unix_ino()));
+    field(line, entry.has_unix_uid().then(|| entry.unix_uid()));
+    field(line, entry.has_unix_gid().then(|| entry.unix_gid()));
+    field(line, entry.has_atime_nanos().then(|| entry.atime_nanos()));
+    field(line, entry.has_mtime_nanos().then(|| entry.mtime_nanos()));
+    field(line, entry.has_ctime_nanos().then(|| entry.ctime_nanos()));
+    field(line, entry.has_btime_nanos().then(|| entry.btime_nanos()));
+    field(line, entry.has_windows_attributes().then(|| entry.windows_attributes()));
+    field(line, entry.has_linux_flags().then(|| entry.linux_flags()));
+    path_field(line, entry.has_symlink().then(|| entry.symlink()));
+
+    if entry.has_sha256() {
+        for byte in entry.sha256() {
+            let _ = write!(line, "{byte:02x}");
+        }
+    }
+    line.push(',');
+
+    if entry.has_change_type() {
+        line.push_str(match entry.change_type() {
+            ChangeType::UNKNOWN_CHANGE => "UNKNOWN_CHANGE",
+            ChangeType::CREATED => "CREATED",
+            ChangeType::MODIFIED => "MODIFIED",
+            ChangeType::DELETED => "DELETED",
+        });
+    }
+    line.push('\n');
+}
+
+/// Writes the given raw path as text, escaping characters that are not safe.
+///
+/// Backslashes and `special` characters are prefixed with a backslash. Control
+/// characters (including newlines) and bytes that are not valid UTF-8 (e.g.
+/// in non-Unicode file names on Linux) are written as `\xNN` escapes of their
+/// raw bytes, so the original path can always be recovered.
+fn write_escaped(text: &mut String, path: &[u8], special: &[char]) {
+    for chunk in path.utf8_chunks() {
+        for char in chunk.valid().chars() {
+            if char == '\\' || special.contains(&char) {
+                text.push('\\');
+                text.push(char);
+            } else if char.is_control() {
+                for byte in char.encode_utf8(&mut [0; 4]).bytes() {
+                    let _ = write!(text, "\\x{byte:02x}");
+                }
+            } else {
+                text.push(char);
+            }
+        }
+
+        for byte in chunk.invalid() {
+            let _ = write!(text, "\\x{byte:02x}");
+        }
+    }
+}
+
+/// Converts the given Unix file mode to a string like `drwxr-xr-x`.
+fn mode_string(mode: i64) -> String {
+    let mut string = String::with_capacity(10);
+
+    string.push(match mode & 0o170000 {
+        0o140000 => 's',
+        0o120000 => 'l',
+        0o100000 => '-',
+        0o060000 => 'b',
+        0o040000 => 'd',
+        0o020000 => 'c',
+        0o010000 => 'p',
+        _ => '?',
+    });
+
+    // Each triple consists of the read, write and execute permissions and the
+    // special bit that is displayed in place of the execute one if set.
+    let triples = [
+        (0o400, 0o200, 0o100, 0o4000, 's'),
+        (0o040, 0o020, 0o010, 0o2000, 's'),
+        (0o004, 0o002, 0o001, 0o1000, 't'),
+    ];
+
+    for (read, write, exec, special, special_char) in triples {
+        string.push(if mode & read != 0 { 'r' } else { '-' });
+        string.push(if mode & write != 0 { 'w' } else { '-' });
+        string.push(match (mode & exec != 0, mode & special != 0) {
+            (false, false) => '-',
+            (true, false) => 'x',
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, true) => special_char,
+        });
+    }
+
+    string
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn escaped(path: &[u8], special: &[char]) -> String {
+        let mut text = String::new();
+        write_escaped(&mut text, path, special);
+
+        text
+    }
+
+    /// Decodes the given gzipped parts into a single string.
+    fn decode<I>(parts: I) -> String
+    where
+        I: IntoIterator<Item = std::io::Result<Vec<u8>>>,
+    {
+        use std::io::Read as _;
+
+        let mut text = String::new();
+        for part in parts {
+            flate2::read::GzDecoder::new(&part.unwrap()[..])
+                .read_to_string(&mut text)
+                .unwrap();
+        }
+
+        text
+    }
+
+    fn entry(path: &str) -> Entry {
+        let mut entry = Entry::default();
+        entry.set_path(Vec::from(path));
+
+        entry
+    }
+
+    #[test]
+    fn escape_plain() {
+        assert_eq!(escaped(b"/foo/bar.txt", &['|']), "/foo/bar.txt");
+    }
+
+    #[test]
+    fn escape_unicode() {
+        assert_eq!(escaped("/zażółć/gęślą".as_bytes(), &['|']), "/zażółć/gęślą");
+    }
+
+    #[test]
+    fn escape_special() {
+        assert_eq!(escaped(b"/foo|bar\\baz", &['|']), "/foo\\|bar\\\\baz");
+        assert_eq!(escaped(b"/foo|bar", &[]), "/foo|bar");
+    }
+
+    #[test]
+    fn escape_control() {
+        assert_eq!(escaped(b"/foo\nbar\r\t", &[]), "/foo\\x0abar\\x0d\\x09");
+        assert_eq!(escaped("/foo\u{85}".as_bytes(), &[]), "/foo\\xc2\\x85");
+    }
+
+    #[test]
+    fn escape_invalid_utf8() {
+        assert_eq!(escaped(b"/foo\xff\xfebar", &[]), "/foo\\xff\\xfebar");
+        assert_eq!(escaped(b"/\xe2\x82", &[]), "/\\xe2\\x82");
+    }
+
+    #[test]
+    fn mode_string_file() {
+        assert_eq!(mode_string(0o100644), "-rw-r--r--");
+        assert_eq!(mode_string(0o100755), "-rwxr-xr-x");
+    }
+
+    #[test]
+    fn mode_string_dir() {
+        assert_eq!(mode_string(0o040755), "drwxr-xr-x");
+        assert_eq!(mode_string(0o041777), "drwxrwxrwt");
+    }
+
+    #[test]
+    fn mode_string_special() {
+        assert_eq!(mode_string(0o120777), "lrwxrwxrwx");
+        assert_eq!(mode_string(0o104755), "-rwsr-xr-x");
+        assert_eq!(mode_string(0o102644), "-rw-r-Sr--");
+        assert_eq!(mode_string(0o0), "?---------");
+    }
+
+    #[test]
+    fn bodyfile_line() {
+        let mut entry = entry("/foo|bar");
+        entry.set_unix_ino(42);
+        entry.set_unix_mode(0o100644);
+        entry.set_unix_uid(1000);
+        entry.set_unix_gid(100);
+        entry.set_size(1337);
+        entry.set_atime_nanos(1_000_000_000);
+        entry.set_mtime_nanos(2_500_000_000);
+        entry.set_ctime_nanos(3_000_000_000);
+
+        let mut line = String::new();
+        write_bodyfile_line(&mut line, &entry);
+
+        assert_eq! {
+            line,
+            "0|/foo\\|bar|42|-rw-r--r--|1000|100|1337|1|2|3|0\n"
+        };
+    }
+
+    #[test]
+    fn bodyfile_line_no_metadata() {
+        let mut line = String::new();
+        write_bodyfile_line(&mut line, &entry("C:\\foo"));
+
+        assert_eq!(line, "0|C:\\\\foo|0|0|0|0|0|0|0|0|0\n");
+    }
+
+    #[test]
+    fn bodyfile_line_negative_time() {
+        let mut entry = entry("/foo");
+        entry.set_mtime_nanos(-1);
+
+        let mut line = String::new();
+        write_bodyfile_line(&mut line, &entry);
+
+        assert_eq!(line, "0|/foo|0|0|0|0|0|0|-1|0|0\n");
+    }
+
+    #[test]
+    fn csv_line() {
+        let mut entry = entry("/foo,\"bar\"");
+        entry.set_unix_mode(0o120777);
+        entry.set_size(3);
+        entry.set_mtime_nanos(1337);
+        entry.set_symlink(Vec::from("baz"));
+        entry.set_sha256(vec![0x00, 0xab, 0xff]);
+        entry.set_change_type(ChangeType::CREATED);
+
+        let mut line = String::new();
+        write_csv_line(&mut line, &entry);
+
+        assert_eq! {
+            line,
+            "\"/foo,\"\"bar\"\"\",41471,3,,,,,,1337,,,,,\"baz\",00abff,CREATED\n"
+        };
+        assert_eq! {
+            line.split(',').count(),
+            CSV_HEADER.split(',').count() + 1 // The path has an extra comma.
+        };
+    }
+
+    #[test]
+    fn encode_empty() {
+        let mut parts = encode(std::iter::empty(), Format::Csv);
+
+        assert!(parts.next().is_none());
+    }
+
+    #[test]
+    fn encode_bodyfile() {
+        let entries = vec![entry("/foo"), entry("/bar")];
+
+        let text = decode(encode(entries.into_iter(), Format::Bodyfile));
+        assert_eq!(text, "0|/foo|0|0|0|0|0|0|0|0|0\n0|/bar|0|0|0|0|0|0|0|0|0\n");
+    }
+
+    #[test]
+    fn encode_csv() {
+        let entries = vec![entry("/foo"), entry("/bar")];
+
+        let text = decode(encode(entries.into_iter(), Format::Csv));
+
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER.trim_end()));
+        assert_eq!(lines.next(), Some("\"/foo\",,,,,,,,,,,,,,,"));
+        assert_eq!(lines.next(), Some("\"/bar\",,,,,,,,,,,,,,,"));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn encode_multiple_parts() {
+        let entries = (0..1024)
+            .map(|i| entry(&format!("/foo/{i}")));
+
+        let mut encode = encode(entries, Format::Csv);
+        encode.part_size = 1;
+
+        let parts = encode.collect::<Vec<_>>();
+        assert!(parts.len() > 1);
+
+        let text = decode(parts);
+
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER.trim_end()));
+        for i in 0..1024 {
+            assert_eq!(lines.next(), Some(format!("\"/foo/{i}\",,,,,,,,,,,,,,,").as_str()));
+        }
+        assert_eq!(lines.next(), None);
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Limit on the size of individual memory part blob sent to the blob sink.
+const MAX_BLOB_LEN: usize = 2 * 1024 * 1024; // 2 MiB.
+
+/// Arguments of the `get_memory_contents` action.
+pub struct Args {
+    /// Identifier of the process to get the memory contents of.
+    pid: u32,
+    /// Ranges of the process memory to get the contents of.
+    ranges: Vec<std::ops::Range<u64>>,
+    /// Whether to compress blobs sent to the blob sink.
+    compress_blobs: bool,
+}
+
+/// Result of the `get_memory_contents` action.
+pub struct Item {
+    /// Address of the memory part sent to the blob sink.
+    addr: u64,
+    /// Number of bytes of the memory part sent to the blob sink.
+    len: usize,
+    /// SHA-256 digest of the memory part sent to the blob sink.
+    blob_sha256: [u8; 32],
+}
+
+/// Handles invocations of the `get_memory_contents` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use sha2::Digest as _;
+
+    let memory = ospect::proc::open_memory(args.pid)
+        .map_err(crate::session::Error::action)?;
+
+    // Parts of the requested ranges might not be readable (e.g. guard pages),
+    // so we do not fail the whole action on the first error. Instead, we fail
+    // only if nothing could be read at all—similarly to how `query_wmi` does
+    // it.
+    let mut first_error = None;
+    let mut any_read = false;
+
+    for range in args.ranges {
+        let mut addr = range.start;
+
+        while addr < range.end {
+            // The cast is safe as the result is capped at `MAX_BLOB_LEN`.
+            let len = std::cmp::min(range.end - addr, MAX_BLOB_LEN as u64) as usize;
+            let mut buf = vec![0; len];
+
+            let len_read = match memory.read_at(addr, &mut buf[..]) {
+                Ok(0) => break,
+                Ok(len_read) => len_read,
+                Err(error) => {
+                    log::error! {
+                        "failed to read memory of process {} at {:#x}: {}",
+                        args.pid, addr, error
+                    };
+                    first_error.get_or_insert(error);
+                    break;
+                }
+            };
+
+            buf.truncate(len_read);
+
+            let blob_sha256 = sha2::Sha256::digest(&buf).into();
+
+            let mut blob = crate::blob::Blob::from(buf);
+            if args.compress_blobs {
+                blob = blob.compress();
+            }
+
+            session.send(crate::Sink::Blob, blob)?;
+            session.reply(Item {
+                addr,
+                len: len_read,
+                blob_sha256,
+            })?;
+            any_read = true;
+
+            addr += len_read as u64;
+        }
+    }
+
+    match first_error {
+        Some(error) if !any_read => Err(crate::session::Error::action(error)),
+        _ => Ok(()),
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_memory_contents::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        if proto.ranges().is_empty() {
+            return Err(ParseArgsError::invalid_field("ranges", RangeError::NoRanges));
+        }
+
+        let mut ranges = Vec::new();
+        for range in proto.take_ranges() {
+            if range.start() >= range.end() {
+                return Err(ParseArgsError::invalid_field("ranges", RangeError::Empty {
+                    start: range.start(),
+                    end: range.end(),
+                }));
+            }
+
+            ranges.push(range.start()..range.end());
+        }
+
+        Ok(Args {
+            pid: proto.pid(),
+            ranges,
+            compress_blobs: proto.compress_blobs(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_memory_contents::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_address(self.addr);
+        proto.set_length(self.len as u64);
+        proto.set_blob_sha256(self.blob_sha256.into());
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with invalid ranges.
+#[derive(Debug)]
+enum RangeError {
+    /// No ranges were specified.
+    NoRanges,
+    /// One of the ranges is empty.
+    Empty {
+        start: u64,
+        end: u64,
+    },
+}
+
+impl std::fmt::Display for RangeError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RangeError::NoRanges => {
+                write!(fmt, "no ranges to read")
+            }
+            RangeError::Empty { start, end } => {
+                write!(fmt, "empty range ({start:#x}..{end:#x})")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RangeError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_self() {
+        let value = *b"foobarbaz";
+        let addr = value.as_ptr() as u64;
+
+        let args = Args {
+            pid: std::process::id(),
+            ranges: vec![addr..addr + 3, addr + 6..addr + 9],
+            compress_blobs: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 2);
+        assert_eq!(session.parcel_count(crate::Sink::Blob), 2);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.addr, addr);
+        assert_eq!(item.len, 3);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), b"foo");
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.addr, addr + 6);
+        assert_eq!(item.len, 3);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 1);
+        assert_eq!(blob.as_bytes(), b"baz");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_unmapped() {
+        // The zero page is never mapped, so reading it should always fail.
+        let args = Args {
+            pid: std::process::id(),
+            ranges: vec![0..8, 8..16],
+            compress_blobs: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn args_from_proto_no_ranges() {
+        let proto = rrg_proto::get_memory_contents::Args::default();
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_empty_range() {
+        let mut range = rrg_proto::get_memory_contents::AddressRange::default();
+        range.set_start(0x1000);
+        range.set_end(0x1000);
+
+        let mut proto = rrg_proto::get_memory_contents::Args::default();
+        proto.mut_ranges().push(range);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+}
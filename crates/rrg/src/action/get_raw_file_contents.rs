@@ -0,0 +1,428 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+use std::path::PathBuf;
+
+use crate::raw::VfsStatus;
+
+/// Limit on the size of individual file part blob sent to the blob sink.
+const MAX_BLOB_LEN: usize = 2 * 1024 * 1024; // 2 MiB.
+
+/// Arguments of the `get_raw_file_contents` action.
+pub struct Args {
+    /// Path to the device (or an image file) with the filesystem.
+    device: PathBuf,
+    /// File (within the filesystem) to get the contents of.
+    target: Target,
+    /// Offset from which to read the file contents.
+    offset: u64,
+    /// Number of bytes to read from the file (`usize::MAX` to read to the end).
+    len: usize,
+    /// Whether to compress blobs sent to the blob sink.
+    compress_blobs: bool,
+}
+
+/// Specification of the file to get the contents of.
+enum Target {
+    /// File specified by its path.
+    Path(PathBuf),
+    /// File specified by the number of its inode.
+    Inode(u32),
+}
+
+/// Result of the `get_raw_file_contents` action.
+struct Item {
+    /// Byte offset of the file part.
+    offset: u64,
+    /// Number of bytes of the file part.
+    len: u64,
+    /// SHA-256 digest of the file part sent to the blob sink.
+    blob_sha256: [u8; 32],
+    /// Result of comparing the file part against the operating system view.
+    vfs_status: VfsStatus,
+}
+
+/// Handles invocations of the `get_raw_file_contents` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    use std::io::{Read as _, Seek as _};
+    use sha2::Digest as _;
+
+    let mut fs = crate::raw::open(&args.device)
+        .map_err(crate::session::Error::action)?;
+
+    let inode = match &args.target {
+        Target::Path(path) => fs.lookup(path),
+        Target::Inode(ino) => fs.inode(*ino),
+    }.map_err(crate::session::Error::action)?;
+
+    // Files specified by inode numbers have no path we could use to compare
+    // against the operating system view.
+    let vfs = match &args.target {
+        Target::Path(path) => crate::raw::Vfs::find(&args.device)
+            .map(|vfs| (vfs, path)),
+        Target::Inode(_) => None,
+    };
+
+    let mut file = fs.open(&inode)
+        .map_err(crate::session::Error::action)?;
+    file.seek(std::io::SeekFrom::Start(args.offset))
+        .map_err(crate::session::Error::action)?;
+
+    let mut file = file.take(args.len as u64);
+    let mut offset = args.offset;
+
+    loop {
+        let mut buf = Vec::with_capacity(MAX_BLOB_LEN);
+
+        // We read until the buffer is full (rather than doing a single `read`
+        // call) to have file parts always aligned the same way.
+        let len_read = (&mut file).take(MAX_BLOB_LEN as u64).read_to_end(&mut buf)
+            .map_err(crate::session::Error::action)?;
+
+        if len_read == 0 {
+            break;
+        }
+
+        let vfs_status = match &vfs {
+            Some((vfs, path)) => vfs.contents_status(path, offset, &buf),
+            None => VfsStatus::Unverified,
+        };
+
+        let blob_sha256 = sha2::Sha256::digest(&buf).into();
+
+        let mut blob = crate::blob::Blob::from(buf);
+        if args.compress_blobs {
+            blob = blob.compress();
+        }
+
+        session.send(crate::Sink::Blob, blob)?;
+        session.reply(Item {
+            offset,
+            len: len_read as u64,
+            blob_sha256,
+            vfs_status,
+        })?;
+
+        offset += len_read as u64;
+    }
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::get_raw_file_contents::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let device = PathBuf::try_from(proto.take_device())
+            .map_err(|error| ParseArgsError::invalid_field("device", error))?;
+
+        let target = match (proto.has_path(), proto.inode()) {
+            (true, 0) => {
+                let path = PathBuf::try_from(proto.take_path())
+                    .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+                Target::Path(path)
+            }
+            (false, 0) | (true, _) => {
+                return Err(ParseArgsError::invalid_field("path", TargetError));
+            }
+            (false, ino) => {
+                let ino = u32::try_from(ino)
+                    .map_err(|error| ParseArgsError::invalid_field("inode", error))?;
+
+                Target::Inode(ino)
+            }
+        };
+
+        let len = match proto.length() {
+            0 => usize::MAX,
+            len if len > MAX_BLOB_LEN as u64 => {
+                return Err(ParseArgsError::invalid_field("length", LenError { len }));
+            }
+            len => len as usize,
+        };
+
+        Ok(Args {
+            device,
+            target,
+            offset: proto.offset(),
+            len,
+            compress_blobs: proto.compress_blobs(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::get_raw_file_contents::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_offset(self.offset);
+        proto.set_length(self.len);
+        proto.set_blob_sha256(self.blob_sha256.into());
+        proto.set_vfs_status(self.vfs_status.into());
+
+        proto
+    }
+}
+
+/// An error indicating that the action was invoked with invalid length.
+#[derive(Debug)]
+struct LenError {
+    len: u64,
+}
+
+impl std::fmt::Display for LenError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "provided length ({}) is bigger than allowed ({})",
+            self.len, MAX_BLOB_LEN
+        }
+    }
+}
+
+impl std::error::Error for LenError {
+}
+
+/// An error indicating that the action was invoked without exactly one of the
+/// path and the inode number.
+#[derive(Debug)]
+struct TargetError;
+
+impl std::fmt::Display for TargetError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "exactly one of path and inode has to be specified")
+    }
+}
+
+impl std::error::Error for TargetError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_small_file() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            target: Target::Path(PathBuf::from("/hello.txt")),
+            offset: 0,
+            len: usize::MAX,
+            compress_blobs: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 0);
+        assert_eq!(item.len, b"Hello, world!\n".len() as u64);
+        assert_eq!(item.vfs_status, VfsStatus::Unverified);
+
+        use sha2::Digest as _;
+        assert_eq!(item.blob_sha256, <[u8; 32]>::from(sha2::Sha256::digest(b"Hello, world!\n")));
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), b"Hello, world!\n");
+    }
+
+    #[test]
+    fn handle_offset_and_len() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            target: Target::Path(PathBuf::from("/hello.txt")),
+            offset: 7,
+            len: 5,
+            compress_blobs: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 7);
+        assert_eq!(item.len, 5);
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), b"world");
+    }
+
+    #[test]
+    fn handle_big_file() {
+        let image = crate::raw::fixture("ext2");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            target: Target::Path(PathBuf::from("/big.txt")),
+            offset: 0,
+            len: usize::MAX,
+            compress_blobs: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let content = session.parcels::<crate::blob::Blob>(crate::Sink::Blob)
+            .flat_map(|blob| blob.as_bytes().to_vec())
+            .collect::<Vec<_>>();
+
+        let expected = b"abcdefghijklmnop\n".iter()
+            .copied()
+            .cycle()
+            .take(300000)
+            .collect::<Vec<_>>();
+
+        assert!(content == expected);
+    }
+
+    #[test]
+    fn handle_compress_blobs() {
+        let image = crate::raw::fixture("ext2");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            target: Target::Path(PathBuf::from("/big.txt")),
+            offset: 0,
+            len: usize::MAX,
+            compress_blobs: true,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let expected = b"abcdefghijklmnop\n".iter()
+            .copied()
+            .cycle()
+            .take(300000)
+            .collect::<Vec<_>>();
+
+        // The digest should be the one of the uncompressed content.
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.len, expected.len() as u64);
+
+        use sha2::Digest as _;
+        assert_eq!(item.blob_sha256, <[u8; 32]>::from(sha2::Sha256::digest(&expected)));
+
+        // The content is very repetitive, so it should have been compressed.
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert!(blob.as_bytes().len() < expected.len());
+    }
+
+    #[test]
+    fn handle_sparse_file() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            target: Target::Path(PathBuf::from("/sparse.bin")),
+            offset: 524288 - 2,
+            len: 10,
+            compress_blobs: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), b"\0\0sparse\0\0");
+    }
+
+    #[test]
+    fn handle_inode() {
+        let image = crate::raw::fixture("basic");
+
+        let ino = crate::raw::open(image.path()).unwrap()
+            .lookup("/dir/nested.txt").unwrap()
+            .ino;
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            target: Target::Inode(ino),
+            offset: 0,
+            len: usize::MAX,
+            compress_blobs: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let blob = session.parcel::<crate::blob::Blob>(crate::Sink::Blob, 0);
+        assert_eq!(blob.as_bytes(), b"foo bar baz\n");
+    }
+
+    #[test]
+    fn handle_non_existent() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            target: Target::Path(PathBuf::from("/foo")),
+            offset: 0,
+            len: usize::MAX,
+            compress_blobs: false,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn args_from_proto_path_and_inode() {
+        let mut proto = rrg_proto::get_raw_file_contents::Args::default();
+        proto.set_device(PathBuf::from("/dev/sda1").into());
+        proto.set_path(PathBuf::from("/foo").into());
+        proto.set_inode(42);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_no_target() {
+        let mut proto = rrg_proto::get_raw_file_contents::Args::default();
+        proto.set_device(PathBuf::from("/dev/sda1").into());
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_inode() {
+        let mut proto = rrg_proto::get_raw_file_contents::Args::default();
+        proto.set_device(PathBuf::from("/dev/sda1").into());
+        proto.set_inode(42);
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+
+        assert!(matches!(args.target, Target::Inode(42)));
+        assert_eq!(args.len, usize::MAX);
+    }
+}
@@ -5,12 +5,30 @@
 
 use std::path::PathBuf;
 
+/// Maximum number of context bytes (before or after a match) that can be
+/// requested in the binary mode.
+const MAX_CONTEXT_LEN: usize = 256 * 1024;
+
 /// Arguments of the `grep_file_contents` action.
 pub struct Args {
     /// Path to the file to grep the contents of.
     path: PathBuf,
     /// Regular expression to search for in the file contents.
-    regex: regex::Regex,
+    regex: Regex,
+    /// Number of bytes preceding each match to include in the results.
+    context_before_len: usize,
+    /// Number of bytes following each match to include in the results.
+    context_after_len: usize,
+    /// Maximum number of matches to report (if limited).
+    max_matches: Option<u64>,
+}
+
+/// Regular expression to search for in the file contents.
+enum Regex {
+    /// Regex to match against lines of the file content decoded as UTF-8.
+    Text(regex::Regex),
+    /// Regex to match against raw bytes of the file content.
+    Bytes(regex::bytes::Regex),
 }
 
 /// Result of the `grep_file_contents` action.
@@ -18,7 +36,17 @@ pub struct Item {
     /// Byte offset within the file from which the content matched.
     offset: u64,
     /// Content that matched the specified regular expression.
+    ///
+    /// This is empty in the binary mode.
     content: String,
+    /// Bytes that matched the specified regular expression.
+    ///
+    /// This is empty if not in the binary mode.
+    content_bytes: Vec<u8>,
+    /// Bytes preceding the match.
+    context_before: Vec<u8>,
+    /// Bytes following the match.
+    context_after: Vec<u8>,
 }
 
 /// Handles invocations of the `grep_file_contents` action.
@@ -29,6 +57,22 @@ where
     let file = std::fs::File::open(&args.path)
         .map_err(crate::session::Error::action)?;
 
+    match &args.regex {
+        Regex::Text(regex) => handle_text(session, file, regex, &args),
+        Regex::Bytes(regex) => handle_bytes(session, file, regex, &args),
+    }
+}
+
+/// Handles invocations of the `grep_file_contents` action in the text mode.
+fn handle_text<S>(
+    session: &mut S,
+    file: std::fs::File,
+    regex: &regex::Regex,
+    args: &Args,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
     let mut file = crate::io::LineReader::new(file)
         // We want to support lines only up to 1 MiB. Fleetspeak does not allow
         // for messages bigger than 2 MiB anyway.
@@ -36,6 +80,7 @@ where
 
     let mut line = String::new();
     let mut offset = 0;
+    let mut match_count = 0;
 
     loop {
         line.clear();
@@ -45,17 +90,67 @@ where
             Err(error) => return Err(crate::session::Error::action(error)),
         };
 
-        for matcz in args.regex.find_iter(&line) {
+        for matcz in regex.find_iter(&line) {
             session.reply(Item {
                 offset: offset + matcz.start() as u64,
                 content: matcz.as_str().to_string(),
+                content_bytes: Vec::new(),
+                context_before: Vec::new(),
+                context_after: Vec::new(),
             })?;
+
+            match_count += 1;
+            if args.max_matches.is_some_and(|max_matches| match_count >= max_matches) {
+                return Ok(());
+            }
         }
 
         offset += len as u64;
     }
 }
 
+/// Handles invocations of the `grep_file_contents` action in the binary mode.
+fn handle_bytes<S>(
+    session: &mut S,
+    file: std::fs::File,
+    regex: &regex::bytes::Regex,
+    args: &Args,
+) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut search = crate::io::RegexSearch::new(file, regex)
+        .with_context(args.context_before_len, args.context_after_len);
+
+    let mut match_count = 0;
+
+    loop {
+        use crate::io::RegexSearchStep::*;
+        match search.step().map_err(crate::session::Error::action)? {
+            Match(matcz) => {
+                session.reply(Item {
+                    offset: matcz.offset,
+                    content: String::new(),
+                    content_bytes: matcz.content.to_vec(),
+                    context_before: matcz.context_before.to_vec(),
+                    context_after: matcz.context_after.to_vec(),
+                })?;
+
+                match_count += 1;
+                if args.max_matches.is_some_and(|max_matches| match_count >= max_matches) {
+                    return Ok(());
+                }
+            }
+            Progress => (),
+            Done => return Ok(()),
+        }
+
+        // Searching big files can take a while, so we need to let Fleetspeak
+        // know that we are still alive and not stuck.
+        session.heartbeat();
+    }
+}
+
 impl crate::request::Args for Args {
 
     type Proto = rrg_proto::grep_file_contents::Args;
@@ -66,16 +161,80 @@ impl crate::request::Args for Args {
         let path = PathBuf::try_from(proto.take_path())
             .map_err(|error| ParseArgsError::invalid_field("path", error))?;
 
-        let regex = regex::Regex::new(proto.regex())
-            .map_err(|error| ParseArgsError::invalid_field("regex", error))?;
+        let regex = if proto.binary() {
+            regex::bytes::Regex::new(proto.regex())
+                .map(Regex::Bytes)
+                .map_err(|error| ParseArgsError::invalid_field("regex", error))?
+        } else {
+            regex::Regex::new(proto.regex())
+                .map(Regex::Text)
+                .map_err(|error| ParseArgsError::invalid_field("regex", error))?
+        };
+
+        let context_before_len = parse_context_len(&proto, proto.context_before_len())
+            .map_err(|error| ParseArgsError::invalid_field("context_before_len", error))?;
+        let context_after_len = parse_context_len(&proto, proto.context_after_len())
+            .map_err(|error| ParseArgsError::invalid_field("context_after_len", error))?;
+
+        let max_matches = match proto.max_matches() {
+            0 => None,
+            max_matches => Some(max_matches),
+        };
 
         Ok(Args {
             path,
             regex,
+            context_before_len,
+            context_after_len,
+            max_matches,
         })
     }
 }
 
+/// Validates the requested context length.
+fn parse_context_len(
+    proto: &rrg_proto::grep_file_contents::Args,
+    len: u64,
+) -> Result<usize, InvalidContextLenError> {
+    if len == 0 {
+        return Ok(0);
+    }
+    if !proto.binary() {
+        return Err(InvalidContextLenError::NonBinary);
+    }
+
+    match usize::try_from(len) {
+        Ok(len) if len <= MAX_CONTEXT_LEN => Ok(len),
+        _ => Err(InvalidContextLenError::TooLong(len)),
+    }
+}
+
+/// An error type for situations where the requested context length is invalid.
+#[derive(Debug)]
+enum InvalidContextLenError {
+    /// Context was requested not in the binary mode.
+    NonBinary,
+    /// Requested context is longer than supported.
+    TooLong(u64),
+}
+
+impl std::fmt::Display for InvalidContextLenError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidContextLenError::NonBinary => {
+                write!(fmt, "context is supported only in the binary mode")
+            }
+            InvalidContextLenError::TooLong(len) => {
+                write!(fmt, "context of {len} bytes exceeds {MAX_CONTEXT_LEN} bytes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidContextLenError {
+}
+
 impl crate::response::Item for Item {
 
     type Proto = rrg_proto::grep_file_contents::Result;
@@ -84,6 +243,9 @@ impl crate::response::Item for Item {
         let mut proto = Self::Proto::default();
         proto.set_offset(self.offset);
         proto.set_content(self.content);
+        proto.set_content_bytes(self.content_bytes);
+        proto.set_context_before(self.context_before);
+        proto.set_context_after(self.context_after);
 
         proto
     }
@@ -104,7 +266,10 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("file"),
-            regex: regex::Regex::new("").unwrap(),
+            regex: Regex::Text(regex::Regex::new("").unwrap()),
+            context_before_len: 0,
+            context_after_len: 0,
+            max_matches: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -124,7 +289,10 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("file"),
-            regex: regex::Regex::new("bar").unwrap(),
+            regex: Regex::Text(regex::Regex::new("bar").unwrap()),
+            context_before_len: 0,
+            context_after_len: 0,
+            max_matches: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -144,7 +312,10 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("file"),
-            regex: regex::Regex::new("bar").unwrap(),
+            regex: Regex::Text(regex::Regex::new("bar").unwrap()),
+            context_before_len: 0,
+            context_after_len: 0,
+            max_matches: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -168,7 +339,10 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("file"),
-            regex: regex::Regex::new("ba[rz]").unwrap(),
+            regex: Regex::Text(regex::Regex::new("ba[rz]").unwrap()),
+            context_before_len: 0,
+            context_after_len: 0,
+            max_matches: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -200,7 +374,10 @@ mod tests {
 
         let args = Args {
             path: tempdir.path().join("file"),
-            regex: regex::Regex::new("ba[rz]").unwrap(),
+            regex: Regex::Text(regex::Regex::new("ba[rz]").unwrap()),
+            context_before_len: 0,
+            context_after_len: 0,
+            max_matches: None,
         };
 
         let mut session = crate::session::FakeSession::new();
@@ -221,4 +398,254 @@ mod tests {
         assert_eq!(item.offset, 12);
         assert_eq!(item.content, "bar");
     }
+
+    #[test]
+    fn handle_text_max_matches() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"bar\nbaz\nbar")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("file"),
+            regex: Regex::Text(regex::Regex::new("ba[rz]").unwrap()),
+            context_before_len: 0,
+            context_after_len: 0,
+            max_matches: Some(2),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 2);
+        assert_eq!(session.reply::<Item>(0).content, "bar");
+        assert_eq!(session.reply::<Item>(1).content, "baz");
+    }
+
+    /// Constructs arguments for grepping the given file in the binary mode.
+    fn bytes_args(path: PathBuf, regex: &str) -> Args {
+        Args {
+            path,
+            regex: Regex::Bytes(regex::bytes::Regex::new(regex).unwrap()),
+            context_before_len: 0,
+            context_after_len: 0,
+            max_matches: None,
+        }
+    }
+
+    #[test]
+    fn handle_bytes_invalid_utf8_offsets() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"\xff\xfefoo\xffbar\xfffoo")
+            .unwrap();
+
+        let args = bytes_args(tempdir.path().join("file"), "foo");
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 2);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 2);
+        assert_eq!(item.content_bytes, b"foo");
+        assert_eq!(item.content, "");
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.offset, 10);
+        assert_eq!(item.content_bytes, b"foo");
+    }
+
+    #[test]
+    fn handle_bytes_binary_content() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"\x00\x01\xff\xfe\x00\xff\xfe")
+            .unwrap();
+
+        let args = bytes_args(tempdir.path().join("file"), r"(?-u)\xff\xfe");
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 2);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 2);
+        assert_eq!(item.content_bytes, b"\xff\xfe");
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.offset, 5);
+        assert_eq!(item.content_bytes, b"\xff\xfe");
+    }
+
+    #[test]
+    fn handle_bytes_multiline_match() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"quux\nfoo\nbar\n")
+            .unwrap();
+
+        let args = bytes_args(tempdir.path().join("file"), r"(?s)foo.bar");
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 5);
+        assert_eq!(item.content_bytes, b"foo\nbar");
+    }
+
+    #[test]
+    fn handle_bytes_context() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"foo 0123456789 bar")
+            .unwrap();
+
+        let mut args = bytes_args(tempdir.path().join("file"), "foo|bar|5");
+        args.context_before_len = 3;
+        args.context_after_len = 3;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 3);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.offset, 0);
+        assert_eq!(item.context_before, b"");
+        assert_eq!(item.content_bytes, b"foo");
+        assert_eq!(item.context_after, b" 01");
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.offset, 9);
+        assert_eq!(item.context_before, b"234");
+        assert_eq!(item.content_bytes, b"5");
+        assert_eq!(item.context_after, b"678");
+
+        let item = session.reply::<Item>(2);
+        assert_eq!(item.offset, 15);
+        assert_eq!(item.context_before, b"89 ");
+        assert_eq!(item.content_bytes, b"bar");
+        assert_eq!(item.context_after, b"");
+    }
+
+    #[test]
+    fn handle_bytes_max_matches() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"foo foo foo foo")
+            .unwrap();
+
+        let mut args = bytes_args(tempdir.path().join("file"), "foo");
+        args.max_matches = Some(3);
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 3);
+        assert_eq!(session.reply::<Item>(2).offset, 8);
+    }
+
+    #[test]
+    fn handle_bytes_empty_matches() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"ab")
+            .unwrap();
+
+        let args = bytes_args(tempdir.path().join("file"), "");
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 3);
+        assert_eq!(session.reply::<Item>(0).offset, 0);
+        assert_eq!(session.reply::<Item>(1).offset, 1);
+        assert_eq!(session.reply::<Item>(2).offset, 2);
+    }
+
+    #[test]
+    fn handle_bytes_long_file_without_newlines() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        // Needles are placed so that they straddle boundaries of the search
+        // windows used internally.
+        let offsets = [
+            crate::io::MAX_REGEX_MATCH_LEN - 3,
+            2 * crate::io::MAX_REGEX_MATCH_LEN - 3,
+            3 * crate::io::MAX_REGEX_MATCH_LEN + 42,
+            5 * crate::io::MAX_REGEX_MATCH_LEN - 1,
+        ];
+
+        let mut content = vec![b'x'; 5 * crate::io::MAX_REGEX_MATCH_LEN + 5];
+        for offset in offsets {
+            content[offset..offset + 6].copy_from_slice(b"needle");
+        }
+
+        std::fs::write(tempdir.path().join("file"), &content)
+            .unwrap();
+
+        let mut args = bytes_args(tempdir.path().join("file"), "needle");
+        args.context_before_len = 2;
+        args.context_after_len = 2;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), offsets.len());
+
+        for (i, offset) in offsets.into_iter().enumerate() {
+            let item = session.reply::<Item>(i);
+            assert_eq!(item.offset, offset as u64);
+            assert_eq!(item.content_bytes, b"needle");
+            assert_eq!(item.context_before, b"xx");
+        }
+
+        assert_eq!(session.reply::<Item>(0).context_after, b"xx");
+        assert_eq!(session.reply::<Item>(3).context_after, b"");
+    }
+
+    #[test]
+    fn args_from_proto_context_non_binary() {
+        let mut proto = rrg_proto::grep_file_contents::Args::default();
+        proto.set_path(PathBuf::from("/foo").into());
+        proto.set_regex(String::from("foo"));
+        proto.set_context_before_len(42);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_context_too_long() {
+        let mut proto = rrg_proto::grep_file_contents::Args::default();
+        proto.set_path(PathBuf::from("/foo").into());
+        proto.set_regex(String::from("foo"));
+        proto.set_binary(true);
+        proto.set_context_after_len(MAX_CONTEXT_LEN as u64 + 1);
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
 }
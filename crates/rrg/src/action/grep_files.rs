@@ -0,0 +1,498 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::PathBuf;
+
+/// Arguments of the `grep_files` action.
+pub struct Args {
+    /// Path to the root directory to search the files in.
+    root: PathBuf,
+    /// Regular expressions to search for in the file contents.
+    regexes: Vec<regex::bytes::Regex>,
+    /// Limit on the depth of recursion when visiting subdirectories.
+    max_depth: Option<u32>,
+    /// Regular expressions that paths of the files to search must match.
+    path_include_regexes: Vec<regex::Regex>,
+    /// Regular expressions that paths of the files to search must not match.
+    path_exclude_regexes: Vec<regex::Regex>,
+    /// Limit on the size of files to search.
+    max_file_size: Option<u64>,
+    /// Limit on the number of matches to report.
+    max_matches: Option<u64>,
+}
+
+/// Result of the `grep_files` action.
+pub struct Item {
+    /// Path to the file in which the match was found.
+    path: PathBuf,
+    /// Index of the regex that matched.
+    regex_index: usize,
+    /// Byte offset within the file at which the match starts.
+    offset: u64,
+    /// Bytes that matched the regex.
+    content: Vec<u8>,
+}
+
+/// Handles invocations of the `grep_files` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    // Excluded paths are passed to the walker, so that excluded folders (like
+    // `/proc` or huge caches) are not descended into at all.
+    let mut entries = crate::fs::walk_dir(&args.root)
+        .map_err(crate::session::Error::action)?
+        .with_excluded_paths(args.path_exclude_regexes.clone());
+    if let Some(max_depth) = args.max_depth {
+        entries = entries.with_max_depth(max_depth);
+    }
+
+    let mut match_count = 0;
+
+    for entry in entries {
+        // Walking big directory trees can take a while even if there is not
+        // a single file to search, so we need to let Fleetspeak know that we
+        // are still alive and stop once we run out of time.
+        session.heartbeat();
+        session.check_time_limits()?;
+
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                log::error!("failed to read directory entry: {error}");
+                continue
+            }
+        };
+
+        if !args.should_search(&entry) {
+            continue;
+        }
+
+        let file = match std::fs::File::open(&entry.path) {
+            Ok(file) => file,
+            Err(error) => {
+                log::error! {
+                    "failed to open '{}': {error}",
+                    entry.path.display()
+                };
+                continue;
+            }
+        };
+
+        // All the regexes are searched for in a single pass over the file.
+        let mut search = crate::io::RegexSearch::new_many(file, args.regexes.iter().collect());
+
+        loop {
+            use crate::io::RegexSearchStep::*;
+            match search.step() {
+                Ok(Match(matcz)) => {
+                    session.reply(Item {
+                        path: entry.path.clone(),
+                        regex_index: matcz.regex_index,
+                        offset: matcz.offset,
+                        content: matcz.content.to_vec(),
+                    })?;
+
+                    match_count += 1;
+                    if args.max_matches.is_some_and(|max_matches| match_count >= max_matches) {
+                        return Ok(());
+                    }
+                }
+                Ok(Progress) => (),
+                Ok(Done) => break,
+                Err(error) => {
+                    log::error! {
+                        "failed to read '{}': {error}",
+                        entry.path.display()
+                    };
+                    break;
+                }
+            }
+
+            // Searching big files can take a while, so we need to let
+            // Fleetspeak know that we are still alive and not stuck and stop
+            // once we run out of time.
+            session.heartbeat();
+            session.check_time_limits()?;
+        }
+    }
+
+    Ok(())
+}
+
+impl Args {
+
+    /// Determines whether the given entry should be searched.
+    fn should_search(&self, entry: &crate::fs::Entry) -> bool {
+        // Non-regular files (like devices or named pipes) can block or yield
+        // infinite content, so we never search them. Note that directory
+        // entries do not follow symlinks, so symlinks are skipped as well.
+        if !entry.metadata.is_file() {
+            return false;
+        }
+
+        if let Some(max_file_size) = self.max_file_size {
+            if entry.metadata.len() > max_file_size {
+                return false;
+            }
+        }
+
+        let path = entry.path.to_string_lossy();
+
+        let is_included = self.path_include_regexes.is_empty() ||
+            self.path_include_regexes.iter().any(|regex| regex.is_match(&path));
+        if !is_included {
+            return false;
+        }
+
+        // Excluded paths are skipped by the walker already.
+        true
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::grep_files::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let root = PathBuf::try_from(proto.take_root())
+            .map_err(|error| ParseArgsError::invalid_field("root", error))?;
+
+        if proto.regexes().is_empty() {
+            return Err(ParseArgsError::invalid_field("regexes", NoRegexesError));
+        }
+
+        let regexes = proto.regexes().iter()
+            .map(|regex| regex::bytes::Regex::new(regex))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("regexes", error))?;
+
+        let path_include_regexes = proto.path_include_regexes().iter()
+            .map(|regex| regex::Regex::new(regex))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("path_include_regexes", error))?;
+
+        let path_exclude_regexes = proto.path_exclude_regexes().iter()
+            .map(|regex| regex::Regex::new(regex))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| ParseArgsError::invalid_field("path_exclude_regexes", error))?;
+
+        let max_depth = match proto.max_depth() {
+            0 => None,
+            max_depth => Some(max_depth),
+        };
+
+        let max_file_size = match proto.max_file_size() {
+            0 => None,
+            max_file_size => Some(max_file_size),
+        };
+
+        let max_matches = match proto.max_matches() {
+            0 => None,
+            max_matches => Some(max_matches),
+        };
+
+        Ok(Args {
+            root,
+            regexes,
+            max_depth,
+            path_include_regexes,
+            path_exclude_regexes,
+            max_file_size,
+            max_matches,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::grep_files::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_path(self.path.into());
+        // The number of regexes comes from a protobuf repeated field, so it is
+        // impossible for an index to overflow `u32`.
+        proto.set_regex_index(self.regex_index as u32);
+        proto.set_offset(self.offset);
+        proto.set_content(self.content);
+
+        proto
+    }
+}
+
+/// An error type for situations where no regexes to search for were specified.
+#[derive(Debug)]
+struct NoRegexesError;
+
+impl std::fmt::Display for NoRegexesError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "no regexes to search for")
+    }
+}
+
+impl std::error::Error for NoRegexesError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Constructs arguments searching the given root for the given regexes.
+    fn args(root: PathBuf, regexes: &[&str]) -> Args {
+        Args {
+            root,
+            regexes: regexes.iter()
+                .map(|regex| regex::bytes::Regex::new(regex).unwrap())
+                .collect(),
+            max_depth: None,
+            path_include_regexes: vec![],
+            path_exclude_regexes: vec![],
+            max_file_size: None,
+            max_matches: None,
+        }
+    }
+
+    /// Returns all replies of the session sorted by their path and offset.
+    fn sorted_replies(session: &crate::session::FakeSession) -> Vec<&Item> {
+        let mut items = session.replies::<Item>().collect::<Vec<_>>();
+        items.sort_by_key(|item| (item.path.clone(), item.regex_index, item.offset));
+
+        items
+    }
+
+    #[test]
+    fn handle_empty_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(tempdir.path().to_path_buf(), &["foo"]))
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_nested_files() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir_all(tempdir.path().join("bar").join("baz"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"quux foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar").join("baz").join("thud"), b"foo\xff")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar").join("norf"), b"blargh")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(tempdir.path().to_path_buf(), &["foo"]))
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].path, tempdir.path().join("bar").join("baz").join("thud"));
+        assert_eq!(items[0].offset, 0);
+        assert_eq!(items[0].content, b"foo");
+
+        assert_eq!(items[1].path, tempdir.path().join("foo"));
+        assert_eq!(items[1].offset, 5);
+        assert_eq!(items[1].content, b"foo");
+    }
+
+    #[test]
+    fn handle_multiple_regexes() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"foo bar foo")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(tempdir.path().to_path_buf(), &["foo", "bar"]))
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 3);
+
+        assert_eq!(items[0].regex_index, 0);
+        assert_eq!(items[0].offset, 0);
+
+        assert_eq!(items[1].regex_index, 0);
+        assert_eq!(items[1].offset, 8);
+
+        assert_eq!(items[2].regex_index, 1);
+        assert_eq!(items[2].offset, 4);
+        assert_eq!(items[2].content, b"bar");
+    }
+
+    #[test]
+    fn handle_max_depth() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("sub"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("sub").join("foo"), b"foo")
+            .unwrap();
+
+        let mut args = args(tempdir.path().to_path_buf(), &["foo"]);
+        args.max_depth = Some(1);
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, tempdir.path().join("foo"));
+    }
+
+    #[test]
+    fn handle_path_include_exclude_regexes() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo.log"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo.txt"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar.log"), b"foo")
+            .unwrap();
+
+        let mut args = args(tempdir.path().to_path_buf(), &["foo"]);
+        args.path_include_regexes = vec![regex::Regex::new(r"\.log$").unwrap()];
+        args.path_exclude_regexes = vec![regex::Regex::new(r"bar").unwrap()];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, tempdir.path().join("foo.log"));
+    }
+
+    #[test]
+    fn handle_path_exclude_regexes_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("cache"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("cache").join("file"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("file"), b"foo")
+            .unwrap();
+
+        let mut args = args(tempdir.path().to_path_buf(), &["foo"]);
+        args.path_exclude_regexes = vec![regex::Regex::new(r"cache$").unwrap()];
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, tempdir.path().join("file"));
+    }
+
+    #[test]
+    fn handle_max_file_size() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("small"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("big"), b"foo bar baz")
+            .unwrap();
+
+        let mut args = args(tempdir.path().to_path_buf(), &["foo"]);
+        args.max_file_size = Some(3);
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, tempdir.path().join("small"));
+    }
+
+    #[test]
+    fn handle_max_matches() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"foo foo foo foo")
+            .unwrap();
+
+        let mut args = args(tempdir.path().to_path_buf(), &["foo"]);
+        args.max_matches = Some(3);
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 3);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_skips_symlinks() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"foo")
+            .unwrap();
+        std::os::unix::fs::symlink(tempdir.path().join("file"), tempdir.path().join("link"))
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(tempdir.path().to_path_buf(), &["foo"]))
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, tempdir.path().join("file"));
+    }
+
+    #[test]
+    fn handle_real_time_limit() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        // The file has no matches, the limit should be enforced nonetheless.
+        std::fs::write(tempdir.path().join("file"), b"bar")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new()
+            .with_real_time_limit(std::time::Duration::ZERO);
+
+        let error = handle(&mut session, args(tempdir.path().to_path_buf(), &["foo"]))
+            .unwrap_err();
+
+        assert_eq!(error.kind(), crate::session::ErrorKind::RealTimeLimitExceeded);
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn args_from_proto_no_regexes() {
+        let mut proto = rrg_proto::grep_files::Args::default();
+        proto.set_root(PathBuf::from("/").into());
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+}
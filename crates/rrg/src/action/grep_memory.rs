@@ -0,0 +1,269 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Arguments of the `grep_memory` action.
+pub struct Args {
+    /// Identifier of the process to search the memory of.
+    pid: u32,
+    /// Regular expression to search for in the process memory.
+    regex: regex::bytes::Regex,
+    /// Maximum number of matches to report.
+    max_matches: Option<u64>,
+}
+
+/// Result of the `grep_memory` action.
+pub struct Item {
+    /// Memory region in which the match was found.
+    region: ospect::proc::MemoryRegion,
+    /// Byte offset within the region at which the match starts.
+    offset: u64,
+    /// Bytes that matched the pattern.
+    content: Vec<u8>,
+}
+
+/// Handles invocations of the `grep_memory` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let regions = ospect::proc::memory_regions(args.pid)
+        .map_err(crate::session::Error::action)?;
+
+    let memory = ospect::proc::open_memory(args.pid)
+        .map_err(crate::session::Error::action)?;
+
+    let mut match_count = 0;
+
+    for region in regions {
+        if !region.readable {
+            continue;
+        }
+
+        let reader = RegionReader {
+            memory: &memory,
+            addr: region.start,
+            end: region.end,
+        };
+        let mut search = crate::io::RegexSearch::new(reader, &args.regex);
+
+        loop {
+            use crate::io::RegexSearchStep::*;
+            match search.step() {
+                Ok(Match(matcz)) => {
+                    session.reply(Item {
+                        region: region.clone(),
+                        offset: matcz.offset,
+                        content: matcz.content.to_vec(),
+                    })?;
+
+                    match_count += 1;
+                    if args.max_matches.is_some_and(|max_matches| match_count >= max_matches) {
+                        return Ok(());
+                    }
+                }
+                Ok(Progress) => (),
+                Ok(Done) => break,
+                Err(error) => {
+                    // Some readable regions cannot be actually read (e.g. the
+                    // `[vvar]` region on Linux), so we just skip them.
+                    log::error! {
+                        "failed to read memory of process {} at {:#x}: {}",
+                        args.pid, region.start, error
+                    };
+                    break;
+                }
+            }
+
+            // Memory of some processes can be huge, so we need to let
+            // Fleetspeak know that we are still alive and not stuck and stop
+            // once we run out of time.
+            session.heartbeat();
+            session.check_time_limits()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A reader over a region of the process memory.
+struct RegionReader<'m> {
+    /// Handle to the memory of the process.
+    memory: &'m ospect::proc::Memory,
+    /// Address from which the next read should start.
+    addr: u64,
+    /// Address at which the region ends (exclusive).
+    end: u64,
+}
+
+impl std::io::Read for RegionReader<'_> {
+
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len_left = self.end.saturating_sub(self.addr);
+        // If the buffer length does not fit in `u64`, the number of bytes left
+        // is definitely smaller, so the cast is safe.
+        let len = match u64::try_from(buf.len()) {
+            Ok(len) => std::cmp::min(len, len_left) as usize,
+            Err(_) => len_left as usize,
+        };
+
+        let len_read = self.memory.read_at(self.addr, &mut buf[..len])?;
+        self.addr += len_read as u64;
+
+        Ok(len_read)
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::grep_memory::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let regex = match () {
+            () if proto.has_regex() => {
+                regex::bytes::Regex::new(proto.regex())
+                    .map_err(|error| ParseArgsError::invalid_field("regex", error))?
+            }
+            () if proto.has_literal() => {
+                let mut pattern = String::from("(?-u)");
+                for byte in proto.take_literal() {
+                    pattern.push_str(&format!("\\x{byte:02X}"));
+                }
+
+                regex::bytes::Regex::new(&pattern)
+                    .map_err(|error| ParseArgsError::invalid_field("literal", error))?
+            }
+            _ => {
+                return Err(ParseArgsError::invalid_field("pattern", NoPatternError));
+            }
+        };
+
+        let max_matches = match proto.max_matches() {
+            0 => None,
+            max_matches => Some(max_matches),
+        };
+
+        Ok(Args {
+            pid: proto.pid(),
+            regex,
+            max_matches,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::grep_memory::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_region(self.region.into());
+        proto.set_offset(self.offset);
+        proto.set_content(self.content);
+
+        proto
+    }
+}
+
+/// An error type for situations where no pattern to search for was specified.
+#[derive(Debug)]
+struct NoPatternError;
+
+impl std::fmt::Display for NoPatternError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "no pattern to search for")
+    }
+}
+
+impl std::error::Error for NoPatternError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_self() {
+        // We construct the content at runtime so that the matching bytes do
+        // not appear anywhere else in the memory (e.g. in the data section).
+        let content = format!("quux{}norf", 1337 * 1337);
+        let addr = content.as_ptr() as u64;
+
+        let args = Args {
+            pid: std::process::id(),
+            regex: regex::bytes::Regex::new("quux[0-9]+norf").unwrap(),
+            max_matches: None,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert! {
+            session.replies::<Item>().any(|item| {
+                item.region.start + item.offset == addr &&
+                item.content == b"quux1787569norf"
+            })
+        };
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_max_matches() {
+        let args = Args {
+            pid: std::process::id(),
+            regex: regex::bytes::Regex::new("(?-u)\\x00").unwrap(),
+            max_matches: Some(3),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 3);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn handle_real_time_limit() {
+        let args = Args {
+            pid: std::process::id(),
+            regex: regex::bytes::Regex::new("foo").unwrap(),
+            max_matches: None,
+        };
+
+        let mut session = crate::session::FakeSession::new()
+            .with_real_time_limit(std::time::Duration::ZERO);
+
+        let error = handle(&mut session, args)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), crate::session::ErrorKind::RealTimeLimitExceeded);
+    }
+
+    #[test]
+    fn args_from_proto_literal() {
+        let mut proto = rrg_proto::grep_memory::Args::default();
+        proto.set_literal(b"\x00.\xFF".to_vec());
+
+        let args = <Args as crate::request::Args>::from_proto(proto)
+            .unwrap();
+
+        assert!(args.regex.is_match(b"foo\x00.\xFFbar"));
+        assert!(!args.regex.is_match(b"foo\x00x\xFFbar"));
+    }
+
+    #[test]
+    fn args_from_proto_no_pattern() {
+        let proto = rrg_proto::grep_memory::Args::default();
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+}
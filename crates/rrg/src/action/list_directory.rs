@@ -0,0 +1,274 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+use std::path::PathBuf;
+
+/// Arguments of the `list_directory` action.
+pub struct Args {
+    /// Path to the directory to list the contents of.
+    path: PathBuf,
+    /// Number of entries (in the name order) to skip.
+    offset: usize,
+    /// Maximum number of entries to return.
+    limit: usize,
+}
+
+/// Result of the `list_directory` action.
+struct Item {
+    /// Path to the directory entry.
+    path: PathBuf,
+    /// Metadata of the directory entry.
+    metadata: std::fs::Metadata,
+    /// Path to the file pointed by a symlink (if available).
+    symlink: Option<PathBuf>,
+}
+
+/// Handles invocations of the `list_directory` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    if args.path.is_relative() {
+        use std::io::{Error, ErrorKind};
+
+        let error = Error::new(ErrorKind::InvalidInput, "relative path");
+        return Err(crate::session::Error::action(error));
+    }
+
+    // We collect all the entries upfront to sort them and have a stable order
+    // (required for the pagination to make sense). Entries that fail to be
+    // listed are skipped, so that one bad entry does not fail the listing.
+    let mut entries = crate::fs::list_dir(&args.path)
+        .map_err(crate::session::Error::action)?
+        .filter_map(|entry| match entry {
+            Ok(entry) => Some(entry),
+            Err(error) => {
+                log::error! {
+                    "failed to list entry of '{}': {error}",
+                    args.path.display()
+                };
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+    for entry in entries.into_iter().skip(args.offset).take(args.limit) {
+        let crate::fs::Entry { path, metadata, .. } = entry;
+
+        let symlink = if metadata.is_symlink() {
+            match std::fs::read_link(&path) {
+                Ok(symlink) => Some(symlink),
+                Err(error) => {
+                    log::error! {
+                        "failed to read symlink target for '{}': {error}",
+                        path.display()
+                    };
+
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        session.reply(Item {
+            path,
+            metadata,
+            symlink,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_directory::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let path = PathBuf::try_from(proto.take_path())
+            .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+        // Offsets and limits that do not fit `usize` (possible only on 32-bit
+        // platforms) are effectively infinite anyway, so we just saturate.
+        let offset = usize::try_from(proto.offset())
+            .unwrap_or(usize::MAX);
+        let limit = match proto.limit() {
+            0 => usize::MAX,
+            limit => usize::try_from(limit).unwrap_or(usize::MAX),
+        };
+
+        Ok(Args {
+            path,
+            offset,
+            limit,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_directory::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_directory::Result::default();
+        proto.set_path(self.path.into());
+        proto.set_metadata(self.metadata.into());
+
+        if let Some(symlink) = self.symlink {
+            proto.set_symlink(symlink.into());
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_non_existent() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().join("foo"),
+            offset: 0,
+            limit: usize::MAX,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_relative_path() {
+        let args = Args {
+            path: PathBuf::from("foo"),
+            offset: 0,
+            limit: usize::MAX,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_empty() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().to_path_buf(),
+            offset: 0,
+            limit: usize::MAX,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_files_and_dirs() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"hello")
+            .unwrap();
+        std::fs::create_dir(tempdir.path().join("bar"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar").join("baz"), b"")
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().to_path_buf(),
+            offset: 0,
+            limit: usize::MAX,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        // The listing is not recursive, so `bar/baz` should not be there.
+        assert_eq!(session.reply_count(), 2);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, tempdir.path().join("bar"));
+        assert!(item.metadata.is_dir());
+        assert!(item.symlink.is_none());
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.path, tempdir.path().join("foo"));
+        assert!(item.metadata.is_file());
+        assert_eq!(item.metadata.len(), 5);
+        assert!(item.symlink.is_none());
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn handle_symlink() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"")
+            .unwrap();
+        std::os::unix::fs::symlink("file", tempdir.path().join("link"))
+            .unwrap();
+
+        let args = Args {
+            path: tempdir.path().to_path_buf(),
+            offset: 0,
+            limit: usize::MAX,
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_ok());
+
+        assert_eq!(session.reply_count(), 2);
+
+        let item = session.reply::<Item>(1);
+        assert_eq!(item.path, tempdir.path().join("link"));
+        assert!(item.metadata.is_symlink());
+        assert_eq!(item.symlink, Some(PathBuf::from("file")));
+    }
+
+    #[test]
+    fn handle_pagination() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        for name in ["a", "b", "c", "d", "e"] {
+            std::fs::write(tempdir.path().join(name), b"")
+                .unwrap();
+        }
+
+        let list = |offset, limit| {
+            let args = Args {
+                path: tempdir.path().to_path_buf(),
+                offset,
+                limit,
+            };
+
+            let mut session = crate::session::FakeSession::new();
+            assert!(handle(&mut session, args).is_ok());
+
+            session.replies::<Item>()
+                .map(|item| item.path.file_name().unwrap().to_os_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(list(0, 2), ["a", "b"]);
+        assert_eq!(list(2, 2), ["c", "d"]);
+        assert_eq!(list(4, 2), ["e"]);
+        assert!(list(6, 2).is_empty());
+        assert_eq!(list(1, usize::MAX), ["b", "c", "d", "e"]);
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// Arguments of the `list_memory_regions` action.
+pub struct Args {
+    /// Identifier of the process to list the memory regions of.
+    pid: u32,
+}
+
+/// A result of the `list_memory_regions` action.
+struct Item {
+    /// Information about the individual memory region.
+    region: ospect::proc::MemoryRegion,
+}
+
+/// Handles invocations of the `list_memory_regions` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let regions = ospect::proc::memory_regions(args.pid)
+        .map_err(crate::session::Error::action)?;
+
+    for region in regions {
+        session.reply(Item {
+            region,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_memory_regions::Args;
+
+    fn from_proto(proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        Ok(Args {
+            pid: proto.pid(),
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_memory_regions::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_region(self.region.into());
+
+        proto
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_self() {
+        let value = 0xC0DEu64;
+        let addr = &value as *const u64 as u64;
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, Args { pid: std::process::id() })
+            .unwrap();
+
+        assert! {
+            session.replies::<Item>().any(|item| {
+                item.region.readable && item.region.writable &&
+                item.region.start <= addr && addr < item.region.end
+            })
+        };
+    }
+
+    #[test]
+    fn handle_non_existent() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, Args { pid: u32::MAX }).is_err());
+    }
+}
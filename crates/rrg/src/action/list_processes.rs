@@ -0,0 +1,158 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// A result of the `list_processes` action.
+struct Item {
+    // Information about the individual process.
+    process: ospect::proc::Process,
+}
+
+// Handles invocations of the `list_processes` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let processes = ospect::proc::all()
+        .map_err(crate::session::Error::action)?;
+
+    for process in processes {
+        let process = match process {
+            Ok(process) => process,
+            Err(error) => {
+                log::warn!("failed to obtain process information: {}", error);
+                continue;
+            }
+        };
+
+        session.reply(Item {
+            process,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_processes::Result;
+
+    fn into_proto(self) -> rrg_proto::list_processes::Result {
+        use rrg_proto::list_processes::State;
+
+        let process = self.process;
+
+        let mut proto = rrg_proto::list_processes::Result::default();
+        proto.set_pid(process.pid);
+        if let Some(ppid) = process.ppid {
+            proto.set_ppid(ppid);
+        }
+        if let Some(name) = process.name {
+            proto.set_name(name.to_string_lossy().into_owned());
+        }
+        if let Some(exe) = process.exe {
+            proto.set_exe(exe.into());
+        }
+        proto.set_cmdline(process.cmdline.iter().map(|arg| {
+            arg.to_string_lossy().into_owned()
+        }).collect());
+        if let Some(uid) = process.uid {
+            proto.set_uid(uid);
+        }
+        if let Some(gid) = process.gid {
+            proto.set_gid(gid);
+        }
+        if let Some(start_time) = process.start_time {
+            proto.set_start_time(rrg_proto::into_timestamp(start_time));
+        }
+        proto.set_state(match process.state {
+            Some(ospect::proc::State::Running) => State::RUNNING,
+            Some(ospect::proc::State::Sleeping) => State::SLEEPING,
+            Some(ospect::proc::State::DiskSleep) => State::DISK_SLEEP,
+            Some(ospect::proc::State::Stopped) => State::STOPPED,
+            Some(ospect::proc::State::Traced) => State::TRACED,
+            Some(ospect::proc::State::Zombie) => State::ZOMBIE,
+            Some(ospect::proc::State::Dead) => State::DEAD,
+            Some(ospect::proc::State::Idle) => State::IDLE,
+            None => State::UNKNOWN,
+        });
+        if let Some(rss) = process.rss {
+            proto.set_rss(rss);
+        }
+        if let Some(vsz) = process.vsz {
+            proto.set_vsz(vsz);
+        }
+        if let Some(user_cpu_time) = process.user_cpu_time {
+            proto.set_user_cpu_time(user_cpu_time.into());
+        }
+        if let Some(system_cpu_time) = process.system_cpu_time {
+            proto.set_system_cpu_time(system_cpu_time.into());
+        }
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_self() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.replies::<Item>().find(|item| {
+            item.process.pid == std::process::id()
+        }).unwrap();
+
+        assert_eq!(item.process.exe, Some(std::env::current_exe().unwrap()));
+    }
+
+    #[test]
+    fn handle_child() {
+        let mut child = std::process::Command::new(SLEEP_PATH)
+            .args(SLEEP_ARGS)
+            .spawn()
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        let result = handle(&mut session, ());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        assert!(result.is_ok());
+
+        let item = session.replies::<Item>().find(|item| {
+            item.process.pid == child.id()
+        }).unwrap();
+
+        assert_eq!(item.process.ppid, Some(std::process::id()));
+    }
+
+    #[test]
+    fn into_proto_self() {
+        let process = ospect::proc::info(std::process::id())
+            .unwrap();
+
+        let proto = crate::response::Item::into_proto(Item { process });
+        assert_eq!(proto.pid(), std::process::id());
+        assert!(proto.has_exe());
+        assert!(proto.has_start_time());
+    }
+
+    #[cfg(target_family = "unix")]
+    const SLEEP_PATH: &str = "sleep";
+
+    #[cfg(target_family = "unix")]
+    const SLEEP_ARGS: &[&str] = &["3600"];
+
+    #[cfg(target_family = "windows")]
+    const SLEEP_PATH: &str = "powershell";
+
+    #[cfg(target_family = "windows")]
+    const SLEEP_ARGS: &[&str] = &["-Command", "Start-Sleep -Seconds 3600"];
+}
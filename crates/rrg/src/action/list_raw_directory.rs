@@ -0,0 +1,400 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::PathBuf;
+
+use crate::raw::VfsStatus;
+
+/// Arguments of the `list_raw_directory` action.
+pub struct Args {
+    /// Path to the device (or an image file) with the filesystem.
+    device: PathBuf,
+    /// Path (within the filesystem) to the directory to list.
+    path: PathBuf,
+}
+
+/// Result of the `list_raw_directory` action.
+struct Item {
+    /// Path (within the filesystem) to the directory entry.
+    path: PathBuf,
+    /// Number of the inode the directory entry points to.
+    ino: u32,
+    /// Inode of the directory entry as stored on the disk (if available).
+    inode: Option<ospect::fs::ext4::Inode>,
+    /// Metadata of the directory entry as reported by the operating system.
+    ///
+    /// This is set only for entries that are not present on the disk.
+    vfs_metadata: Option<std::fs::Metadata>,
+    /// Raw target of a symlink (if the entry is a symlink).
+    symlink: Option<Vec<u8>>,
+    /// Whether the entry has been deleted.
+    deleted: bool,
+    /// Result of comparing the entry against the operating system view.
+    vfs_status: VfsStatus,
+}
+
+/// Handles invocations of the `list_raw_directory` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut fs = crate::raw::open(&args.device)
+        .map_err(crate::session::Error::action)?;
+
+    let dir = fs.lookup(&args.path)
+        .map_err(crate::session::Error::action)?;
+    let entries = fs.read_dir(&dir)
+        .map_err(crate::session::Error::action)?;
+
+    let vfs = crate::raw::Vfs::find(&args.device);
+
+    // Names of entries that exist on the disk, used to find entries that are
+    // visible only to the operating system.
+    let mut names = std::collections::HashSet::new();
+
+    for entry in entries {
+        let name = crate::raw::os_string(entry.name);
+        let path = args.path.join(&name);
+
+        if !entry.deleted {
+            names.insert(name);
+        }
+
+        let inode = if entry.ino != 0 {
+            match fs.inode(entry.ino) {
+                Ok(inode) => Some(inode),
+                Err(error) => {
+                    log::error! {
+                        "failed to read inode {} of '{}': {error}",
+                        entry.ino, path.display(),
+                    };
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let symlink = match &inode {
+            Some(inode) if inode.is_symlink() => match fs.read_link(inode) {
+                Ok(symlink) => Some(symlink),
+                Err(error) => {
+                    log::error! {
+                        "failed to read symlink target of '{}': {error}",
+                        path.display(),
+                    };
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        // Deleted entries are not expected to be visible to the operating
+        // system, so there is nothing to compare.
+        let vfs_status = match (&vfs, &inode) {
+            (Some(vfs), Some(inode)) if !entry.deleted => {
+                vfs.metadata_status(&path, inode)
+            }
+            _ => VfsStatus::Unverified,
+        };
+
+        session.reply(Item {
+            path,
+            ino: entry.ino,
+            inode,
+            vfs_metadata: None,
+            symlink,
+            deleted: entry.deleted,
+            vfs_status,
+        })?;
+    }
+
+    let Some(vfs) = vfs else {
+        return Ok(());
+    };
+
+    // Raw entries have already been reported at this point, so failing to
+    // read the directory through the operating system should not fail the
+    // whole action.
+    let vfs_path = vfs.path(&args.path);
+    let vfs_entries = match std::fs::read_dir(&vfs_path) {
+        Ok(vfs_entries) => vfs_entries,
+        Err(error) => {
+            log::error! {
+                "failed to list '{}': {error}",
+                vfs_path.display(),
+            };
+            return Ok(());
+        }
+    };
+
+    for vfs_entry in vfs_entries {
+        let vfs_entry = match vfs_entry {
+            Ok(vfs_entry) => vfs_entry,
+            Err(error) => {
+                log::error!("failed to read directory entry: {error}");
+                continue;
+            }
+        };
+
+        let name = vfs_entry.file_name();
+        if names.contains(&name) {
+            continue;
+        }
+
+        let path = args.path.join(&name);
+
+        let vfs_metadata = match vfs_entry.metadata() {
+            Ok(vfs_metadata) => Some(vfs_metadata),
+            Err(error) => {
+                log::error! {
+                    "failed to collect metadata for '{}': {error}",
+                    path.display(),
+                };
+                None
+            }
+        };
+
+        session.reply(Item {
+            path,
+            ino: 0,
+            inode: None,
+            vfs_metadata,
+            symlink: None,
+            deleted: false,
+            vfs_status: VfsStatus::VfsOnly,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::list_raw_directory::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let device = PathBuf::try_from(proto.take_device())
+            .map_err(|error| ParseArgsError::invalid_field("device", error))?;
+        let path = PathBuf::try_from(proto.take_path())
+            .map_err(|error| ParseArgsError::invalid_field("path", error))?;
+
+        Ok(Args {
+            device,
+            path,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_raw_directory::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = rrg_proto::list_raw_directory::Result::default();
+        proto.set_path(self.path.into());
+        proto.set_inode(u64::from(self.ino));
+
+        if let Some(inode) = self.inode {
+            proto.set_mode(u32::from(inode.mode));
+            proto.set_uid(inode.uid);
+            proto.set_gid(inode.gid);
+            proto.set_metadata(inode.into());
+        }
+        if let Some(vfs_metadata) = self.vfs_metadata {
+            proto.set_metadata(vfs_metadata.into());
+        }
+
+        if let Some(symlink) = self.symlink {
+            // Symlink targets are stored on the disk as raw bytes, so we pass
+            // them as-is instead of converting to an OS path first.
+            let mut symlink_proto = rrg_proto::fs::Path::default();
+            symlink_proto.set_raw_bytes(symlink);
+
+            proto.set_symlink(symlink_proto);
+        }
+
+        proto.set_deleted(self.deleted);
+        proto.set_vfs_status(self.vfs_status.into());
+
+        proto
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_root() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            path: PathBuf::from("/"),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let mut items = session.replies::<Item>()
+            .filter(|item| !item.deleted)
+            .collect::<Vec<_>>();
+        items.sort_by_key(|item| item.path.clone());
+
+        let paths = items.iter()
+            .map(|item| item.path.as_path())
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![
+            PathBuf::from("/big.txt"),
+            PathBuf::from("/dir"),
+            PathBuf::from("/fragmented.bin"),
+            PathBuf::from("/hello.txt"),
+            PathBuf::from("/link"),
+            PathBuf::from("/longlink"),
+            PathBuf::from("/lost+found"),
+            PathBuf::from("/many"),
+            PathBuf::from("/sparse.bin"),
+        ]);
+
+        let hello = items.iter()
+            .find(|item| item.path == std::path::Path::new("/hello.txt"))
+            .unwrap();
+        let inode = hello.inode.as_ref()
+            .unwrap();
+        assert!(inode.is_file());
+        assert_eq!(inode.size, b"Hello, world!\n".len() as u64);
+        assert_eq!(hello.ino, inode.ino);
+        assert_eq!(hello.symlink, None);
+
+        // The image is not mounted, so there is nothing to compare against.
+        for item in &items {
+            assert_eq!(item.vfs_status, VfsStatus::Unverified);
+        }
+    }
+
+    #[test]
+    fn handle_nested() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            path: PathBuf::from("/dir"),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let mut paths = session.replies::<Item>()
+            .map(|item| item.path.clone())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(paths, vec![
+            PathBuf::from("/dir/nested.txt"),
+            PathBuf::from("/dir/subdir"),
+        ]);
+    }
+
+    #[test]
+    fn handle_deleted() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            path: PathBuf::from("/"),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let item = session.replies::<Item>()
+            .find(|item| item.path == std::path::Path::new("/deleted.txt"))
+            .unwrap();
+
+        assert!(item.deleted);
+        assert_eq!(item.vfs_status, VfsStatus::Unverified);
+    }
+
+    #[test]
+    fn handle_symlinks() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            path: PathBuf::from("/"),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let link = session.replies::<Item>()
+            .find(|item| item.path == std::path::Path::new("/link"))
+            .unwrap();
+        assert_eq!(link.symlink.as_deref(), Some(&b"hello.txt"[..]));
+
+        // Long symlinks are stored in data blocks rather than in the inode.
+        let longlink = session.replies::<Item>()
+            .find(|item| item.path == std::path::Path::new("/longlink"))
+            .unwrap();
+        let mut target = b"x".repeat(100);
+        target.extend_from_slice(b"/target");
+        assert_eq!(longlink.symlink, Some(target));
+    }
+
+    #[test]
+    fn handle_non_existent() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            path: PathBuf::from("/foo"),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_not_dir() {
+        let image = crate::raw::fixture("basic");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            path: PathBuf::from("/hello.txt"),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, args).is_err());
+    }
+
+    #[test]
+    fn handle_ext2() {
+        let image = crate::raw::fixture("ext2");
+
+        let args = Args {
+            device: image.path().to_path_buf(),
+            path: PathBuf::from("/dir/subdir"),
+        };
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        assert_eq!(session.reply_count(), 1);
+
+        let item = session.reply::<Item>(0);
+        assert_eq!(item.path, PathBuf::from("/dir/subdir/deep.txt"));
+        assert_eq!(item.inode.as_ref().unwrap().size, b"quux\n".len() as u64);
+    }
+}
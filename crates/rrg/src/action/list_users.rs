@@ -0,0 +1,128 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+/// A result of the `list_users` action.
+struct Item {
+    // Information about the individual user account.
+    user: ospect::os::User,
+}
+
+// Handles invocations of the `list_users` action.
+pub fn handle<S>(session: &mut S, _: ()) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let users = ospect::os::users()
+        .map_err(crate::session::Error::action)?;
+
+    for user in users {
+        let user = match user {
+            Ok(user) => user,
+            Err(error) => {
+                log::warn!("failed to obtain user information: {}", error);
+                continue;
+            }
+        };
+
+        session.reply(Item {
+            user,
+        })?;
+    }
+
+    Ok(())
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::list_users::Result;
+
+    fn into_proto(self) -> rrg_proto::list_users::Result {
+        let user = self.user;
+
+        let mut proto = rrg_proto::list_users::Result::default();
+        proto.set_name(user.name.to_string_lossy().into_owned());
+        proto.set_uid(user.uid);
+        proto.set_gid(user.gid);
+        if let Some(full_name) = user.full_name {
+            proto.set_full_name(full_name.to_string_lossy().into_owned());
+        }
+        if let Some(home_dir) = user.home_dir {
+            proto.set_home_dir(home_dir.into());
+        }
+        if let Some(shell) = user.shell {
+            proto.set_shell(shell.into());
+        }
+        proto.set_supplementary_gids(user.groups);
+        if let Some(password) = user.password {
+            proto.set_password(password_into_proto(password));
+        }
+        if let Some(last_login) = user.last_login {
+            proto.set_last_login_time(rrg_proto::into_timestamp(last_login));
+        }
+
+        proto
+    }
+}
+
+/// Converts password metadata to its Protocol Buffers representation.
+fn password_into_proto(
+    password: ospect::os::Password,
+) -> rrg_proto::list_users::Password {
+    let mut proto = rrg_proto::list_users::Password::default();
+    proto.set_locked(password.locked);
+    proto.set_empty(password.empty);
+    if let Some(last_change) = password.last_change {
+        proto.set_last_change_time(rrg_proto::into_timestamp(last_change));
+    }
+    if let Some(min_age) = password.min_age {
+        proto.set_min_age(min_age.into());
+    }
+    if let Some(max_age) = password.max_age {
+        proto.set_max_age(max_age.into());
+    }
+    if let Some(warn_period) = password.warn_period {
+        proto.set_warn_period(warn_period.into());
+    }
+    if let Some(inactive_period) = password.inactive_period {
+        proto.set_inactive_period(inactive_period.into());
+    }
+    if let Some(expiration) = password.expiration {
+        proto.set_expiration_time(rrg_proto::into_timestamp(expiration));
+    }
+
+    proto
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn handle_root() {
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        let item = session.replies::<Item>().find(|item| {
+            item.user.uid == 0
+        }).unwrap();
+
+        assert_eq!(item.user.name, "root");
+    }
+
+    #[test]
+    fn handle_self() {
+        // SAFETY: This function is always safe to call.
+        let uid = unsafe { libc::getuid() };
+
+        let mut session = crate::session::FakeSession::new();
+        assert!(handle(&mut session, ()).is_ok());
+
+        assert! {
+            session.replies::<Item>().any(|item| item.user.uid == uid)
+        };
+    }
+}
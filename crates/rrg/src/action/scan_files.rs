@@ -0,0 +1,402 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::path::PathBuf;
+
+/// Arguments of the `scan_files` action.
+pub struct Args {
+    /// Path to the root directory to scan the files in.
+    root: PathBuf,
+    /// Rules to scan the files against.
+    rules: crate::scan::Rules,
+    /// Limit on the depth of recursion when visiting subdirectories.
+    max_depth: Option<u32>,
+    /// Limit on the size of files to scan.
+    max_file_size: Option<u64>,
+}
+
+/// Result of the `scan_files` action.
+pub struct Item {
+    /// Path to the file that matched the rule.
+    path: PathBuf,
+    /// Details about the rule that matched.
+    rule_match: crate::scan::RuleMatch,
+}
+
+/// Handles invocations of the `scan_files` action.
+pub fn handle<S>(session: &mut S, args: Args) -> crate::session::Result<()>
+where
+    S: crate::session::Session,
+{
+    let mut entries = crate::fs::walk_dir(&args.root)
+        .map_err(crate::session::Error::action)?;
+    if let Some(max_depth) = args.max_depth {
+        entries = entries.with_max_depth(max_depth);
+    }
+
+    'entries: for entry in entries {
+        // Scans can run for a long time without sending any replies, so we
+        // have to make sure we stop once we run out of time.
+        session.check_time_limits()?;
+
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                log::error!("failed to read directory entry: {error}");
+                continue
+            }
+        };
+
+        if !args.should_scan(&entry) {
+            continue;
+        }
+
+        let mut scan = args.rules.scan();
+
+        let file = match std::fs::File::open(&entry.path) {
+            Ok(file) => file,
+            Err(error) => {
+                log::error! {
+                    "failed to open '{}': {error}",
+                    entry.path.display()
+                };
+                continue;
+            }
+        };
+
+        // All the strings are searched for in a single pass over the file.
+        let mut search = crate::io::RegexSearch::new_many(file, args.rules.regexes().collect());
+
+        loop {
+            use crate::io::RegexSearchStep::*;
+            match search.step() {
+                Ok(Match(matcz)) => {
+                    scan.record(matcz.regex_index, matcz.offset, matcz.content);
+                }
+                Ok(Progress) => (),
+                Ok(Done) => break,
+                Err(error) => {
+                    // We do not report partial results as conditions of the
+                    // rules might not hold for the whole file.
+                    log::error! {
+                        "failed to read '{}': {error}",
+                        entry.path.display()
+                    };
+                    continue 'entries;
+                }
+            }
+
+            session.heartbeat();
+            session.check_time_limits()?;
+        }
+
+        for rule_match in scan.finish(entry.metadata.len()) {
+            session.reply(Item {
+                path: entry.path.clone(),
+                rule_match,
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+impl Args {
+
+    /// Determines whether the given entry should be scanned.
+    fn should_scan(&self, entry: &crate::fs::Entry) -> bool {
+        // Non-regular files (like devices or named pipes) can block or yield
+        // infinite content, so we never scan them. Note that directory entries
+        // do not follow symlinks, so symlinks are skipped as well.
+        if !entry.metadata.is_file() {
+            return false;
+        }
+
+        if let Some(max_file_size) = self.max_file_size {
+            if entry.metadata.len() > max_file_size {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl crate::request::Args for Args {
+
+    type Proto = rrg_proto::scan_files::Args;
+
+    fn from_proto(mut proto: Self::Proto) -> Result<Args, crate::request::ParseArgsError> {
+        use crate::request::ParseArgsError;
+
+        let root = PathBuf::try_from(proto.take_root())
+            .map_err(|error| ParseArgsError::invalid_field("root", error))?;
+
+        let rules = crate::scan::Rules::parse(proto.rules())
+            .map_err(|error| ParseArgsError::invalid_field("rules", error))?;
+        if rules.is_empty() {
+            return Err(ParseArgsError::invalid_field("rules", NoRulesError));
+        }
+
+        let max_depth = match proto.max_depth() {
+            0 => None,
+            max_depth => Some(max_depth),
+        };
+
+        let max_file_size = match proto.max_file_size() {
+            0 => None,
+            max_file_size => Some(max_file_size),
+        };
+
+        Ok(Args {
+            root,
+            rules,
+            max_depth,
+            max_file_size,
+        })
+    }
+}
+
+impl crate::response::Item for Item {
+
+    type Proto = rrg_proto::scan_files::Result;
+
+    fn into_proto(self) -> Self::Proto {
+        let mut proto = Self::Proto::default();
+        proto.set_path(self.path.into());
+        proto.set_rule_name(self.rule_match.rule_name);
+
+        for string_match in self.rule_match.string_matches {
+            let mut string_match_proto = rrg_proto::scan_files::StringMatch::default();
+            string_match_proto.set_id(string_match.id);
+            string_match_proto.set_offset(string_match.offset);
+            string_match_proto.set_data(string_match.data);
+
+            proto.mut_string_matches().push(string_match_proto);
+        }
+
+        proto
+    }
+}
+
+/// An error type for situations where no rules to scan against were specified.
+#[derive(Debug)]
+struct NoRulesError;
+
+impl std::fmt::Display for NoRulesError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "no rules to scan against")
+    }
+}
+
+impl std::error::Error for NoRulesError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Constructs arguments scanning the given root against the given rules.
+    fn args(root: PathBuf, rules: &str) -> Args {
+        Args {
+            root,
+            rules: crate::scan::Rules::parse(rules).unwrap(),
+            max_depth: None,
+            max_file_size: None,
+        }
+    }
+
+    /// Returns all replies of the session sorted by their path and rule name.
+    fn sorted_replies(session: &crate::session::FakeSession) -> Vec<&Item> {
+        let mut items = session.replies::<Item>().collect::<Vec<_>>();
+        items.sort_by_key(|item| (item.path.clone(), item.rule_match.rule_name.clone()));
+
+        items
+    }
+
+    #[test]
+    fn handle_empty_dir() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(tempdir.path().to_path_buf(), r#"
+            rule foo { condition: true }
+        "#)).unwrap();
+
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn handle_nested_files() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir_all(tempdir.path().join("bar").join("baz"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"MZ\x00\x01 quux")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar").join("baz").join("thud"), b"QUUX MZ\xFF\x01")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar").join("norf"), b"blargh")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(tempdir.path().to_path_buf(), r#"
+            rule foo {
+                strings:
+                    $magic = { 4D 5A ?? 01 }
+                    $text = "quux" nocase
+                condition:
+                    all of them
+            }
+        "#)).unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].path, tempdir.path().join("bar").join("baz").join("thud"));
+        assert_eq!(items[0].rule_match.rule_name, "foo");
+        assert_eq!(items[0].rule_match.string_matches.len(), 2);
+        assert_eq!(items[0].rule_match.string_matches[0].id, "$magic");
+        assert_eq!(items[0].rule_match.string_matches[0].offset, 5);
+        assert_eq!(items[0].rule_match.string_matches[0].data, b"MZ\xFF\x01");
+        assert_eq!(items[0].rule_match.string_matches[1].id, "$text");
+        assert_eq!(items[0].rule_match.string_matches[1].offset, 0);
+        assert_eq!(items[0].rule_match.string_matches[1].data, b"QUUX");
+
+        assert_eq!(items[1].path, tempdir.path().join("foo"));
+        assert_eq!(items[1].rule_match.string_matches[0].offset, 0);
+        assert_eq!(items[1].rule_match.string_matches[1].offset, 5);
+    }
+
+    #[test]
+    fn handle_multiple_rules() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"foo bar foo")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args(tempdir.path().to_path_buf(), r#"
+            rule foo {
+                strings: $foo = "foo"
+                condition: #foo == 2
+            }
+
+            rule bar {
+                strings: $bar = /ba./
+                condition: $bar and filesize > 10
+            }
+
+            rule baz {
+                strings: $baz = "baz"
+                condition: $baz
+            }
+        "#)).unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 2);
+
+        assert_eq!(items[0].rule_match.rule_name, "bar");
+        assert_eq!(items[0].rule_match.string_matches.len(), 1);
+        assert_eq!(items[0].rule_match.string_matches[0].offset, 4);
+
+        assert_eq!(items[1].rule_match.rule_name, "foo");
+        assert_eq!(items[1].rule_match.string_matches.len(), 2);
+    }
+
+    #[test]
+    fn handle_max_depth() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::create_dir(tempdir.path().join("sub"))
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("sub").join("foo"), b"foo")
+            .unwrap();
+
+        let mut args = args(tempdir.path().to_path_buf(), r#"
+            rule foo { strings: $foo = "foo" condition: $foo }
+        "#);
+        args.max_depth = Some(1);
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, tempdir.path().join("foo"));
+    }
+
+    #[test]
+    fn handle_max_file_size() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("small"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("big"), b"foo bar baz")
+            .unwrap();
+
+        let mut args = args(tempdir.path().to_path_buf(), r#"
+            rule foo { strings: $foo = "foo" condition: $foo }
+        "#);
+        args.max_file_size = Some(3);
+
+        let mut session = crate::session::FakeSession::new();
+        handle(&mut session, args)
+            .unwrap();
+
+        let items = sorted_replies(&session);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].path, tempdir.path().join("small"));
+    }
+
+    #[test]
+    fn handle_real_time_limit() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("file"), b"foo")
+            .unwrap();
+
+        let mut session = crate::session::FakeSession::new()
+            .with_real_time_limit(std::time::Duration::ZERO);
+
+        let error = handle(&mut session, args(tempdir.path().to_path_buf(), r#"
+            rule foo { strings: $foo = "foo" condition: $foo }
+        "#)).unwrap_err();
+
+        assert_eq!(error.kind(), crate::session::ErrorKind::RealTimeLimitExceeded);
+        assert_eq!(session.reply_count(), 0);
+    }
+
+    #[test]
+    fn args_from_proto_no_rules() {
+        let mut proto = rrg_proto::scan_files::Args::default();
+        proto.set_root(PathBuf::from("/").into());
+        proto.set_rules(String::from("// Nothing to see here."));
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn args_from_proto_invalid_rules() {
+        let mut proto = rrg_proto::scan_files::Args::default();
+        proto.set_root(PathBuf::from("/").into());
+        proto.set_rules(String::from("rule foo { condition: $foo }"));
+
+        let result = <Args as crate::request::Args>::from_proto(proto);
+        assert!(result.is_err());
+    }
+}
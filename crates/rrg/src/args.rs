@@ -49,6 +49,44 @@ pub struct Args {
            arg_name="PATH",
            description="whether to log to a file")]
     pub log_to_file: Option<std::path::PathBuf>,
+
+    /// Determines whether to run offline (and where to read requests from).
+    #[argh(option,
+           long="offline-input",
+           arg_name="PATH",
+           description="directory with requests to process offline (or '-' for standard input)")]
+    pub offline_input: Option<std::path::PathBuf>,
+
+    /// Determines the file to write offline responses and parcels to.
+    #[argh(option,
+           long="offline-output-file",
+           arg_name="PATH",
+           description="file to write offline responses to")]
+    pub offline_output_file: Option<std::path::PathBuf>,
+
+    /// Determines the spool directory to write offline responses and parcels to.
+    #[argh(option,
+           long="offline-output-dir",
+           arg_name="PATH",
+           description="spool directory to write offline responses to")]
+    pub offline_output_dir: Option<std::path::PathBuf>,
+
+    /// A public key used for verifying signatures of commands to execute.
+    #[cfg(feature = "action-execute_signed_command")]
+    #[argh(option,
+           long="command-verification-key",
+           arg_name="KEY",
+           description="hex-encoded Ed25519 public key for verifying commands",
+           from_str_fn(parse_verifying_key))]
+    pub command_verification_key: Option<ed25519_dalek::VerifyingKey>,
+
+    /// A directory to persist filesystem timeline snapshots in.
+    #[cfg(feature = "action-get_filesystem_timeline")]
+    #[argh(option,
+           long="timeline-snapshot-dir",
+           arg_name="PATH",
+           description="directory to store filesystem timeline snapshots in")]
+    pub timeline_snapshot_dir: Option<std::path::PathBuf>,
 }
 
 /// Parses command-line arguments.
@@ -66,3 +104,27 @@ pub fn from_env_args() -> Args {
 fn parse_duration(value: &str) -> Result<Duration, String> {
     humantime::parse_duration(value).map_err(|error| error.to_string())
 }
+
+/// Parses a hex-encoded Ed25519 public key.
+#[cfg(feature = "action-execute_signed_command")]
+fn parse_verifying_key(value: &str) -> Result<ed25519_dalek::VerifyingKey, String> {
+    if value.len() != 2 * ed25519_dalek::PUBLIC_KEY_LENGTH {
+        return Err(format! {
+            "invalid verification key length: expected {} hex digits, got {}",
+            2 * ed25519_dalek::PUBLIC_KEY_LENGTH,
+            value.len(),
+        });
+    }
+
+    let mut bytes = [0; ed25519_dalek::PUBLIC_KEY_LENGTH];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        let digits = value.get(2 * i..2 * i + 2)
+            .ok_or_else(|| String::from("non-ASCII verification key"))?;
+
+        *byte = u8::from_str_radix(digits, 16)
+            .map_err(|error| format!("invalid verification key: {error}"))?;
+    }
+
+    ed25519_dalek::VerifyingKey::from_bytes(&bytes)
+        .map_err(|error| format!("invalid verification key: {error}"))
+}
@@ -5,16 +5,68 @@
 
 // Binary data object.
 pub struct Blob {
-    // Binary data that the blob represents.
+    // Binary data that the blob represents (possibly compressed).
     data: Vec<u8>,
+    // Compression applied to the data.
+    compression: Compression,
+}
+
+/// Compression applied to the blob data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// The data is not compressed.
+    None,
+    /// The data is compressed using the zlib format.
+    Zlib,
 }
 
 impl Blob {
 
     /// Extracts the slice of blob data bytes.
+    ///
+    /// Note that if the blob is compressed, the returned bytes are compressed
+    /// as well.
     pub fn as_bytes(&self) -> &[u8] {
         self.data.as_slice()
     }
+
+    /// Compresses the blob data if it pays off.
+    ///
+    /// Data that does not compress well (e.g. because it is compressed already)
+    /// is left uncompressed, as sending it compressed would only make it harder
+    /// to process on the server.
+    #[cfg(any(
+        feature = "action-get_file_contents",
+        feature = "action-get_memory_contents",
+        feature = "action-get_raw_file_contents",
+        feature = "action-find_files",
+    ))]
+    pub fn compress(self) -> Blob {
+        use std::io::Write as _;
+
+        if self.compression != Compression::None {
+            return self;
+        }
+
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        // Writing to a vector cannot fail, so we could just unwrap here but we
+        // fall back to uncompressed data just to be on the safe side.
+        let data = match encoder.write_all(&self.data).and_then(|()| encoder.finish()) {
+            Ok(data) => data,
+            Err(_) => return self,
+        };
+
+        // We consider compression worth it only if it saves at least 1/8 of the
+        // original size.
+        if data.len() > self.data.len() - self.data.len() / 8 {
+            return self;
+        }
+
+        Blob {
+            data,
+            compression: Compression::Zlib,
+        }
+    }
 }
 
 impl From<Vec<u8>> for Blob {
@@ -22,6 +74,7 @@ impl From<Vec<u8>> for Blob {
     fn from(data: Vec<u8>) -> Blob {
         Blob {
             data,
+            compression: Compression::None,
         }
     }
 }
@@ -33,7 +86,73 @@ impl crate::response::Item for Blob {
     fn into_proto(self) -> Self::Proto {
         let mut proto = Self::Proto::default();
         proto.set_data(self.data);
+        proto.set_compression(self.compression.into());
 
         proto
     }
 }
+
+impl From<Compression> for rrg_proto::blob::blob::Compression {
+
+    fn from(compression: Compression) -> rrg_proto::blob::blob::Compression {
+        match compression {
+            Compression::None => Self::NONE,
+            Compression::Zlib => Self::ZLIB,
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(
+    feature = "action-get_file_contents",
+    feature = "action-get_memory_contents",
+))]
+mod tests {
+
+    use super::*;
+
+    /// Decompresses the data of a zlib-compressed blob.
+    fn decompress(blob: &Blob) -> Vec<u8> {
+        use std::io::Read as _;
+
+        let mut data = Vec::new();
+        flate2::read::ZlibDecoder::new(blob.as_bytes())
+            .read_to_end(&mut data)
+            .unwrap();
+
+        data
+    }
+
+    #[test]
+    fn compress_text() {
+        let data = b"foo bar baz\n".repeat(1024);
+
+        let blob = Blob::from(data.clone()).compress();
+        assert_eq!(blob.compression, Compression::Zlib);
+        assert!(blob.as_bytes().len() < data.len());
+        assert_eq!(decompress(&blob), data);
+    }
+
+    #[test]
+    fn compress_empty() {
+        let blob = Blob::from(Vec::new()).compress();
+        assert_eq!(blob.compression, Compression::None);
+        assert!(blob.as_bytes().is_empty());
+    }
+
+    #[test]
+    fn compress_compressed() {
+        let data = b"foo bar baz\n".repeat(1024);
+
+        let blob = Blob::from(data).compress();
+        assert_eq!(blob.compression, Compression::Zlib);
+
+        let compressed = blob.as_bytes().to_vec();
+
+        // Compressing already compressed data should not pay off, so the data
+        // should be left as is.
+        let blob = Blob::from(compressed.clone()).compress();
+        assert_eq!(blob.compression, Compression::None);
+        assert_eq!(blob.as_bytes(), compressed);
+    }
+}
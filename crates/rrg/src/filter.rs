@@ -40,6 +40,8 @@ struct Cond {
     var: CondVar,
     /// The operator to apply.
     op: CondFullOp,
+    /// The quantifier to apply if the variable refers to many values.
+    quant: CondQuant,
 }
 
 /// Individual condition variable.
@@ -69,9 +71,28 @@ struct Cond {
 /// Variable `1.3` on `Foo` instance will refer to `bar.thud` of type `string`
 /// whereas the same variable on `Bar` instance will refer to field `quux.norf`
 /// of type `uint32`.
+///
+/// If the variable goes through repeated or map fields, it can refer to many
+/// values (or none at all). The n-th map field on the path is looked up using
+/// the n-th map key (if there is one).
 struct CondVar {
     top_field_num: u32,
     nested_field_nums: Vec<u32>,
+    map_keys: Vec<CondMapKey>,
+}
+
+/// Key to look up in a map field that the condition variable goes through.
+enum CondMapKey {
+    /// Wildcard key that refers to all values of the map.
+    Any,
+    /// Key of maps with [`bool`] keys.
+    Bool(bool),
+    /// Key of maps with [`String`] keys.
+    String(String),
+    /// Key of maps with unsigned integer keys.
+    U64(u64),
+    /// Key of maps with signed integer keys.
+    I64(i64),
 }
 
 /// Borrowed reference to an individual condition variable.
@@ -94,6 +115,32 @@ struct CondFullOp {
     negated: bool,
 }
 
+/// Quantifier determining how to combine results for many variable values.
+#[derive(Clone, Copy)]
+enum CondQuant {
+    /// Variable must refer to exactly one value.
+    Singular,
+    /// Any of the values has to satisfy the operator.
+    Any,
+    /// All of the values have to satisfy the operator.
+    All,
+    /// Number of values satisfying the operator has to be equal to the count.
+    CountEqual(u64),
+    /// Number of values satisfying the operator has to be less than the count.
+    CountLess(u64),
+    /// Number of values satisfying the operator has to be greater than the count.
+    CountGreater(u64),
+}
+
+/// Number of variable values that satisfied the operator during evaluation.
+#[derive(Default)]
+struct CondTally {
+    /// Number of values that satisfied the operator.
+    matched: u64,
+    /// Number of all values the operator was applied to.
+    total: u64,
+}
+
 /// Individual basic condition operator of the form _□ ⋄ l_ (without negation.
 ///
 /// See documentation for [`Filter`] for more details.
@@ -116,6 +163,44 @@ enum CondOp {
     I64Equal(i64),
     /// Less-than check against an [`i64`] value.
     I64Less(i64),
+    /// Range check against a non-empty range of [`u64`] values.
+    U64Range(std::ops::Range<u64>),
+    /// Range check against a non-empty range of [`i64`] values.
+    I64Range(std::ops::Range<i64>),
+    /// Equality check against a non-NaN [`f64`] value.
+    F64Equal(f64),
+    /// Less-than check against a non-NaN [`f64`] value.
+    F64Less(f64),
+    /// Range check against a non-empty range of non-NaN [`f64`] values.
+    F64Range(std::ops::Range<f64>),
+    /// Equality check against a name of an enum value.
+    EnumEqual(String),
+    /// Membership check against a non-empty set of [`String`] values.
+    StringIn(std::collections::BTreeSet<String>),
+    /// Membership check against a non-empty set of [`u64`] values.
+    U64In(std::collections::BTreeSet<u64>),
+    /// Membership check against a non-empty set of [`i64`] values.
+    I64In(std::collections::BTreeSet<i64>),
+    /// Membership check against a non-empty set of names of enum values.
+    EnumIn(std::collections::BTreeSet<String>),
+    /// Prefix check against a [`String`] value.
+    StringPrefix(String),
+    /// Suffix check against a [`String`] value.
+    StringSuffix(String),
+    /// Substring check against a [`String`] value.
+    StringContains(String),
+    /// Membership check of IP address octets against an IP network.
+    IpInNetwork(IpNetwork),
+}
+
+/// IP network given by an address and a prefix length (as in CIDR notation).
+///
+/// The address is guaranteed to have all the bits beyond the prefix unset.
+struct IpNetwork {
+    /// Address of the network.
+    addr: std::net::IpAddr,
+    /// Number of leading bits of the address that identify the network.
+    prefix_len: u8,
 }
 
 impl FilterSet {
@@ -164,15 +249,22 @@ impl Cond {
         &self,
         message: &dyn protobuf::MessageDyn,
     ) -> Result<bool, Error> {
-        self.eval_at(message, self.var.as_ref())
+        let mut tally = CondTally::default();
+        self.eval_at(message, self.var.as_ref(), &self.var.map_keys, &mut tally)?;
+
+        Ok(self.quant.eval(tally))
     }
 
-    /// Verifies whether the message at certain field passes the condition.
+    /// Applies the operator to all values of the message at certain field.
     fn eval_at(
         &self,
         message: &dyn protobuf::MessageDyn,
         var: CondVarRef<'_>,
-    ) -> Result<bool, Error> {
+        map_keys: &[CondMapKey],
+        tally: &mut CondTally,
+    ) -> Result<(), Error> {
+        use protobuf::reflect::RuntimeFieldType;
+
         let message_desc = message.descriptor_dyn();
         let field_desc = message_desc.field_by_number(var.top_field_num)
             .ok_or(ErrorRepr::InvalidFieldNum {
@@ -180,29 +272,155 @@ impl Cond {
                 field_num: var.top_field_num
             })?;
 
-        // We only support singular fields. The call below could panic if not
-        // for this check.
-        if !field_desc.is_singular() {
-            return Err(ErrorRepr::NonSingularField {
-                field_name: field_desc.full_name(),
-            }.into());
-        }
+        match field_desc.runtime_field_type() {
+            RuntimeFieldType::Singular(_) => {
+                let value = field_desc.get_singular_field_or_default(message);
+                self.eval_value_at(&field_desc, value, var, map_keys, tally)
+            }
+            RuntimeFieldType::Repeated(_) => {
+                self.ensure_quantified(&field_desc)?;
+
+                for value in field_desc.get_repeated(message) {
+                    self.eval_value_at(&field_desc, value, var, map_keys, tally)?;
+                }
+
+                Ok(())
+            }
+            RuntimeFieldType::Map(key_type, _) => {
+                let (map_key, map_keys) = match map_keys.split_first() {
+                    Some((map_key, map_keys)) => (map_key, map_keys),
+                    None => (&CondMapKey::Any, map_keys),
+                };
+
+                let map = field_desc.get_map(message);
+
+                if let CondMapKey::Any = map_key {
+                    self.ensure_quantified(&field_desc)?;
+
+                    for (_, value) in &map {
+                        self.eval_value_at(&field_desc, value, var, map_keys, tally)?;
+                    }
+
+                    return Ok(());
+                }
+
+                let key = map_key.as_reflect(&key_type)
+                    .ok_or_else(|| ErrorRepr::MapKeyTypeMismatch {
+                        field_name: field_desc.full_name(),
+                        key_type,
+                    })?;
 
-        let field = field_desc.get_singular_field_or_default(message);
+                // Keys outside of the range of the map key type and keys that
+                // are not present in the map simply refer to no values.
+                if let Some(value) = key.and_then(|key| map.get(key)) {
+                    self.eval_value_at(&field_desc, value, var, map_keys, tally)?;
+                }
 
+                Ok(())
+            }
+        }
+    }
+
+    /// Applies the operator to the given field value or values nested in it.
+    fn eval_value_at(
+        &self,
+        field_desc: &protobuf::reflect::FieldDescriptor,
+        value: ReflectValueRef<'_>,
+        var: CondVarRef<'_>,
+        map_keys: &[CondMapKey],
+        tally: &mut CondTally,
+    ) -> Result<(), Error> {
         match var.nested() {
-            None => self.op.eval_value(field),
+            None => {
+                tally.total += 1;
+                if self.op.eval_value(value)? {
+                    tally.matched += 1;
+                }
+
+                Ok(())
+            }
             Some(var) => {
-                let ReflectValueRef::Message(message) = field else {
+                let ReflectValueRef::Message(message) = value else {
                     return Err(ErrorRepr::NonMessageFieldAccess {
                         field_name: field_desc.full_name(),
                     }.into());
                 };
 
-                self.eval_at(&*message, var)
+                self.eval_at(&*message, var, map_keys, tally)
             }
         }
     }
+
+    /// Verifies that the condition is allowed to go through the given field.
+    ///
+    /// Fields that hold many values (repeated fields and non-keyed maps) can be
+    /// accessed only by quantified conditions.
+    fn ensure_quantified(
+        &self,
+        field_desc: &protobuf::reflect::FieldDescriptor,
+    ) -> Result<(), Error> {
+        if let CondQuant::Singular = self.quant {
+            return Err(ErrorRepr::NonSingularField {
+                field_name: field_desc.full_name(),
+            }.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl CondQuant {
+
+    /// Determines whether the given tally satisfies the quantifier.
+    fn eval(self, tally: CondTally) -> bool {
+        match self {
+            // Singular variables refer to at most one value (none in case of
+            // missing map keys), so they behave like existential quantifier.
+            CondQuant::Singular => tally.matched > 0,
+            CondQuant::Any => tally.matched > 0,
+            CondQuant::All => tally.matched == tally.total,
+            CondQuant::CountEqual(count) => tally.matched == count,
+            CondQuant::CountLess(count) => tally.matched < count,
+            CondQuant::CountGreater(count) => tally.matched > count,
+        }
+    }
+}
+
+impl CondMapKey {
+
+    /// Converts the key to a reflected value of the given map key type.
+    ///
+    /// Returns `None` if the key type does not match the map key type. Returns
+    /// `Some(None)` if the key is of correct type but outside of its range (in
+    /// which case it cannot be present in the map).
+    fn as_reflect(
+        &self,
+        key_type: &protobuf::reflect::RuntimeType,
+    ) -> Option<Option<ReflectValueRef<'_>>> {
+        use protobuf::reflect::RuntimeType;
+
+        match (self, key_type) {
+            (CondMapKey::Bool(bool), RuntimeType::Bool) => {
+                Some(Some(ReflectValueRef::Bool(*bool)))
+            }
+            (CondMapKey::String(string), RuntimeType::String) => {
+                Some(Some(ReflectValueRef::String(string)))
+            }
+            (CondMapKey::U64(u64), RuntimeType::U32) => {
+                Some(u32::try_from(*u64).ok().map(ReflectValueRef::U32))
+            }
+            (CondMapKey::U64(u64), RuntimeType::U64) => {
+                Some(Some(ReflectValueRef::U64(*u64)))
+            }
+            (CondMapKey::I64(i64), RuntimeType::I32) => {
+                Some(i32::try_from(*i64).ok().map(ReflectValueRef::I32))
+            }
+            (CondMapKey::I64(i64), RuntimeType::I64) => {
+                Some(Some(ReflectValueRef::I64(*i64)))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl CondFullOp {
@@ -277,34 +495,220 @@ impl CondOp {
             (I64(value), CondOp::I64Less(i64)) => {
                 Ok(value < *i64)
             }
-            (value, _) => Err(ErrorRepr::TypeMismatch {
-                var_type: value.get_type(),
-                op_type: self.runtime_type(),
-            }.into())
+            (U32(value), CondOp::U64Range(range)) => {
+                Ok(range.contains(&u64::from(value)))
+            }
+            (U64(value), CondOp::U64Range(range)) => {
+                Ok(range.contains(&value))
+            }
+            (I32(value), CondOp::I64Range(range)) => {
+                Ok(range.contains(&i64::from(value)))
+            }
+            (I64(value), CondOp::I64Range(range)) => {
+                Ok(range.contains(&value))
+            }
+            (F32(value), CondOp::F64Equal(f64)) => {
+                Ok(f64::from(value) == *f64)
+            }
+            (F32(value), CondOp::F64Less(f64)) => {
+                Ok(f64::from(value) < *f64)
+            }
+            (F32(value), CondOp::F64Range(range)) => {
+                Ok(range.contains(&f64::from(value)))
+            }
+            (F64(value), CondOp::F64Equal(f64)) => {
+                Ok(value == *f64)
+            }
+            (F64(value), CondOp::F64Less(f64)) => {
+                Ok(value < *f64)
+            }
+            (F64(value), CondOp::F64Range(range)) => {
+                Ok(range.contains(&value))
+            }
+            (Enum(enum_desc, value), CondOp::EnumEqual(name)) => {
+                let expected = enum_value_by_name(&enum_desc, name)?;
+                Ok(value == expected.value())
+            }
+            (Enum(enum_desc, value), CondOp::EnumIn(names)) => {
+                let mut result = false;
+                // We verify all the names (rather than stopping at the first
+                // match) so that invalid names are always reported.
+                for name in names {
+                    let expected = enum_value_by_name(&enum_desc, name)?;
+                    result |= value == expected.value();
+                }
+
+                Ok(result)
+            }
+            (String(value), CondOp::StringIn(strings)) => {
+                Ok(strings.contains(value))
+            }
+            (U32(value), CondOp::U64In(u64s)) => {
+                Ok(u64s.contains(&u64::from(value)))
+            }
+            (U64(value), CondOp::U64In(u64s)) => {
+                Ok(u64s.contains(&value))
+            }
+            (I32(value), CondOp::I64In(i64s)) => {
+                Ok(i64s.contains(&i64::from(value)))
+            }
+            (I64(value), CondOp::I64In(i64s)) => {
+                Ok(i64s.contains(&value))
+            }
+            (String(value), CondOp::StringPrefix(string)) => {
+                Ok(value.starts_with(string.as_str()))
+            }
+            (String(value), CondOp::StringSuffix(string)) => {
+                Ok(value.ends_with(string.as_str()))
+            }
+            (String(value), CondOp::StringContains(string)) => {
+                Ok(value.contains(string.as_str()))
+            }
+            (Bytes(value), CondOp::IpInNetwork(network)) => {
+                Ok(network.contains_octets(value))
+            }
+            (value, _) => match self.runtime_type() {
+                Some(op_type) => Err(ErrorRepr::TypeMismatch {
+                    var_type: value.get_type(),
+                    op_type,
+                }.into()),
+                None => Err(ErrorRepr::NonEnumTypeMismatch {
+                    var_type: value.get_type(),
+                }.into()),
+            }
         }
     }
 
     /// Returns the runtime type of values this operator works with.
-    fn runtime_type(&self) -> protobuf::reflect::RuntimeType {
+    ///
+    /// Enum operators work with values of any enum type, so for them [`None`]
+    /// is returned instead.
+    fn runtime_type(&self) -> Option<protobuf::reflect::RuntimeType> {
         use protobuf::reflect::RuntimeType;
         match self {
-            CondOp::BoolEqual(_) => RuntimeType::Bool,
-            CondOp::StringEqual(_) => RuntimeType::String,
-            CondOp::StringMatch(_) => RuntimeType::String,
-            CondOp::BytesEqual(_) => RuntimeType::VecU8,
-            CondOp::BytesMatch(_) => RuntimeType::VecU8,
-            CondOp::U64Equal(_) => RuntimeType::U64,
-            CondOp::U64Less(_) => RuntimeType::U64,
-            CondOp::I64Equal(_) => RuntimeType::I64,
-            CondOp::I64Less(_) => RuntimeType::I64,
+            CondOp::BoolEqual(_) => Some(RuntimeType::Bool),
+            CondOp::StringEqual(_) => Some(RuntimeType::String),
+            CondOp::StringMatch(_) => Some(RuntimeType::String),
+            CondOp::BytesEqual(_) => Some(RuntimeType::VecU8),
+            CondOp::BytesMatch(_) => Some(RuntimeType::VecU8),
+            CondOp::U64Equal(_) => Some(RuntimeType::U64),
+            CondOp::U64Less(_) => Some(RuntimeType::U64),
+            CondOp::I64Equal(_) => Some(RuntimeType::I64),
+            CondOp::I64Less(_) => Some(RuntimeType::I64),
+            CondOp::U64Range(_) => Some(RuntimeType::U64),
+            CondOp::I64Range(_) => Some(RuntimeType::I64),
+            CondOp::F64Equal(_) => Some(RuntimeType::F64),
+            CondOp::F64Less(_) => Some(RuntimeType::F64),
+            CondOp::F64Range(_) => Some(RuntimeType::F64),
+            CondOp::EnumEqual(_) => None,
+            CondOp::StringIn(_) => Some(RuntimeType::String),
+            CondOp::U64In(_) => Some(RuntimeType::U64),
+            CondOp::I64In(_) => Some(RuntimeType::I64),
+            CondOp::EnumIn(_) => None,
+            CondOp::StringPrefix(_) => Some(RuntimeType::String),
+            CondOp::StringSuffix(_) => Some(RuntimeType::String),
+            CondOp::StringContains(_) => Some(RuntimeType::String),
+            CondOp::IpInNetwork(_) => Some(RuntimeType::VecU8),
+        }
+    }
+}
+
+/// Returns the value of the given enum with the specified name.
+///
+/// This function will return an error if there is no such value.
+fn enum_value_by_name(
+    enum_desc: &protobuf::reflect::EnumDescriptor,
+    name: &str,
+) -> Result<protobuf::reflect::EnumValueDescriptor, Error> {
+    enum_desc.value_by_name(name)
+        .ok_or_else(|| ErrorRepr::InvalidEnumValueName {
+            enum_name: enum_desc.full_name().to_owned(),
+            value_name: name.to_owned(),
+        }.into())
+}
+
+/// Checks whether the given string is syntactically a valid enum value name.
+fn is_enum_value_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(char) if char.is_ascii_alphabetic() || char == '_' => (),
+        _ => return false,
+    }
+
+    chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+impl IpNetwork {
+
+    /// Parses the network from its CIDR notation (e.g. `10.0.0.0/8`).
+    ///
+    /// Returns [`None`] if the string is not a valid CIDR notation or if the
+    /// address has some bits beyond the prefix set.
+    fn parse(string: &str) -> Option<IpNetwork> {
+        let (addr, prefix_len) = string.split_once('/')?;
+
+        let addr = addr.parse::<std::net::IpAddr>().ok()?;
+        let prefix_len = prefix_len.parse::<u8>().ok()?;
+
+        let network = IpNetwork { addr, prefix_len };
+        match addr {
+            std::net::IpAddr::V4(addr) => {
+                if prefix_len > 32 {
+                    return None;
+                }
+                if u32::from(addr) & !network.mask_v4() != 0 {
+                    return None;
+                }
+            }
+            std::net::IpAddr::V6(addr) => {
+                if prefix_len > 128 {
+                    return None;
+                }
+                if u128::from(addr) & !network.mask_v6() != 0 {
+                    return None;
+                }
+            }
+        }
+
+        Some(network)
+    }
+
+    /// Checks whether the address with the given octets belongs to the network.
+    ///
+    /// Octets of length other than 4 (IPv4) or 16 (IPv6) and addresses of the
+    /// family different than the network one never belong to the network.
+    fn contains_octets(&self, octets: &[u8]) -> bool {
+        match self.addr {
+            std::net::IpAddr::V4(addr) => match <[u8; 4]>::try_from(octets) {
+                Ok(octets) => {
+                    u32::from_be_bytes(octets) & self.mask_v4() == u32::from(addr)
+                }
+                Err(_) => false,
+            }
+            std::net::IpAddr::V6(addr) => match <[u8; 16]>::try_from(octets) {
+                Ok(octets) => {
+                    u128::from_be_bytes(octets) & self.mask_v6() == u128::from(addr)
+                }
+                Err(_) => false,
+            }
         }
     }
+
+    /// Returns the mask of the network prefix for IPv4 addresses.
+    fn mask_v4(&self) -> u32 {
+        u32::MAX.checked_shl(32 - u32::from(self.prefix_len)).unwrap_or(0)
+    }
+
+    /// Returns the mask of the network prefix for IPv6 addresses.
+    fn mask_v6(&self) -> u128 {
+        u128::MAX.checked_shl(128 - u32::from(self.prefix_len)).unwrap_or(0)
+    }
 }
 
 impl CondVar {
 
     /// Converts the variable to its reference wrapper.
-    fn as_ref<'a>(&'a self) -> CondVarRef<'a> {
+    fn as_ref(&self) -> CondVarRef<'_> {
         CondVarRef {
             top_field_num: self.top_field_num,
             nested_field_nums: &self.nested_field_nums,
@@ -373,14 +777,46 @@ impl std::fmt::Display for Filter {
 impl std::fmt::Display for Cond {
 
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(fmt, "{} {}", self.var, self.op)
+        write!(fmt, "{}{} {}", self.quant, self.var, self.op)
+    }
+}
+
+impl std::fmt::Display for CondQuant {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CondQuant::Singular => Ok(()),
+            CondQuant::Any => write!(fmt, "∃ "),
+            CondQuant::All => write!(fmt, "∀ "),
+            CondQuant::CountEqual(count) => write!(fmt, "∃={} ", count),
+            CondQuant::CountLess(count) => write!(fmt, "∃<{} ", count),
+            CondQuant::CountGreater(count) => write!(fmt, "∃>{} ", count),
+        }
     }
 }
 
 impl std::fmt::Display for CondVar {
 
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(fmt, "{}", self.as_ref())
+        write!(fmt, "{}", self.as_ref())?;
+        for map_key in &self.map_keys {
+            write!(fmt, "[{}]", map_key)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for CondMapKey {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CondMapKey::Any => write!(fmt, "*"),
+            CondMapKey::Bool(bool) => write!(fmt, "{}", bool),
+            CondMapKey::String(string) => write!(fmt, "{:?}", string),
+            CondMapKey::U64(u64) => write!(fmt, "{}", u64),
+            CondMapKey::I64(i64) => write!(fmt, "{}", i64),
+        }
     }
 }
 
@@ -413,6 +849,20 @@ impl std::fmt::Display for CondFullOp {
                 U64Less(u64) => write!(fmt, "≮ {}", u64),
                 I64Equal(i64) => write!(fmt, "≠ {}", i64),
                 I64Less(i64) => write!(fmt, "≮ {}", i64),
+                U64Range(range) => write!(fmt, "∉ [{}, {})", range.start, range.end),
+                I64Range(range) => write!(fmt, "∉ [{}, {})", range.start, range.end),
+                F64Equal(f64) => write!(fmt, "≠ {:?}", f64),
+                F64Less(f64) => write!(fmt, "≮ {:?}", f64),
+                F64Range(range) => write!(fmt, "∉ [{:?}, {:?})", range.start, range.end),
+                EnumEqual(name) => write!(fmt, "≠ {}", name),
+                StringIn(strings) => write!(fmt, "∉ {:?}", strings),
+                U64In(u64s) => write!(fmt, "∉ {:?}", u64s),
+                I64In(i64s) => write!(fmt, "∉ {:?}", i64s),
+                EnumIn(names) => write!(fmt, "∉ {}", EnumNames(names)),
+                StringPrefix(string) => write!(fmt, "!^= {:?}", string),
+                StringSuffix(string) => write!(fmt, "!$= {:?}", string),
+                StringContains(string) => write!(fmt, "!*= {:?}", string),
+                IpInNetwork(network) => write!(fmt, "∉ {}", network),
             }
         } else {
             write!(fmt, "{}", self.op)
@@ -434,7 +884,47 @@ impl std::fmt::Display for CondOp {
             U64Less(u64) => write!(fmt, "< {}", u64),
             I64Equal(i64) => write!(fmt, "= {}", i64),
             I64Less(i64) => write!(fmt, "< {}", i64),
+            U64Range(range) => write!(fmt, "∈ [{}, {})", range.start, range.end),
+            I64Range(range) => write!(fmt, "∈ [{}, {})", range.start, range.end),
+            F64Equal(f64) => write!(fmt, "= {:?}", f64),
+            F64Less(f64) => write!(fmt, "< {:?}", f64),
+            F64Range(range) => write!(fmt, "∈ [{:?}, {:?})", range.start, range.end),
+            EnumEqual(name) => write!(fmt, "= {}", name),
+            StringIn(strings) => write!(fmt, "∈ {:?}", strings),
+            U64In(u64s) => write!(fmt, "∈ {:?}", u64s),
+            I64In(i64s) => write!(fmt, "∈ {:?}", i64s),
+            EnumIn(names) => write!(fmt, "∈ {}", EnumNames(names)),
+            StringPrefix(string) => write!(fmt, "^= {:?}", string),
+            StringSuffix(string) => write!(fmt, "$= {:?}", string),
+            StringContains(string) => write!(fmt, "*= {:?}", string),
+            IpInNetwork(network) => write!(fmt, "∈ {}", network),
+        }
+    }
+}
+
+/// Wrapper for displaying set of enum value names.
+struct EnumNames<'a>(&'a std::collections::BTreeSet<String>);
+
+impl<'a> std::fmt::Display for EnumNames<'a> {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{{")?;
+        for (i, name) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(fmt, ", ")?;
+            }
+            write!(fmt, "{}", name)?;
         }
+        write!(fmt, "}}")?;
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for IpNetwork {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(fmt, "{}/{}", self.addr, self.prefix_len)
     }
 }
 
@@ -464,6 +954,13 @@ enum ErrorRepr {
         /// Full name of the field that caused the error.
         field_name: String,
     },
+    /// Map key is not compatible with the key type of the map field.
+    MapKeyTypeMismatch {
+        /// Full name of the field that caused the error.
+        field_name: String,
+        /// Runtime type of the map field keys.
+        key_type: protobuf::reflect::RuntimeType,
+    },
     /// Operator was applied to a value of incorrect type.
     TypeMismatch {
         /// Runtime type of the applied value.
@@ -471,6 +968,18 @@ enum ErrorRepr {
         /// Runtime type expected by the applied operator.
         op_type: protobuf::reflect::RuntimeType,
     },
+    /// Enum operator was applied to a value of non-enum type.
+    NonEnumTypeMismatch {
+        /// Runtime type of the applied value.
+        var_type: protobuf::reflect::RuntimeType,
+    },
+    /// Enum operator used a name not declared by the enum.
+    InvalidEnumValueName {
+        /// Full name of the enum that caused the error.
+        enum_name: String,
+        /// Name of the value that caused the error.
+        value_name: String,
+    },
 }
 
 impl From<ErrorRepr> for Error {
@@ -505,9 +1014,18 @@ impl std::fmt::Display for ErrorRepr {
             ErrorRepr::NonMessageFieldAccess { field_name } => {
                 write!(f, "access on non-message field `{field_name}`")
             }
+            ErrorRepr::MapKeyTypeMismatch { field_name, key_type } => {
+                write!(f, "lookup on `{field_name}` using non-`{key_type}` key")
+            }
             ErrorRepr::TypeMismatch { var_type, op_type } => {
                 write!(f, "comparison of `{var_type}` variable using `{op_type}` operator")
             }
+            ErrorRepr::NonEnumTypeMismatch { var_type } => {
+                write!(f, "comparison of `{var_type}` variable using enum operator")
+            }
+            ErrorRepr::InvalidEnumValueName { enum_name, value_name } => {
+                write!(f, "invalid value name '{value_name}' of `{enum_name}`")
+            }
         }
     }
 }
@@ -552,9 +1070,30 @@ impl TryFrom<rrg_proto::rrg::Condition> for Cond {
         let top_field_num = field_nums[0];
         field_nums.remove(0);
 
+        let map_keys = proto.take_map_keys().into_iter()
+            .map(CondMapKey::from)
+            .collect();
+
         let var = CondVar {
             top_field_num,
             nested_field_nums: field_nums,
+            map_keys,
+        };
+
+        let quant = {
+            use rrg_proto::rrg::condition::Quantifier::*;
+
+            match proto.quantifier.enum_value() {
+                Ok(SINGULAR) => CondQuant::Singular,
+                Ok(ANY) => CondQuant::Any,
+                Ok(ALL) => CondQuant::All,
+                Ok(COUNT_EQUAL) => CondQuant::CountEqual(proto.count()),
+                Ok(COUNT_LESS) => CondQuant::CountLess(proto.count()),
+                Ok(COUNT_GREATER) => CondQuant::CountGreater(proto.count()),
+                Err(value) => {
+                    return Err(ParseErrorRepr::UnknownQuantifier(value).into());
+                }
+            }
         };
 
         let op = match () {
@@ -591,6 +1130,105 @@ impl TryFrom<rrg_proto::rrg::Condition> for Cond {
             () if proto.has_int64_less() => {
                 CondOp::I64Less(proto.int64_less())
             }
+            () if proto.has_uint64_range() => {
+                let range = proto.uint64_range();
+                if range.start() >= range.end() {
+                    return Err(ParseErrorRepr::EmptyRange.into());
+                }
+
+                CondOp::U64Range(range.start()..range.end())
+            }
+            () if proto.has_int64_range() => {
+                let range = proto.int64_range();
+                if range.start() >= range.end() {
+                    return Err(ParseErrorRepr::EmptyRange.into());
+                }
+
+                CondOp::I64Range(range.start()..range.end())
+            }
+            () if proto.has_double_equal() => {
+                if proto.double_equal().is_nan() {
+                    return Err(ParseErrorRepr::NanLiteral.into());
+                }
+
+                CondOp::F64Equal(proto.double_equal())
+            }
+            () if proto.has_double_less() => {
+                if proto.double_less().is_nan() {
+                    return Err(ParseErrorRepr::NanLiteral.into());
+                }
+
+                CondOp::F64Less(proto.double_less())
+            }
+            () if proto.has_double_range() => {
+                let range = proto.double_range();
+                if range.start().is_nan() || range.end().is_nan() {
+                    return Err(ParseErrorRepr::NanLiteral.into());
+                }
+                if range.start() >= range.end() {
+                    return Err(ParseErrorRepr::EmptyRange.into());
+                }
+
+                CondOp::F64Range(range.start()..range.end())
+            }
+            () if proto.has_enum_equal() => {
+                let name = proto.take_enum_equal();
+                if !is_enum_value_name(&name) {
+                    return Err(ParseErrorRepr::InvalidEnumValueName(name).into());
+                }
+
+                CondOp::EnumEqual(name)
+            }
+            () if proto.has_string_in() => {
+                let strings = proto.take_string_in().values;
+                if strings.is_empty() {
+                    return Err(ParseErrorRepr::EmptySet.into());
+                }
+
+                CondOp::StringIn(strings.into_iter().collect())
+            }
+            () if proto.has_uint64_in() => {
+                let u64s = proto.take_uint64_in().values;
+                if u64s.is_empty() {
+                    return Err(ParseErrorRepr::EmptySet.into());
+                }
+
+                CondOp::U64In(u64s.into_iter().collect())
+            }
+            () if proto.has_int64_in() => {
+                let i64s = proto.take_int64_in().values;
+                if i64s.is_empty() {
+                    return Err(ParseErrorRepr::EmptySet.into());
+                }
+
+                CondOp::I64In(i64s.into_iter().collect())
+            }
+            () if proto.has_enum_in() => {
+                let names = proto.take_enum_in().values;
+                if names.is_empty() {
+                    return Err(ParseErrorRepr::EmptySet.into());
+                }
+                if let Some(name) = names.iter().find(|name| !is_enum_value_name(name)) {
+                    return Err(ParseErrorRepr::InvalidEnumValueName(name.clone()).into());
+                }
+
+                CondOp::EnumIn(names.into_iter().collect())
+            }
+            () if proto.has_string_prefix() => {
+                CondOp::StringPrefix(proto.take_string_prefix())
+            }
+            () if proto.has_string_suffix() => {
+                CondOp::StringSuffix(proto.take_string_suffix())
+            }
+            () if proto.has_string_contains() => {
+                CondOp::StringContains(proto.take_string_contains())
+            }
+            () if proto.has_ip_in_network() => {
+                let network = IpNetwork::parse(proto.ip_in_network())
+                    .ok_or_else(|| ParseErrorRepr::InvalidIpNetwork(proto.take_ip_in_network()))?;
+
+                CondOp::IpInNetwork(network)
+            }
             () => return Err(ParseErrorRepr::NoOperator.into()),
         };
 
@@ -599,11 +1237,25 @@ impl TryFrom<rrg_proto::rrg::Condition> for Cond {
             op: CondFullOp {
                 op,
                 negated: proto.negated(),
-            }
+            },
+            quant,
         })
     }
 }
 
+impl From<rrg_proto::rrg::MapKey> for CondMapKey {
+
+    fn from(mut proto: rrg_proto::rrg::MapKey) -> CondMapKey {
+        match () {
+            () if proto.has_bool() => CondMapKey::Bool(proto.bool()),
+            () if proto.has_string() => CondMapKey::String(proto.take_string()),
+            () if proto.has_uint64() => CondMapKey::U64(proto.uint64()),
+            () if proto.has_int64() => CondMapKey::I64(proto.int64()),
+            () => CondMapKey::Any,
+        }
+    }
+}
+
 /// The error type for parsing filters from Protocol Buffer messages.
 #[derive(Debug)]
 pub struct ParseError {
@@ -617,10 +1269,22 @@ enum ParseErrorRepr {
     NoField,
     /// Condition has no operator specified.
     NoOperator,
+    /// Condition has an unknown quantifier specified.
+    UnknownQuantifier(i32),
     /// Regex in a string match operator is invalid.
     InvalidStringMatchRegex(regex::Error),
     /// Regex in a bytes match operator is invalid.
     InvalidBytesMatchRegex(regex::Error),
+    /// Range in a range operator is empty.
+    EmptyRange,
+    /// Set in a membership operator is empty.
+    EmptySet,
+    /// Floating point literal of an operator is NaN.
+    NanLiteral,
+    /// Name in an enum operator is not a valid enum value name.
+    InvalidEnumValueName(String),
+    /// Network in an IP network operator is not valid CIDR notation.
+    InvalidIpNetwork(String),
 }
 
 impl From<ParseErrorRepr> for ParseError {
@@ -645,8 +1309,14 @@ impl std::error::Error for ParseError {
         match self.repr {
             ParseErrorRepr::NoField => None,
             ParseErrorRepr::NoOperator => None,
+            ParseErrorRepr::UnknownQuantifier(_) => None,
             ParseErrorRepr::InvalidStringMatchRegex(ref error) => Some(error),
             ParseErrorRepr::InvalidBytesMatchRegex(ref error) => Some(error),
+            ParseErrorRepr::EmptyRange => None,
+            ParseErrorRepr::EmptySet => None,
+            ParseErrorRepr::NanLiteral => None,
+            ParseErrorRepr::InvalidEnumValueName(_) => None,
+            ParseErrorRepr::InvalidIpNetwork(_) => None,
         }
     }
 }
@@ -664,9 +1334,27 @@ impl std::fmt::Display for ParseErrorRepr {
             ParseErrorRepr::NoOperator => {
                 write!(f, "no operator")
             }
+            ParseErrorRepr::UnknownQuantifier(value) => {
+                write!(f, "unknown quantifier: {value}")
+            }
             ParseErrorRepr::NoField => {
                 write!(f, "no field")
             }
+            ParseErrorRepr::EmptyRange => {
+                write!(f, "empty range")
+            }
+            ParseErrorRepr::EmptySet => {
+                write!(f, "empty set")
+            }
+            ParseErrorRepr::NanLiteral => {
+                write!(f, "NaN literal")
+            }
+            ParseErrorRepr::InvalidEnumValueName(name) => {
+                write!(f, "invalid enum value name: {name:?}")
+            }
+            ParseErrorRepr::InvalidIpNetwork(network) => {
+                write!(f, "invalid IP network: {network:?}")
+            }
         }
     }
 }
@@ -681,12 +1369,14 @@ mod tests {
             crate::filter::CondVar {
                 top_field_num: $top_field_num,
                 nested_field_nums: vec![],
+                map_keys: vec![],
             }
         };
         ($top_field_num:literal : $($nested_field_num:literal):*) => {
             crate::filter::CondVar {
                 top_field_num: $top_field_num,
                 nested_field_nums: vec![$($nested_field_num),*],
+                map_keys: vec![],
             }
         };
     }
@@ -728,6 +1418,50 @@ mod tests {
         (< i64($val:literal)) => {
             crate::filter::CondOp::I64Less($val)
         };
+        (in u64($start:literal..$end:literal)) => {
+            crate::filter::CondOp::U64Range($start..$end)
+        };
+        (in i64($start:literal..$end:literal)) => {
+            crate::filter::CondOp::I64Range($start..$end)
+        };
+        (= f64($val:literal)) => {
+            crate::filter::CondOp::F64Equal($val)
+        };
+        (< f64($val:literal)) => {
+            crate::filter::CondOp::F64Less($val)
+        };
+        (in f64($start:literal..$end:literal)) => {
+            crate::filter::CondOp::F64Range($start..$end)
+        };
+        (= enum($val:literal)) => {
+            crate::filter::CondOp::EnumEqual(String::from($val))
+        };
+        (in str[$($val:literal),*]) => {
+            crate::filter::CondOp::StringIn([$(String::from($val)),*].into())
+        };
+        (in u64[$($val:literal),*]) => {
+            crate::filter::CondOp::U64In([$($val),*].into())
+        };
+        (in i64[$($val:literal),*]) => {
+            crate::filter::CondOp::I64In([$($val),*].into())
+        };
+        (in enum[$($val:literal),*]) => {
+            crate::filter::CondOp::EnumIn([$(String::from($val)),*].into())
+        };
+        (starts_with str($val:literal)) => {
+            crate::filter::CondOp::StringPrefix(String::from($val))
+        };
+        (ends_with str($val:literal)) => {
+            crate::filter::CondOp::StringSuffix(String::from($val))
+        };
+        (contains str($val:literal)) => {
+            crate::filter::CondOp::StringContains(String::from($val))
+        };
+        (in ip($val:literal)) => {
+            crate::filter::CondOp::IpInNetwork({
+                crate::filter::IpNetwork::parse($val).unwrap()
+            })
+        };
     }
 
     macro_rules! cond {
@@ -738,6 +1472,41 @@ mod tests {
                 cond
             }
         };
+        (any $($cond:tt)*) => {
+            {
+                let mut cond = cond!($($cond)*);
+                cond.quant = crate::filter::CondQuant::Any;
+                cond
+            }
+        };
+        (all $($cond:tt)*) => {
+            {
+                let mut cond = cond!($($cond)*);
+                cond.quant = crate::filter::CondQuant::All;
+                cond
+            }
+        };
+        (count(= $count:literal) $($cond:tt)*) => {
+            {
+                let mut cond = cond!($($cond)*);
+                cond.quant = crate::filter::CondQuant::CountEqual($count);
+                cond
+            }
+        };
+        (count(< $count:literal) $($cond:tt)*) => {
+            {
+                let mut cond = cond!($($cond)*);
+                cond.quant = crate::filter::CondQuant::CountLess($count);
+                cond
+            }
+        };
+        (count(> $count:literal) $($cond:tt)*) => {
+            {
+                let mut cond = cond!($($cond)*);
+                cond.quant = crate::filter::CondQuant::CountGreater($count);
+                cond
+            }
+        };
         (var($($var:tt)*) $($op:tt)*) => {
             crate::filter::Cond {
                 var: var!($($var)*),
@@ -745,6 +1514,7 @@ mod tests {
                     op: op!($($op)*),
                     negated: false,
                 },
+                quant: crate::filter::CondQuant::Singular,
             }
         };
     }
@@ -915,6 +1685,224 @@ mod tests {
         assert_eq!(filter.eval(&message).unwrap(), false);
     }
 
+    #[test]
+    fn eval_u64_range() {
+        use protobuf::well_known_types::wrappers::UInt64Value;
+        let message = UInt64Value {
+            value: 42,
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) in u64(42..43)).eval(&message).unwrap());
+        assert!(filter!(var(1) in u64(0..1337)).eval(&message).unwrap());
+        assert!(!filter!(var(1) in u64(0..42)).eval(&message).unwrap());
+        assert!(!filter!(var(1) in u64(43..1337)).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_i64_range() {
+        use protobuf::well_known_types::wrappers::Int64Value;
+        let message = Int64Value {
+            value: -42,
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) in i64(-42..-41)).eval(&message).unwrap());
+        assert!(filter!(var(1) in i64(-1337..0)).eval(&message).unwrap());
+        assert!(!filter!(var(1) in i64(-41..0)).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_f64_equal() {
+        use protobuf::well_known_types::wrappers::DoubleValue;
+        let message = DoubleValue {
+            value: 0.5,
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) = f64(0.5)).eval(&message).unwrap());
+        assert!(!filter!(var(1) = f64(0.25)).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_f64_less() {
+        use protobuf::well_known_types::wrappers::DoubleValue;
+        let message = DoubleValue {
+            value: 0.5,
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) < f64(1.0)).eval(&message).unwrap());
+        assert!(!filter!(var(1) < f64(0.5)).eval(&message).unwrap());
+        assert!(!filter!(var(1) < f64(-1.0)).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_f64_range() {
+        use protobuf::well_known_types::wrappers::DoubleValue;
+        let message = DoubleValue {
+            value: 0.5,
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) in f64(0.5..1.0)).eval(&message).unwrap());
+        assert!(!filter!(var(1) in f64(0.0..0.5)).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_f32_coercion() {
+        use protobuf::well_known_types::wrappers::FloatValue;
+        let message = FloatValue {
+            value: 0.5,
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) = f64(0.5)).eval(&message).unwrap());
+        assert!(filter!(var(1) < f64(1.0)).eval(&message).unwrap());
+        assert!(filter!(var(1) in f64(0.0..1.0)).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_enum_equal() {
+        let mut message = rrg_proto::net::TcpConnection::default();
+        message.set_state(rrg_proto::net::TcpState::LISTEN);
+
+        assert!(filter!(var(4) = enum("LISTEN")).eval(&message).unwrap());
+        assert!(!filter!(var(4) = enum("CLOSED")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_enum_in() {
+        let mut message = rrg_proto::net::TcpConnection::default();
+        message.set_state(rrg_proto::net::TcpState::LISTEN);
+
+        assert!(filter!(var(4) in enum["CLOSED", "LISTEN"]).eval(&message).unwrap());
+        assert!(!filter!(var(4) in enum["CLOSED", "CLOSING"]).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_enum_invalid_value_name() {
+        let error = filter!(var(4) in enum["LISTEN", "LISTENING"])
+            .eval(&rrg_proto::net::TcpConnection::default())
+            .unwrap_err();
+
+        match error.repr {
+            ErrorRepr::InvalidEnumValueName { enum_name, value_name } => {
+                assert_eq!(enum_name, "rrg.net.TcpState");
+                assert_eq!(value_name, "LISTENING");
+            }
+            _ => panic!("unexpected error: {error}"),
+        }
+    }
+
+    #[test]
+    fn eval_enum_type_mismatch() {
+        let error = filter!(var(1) = enum("LISTEN"))
+            .eval(&rrg_proto::net::TcpConnection::default())
+            .unwrap_err();
+
+        match error.repr {
+            ErrorRepr::NonEnumTypeMismatch { var_type } => {
+                assert_eq!(var_type, protobuf::reflect::RuntimeType::U32);
+            }
+            _ => panic!("unexpected error: {error}"),
+        }
+    }
+
+    #[test]
+    fn eval_string_in() {
+        use protobuf::well_known_types::wrappers::StringValue;
+        let message = StringValue {
+            value: String::from("foo"),
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) in str["foo", "bar"]).eval(&message).unwrap());
+        assert!(!filter!(var(1) in str["bar", "baz"]).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_u64_in() {
+        use protobuf::well_known_types::wrappers::UInt64Value;
+        let message = UInt64Value {
+            value: 42,
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) in u64[42, 1337]).eval(&message).unwrap());
+        assert!(!filter!(var(1) in u64[1337]).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_i64_in() {
+        use protobuf::well_known_types::wrappers::Int64Value;
+        let message = Int64Value {
+            value: -42,
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) in i64[-42, 42]).eval(&message).unwrap());
+        assert!(!filter!(var(1) in i64[42]).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_string_prefix_suffix_contains() {
+        use protobuf::well_known_types::wrappers::StringValue;
+        let message = StringValue {
+            value: String::from("foobarbaz"),
+            ..Default::default()
+        };
+
+        assert!(filter!(var(1) starts_with str("foo")).eval(&message).unwrap());
+        assert!(!filter!(var(1) starts_with str("bar")).eval(&message).unwrap());
+        assert!(filter!(var(1) ends_with str("baz")).eval(&message).unwrap());
+        assert!(!filter!(var(1) ends_with str("bar")).eval(&message).unwrap());
+        assert!(filter!(var(1) contains str("bar")).eval(&message).unwrap());
+        assert!(!filter!(var(1) contains str("quux")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_ip_in_network_v4() {
+        let mut message = rrg_proto::net::IpAddress::default();
+        message.set_octets(vec![10, 1, 2, 3]);
+
+        assert!(filter!(var(1) in ip("10.0.0.0/8")).eval(&message).unwrap());
+        assert!(filter!(var(1) in ip("10.1.2.3/32")).eval(&message).unwrap());
+        assert!(filter!(var(1) in ip("0.0.0.0/0")).eval(&message).unwrap());
+        assert!(!filter!(var(1) in ip("10.2.0.0/16")).eval(&message).unwrap());
+        assert!(!filter!(var(1) in ip("::/0")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_ip_in_network_v6() {
+        let mut message = rrg_proto::net::IpAddress::default();
+        message.set_octets(std::net::Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1).octets().to_vec());
+
+        assert!(filter!(var(1) in ip("fe80::/10")).eval(&message).unwrap());
+        assert!(filter!(var(1) in ip("::/0")).eval(&message).unwrap());
+        assert!(!filter!(var(1) in ip("fc00::/7")).eval(&message).unwrap());
+        assert!(!filter!(var(1) in ip("0.0.0.0/0")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_ip_in_network_invalid_octets() {
+        let mut message = rrg_proto::net::IpAddress::default();
+        message.set_octets(vec![10, 1, 2]);
+
+        assert!(!filter!(var(1) in ip("0.0.0.0/0")).eval(&message).unwrap());
+        assert!(!filter!(var(1) in ip("::/0")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn ip_network_parse_invalid() {
+        assert!(IpNetwork::parse("10.0.0.0").is_none());
+        assert!(IpNetwork::parse("10.0.0.0/33").is_none());
+        assert!(IpNetwork::parse("10.0.0.1/8").is_none());
+        assert!(IpNetwork::parse("fe80::/129").is_none());
+        assert!(IpNetwork::parse("foo/8").is_none());
+        assert!(IpNetwork::parse("10.0.0.0/-1").is_none());
+    }
+
     #[test]
     fn eval_negation() {
         use protobuf::well_known_types::wrappers::BoolValue;
@@ -1034,6 +2022,160 @@ mod tests {
         }
     }
 
+    #[test]
+    fn eval_quant_any() {
+        let mut message = rrg_proto::startup::Startup::default();
+        message.mut_args().push(String::from("--foo"));
+        message.mut_args().push(String::from("--bar"));
+
+        assert!(filter!(any var(2) = str("--foo")).eval(&message).unwrap());
+        assert!(filter!(any var(2) = str("--bar")).eval(&message).unwrap());
+        assert!(!filter!(any var(2) = str("--baz")).eval(&message).unwrap());
+        assert!(filter!(any not var(2) = str("--foo")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_quant_all() {
+        let mut message = rrg_proto::startup::Startup::default();
+        message.mut_args().push(String::from("--foo"));
+        message.mut_args().push(String::from("--bar"));
+
+        assert!(filter!(all var(2) ~= str("^--")).eval(&message).unwrap());
+        assert!(!filter!(all var(2) = str("--foo")).eval(&message).unwrap());
+        assert!(filter!(all not var(2) = str("--baz")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_quant_empty() {
+        let message = rrg_proto::startup::Startup::default();
+
+        assert!(!filter!(any var(2) = str("--foo")).eval(&message).unwrap());
+        assert!(filter!(all var(2) = str("--foo")).eval(&message).unwrap());
+        assert!(filter!(count(= 0) var(2) = str("--foo")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_quant_count() {
+        let mut message = rrg_proto::startup::Startup::default();
+        message.mut_args().push(String::from("--foo"));
+        message.mut_args().push(String::from("--bar"));
+        message.mut_args().push(String::from("--foo"));
+
+        assert!(filter!(count(= 2) var(2) = str("--foo")).eval(&message).unwrap());
+        assert!(!filter!(count(= 1) var(2) = str("--foo")).eval(&message).unwrap());
+        assert!(filter!(count(< 2) var(2) = str("--bar")).eval(&message).unwrap());
+        assert!(!filter!(count(< 2) var(2) = str("--foo")).eval(&message).unwrap());
+        assert!(filter!(count(> 1) var(2) = str("--foo")).eval(&message).unwrap());
+        assert!(!filter!(count(> 1) var(2) = str("--bar")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_quant_nested() {
+        let mut message = rrg_proto::get_file_metadata::Result::default();
+        message.mut_ext_attrs().push({
+            let mut ext_attr = rrg_proto::fs::FileExtAttr::default();
+            ext_attr.set_name(b"user.foo".to_vec());
+            ext_attr
+        });
+        message.mut_ext_attrs().push({
+            let mut ext_attr = rrg_proto::fs::FileExtAttr::default();
+            ext_attr.set_name(b"user.bar".to_vec());
+            ext_attr
+        });
+
+        assert!(filter!(any var(3:1) = bytes(b"user.bar")).eval(&message).unwrap());
+        assert!(!filter!(any var(3:1) = bytes(b"user.baz")).eval(&message).unwrap());
+        assert!(filter!(all var(3:1) ~= bytes("^user\\.")).eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_map_key() {
+        let mut message = rrg_proto::execute_signed_command::SignedCommand::default();
+        message.mut_env().insert(String::from("FOO"), String::from("foo"));
+        message.mut_env().insert(String::from("BAR"), String::from("bar"));
+
+        let mut cond = cond!(var(3) = str("foo"));
+        cond.var.map_keys.push(CondMapKey::String(String::from("FOO")));
+        assert!(Filter { conds: vec![cond] }.eval(&message).unwrap());
+
+        let mut cond = cond!(var(3) = str("foo"));
+        cond.var.map_keys.push(CondMapKey::String(String::from("BAR")));
+        assert!(!Filter { conds: vec![cond] }.eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_map_key_missing() {
+        let mut message = rrg_proto::execute_signed_command::SignedCommand::default();
+        message.mut_env().insert(String::from("FOO"), String::from("foo"));
+
+        let mut cond = cond!(not var(3) = str("foo"));
+        cond.var.map_keys.push(CondMapKey::String(String::from("BAR")));
+        assert!(!Filter { conds: vec![cond] }.eval(&message).unwrap());
+
+        let mut cond = cond!(all var(3) = str("foo"));
+        cond.var.map_keys.push(CondMapKey::String(String::from("BAR")));
+        assert!(Filter { conds: vec![cond] }.eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_map_key_wildcard() {
+        let mut message = rrg_proto::execute_signed_command::SignedCommand::default();
+        message.mut_env().insert(String::from("FOO"), String::from("foo"));
+        message.mut_env().insert(String::from("BAR"), String::from("bar"));
+
+        assert!(filter!(any var(3) = str("bar")).eval(&message).unwrap());
+        assert!(!filter!(all var(3) = str("bar")).eval(&message).unwrap());
+
+        let mut cond = cond!(count(= 2) var(3) ~= str("^[a-z]+$"));
+        cond.var.map_keys.push(CondMapKey::Any);
+        assert!(Filter { conds: vec![cond] }.eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_map_key_nested() {
+        let mut message = rrg_proto::query_wmi::Result::default();
+        message.mut_row().insert(String::from("Foo"), {
+            let mut value = rrg_proto::query_wmi::Value::default();
+            value.set_uint(42);
+            value
+        });
+
+        let mut cond = cond!(var(1:2) = u64(42));
+        cond.var.map_keys.push(CondMapKey::String(String::from("Foo")));
+        assert!(Filter { conds: vec![cond] }.eval(&message).unwrap());
+    }
+
+    #[test]
+    fn eval_map_non_singular_field() {
+        let error = filter!(var(3) = str("foo"))
+            .eval(&rrg_proto::execute_signed_command::SignedCommand::default())
+            .unwrap_err();
+
+        match error.repr {
+            ErrorRepr::NonSingularField { field_name } => {
+                assert_eq!(field_name, "rrg.action.execute_signed_command.SignedCommand.env");
+            }
+            _ => panic!("unexpected error: {error}"),
+        }
+    }
+
+    #[test]
+    fn eval_map_key_type_mismatch() {
+        let mut cond = cond!(var(3) = str("foo"));
+        cond.var.map_keys.push(CondMapKey::U64(42));
+
+        let error = Filter { conds: vec![cond] }
+            .eval(&rrg_proto::execute_signed_command::SignedCommand::default())
+            .unwrap_err();
+
+        match error.repr {
+            ErrorRepr::MapKeyTypeMismatch { field_name, .. } => {
+                assert_eq!(field_name, "rrg.action.execute_signed_command.SignedCommand.env");
+            }
+            _ => panic!("unexpected error: {error}"),
+        }
+    }
+
     #[test]
     fn eval_type_mismatch() {
         let error = filter!(var(1) = str("foo"))
@@ -1168,6 +2310,22 @@ mod tests {
         };
     }
 
+    #[test]
+    fn cond_quant_to_string() {
+        assert_eq! {
+            cond!(any var(2) = str("foo")).to_string(),
+            "∃ 𝛸(2) = \"foo\""
+        };
+        assert_eq! {
+            cond!(all not var(2) = str("foo")).to_string(),
+            "∀ 𝛸(2) ≠ \"foo\""
+        };
+        assert_eq! {
+            cond!(count(> 3) var(2) = str("foo")).to_string(),
+            "∃>3 𝛸(2) = \"foo\""
+        };
+    }
+
     #[test]
     fn cond_var_to_string() {
         assert_eq!(var!(1).to_string(), "𝛸(1)");
@@ -1175,6 +2333,47 @@ mod tests {
         assert_eq!(var!(1:3:3:7).to_string(), "𝛸(1.3.3.7)");
     }
 
+    #[test]
+    fn cond_var_with_map_keys_to_string() {
+        let mut var = var!(3:1);
+        var.map_keys.push(CondMapKey::String(String::from("foo")));
+        var.map_keys.push(CondMapKey::Any);
+        var.map_keys.push(CondMapKey::I64(-42));
+
+        assert_eq!(var.to_string(), "𝛸(3.1)[\"foo\"][*][-42]");
+    }
+
+    #[test]
+    fn cond_from_proto_quant() {
+        let mut proto = rrg_proto::rrg::Condition::new();
+        proto.set_field(vec![2]);
+        proto.set_string_equal(String::from("--foo"));
+        proto.set_quantifier(rrg_proto::rrg::condition::Quantifier::COUNT_LESS);
+        proto.set_count(2);
+
+        let cond = Cond::try_from(proto).unwrap();
+        assert!(matches!(cond.quant, CondQuant::CountLess(2)));
+    }
+
+    #[test]
+    fn cond_from_proto_map_keys() {
+        let mut proto = rrg_proto::rrg::Condition::new();
+        proto.set_field(vec![3]);
+        proto.set_string_equal(String::from("foo"));
+        proto.mut_map_keys().push({
+            let mut map_key = rrg_proto::rrg::MapKey::new();
+            map_key.set_string(String::from("FOO"));
+            map_key
+        });
+        proto.mut_map_keys().push(rrg_proto::rrg::MapKey::new());
+
+        let cond = Cond::try_from(proto).unwrap();
+        assert!(matches!(&cond.var.map_keys[..], [
+            CondMapKey::String(key),
+            CondMapKey::Any,
+        ] if key == "FOO"));
+    }
+
     #[test]
     fn cond_full_op_to_string() {
         assert_eq! {
@@ -1195,6 +2394,68 @@ mod tests {
         };
     }
 
+    #[test]
+    fn cond_from_proto_empty_range() {
+        let mut proto = rrg_proto::rrg::Condition::new();
+        proto.set_field(vec![1]);
+        proto.mut_uint64_range().set_start(42);
+        proto.mut_uint64_range().set_end(42);
+
+        let Err(error) = Cond::try_from(proto) else {
+            panic!("unexpected success");
+        };
+        assert!(matches!(error.repr, ParseErrorRepr::EmptyRange));
+    }
+
+    #[test]
+    fn cond_from_proto_nan_literal() {
+        let mut proto = rrg_proto::rrg::Condition::new();
+        proto.set_field(vec![1]);
+        proto.mut_double_range().set_start(0.0);
+        proto.mut_double_range().set_end(f64::NAN);
+
+        let Err(error) = Cond::try_from(proto) else {
+            panic!("unexpected success");
+        };
+        assert!(matches!(error.repr, ParseErrorRepr::NanLiteral));
+    }
+
+    #[test]
+    fn cond_from_proto_empty_set() {
+        let mut proto = rrg_proto::rrg::Condition::new();
+        proto.set_field(vec![1]);
+        proto.mut_string_in();
+
+        let Err(error) = Cond::try_from(proto) else {
+            panic!("unexpected success");
+        };
+        assert!(matches!(error.repr, ParseErrorRepr::EmptySet));
+    }
+
+    #[test]
+    fn cond_from_proto_invalid_enum_value_name() {
+        let mut proto = rrg_proto::rrg::Condition::new();
+        proto.set_field(vec![1]);
+        proto.set_enum_equal(String::from("FOO BAR"));
+
+        let Err(error) = Cond::try_from(proto) else {
+            panic!("unexpected success");
+        };
+        assert!(matches!(error.repr, ParseErrorRepr::InvalidEnumValueName(_)));
+    }
+
+    #[test]
+    fn cond_from_proto_invalid_ip_network() {
+        let mut proto = rrg_proto::rrg::Condition::new();
+        proto.set_field(vec![1]);
+        proto.set_ip_in_network(String::from("10.0.0.1/8"));
+
+        let Err(error) = Cond::try_from(proto) else {
+            panic!("unexpected success");
+        };
+        assert!(matches!(error.repr, ParseErrorRepr::InvalidIpNetwork(_)));
+    }
+
     #[test]
     fn cond_op_to_string() {
         assert_eq!(op!(= true).to_string(), "= true");
@@ -1205,5 +2466,22 @@ mod tests {
 
         assert_eq!(op!(= i64(-42)).to_string(), "= -42");
         assert_eq!(op!(< i64(1337)).to_string(), "< 1337");
+
+        assert_eq!(op!(in u64(1..42)).to_string(), "∈ [1, 42)");
+        assert_eq!(op!(in i64(-42..1)).to_string(), "∈ [-42, 1)");
+        assert_eq!(op!(in f64(0.5..1.0)).to_string(), "∈ [0.5, 1.0)");
+        assert_eq!(op!(= f64(1.0)).to_string(), "= 1.0");
+        assert_eq!(op!(< f64(0.5)).to_string(), "< 0.5");
+
+        assert_eq!(op!(= enum("LISTEN")).to_string(), "= LISTEN");
+        assert_eq!(op!(in enum["LISTEN", "CLOSED"]).to_string(), "∈ {CLOSED, LISTEN}");
+        assert_eq!(op!(in str["foo", "bar"]).to_string(), "∈ {\"bar\", \"foo\"}");
+        assert_eq!(op!(in u64[42, 7]).to_string(), "∈ {7, 42}");
+
+        assert_eq!(op!(starts_with str("foo")).to_string(), "^= \"foo\"");
+        assert_eq!(op!(ends_with str("foo")).to_string(), "$= \"foo\"");
+        assert_eq!(op!(contains str("foo")).to_string(), "*= \"foo\"");
+
+        assert_eq!(op!(in ip("10.0.0.0/8")).to_string(), "∈ 10.0.0.0/8");
     }
 }
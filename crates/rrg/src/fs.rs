@@ -12,6 +12,11 @@
 use std::fs::Metadata;
 use std::path::{Path, PathBuf};
 
+/// Limit on the number of symlinks followed when resolving a single entry.
+///
+/// This is the same limit as the one used by Linux (`MAXSYMLINKS`).
+const MAX_SYMLINK_CHAIN_LEN: usize = 40;
+
 /// A path to a filesystem item and associated metadata.
 ///
 /// This type is very similar to standard [`DirEntry`] but its metadata and path
@@ -23,6 +28,13 @@ pub struct Entry {
     pub path: PathBuf,
     /// Metadata associated with the item.
     pub metadata: Metadata,
+    /// Paths that a followed symlink resolved through.
+    ///
+    /// Every path is the target of the previous one (the first one being the
+    /// target of the entry itself) and the last one is not a symlink. It is
+    /// empty unless the iterator follows symlinks and the entry is a symlink,
+    /// in which case `metadata` is the metadata of the final target.
+    pub symlink_chain: Vec<PathBuf>,
 }
 
 /// Returns a deep iterator over entries within a directory.
@@ -31,8 +43,10 @@ pub struct Entry {
 /// yield entries for all encountered files.
 ///
 /// Note that symlinked folders or directories mounted to a different device
-/// than the root will not be recursively searched. This is done to avoid cycles
-/// and undesired traversal of network filesystems (which can be very slow).
+/// than the root will not be recursively searched (unless configured otherwise
+/// with [`WalkDir::with_follow_symlinks`] and [`WalkDir::with_cross_dev_fs_types`]
+/// respectively). This is done to avoid cycles and undesired traversal of
+/// network filesystems (which can be very slow).
 ///
 /// # Errors
 ///
@@ -68,34 +82,68 @@ pub fn walk_dir<P: AsRef<Path>>(root: P) -> std::io::Result<WalkDir> {
     };
 
     Ok(WalkDir {
-        max_depth: u32::MAX,
+        root: root.to_path_buf(),
         iter,
         pending_iters: vec![],
-        #[cfg(target_family = "unix")]
-        dev: dev,
+        visited: None,
+        opts: WalkOpts {
+            max_depth: u32::MAX,
+            excluded_paths: vec![],
+            follow_symlinks: false,
+            descend_filter: None,
+            #[cfg(target_family = "unix")]
+            dev: dev,
+            #[cfg(target_family = "unix")]
+            cross_devs: std::collections::HashSet::new(),
+        },
     })
 }
 
 /// Iterator over entries in all subdirectories.
 ///
 /// This iterator will recursively descent to all subdirectories and yield
-/// entries for every file encountered along the way. However, during the
-/// traversal it will not cross device boundaries and enter symlinked
-/// directories.
+/// entries for every file encountered along the way. However, unless configured
+/// otherwise with [`with_follow_symlinks`] and [`with_cross_dev_fs_types`], it
+/// will not enter symlinked directories and will not cross device boundaries.
 ///
 /// To limit depth of the recursion once can use the [`with_max_depth`] method
-/// on the instance.
+/// on the instance. To skip some of the subtrees altogether one can use the
+/// [`with_excluded_paths`] or [`with_descend_filter`] methods.
 ///
-/// The iterator can be constructed with the [`walk_dir`] function.
+/// The iterator can be constructed with the [`walk_dir`] function. It lists
+/// directories sequentially, but it can be turned into an iterator that uses
+/// a pool of threads with the [`into_par`] method.
 ///
 /// [`with_max_depth`]: WalkDir::with_max_depth
+/// [`with_excluded_paths`]: WalkDir::with_excluded_paths
+/// [`with_descend_filter`]: WalkDir::with_descend_filter
+/// [`with_cross_dev_fs_types`]: WalkDir::with_cross_dev_fs_types
+/// [`with_follow_symlinks`]: WalkDir::with_follow_symlinks
+/// [`into_par`]: WalkDir::into_par
 pub struct WalkDir {
-    max_depth: u32,
+    root: PathBuf,
     iter: ListDir,
     pending_iters: Vec<std::io::Result<ListDir>>,
+    /// Directories descended into so far (only when following symlinks).
+    visited: Option<Visited>,
+    opts: WalkOpts,
+}
+
+/// Options of the traversal shared by the sequential and parallel iterators.
+struct WalkOpts {
+    max_depth: u32,
+    excluded_paths: Vec<regex::Regex>,
+    follow_symlinks: bool,
+    /// Predicate that directories have to satisfy to be descended into.
+    descend_filter: Option<Box<DescendFilter>>,
     #[cfg(target_family = "unix")] dev: u64,
+    /// Devices other than the root one that the iterator can descend into.
+    #[cfg(target_family = "unix")] cross_devs: std::collections::HashSet<u64>,
 }
 
+/// Predicate on paths of directories to descend into.
+type DescendFilter = dyn Fn(&Path) -> bool + Send + Sync;
+
 impl WalkDir {
 
     /// Limits recursion to the specified `max_depth`.
@@ -119,19 +167,334 @@ impl WalkDir {
     pub fn with_max_depth(mut self, max_depth: u32) -> WalkDir {
         assert!(max_depth > 0);
 
-        self.max_depth = max_depth;
+        self.opts.max_depth = max_depth;
+        self
+    }
+
+    /// Excludes paths matching any of the specified regexes.
+    ///
+    /// Entries with excluded paths are not yielded and, in case of directories,
+    /// not descended into. This is useful to skip pseudo-filesystems like
+    /// `/proc` or huge cache directories.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let excluded_paths = vec![regex::Regex::new("^/usr/lib").unwrap()];
+    ///
+    /// let paths = rrg::fs::walk_dir("/usr").unwrap()
+    ///     .with_excluded_paths(excluded_paths)
+    ///     .filter_map(Result::ok)
+    ///     .map(|entry| entry.path)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert!(paths.contains(&"/usr/bin".into()));
+    /// assert!(!paths.contains(&"/usr/lib".into()));
+    /// ```
+    pub fn with_excluded_paths(mut self, excluded_paths: Vec<regex::Regex>) -> WalkDir {
+        self.opts.excluded_paths = excluded_paths;
+        self
+    }
+
+    /// Descends only into directories with paths satisfying the predicate.
+    ///
+    /// Unlike with [`with_excluded_paths`], directories not satisfying the
+    /// predicate are still yielded, only their contents are skipped. This is
+    /// useful to prune subtrees that are known not to contain anything of
+    /// interest.
+    ///
+    /// [`with_excluded_paths`]: WalkDir::with_excluded_paths
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let paths = rrg::fs::walk_dir("/usr").unwrap()
+    ///     .with_descend_filter(|path| path.ends_with("bin"))
+    ///     .filter_map(Result::ok)
+    ///     .map(|entry| entry.path)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert!(paths.contains(&"/usr/bin/env".into()));
+    /// assert!(paths.contains(&"/usr/lib".into()));
+    /// assert!(!paths.contains(&"/usr/lib/os-release".into()));
+    /// ```
+    pub fn with_descend_filter<F>(mut self, filter: F) -> WalkDir
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        self.opts.descend_filter = Some(Box::new(filter));
         self
     }
 
+    /// Allows crossing into devices with filesystems of the specified types.
+    ///
+    /// By default, the iterator does not descend into directories located on
+    /// a different device than the root. With this option, it will descend
+    /// into them as long as the type of the filesystem mounted on the device
+    /// (e.g. `ext4`, `xfs` or `btrfs`) is one of `fs_types`.
+    ///
+    /// Filesystem types are determined using the list of mounted filesystems
+    /// at the time of the call. This option has no effect on Windows, where
+    /// device boundaries are never checked.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let paths = rrg::fs::walk_dir("/").unwrap()
+    ///     .with_cross_dev_fs_types(&["ext4", "xfs", "btrfs"])
+    ///     .filter_map(Result::ok)
+    ///     .map(|entry| entry.path)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert!(paths.contains(&"/home".into()));
+    /// ```
+    pub fn with_cross_dev_fs_types<S>(self, fs_types: &[S]) -> WalkDir
+    where
+        S: AsRef<str>,
+    {
+        #[cfg(target_family = "unix")]
+        {
+            WalkDir {
+                opts: WalkOpts {
+                    cross_devs: cross_devs(fs_types),
+                    ..self.opts
+                },
+                ..self
+            }
+        }
+
+        #[cfg(not(target_family = "unix"))]
+        {
+            let _ = fs_types;
+            self
+        }
+    }
+
+    /// Makes the iterator follow symlinks.
+    ///
+    /// Entries that are symlinks are yielded with the metadata of the files
+    /// they eventually point to (along with the chain of paths the symlinks
+    /// resolved through) and symlinked directories are descended into. Entries
+    /// with symlinks that cannot be resolved (e.g. dangling ones) are yielded
+    /// as they are.
+    ///
+    /// To break cycles, every directory (identified by the device and inode
+    /// numbers on Unix and the canonical path on Windows) is descended into at
+    /// most once, no matter through how many symlinks it can be reached.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let paths = rrg::fs::walk_dir("/").unwrap()
+    ///     .with_follow_symlinks()
+    ///     .filter_map(Result::ok)
+    ///     .map(|entry| entry.path)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert!(paths.contains(&"/bin/sh".into()));
+    /// ```
+    pub fn with_follow_symlinks(mut self) -> WalkDir {
+        let mut visited = Visited::default();
+        match std::fs::metadata(&self.root) {
+            Ok(metadata) => {
+                visited.insert(&self.root, &metadata);
+            }
+            Err(error) => {
+                log::warn! {
+                    "failed to collect metadata of '{}': {error}",
+                    self.root.display()
+                };
+            }
+        }
+
+        self.visited = Some(visited);
+        self.opts.follow_symlinks = true;
+        self
+    }
+
+    /// Turns the iterator into one that lists directories in parallel.
+    ///
+    /// Directories are listed (and metadata of their entries collected) by a
+    /// pool of `threads` worker threads. This is useful for huge filesystems
+    /// or network filesystems where each `stat` call is slow. All the other
+    /// options of the iterator (like the depth limit) are preserved.
+    ///
+    /// With [`Order::Deterministic`], the entries are yielded in exactly the
+    /// same order as the sequential iterator would yield them. With
+    /// [`Order::Unordered`], entries are yielded as soon as they are listed,
+    /// which keeps all the threads busy at all times.
+    ///
+    /// # Errors
+    ///
+    /// This method will fail if it is not possible to spawn worker threads.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rrg::fs::Order;
+    ///
+    /// let threads = std::num::NonZeroUsize::new(8).unwrap();
+    ///
+    /// let paths = rrg::fs::walk_dir("/").unwrap()
+    ///     .into_par(threads, Order::Unordered).unwrap()
+    ///     .filter_map(Result::ok)
+    ///     .map(|entry| entry.path)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// assert!(paths.contains(&"/usr/bin".into()));
+    /// ```
+    pub fn into_par(
+        self,
+        threads: std::num::NonZeroUsize,
+        order: Order,
+    ) -> std::io::Result<ParWalkDir> {
+        let opts = std::sync::Arc::new(self.opts);
+
+        let (task_sender, task_receiver) = std::sync::mpsc::channel::<Task>();
+        let task_receiver = std::sync::Arc::new(std::sync::Mutex::new(task_receiver));
+
+        for _ in 0..threads.get() {
+            let opts = opts.clone();
+            let task_receiver = task_receiver.clone();
+
+            std::thread::Builder::new()
+                .name(String::from("rrg-walk-dir"))
+                .spawn(move || {
+                    // Sessions account only for CPU time of their own thread,
+                    // so workers have to charge the time they spend explicitly
+                    // for CPU time limits to be enforced.
+                    let mut cpu_time = crate::session::HelperCpuTime::start();
+
+                    loop {
+                        // The lock is released as soon as we get the task so
+                        // that other workers can pick up tasks in the meantime.
+                        let task = task_receiver.lock()
+                            .map_err(|_| ())
+                            .and_then(|task_receiver| task_receiver.recv().map_err(|_| ()));
+
+                        let Ok(task) = task else {
+                            // The iterator has been dropped, there is nothing
+                            // else to do.
+                            return;
+                        };
+
+                        // Failure to send means that the iterator has been
+                        // dropped while we were listing the directory, we
+                        // simply abandon it then and wait for the next task
+                        // (or the pool shutdown).
+                        let _ = task.run(&opts);
+
+                        cpu_time.charge();
+                    }
+                })?;
+        }
+
+        // Jobs are processed in the reverse order, so that the current listing
+        // goes first.
+        let mut jobs = self.pending_iters.into_iter()
+            .map(Job::Opened)
+            .collect::<Vec<_>>();
+        jobs.push(Job::Opened(Ok(self.iter)));
+
+        let state = match order {
+            Order::Deterministic => ParState::Deterministic {
+                pending: jobs.into_iter().map(Pending::Waiting).collect(),
+                current: None,
+            },
+            Order::Unordered => {
+                let (sender, receiver) = std::sync::mpsc::sync_channel(PAR_CHANNEL_CAPACITY);
+
+                ParState::Unordered {
+                    pending: jobs,
+                    sender,
+                    receiver,
+                }
+            }
+        };
+
+        Ok(ParWalkDir {
+            task_sender,
+            threads: threads.get(),
+            in_flight: 0,
+            visited: self.visited,
+            state,
+        })
+    }
+}
+
+impl WalkOpts {
+
+    /// Checks whether the given entry should be skipped.
+    fn is_excluded(&self, entry: &Entry) -> bool {
+        if self.excluded_paths.is_empty() {
+            return false;
+        }
+
+        self.is_path_excluded(&entry.path)
+    }
+
+    /// Checks whether the given followed entry resolves to an excluded path.
+    ///
+    /// Without this, excluded paths could be reached through symlinks that
+    /// point into them.
+    fn is_target_excluded(&self, entry: &Entry) -> bool {
+        if self.excluded_paths.is_empty() || entry.symlink_chain.is_empty() {
+            return false;
+        }
+
+        // Paths in the chain are not normalized (e.g. relative targets can
+        // contain `..`), so we check the canonical path of the target as well.
+        let canonical_path = std::fs::canonicalize(&entry.path).ok();
+
+        entry.symlink_chain.iter()
+            .chain(canonical_path.iter())
+            .any(|path| self.is_path_excluded(path))
+    }
+
+    /// Checks whether the given path matches any of the excluded paths.
+    fn is_path_excluded(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy();
+        self.excluded_paths.iter().any(|regex| regex.is_match(&path))
+    }
+
     #[cfg(target_family = "unix")]
-    fn is_same_dev(&self, entry: &Entry) -> bool {
-        self.dev == std::os::unix::fs::MetadataExt::dev(&entry.metadata)
+    fn is_allowed_dev(&self, entry: &Entry) -> bool {
+        let dev = std::os::unix::fs::MetadataExt::dev(&entry.metadata);
+        self.dev == dev || self.cross_devs.contains(&dev)
     }
 
     #[cfg(target_family = "windows")]
-    fn is_same_dev(&self, _entry: &Entry) -> bool {
+    fn is_allowed_dev(&self, _entry: &Entry) -> bool {
         true
     }
+
+    /// Resolves the given entry if it is a symlink that should be followed.
+    fn follow(&self, entry: &mut Entry) {
+        if !self.follow_symlinks || !entry.metadata.is_symlink() {
+            return;
+        }
+
+        match resolve_symlink(&entry.path) {
+            Ok((symlink_chain, metadata)) => {
+                entry.symlink_chain = symlink_chain;
+                entry.metadata = metadata;
+            }
+            Err(error) => {
+                log::warn! {
+                    "failed to resolve symlink '{}': {error}",
+                    entry.path.display()
+                };
+            }
+        }
+    }
+
+    /// Checks whether the given entry (found at `depth`) should be descended.
+    fn is_descendable(&self, entry: &Entry, depth: u32) -> bool {
+        entry.metadata.is_dir() &&
+            self.is_allowed_dev(entry) &&
+            depth < self.max_depth &&
+            self.descend_filter.as_ref().is_none_or(|filter| filter(&entry.path))
+    }
 }
 
 impl std::iter::Iterator for WalkDir {
@@ -141,12 +504,27 @@ impl std::iter::Iterator for WalkDir {
     fn next(&mut self) -> Option<std::io::Result<Entry>> {
         loop {
             if let Some(entry) = self.iter.next() {
-                let entry = match entry {
+                let mut entry = match entry {
                     Ok(entry) => entry,
                     Err(error) => return Some(Err(error)),
                 };
 
-                if entry.metadata.is_dir() && self.is_same_dev(&entry) && self.iter.cur_depth < self.max_depth {
+                if self.opts.is_excluded(&entry) {
+                    continue;
+                }
+
+                self.opts.follow(&mut entry);
+                if self.opts.is_target_excluded(&entry) {
+                    continue;
+                }
+
+                let is_descendable = self.opts.is_descendable(&entry, self.iter.cur_depth) &&
+                    match &mut self.visited {
+                        Some(visited) => visited.insert(&entry.path, &entry.metadata),
+                        None => true,
+                    };
+
+                if is_descendable {
                     self.pending_iters.push({
                         std::fs::read_dir(&entry.path).map(|iter| ListDir {
                             iter,
@@ -177,6 +555,437 @@ impl std::iter::Iterator for WalkDir {
     }
 }
 
+/// Order in which the parallel iterator yields entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// Entries are yielded in the same order as the sequential iterator would.
+    ///
+    /// Only directories that are going to be yielded next are listed ahead, so
+    /// the parallelism is limited by how many of them there are.
+    Deterministic,
+    /// Entries are yielded in the order in which they are listed.
+    Unordered,
+}
+
+/// Maximum number of entries buffered by a worker before it blocks.
+const PAR_CHANNEL_CAPACITY: usize = 1024;
+
+/// Iterator over entries in all subdirectories listed by a pool of threads.
+///
+/// It yields the same entries as [`WalkDir`] (in the same order, if requested)
+/// but directories are listed by a fixed number of worker threads. Workers do
+/// not run ahead of the iterator arbitrarily: the number of directories being
+/// listed (or listed but not yet yielded) never exceeds the number of threads
+/// and each of them has a bounded buffer of entries.
+///
+/// Worker threads finish once the iterator is dropped and they are done with
+/// their current directories.
+///
+/// The iterator can be constructed with the [`WalkDir::into_par`] method.
+pub struct ParWalkDir {
+    task_sender: std::sync::mpsc::Sender<Task>,
+    threads: usize,
+    /// Number of jobs submitted to the pool that have not finished yet.
+    in_flight: usize,
+    /// Directories descended into so far (only when following symlinks).
+    ///
+    /// Workers do not access it: directories are marked as visited when their
+    /// entries are yielded, so that the deterministic order yields the same
+    /// entries as the sequential iterator.
+    visited: Option<Visited>,
+    state: ParState,
+}
+
+/// State of the parallel iterator specific to the order of yielded entries.
+enum ParState {
+    Deterministic {
+        /// Jobs to yield entries of (the last one is yielded first).
+        pending: Vec<Pending>,
+        /// Receiver of entries of the job being yielded at the moment.
+        current: Option<std::sync::mpsc::Receiver<Message>>,
+    },
+    Unordered {
+        /// Jobs yet to be submitted (the last one is submitted first).
+        pending: Vec<Job>,
+        /// Sender shared by all the submitted jobs.
+        sender: std::sync::mpsc::SyncSender<Message>,
+        receiver: std::sync::mpsc::Receiver<Message>,
+    },
+}
+
+/// Job of the deterministic iterator which might have been submitted already.
+enum Pending {
+    Waiting(Job),
+    Submitted(std::sync::mpsc::Receiver<Message>),
+}
+
+/// Directory to be listed by a worker thread.
+enum Job {
+    /// Directory that has been opened already (or failed to be opened).
+    Opened(std::io::Result<ListDir>),
+    /// Directory at the given depth that is yet to be opened.
+    Path(PathBuf, u32),
+}
+
+/// Job submitted to the pool with the channel to send the results to.
+struct Task {
+    job: Job,
+    sender: std::sync::mpsc::SyncSender<Message>,
+}
+
+/// Message sent by the worker thread listing a directory.
+enum Message {
+    /// Entry of the directory and the job to list it (if it is to be descended).
+    ///
+    /// The entry is boxed as metadata objects are quite big and they would make
+    /// buffers of all the channels unnecessarily big.
+    Entry(Box<std::io::Result<Entry>>, Option<Job>),
+    /// The directory has been listed entirely.
+    Done,
+}
+
+impl Task {
+
+    /// Lists the directory of the job and sends the results.
+    ///
+    /// An error is returned if the results cannot be sent (which happens only
+    /// if the iterator has been dropped).
+    fn run(self, opts: &WalkOpts) -> Result<(), std::sync::mpsc::SendError<Message>> {
+        let iter = match self.job {
+            Job::Opened(iter) => iter,
+            Job::Path(path, depth) => std::fs::read_dir(path).map(|iter| ListDir {
+                iter,
+                cur_depth: depth,
+            }),
+        };
+
+        match iter {
+            Ok(iter) => {
+                let depth = iter.cur_depth;
+
+                for entry in iter {
+                    let mut entry = match entry {
+                        Ok(entry) => entry,
+                        Err(error) => {
+                            self.sender.send(Message::Entry(Box::new(Err(error)), None))?;
+                            continue;
+                        }
+                    };
+
+                    if opts.is_excluded(&entry) {
+                        continue;
+                    }
+
+                    opts.follow(&mut entry);
+                    if opts.is_target_excluded(&entry) {
+                        continue;
+                    }
+
+                    let job = if opts.is_descendable(&entry, depth) {
+                        // This cannot overflow for the same reasons as in the
+                        // sequential iterator.
+                        Some(Job::Path(entry.path.clone(), depth + 1))
+                    } else {
+                        None
+                    };
+
+                    self.sender.send(Message::Entry(Box::new(Ok(entry)), job))?;
+                }
+            }
+            Err(error) => {
+                self.sender.send(Message::Entry(Box::new(Err(error)), None))?;
+            }
+        }
+
+        self.sender.send(Message::Done)
+    }
+}
+
+impl ParWalkDir {
+
+    /// Submits the given job to the pool.
+    ///
+    /// The results will be sent to the given channel.
+    fn submit(&mut self, job: Job, sender: std::sync::mpsc::SyncSender<Message>) {
+        // Workers are alive as long as the task sender, so this cannot fail.
+        self.task_sender.send(Task { job, sender })
+            .expect("worker threads finished prematurely");
+        self.in_flight += 1;
+    }
+
+    /// Submits pending jobs as long as there are idle worker threads.
+    fn fill(&mut self) {
+        let threads = self.threads;
+
+        match &mut self.state {
+            ParState::Deterministic { pending, .. } => {
+                let mut jobs = vec![];
+
+                // We submit jobs that will be yielded first, so we go from the
+                // end. There are never more submitted jobs than threads, so we
+                // never look at more than twice as many jobs.
+                for pending in pending.iter_mut().rev() {
+                    if self.in_flight + jobs.len() >= threads {
+                        break;
+                    }
+
+                    if let Pending::Waiting(_) = pending {
+                        use std::sync::mpsc::sync_channel;
+
+                        let (sender, receiver) = sync_channel(PAR_CHANNEL_CAPACITY);
+                        let submitted = Pending::Submitted(receiver);
+
+                        if let Pending::Waiting(job) = std::mem::replace(pending, submitted) {
+                            jobs.push((job, sender));
+                        }
+                    }
+                }
+
+                for (job, sender) in jobs {
+                    self.submit(job, sender);
+                }
+            }
+            ParState::Unordered { pending, sender, .. } => {
+                let mut jobs = vec![];
+                while self.in_flight + jobs.len() < threads {
+                    match pending.pop() {
+                        Some(job) => jobs.push((job, sender.clone())),
+                        None => break,
+                    }
+                }
+
+                for (job, sender) in jobs {
+                    self.submit(job, sender);
+                }
+            }
+        }
+    }
+}
+
+impl std::iter::Iterator for ParWalkDir {
+
+    type Item = std::io::Result<Entry>;
+
+    fn next(&mut self) -> Option<std::io::Result<Entry>> {
+        loop {
+            self.fill();
+
+            let message = match &mut self.state {
+                ParState::Deterministic { pending, current } => {
+                    let receiver = match current {
+                        Some(receiver) => receiver,
+                        None => match pending.pop() {
+                            Some(Pending::Submitted(receiver)) => current.insert(receiver),
+                            // When there is no current job, at least one thread
+                            // is idle and `fill` always submits the last job
+                            // first, so it cannot be waiting.
+                            Some(Pending::Waiting(_)) => unreachable!(),
+                            None => return None,
+                        },
+                    };
+
+                    let message = receiver.recv()
+                        .expect("worker thread finished prematurely");
+                    if let Message::Done = message {
+                        *current = None;
+                    }
+
+                    message
+                }
+                ParState::Unordered { receiver, .. } => {
+                    // `fill` submits pending jobs first, so if there is nothing
+                    // in flight, there is nothing left to list.
+                    if self.in_flight == 0 {
+                        return None;
+                    }
+
+                    receiver.recv()
+                        .expect("worker thread finished prematurely")
+                }
+            };
+
+            match message {
+                Message::Entry(entry, job) => {
+                    let job = match (job, &*entry, &mut self.visited) {
+                        (Some(job), Ok(entry), Some(visited)) => {
+                            visited.insert(&entry.path, &entry.metadata).then_some(job)
+                        }
+                        (job, _, _) => job,
+                    };
+
+                    if let Some(job) = job {
+                        match &mut self.state {
+                            ParState::Deterministic { pending, .. } => {
+                                pending.push(Pending::Waiting(job));
+                            }
+                            ParState::Unordered { pending, .. } => {
+                                pending.push(job);
+                            }
+                        }
+                    }
+
+                    return Some(*entry);
+                }
+                Message::Done => {
+                    self.in_flight -= 1;
+                }
+            }
+        }
+    }
+}
+
+/// Set of directories descended into, used to break symlink cycles.
+#[derive(Default)]
+struct Visited {
+    #[cfg(target_family = "unix")]
+    ids: std::collections::HashSet<(u64, u64)>,
+    #[cfg(target_family = "windows")]
+    ids: std::collections::HashSet<PathBuf>,
+}
+
+impl Visited {
+
+    /// Marks the given directory as visited.
+    ///
+    /// Returns `false` if the directory has already been visited before.
+    #[cfg(target_family = "unix")]
+    fn insert(&mut self, _path: &Path, metadata: &Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt as _;
+
+        self.ids.insert((metadata.dev(), metadata.ino()))
+    }
+
+    /// Marks the given directory as visited.
+    ///
+    /// Returns `false` if the directory has already been visited before.
+    #[cfg(target_family = "windows")]
+    fn insert(&mut self, path: &Path, _metadata: &Metadata) -> bool {
+        // Standard library does not expose file indices on Windows, so we use
+        // canonical paths as identifiers instead.
+        match path.canonicalize() {
+            Ok(path) => self.ids.insert(path),
+            Err(error) => {
+                log::warn!("failed to canonicalize '{}': {error}", path.display());
+                true
+            }
+        }
+    }
+}
+
+/// Resolves the symlink at the given path.
+///
+/// Returns the chain of paths the symlink resolves through (the last one being
+/// the final target) and metadata of the final target.
+fn resolve_symlink(path: &Path) -> std::io::Result<(Vec<PathBuf>, Metadata)> {
+    let mut chain = Vec::new();
+    let mut path = path.to_path_buf();
+
+    while chain.len() < MAX_SYMLINK_CHAIN_LEN {
+        let target = std::fs::read_link(&path)?;
+
+        // Relative targets are relative to the directory with the symlink. In
+        // case of absolute targets, `join` simply returns the target.
+        path = match path.parent() {
+            Some(parent) => parent.join(target),
+            None => target,
+        };
+        chain.push(path.clone());
+
+        let metadata = path.symlink_metadata()?;
+        if !metadata.is_symlink() {
+            return Ok((chain, metadata));
+        }
+    }
+
+    Err(std::io::Error::other("too many levels of symbolic links"))
+}
+
+/// Returns identifiers of devices with filesystems of the specified types.
+#[cfg(target_family = "unix")]
+fn cross_devs<S>(fs_types: &[S]) -> std::collections::HashSet<u64>
+where
+    S: AsRef<str>,
+{
+    use std::os::unix::fs::MetadataExt as _;
+
+    let mut devs = std::collections::HashSet::new();
+    if fs_types.is_empty() {
+        return devs;
+    }
+
+    let mounts = match ospect::fs::mounts() {
+        Ok(mounts) => mounts,
+        Err(error) => {
+            log::error!("failed to list mounts: {error}");
+            return devs;
+        }
+    };
+
+    // Filesystems can be mounted on top of each other, in which case only the
+    // last one is visible. Mounts are listed in the order they were made, so
+    // we simply overwrite the earlier entries.
+    let mut mount_fs_types = std::collections::HashMap::new();
+    for mount in mounts {
+        match mount {
+            Ok(mount) => {
+                mount_fs_types.insert(mount.path, mount.fs_type);
+            }
+            Err(error) => {
+                log::error!("failed to read mount entry: {error}");
+            }
+        }
+    }
+
+    for (path, mount_fs_type) in mount_fs_types {
+        if !fs_types.iter().any(|fs_type| fs_type.as_ref() == mount_fs_type) {
+            continue;
+        }
+
+        match std::fs::metadata(&path) {
+            Ok(metadata) => {
+                devs.insert(metadata.dev());
+            }
+            Err(error) => {
+                log::warn! {
+                    "failed to collect metadata of mount point '{}': {error}",
+                    path.display()
+                };
+            }
+        }
+    }
+
+    devs
+}
+
+/// Returns a shallow iterator over entries within a directory.
+///
+/// Unlike [`walk_dir`], the iterator yields only immediate children of the
+/// folder and does not descend into subdirectories. Entries are yielded in no
+/// particular order.
+///
+/// # Errors
+///
+/// This function will return an error if the folder cannot be opened. Errors
+/// for individual entries are yielded by the iterator.
+///
+/// # Examples
+///
+/// ```no_run
+/// let paths = rrg::fs::list_dir("/").unwrap()
+///     .filter_map(Result::ok)
+///     .map(|entry| entry.path)
+///     .collect::<Vec<_>>();
+///
+/// assert!(paths.contains(&"/usr".into()));
+/// assert!(!paths.contains(&"/usr/bin".into()));
+/// ```
+pub fn list_dir<P: AsRef<Path>>(path: P) -> std::io::Result<ListDir> {
+    Ok(ListDir {
+        iter: std::fs::read_dir(path)?,
+        cur_depth: 1,
+    })
+}
+
 /// Iterator over the entries in a directory.
 ///
 /// Unlike the [`ReadDir`] iterator entries, [`ListDir`] entries are guaranteed
@@ -205,6 +1014,7 @@ impl std::iter::Iterator for ListDir {
         Some(Ok(Entry {
             path: entry.path(),
             metadata,
+            symlink_chain: Vec::new(),
         }))
     }
 }
@@ -512,4 +1322,463 @@ mod tests {
         assert!(!paths.contains(&tempdir.join("a").join("b").join("c")));
         assert!(!paths.contains(&tempdir.join("a").join("b").join("c").join("d")));
     }
+
+    #[test]
+    fn walk_dir_with_excluded_paths() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path();
+
+        std::fs::create_dir(tempdir.join("abc"))
+            .unwrap();
+        std::fs::create_dir(tempdir.join("abc").join("def"))
+            .unwrap();
+        std::fs::create_dir(tempdir.join("ghi"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("ghi").join("cache.tmp"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("ghi").join("jkl"))
+            .unwrap();
+
+        let excluded_paths = vec![
+            regex::Regex::new("abc$").unwrap(),
+            regex::Regex::new(r"\.tmp$").unwrap(),
+        ];
+
+        let mut paths = walk_dir(tempdir).unwrap()
+            .with_excluded_paths(excluded_paths)
+            .filter_map(Result::ok)
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        // Excluded directories should not be descended into either.
+        assert_eq!(paths, vec![
+            tempdir.join("ghi"),
+            tempdir.join("ghi").join("jkl"),
+        ]);
+    }
+
+    #[test]
+    fn walk_dir_with_descend_filter() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let tempdir = tempdir.path();
+
+        std::fs::create_dir(tempdir.join("abc"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("abc").join("def"))
+            .unwrap();
+        std::fs::create_dir(tempdir.join("ghi"))
+            .unwrap();
+        std::fs::File::create(tempdir.join("ghi").join("jkl"))
+            .unwrap();
+
+        let mut paths = walk_dir(tempdir).unwrap()
+            .with_descend_filter(|path| path.ends_with("ghi"))
+            .filter_map(Result::ok)
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        // Directories not satisfying the filter should be yielded nonetheless.
+        assert_eq!(paths, vec![
+            tempdir.join("abc"),
+            tempdir.join("ghi"),
+            tempdir.join("ghi").join("jkl"),
+        ]);
+    }
+
+    #[test]
+    fn walk_dir_with_cross_dev_fs_types_none() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(tempdir.path().join("abc")).unwrap();
+
+        let no_fs_types: &[&str] = &[];
+
+        let results = walk_dir(&tempdir).unwrap()
+            .with_cross_dev_fs_types(no_fs_types)
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cross_devs_root() {
+        use std::os::unix::fs::MetadataExt as _;
+
+        let root_fs_type = ospect::fs::mounts().unwrap()
+            .filter_map(Result::ok)
+            .filter(|mount| mount.path == Path::new("/"))
+            .last().unwrap()
+            .fs_type;
+
+        let root_dev = std::fs::metadata("/").unwrap().dev();
+
+        assert!(cross_devs(&[root_fs_type]).contains(&root_dev));
+        assert!(!cross_devs(&["nonexistentfs"]).contains(&root_dev));
+    }
+
+    /// Creates a directory hierarchy with multiple levels of nesting.
+    fn create_tree(root: &Path) {
+        for i in 0..8 {
+            let dir = root.join(format!("dir{i}"));
+            std::fs::create_dir(&dir).unwrap();
+
+            for j in 0..4 {
+                let subdir = dir.join(format!("subdir{j}"));
+                std::fs::create_dir(&subdir).unwrap();
+
+                File::create(dir.join(format!("file{j}"))).unwrap();
+                File::create(subdir.join("file")).unwrap();
+            }
+        }
+    }
+
+    fn threads(threads: usize) -> std::num::NonZeroUsize {
+        std::num::NonZeroUsize::new(threads).unwrap()
+    }
+
+    #[test]
+    fn par_walk_dir_empty() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let iter = walk_dir(&tempdir).unwrap()
+            .into_par(threads(4), Order::Unordered).unwrap();
+        assert_eq!(iter.count(), 0);
+    }
+
+    #[test]
+    fn par_walk_dir_deterministic() {
+        let tempdir = tempfile::tempdir().unwrap();
+        create_tree(tempdir.path());
+
+        let paths = walk_dir(&tempdir).unwrap()
+            .map(|entry| entry.unwrap().path)
+            .collect::<Vec<_>>();
+        assert_eq!(paths.len(), 8 + 8 * 4 * 3);
+
+        for threads_count in [1, 2, 16] {
+            let par_paths = walk_dir(&tempdir).unwrap()
+                .into_par(threads(threads_count), Order::Deterministic).unwrap()
+                .map(|entry| entry.unwrap().path)
+                .collect::<Vec<_>>();
+
+            assert_eq!(par_paths, paths);
+        }
+    }
+
+    #[test]
+    fn par_walk_dir_unordered() {
+        let tempdir = tempfile::tempdir().unwrap();
+        create_tree(tempdir.path());
+
+        let mut paths = walk_dir(&tempdir).unwrap()
+            .map(|entry| entry.unwrap().path)
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for threads_count in [1, 2, 16] {
+            let mut par_paths = walk_dir(&tempdir).unwrap()
+                .into_par(threads(threads_count), Order::Unordered).unwrap()
+                .map(|entry| entry.unwrap().path)
+                .collect::<Vec<_>>();
+            par_paths.sort();
+
+            assert_eq!(par_paths, paths);
+        }
+    }
+
+    #[test]
+    fn par_walk_dir_metadata() {
+        let tempdir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(tempdir.path().join("foo")).unwrap();
+        std::fs::write(tempdir.path().join("foo").join("bar"), b"123456789").unwrap();
+
+        let mut results = walk_dir(&tempdir).unwrap()
+            .into_par(threads(2), Order::Unordered).unwrap()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        results.sort_by_key(|entry| entry.path.clone());
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].metadata.is_dir());
+        assert!(results[1].metadata.is_file());
+        assert_eq!(results[1].metadata.len(), 9);
+    }
+
+    #[test]
+    fn par_walk_dir_with_max_depth() {
+        let tempdir = tempfile::tempdir().unwrap();
+        create_tree(tempdir.path());
+
+        for order in [Order::Deterministic, Order::Unordered] {
+            let paths = walk_dir(&tempdir).unwrap()
+                .with_max_depth(1)
+                .into_par(threads(4), order).unwrap()
+                .map(|entry| entry.unwrap().path)
+                .collect::<Vec<_>>();
+
+            assert_eq!(paths.len(), 8);
+            for path in paths {
+                assert_eq!(path.parent(), Some(tempdir.path()));
+            }
+        }
+    }
+
+    #[test]
+    fn par_walk_dir_with_excluded_paths() {
+        let tempdir = tempfile::tempdir().unwrap();
+        create_tree(tempdir.path());
+
+        // This excludes both top-level directories and subdirectories.
+        let excluded_paths = vec![
+            regex::Regex::new("dir[1-7]$").unwrap(),
+        ];
+
+        let mut paths = walk_dir(&tempdir).unwrap()
+            .with_excluded_paths(excluded_paths)
+            .into_par(threads(4), Order::Unordered).unwrap()
+            .map(|entry| entry.unwrap().path)
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let dir = tempdir.path().join("dir0");
+        assert_eq!(paths, vec![
+            dir.clone(),
+            dir.join("file0"),
+            dir.join("file1"),
+            dir.join("file2"),
+            dir.join("file3"),
+            dir.join("subdir0"),
+            dir.join("subdir0").join("file"),
+        ]);
+    }
+
+    #[test]
+    fn par_walk_dir_with_descend_filter() {
+        let tempdir = tempfile::tempdir().unwrap();
+        create_tree(tempdir.path());
+
+        let mut paths = walk_dir(&tempdir).unwrap()
+            .with_descend_filter(|path| !path.ends_with("subdir0"))
+            .into_par(threads(4), Order::Unordered).unwrap()
+            .map(|entry| entry.unwrap().path)
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for i in 0..8 {
+            let dir = tempdir.path().join(format!("dir{i}"));
+            assert!(paths.contains(&dir.join("subdir0")));
+            assert!(!paths.contains(&dir.join("subdir0").join("file")));
+            assert!(paths.contains(&dir.join("subdir1").join("file")));
+        }
+    }
+
+    #[test]
+    fn par_walk_dir_drop_early() {
+        let tempdir = tempfile::tempdir().unwrap();
+        create_tree(tempdir.path());
+
+        for order in [Order::Deterministic, Order::Unordered] {
+            let mut iter = walk_dir(&tempdir).unwrap()
+                .into_par(threads(4), order).unwrap();
+
+            // Dropping the iterator while workers are still listing should not
+            // cause any panics or hangs.
+            assert!(iter.next().is_some());
+            drop(iter);
+        }
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn walk_dir_with_follow_symlinks() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+
+        File::create(outside.path().join("foo")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), tempdir.path().join("bar"))
+            .unwrap();
+
+        let mut results = walk_dir(&tempdir).unwrap()
+            .with_follow_symlinks()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        results.sort_by_key(|entry| entry.path.clone());
+
+        assert_eq!(results.len(), 2);
+
+        assert_eq!(results[0].path, tempdir.path().join("bar"));
+        assert!(results[0].metadata.is_dir());
+        assert_eq!(results[0].symlink_chain, vec![outside.path().to_path_buf()]);
+
+        assert_eq!(results[1].path, tempdir.path().join("bar").join("foo"));
+        assert!(results[1].metadata.is_file());
+        assert!(results[1].symlink_chain.is_empty());
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn walk_dir_with_follow_symlinks_excluded() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+
+        File::create(outside.path().join("foo")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), tempdir.path().join("bar"))
+            .unwrap();
+        std::os::unix::fs::symlink("bar/foo", tempdir.path().join("baz"))
+            .unwrap();
+
+        let outside_path = outside.path().canonicalize().unwrap();
+        let excluded_paths = vec![
+            regex::Regex::new(&regex::escape(&outside_path.to_string_lossy())).unwrap(),
+        ];
+
+        let results = walk_dir(&tempdir).unwrap()
+            .with_excluded_paths(excluded_paths.clone())
+            .with_follow_symlinks()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        assert!(results.is_empty());
+
+        let results = walk_dir(&tempdir).unwrap()
+            .with_excluded_paths(excluded_paths)
+            .with_follow_symlinks()
+            .into_par(threads(4), Order::Unordered).unwrap()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+        assert!(results.is_empty());
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn walk_dir_with_follow_symlinks_chain() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        std::fs::write(tempdir.path().join("foo"), b"123456789").unwrap();
+        std::os::unix::fs::symlink("foo", tempdir.path().join("bar")).unwrap();
+        std::os::unix::fs::symlink("bar", tempdir.path().join("baz")).unwrap();
+
+        let entry = walk_dir(&tempdir).unwrap()
+            .with_follow_symlinks()
+            .filter_map(Result::ok)
+            .find(|entry| entry.path == tempdir.path().join("baz"))
+            .unwrap();
+
+        assert!(entry.metadata.is_file());
+        assert_eq!(entry.metadata.len(), 9);
+        assert_eq!(entry.symlink_chain, vec![
+            tempdir.path().join("bar"),
+            tempdir.path().join("foo"),
+        ]);
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn walk_dir_with_follow_symlinks_dangling() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        std::os::unix::fs::symlink("foo", tempdir.path().join("foo")).unwrap();
+        std::os::unix::fs::symlink("quux", tempdir.path().join("bar")).unwrap();
+
+        let results = walk_dir(&tempdir).unwrap()
+            .with_follow_symlinks()
+            .filter_map(Result::ok)
+            .collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 2);
+        for entry in results {
+            assert!(entry.metadata.is_symlink());
+            assert!(entry.symlink_chain.is_empty());
+        }
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn walk_dir_with_follow_symlinks_cycles() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let dir = tempdir.path().join("foo");
+
+        std::fs::create_dir(&dir).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("bar")).unwrap();
+        std::os::unix::fs::symlink(tempdir.path(), dir.join("baz")).unwrap();
+
+        let mut paths = walk_dir(&tempdir).unwrap()
+            .with_follow_symlinks()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        // Both symlinks point to directories that are visited already.
+        assert_eq!(paths, vec![
+            dir.clone(),
+            dir.join("bar"),
+            dir.join("baz"),
+        ]);
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn walk_dir_with_follow_symlinks_visited_once() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+
+        File::create(outside.path().join("foo")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), tempdir.path().join("bar"))
+            .unwrap();
+        std::os::unix::fs::symlink(outside.path(), tempdir.path().join("baz"))
+            .unwrap();
+
+        let paths = walk_dir(&tempdir).unwrap()
+            .with_follow_symlinks()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+
+        // The directory is reachable through two symlinks but it should be
+        // descended into only once.
+        assert_eq!(paths.len(), 3);
+        assert_eq! {
+            paths.iter().filter(|path| path.ends_with("foo")).count(),
+            1
+        };
+    }
+
+    // Symlinking is supported only on Unix-like systems.
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn par_walk_dir_with_follow_symlinks() {
+        let tempdir = tempfile::tempdir().unwrap();
+        create_tree(tempdir.path());
+
+        for i in 0..8 {
+            let dir = tempdir.path().join(format!("dir{i}"));
+            std::os::unix::fs::symlink(tempdir.path(), dir.join("root")).unwrap();
+            std::os::unix::fs::symlink(&dir, dir.join("subdir0").join("parent"))
+                .unwrap();
+        }
+
+        let paths = walk_dir(&tempdir).unwrap()
+            .with_follow_symlinks()
+            .map(|entry| entry.unwrap().path)
+            .collect::<Vec<_>>();
+
+        for threads_count in [1, 2, 16] {
+            let par_paths = walk_dir(&tempdir).unwrap()
+                .with_follow_symlinks()
+                .into_par(threads(threads_count), Order::Deterministic).unwrap()
+                .map(|entry| entry.unwrap().path)
+                .collect::<Vec<_>>();
+
+            assert_eq!(par_paths, paths);
+        }
+    }
 }
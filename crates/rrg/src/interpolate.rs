@@ -0,0 +1,669 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Interpolation of knowledge-base variables in paths.
+//!
+//! Paths can refer to facts about the system using variables enclosed in double
+//! percent signs, e.g. `%%users.homedir%%/.bash_history`. Variables are replaced
+//! with values gathered locally on the agent, so the server does not need to
+//! know anything about the system upfront.
+//!
+//! Variables with the `users.` prefix refer to attributes of individual user
+//! accounts. A path with such variables is interpolated once for every user
+//! (that has all the referenced attributes), so a single path can fan out into
+//! many. On systems where users cannot be listed, such paths are rejected.
+//!
+//! Path patterns of the `find_files` action are always interpolated. Other file
+//! actions take literal paths (which can contain percent signs as any other
+//! bytes), so they interpolate them only if explicitly requested.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Knowledge-base variable that can be used in paths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Var {
+    /// Home directory of a user (`%%users.homedir%%`).
+    UsersHomedir,
+    /// Name (login) of a user (`%%users.username%%`).
+    UsersUsername,
+    /// System-wide folder for temporary files (`%%environ_temp%%`).
+    EnvironTemp,
+    /// Hostname of the system (`%%os.hostname%%`).
+    OsHostname,
+}
+
+impl Var {
+
+    /// Returns the name of the variable (as used within the percent signs).
+    pub fn name(self) -> &'static str {
+        match self {
+            Var::UsersHomedir => "users.homedir",
+            Var::UsersUsername => "users.username",
+            Var::EnvironTemp => "environ_temp",
+            Var::OsHostname => "os.hostname",
+        }
+    }
+
+    /// Returns the variable with the given name (if there is one).
+    fn from_name(name: &str) -> Option<Var> {
+        match name {
+            "users.homedir" => Some(Var::UsersHomedir),
+            "users.username" => Some(Var::UsersUsername),
+            "environ_temp" => Some(Var::EnvironTemp),
+            "os.hostname" => Some(Var::OsHostname),
+            _ => None,
+        }
+    }
+
+    /// Determines whether the variable refers to an attribute of a user.
+    fn is_user(self) -> bool {
+        matches!(self, Var::UsersHomedir | Var::UsersUsername)
+    }
+}
+
+/// Facts about the system that variables are replaced with.
+#[derive(Debug, Default)]
+pub struct Facts {
+    /// Hostname of the system (if known).
+    pub hostname: Option<String>,
+    /// System-wide folder for temporary files (if known).
+    pub temp_dir: Option<String>,
+    /// User accounts defined on the system.
+    ///
+    /// `None` means that users could not be listed (e.g. because listing them
+    /// is not supported on the platform).
+    pub users: Option<Vec<UserFacts>>,
+}
+
+/// Facts about an individual user account.
+#[derive(Debug, Default)]
+pub struct UserFacts {
+    /// Name (login) of the user.
+    pub username: String,
+    /// Home directory of the user (if known).
+    pub homedir: Option<String>,
+}
+
+impl Facts {
+
+    /// Gathers facts about the currently running system.
+    ///
+    /// Facts that could not be gathered (or are not valid UTF-8) are logged and
+    /// left out, so variables referring to them do not yield any paths.
+    /// Failure to list users makes variables referring to them fail instead.
+    pub fn collect() -> Facts {
+        let hostname = match ospect::os::hostname() {
+            Ok(hostname) => hostname.into_string().ok(),
+            Err(error) => {
+                log::error!("failed to collect system hostname: {error}");
+                None
+            }
+        };
+
+        // The folder for temporary files of the agent process can differ from
+        // the one used by the rest of the system (e.g. when it is running as a
+        // service), so we do not take it from our own environment.
+        let temp_dir = match ospect::os::temp_dir() {
+            Ok(temp_dir) => temp_dir.into_os_string().into_string().ok(),
+            Err(error) => {
+                log::error!("failed to collect system temporary folder: {error}");
+                None
+            }
+        };
+
+        let users = match ospect::os::users() {
+            Ok(users) => Some(users.filter_map(|user| match user {
+                Ok(user) => Some(user),
+                Err(error) => {
+                    log::warn!("failed to obtain user information: {error}");
+                    None
+                }
+            }).filter_map(|user| {
+                let username = user.name.into_string().ok()?;
+                let homedir = user.home_dir
+                    .and_then(|home_dir| home_dir.into_os_string().into_string().ok())
+                    .filter(|homedir| !homedir.is_empty());
+
+                Some(UserFacts {
+                    username,
+                    homedir,
+                })
+            }).collect()),
+            Err(error) => {
+                log::error!("failed to list users: {error}");
+                None
+            }
+        };
+
+        Facts {
+            hostname,
+            temp_dir,
+            users,
+        }
+    }
+}
+
+/// Path with (not yet interpolated) knowledge-base variables.
+#[derive(Debug)]
+pub struct Template {
+    /// Path as originally specified.
+    raw: String,
+    /// Parts of the path between and including the variables.
+    segments: Vec<Segment>,
+}
+
+/// Part of a path template.
+#[derive(Debug)]
+enum Segment {
+    /// Literal text to be used as is.
+    Literal(String),
+    /// Variable to be replaced with its value.
+    Var(Var),
+}
+
+/// Result of interpolating variables in a path template.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Interpolation {
+    /// Path as originally specified (before interpolation).
+    pub raw: String,
+    /// Path with the variables replaced with their (escaped) values.
+    pub path: String,
+    /// Values the variables were replaced with.
+    pub bindings: BTreeMap<Var, String>,
+}
+
+impl Template {
+
+    /// Returns variables used in the template.
+    fn vars(&self) -> impl Iterator<Item = Var> + '_ {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Var(var) => Some(*var),
+            Segment::Literal(_) => None,
+        })
+    }
+
+    /// Parses a template from the given literal path.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the path is not valid UTF-8 or it
+    /// is not a valid template.
+    pub fn from_path(path: &Path) -> Result<Template, ParseError> {
+        path.to_str()
+            .ok_or(ParseError::NonUtf8Path)?
+            .parse()
+    }
+
+    /// Returns the path as originally specified.
+    #[cfg(feature = "action-find_files")]
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// Determines whether the template uses any variables.
+    pub fn has_vars(&self) -> bool {
+        self.vars().next().is_some()
+    }
+
+    /// Determines whether the template uses variables referring to users.
+    ///
+    /// Only such templates can be interpolated into more than one path.
+    #[cfg(feature = "action-get_filesystem_timeline")]
+    pub fn has_user_vars(&self) -> bool {
+        self.vars().any(Var::is_user)
+    }
+
+    /// Replaces the variables with the given facts.
+    ///
+    /// If the template uses variables referring to user attributes, it yields
+    /// one interpolation per every user that has all of them. A template that
+    /// uses variables which values are not known yields no interpolations.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the template uses variables
+    /// referring to user attributes but users could not be listed.
+    ///
+    /// Values are put into the path through `escape`, so that characters with
+    /// special meaning (e.g. glob wildcards) are not interpreted. Bindings keep
+    /// the values as they are.
+    pub fn interpolate<F>(
+        &self,
+        facts: &Facts,
+        escape: F,
+    ) -> Result<Vec<Interpolation>, UsersUnavailableError>
+    where
+        F: Fn(&str) -> String,
+    {
+        let mut bindings = BTreeMap::new();
+        for var in self.vars().filter(|var| !var.is_user()) {
+            let value = match var {
+                Var::EnvironTemp => facts.temp_dir.as_ref(),
+                Var::OsHostname => facts.hostname.as_ref(),
+                Var::UsersHomedir | Var::UsersUsername => unreachable!(),
+            };
+
+            match value {
+                Some(value) => bindings.insert(var, value.clone()),
+                None => return Ok(Vec::new()),
+            };
+        }
+
+        if !self.vars().any(Var::is_user) {
+            return Ok(vec![self.interpolation(bindings, &escape)]);
+        }
+
+        // Reporting no results would be misleading here, as we do not know
+        // whether there are any users with matching files.
+        let users = facts.users.as_ref()
+            .ok_or(UsersUnavailableError)?;
+
+        let mut interpolations = Vec::new();
+        'users: for user in users {
+            let mut bindings = bindings.clone();
+            for var in self.vars().filter(|var| var.is_user()) {
+                let value = match var {
+                    Var::UsersHomedir => user.homedir.as_ref(),
+                    Var::UsersUsername => Some(&user.username),
+                    Var::EnvironTemp | Var::OsHostname => unreachable!(),
+                };
+
+                match value {
+                    Some(value) => bindings.insert(var, value.clone()),
+                    None => continue 'users,
+                };
+            }
+
+            interpolations.push(self.interpolation(bindings, &escape));
+        }
+
+        Ok(interpolations)
+    }
+
+    /// Creates an interpolation of the template with the given bindings.
+    ///
+    /// The bindings must contain values for all the variables of the template.
+    fn interpolation<F>(&self, bindings: BTreeMap<Var, String>, escape: F) -> Interpolation
+    where
+        F: Fn(&str) -> String,
+    {
+        let mut path = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => path.push_str(literal),
+                Segment::Var(var) => path.push_str(&escape(&bindings[var])),
+            }
+        }
+
+        Interpolation {
+            raw: self.raw.clone(),
+            path,
+            bindings,
+        }
+    }
+}
+
+impl std::str::FromStr for Template {
+
+    type Err = ParseError;
+
+    fn from_str(raw: &str) -> Result<Template, ParseError> {
+        let mut segments = Vec::new();
+
+        let mut rest = raw;
+        while let Some(start) = rest.find("%%") {
+            if start > 0 {
+                segments.push(Segment::Literal(String::from(&rest[..start])));
+            }
+
+            let name_rest = &rest[start + 2..];
+            let Some(end) = name_rest.find("%%") else {
+                return Err(ParseError::UnclosedVar);
+            };
+
+            let name = &name_rest[..end];
+            let var = Var::from_name(name)
+                .ok_or_else(|| ParseError::UnknownVar(String::from(name)))?;
+            segments.push(Segment::Var(var));
+
+            rest = &name_rest[end + 2..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(String::from(rest)));
+        }
+
+        Ok(Template {
+            raw: String::from(raw),
+            segments,
+        })
+    }
+}
+
+impl From<Interpolation> for rrg_proto::fs::Interpolation {
+
+    fn from(interpolation: Interpolation) -> rrg_proto::fs::Interpolation {
+        let mut proto = rrg_proto::fs::Interpolation::default();
+        proto.set_template(interpolation.raw);
+        for (var, value) in interpolation.bindings {
+            proto.mut_bindings().insert(String::from(var.name()), value);
+        }
+
+        proto
+    }
+}
+
+/// Calls `f` for every path that the given literal path interpolates into.
+///
+/// Without a template, `f` is called just once for the path itself. Otherwise,
+/// the template is interpolated with facts about the currently running system
+/// (gathered only if there are any variables) and `f` is called for every
+/// resulting path along with the interpolation that produced it.
+///
+/// Interpolated paths (e.g. a file in the home folder of every user) are not
+/// expected to exist, so action failures for individual ones are only logged.
+pub fn for_each_path<F>(
+    path: &Path,
+    template: Option<&Template>,
+    mut f: F,
+) -> crate::session::Result<()>
+where
+    F: FnMut(&Path, Option<&Interpolation>) -> crate::session::Result<()>,
+{
+    let template = match template {
+        Some(template) => template,
+        None => return f(path, None),
+    };
+
+    let facts = if template.has_vars() {
+        Facts::collect()
+    } else {
+        Facts::default()
+    };
+
+    // Paths are literal, so values of the variables are used as they are.
+    let interpolations = template.interpolate(&facts, str::to_owned)
+        .map_err(crate::session::Error::action)?;
+
+    for interpolation in &interpolations {
+        let path = Path::new(&interpolation.path);
+
+        match f(path, Some(interpolation)) {
+            Err(error) if error.kind() == crate::session::ErrorKind::ActionFailure => {
+                log::warn!("failed to handle '{}': {error}", path.display());
+            }
+            result => result?,
+        }
+    }
+
+    Ok(())
+}
+
+/// An error that can occur when parsing a path template.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The path to parse the template from is not valid UTF-8.
+    NonUtf8Path,
+    /// A variable is not followed by the closing percent signs.
+    UnclosedVar,
+    /// A variable with the given name is not known.
+    UnknownVar(String),
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::NonUtf8Path => write!(fmt, "path is not valid UTF-8"),
+            ParseError::UnclosedVar => write!(fmt, "unclosed variable"),
+            ParseError::UnknownVar(name) => write!(fmt, "unknown variable: {name:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+}
+
+/// An error indicating that a template refers to users that cannot be listed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UsersUnavailableError;
+
+impl std::fmt::Display for UsersUnavailableError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(fmt, "users cannot be listed on this system")
+    }
+}
+
+impl std::error::Error for UsersUnavailableError {
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Returns facts about a fake system with a few users.
+    fn facts() -> Facts {
+        Facts {
+            hostname: Some(String::from("foohost")),
+            temp_dir: Some(String::from("/tmp")),
+            users: Some(vec![
+                UserFacts {
+                    username: String::from("foo"),
+                    homedir: Some(String::from("/home/foo")),
+                },
+                UserFacts {
+                    username: String::from("bar"),
+                    homedir: Some(String::from("/home/bar")),
+                },
+                UserFacts {
+                    username: String::from("nobody"),
+                    homedir: None,
+                },
+            ]),
+        }
+    }
+
+    /// Interpolates the given template with the fake facts.
+    fn paths(template: &str) -> Vec<String> {
+        template.parse::<Template>().unwrap()
+            .interpolate(&facts(), str::to_owned).unwrap()
+            .into_iter()
+            .map(|interpolation| interpolation.path)
+            .collect()
+    }
+
+    #[test]
+    fn from_path() {
+        let template = Template::from_path(Path::new("%%users.homedir%%/foo"))
+            .unwrap();
+
+        assert_eq!(template.vars().collect::<Vec<_>>(), vec![Var::UsersHomedir]);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn from_path_non_utf8() {
+        use std::os::unix::ffi::OsStrExt as _;
+
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"/foo/\xff/bar"));
+
+        let error = Template::from_path(path)
+            .unwrap_err();
+        assert_eq!(error, ParseError::NonUtf8Path);
+    }
+
+    #[test]
+    fn parse_unclosed_var() {
+        let error = "%%users.homedir/foo".parse::<Template>()
+            .unwrap_err();
+
+        assert_eq!(error, ParseError::UnclosedVar);
+    }
+
+    #[test]
+    fn parse_unknown_var() {
+        let error = "/foo/%%users.sid%%".parse::<Template>()
+            .unwrap_err();
+
+        assert_eq!(error, ParseError::UnknownVar(String::from("users.sid")));
+    }
+
+    #[test]
+    fn interpolate_no_vars() {
+        let template = "/foo/%bar%/*".parse::<Template>()
+            .unwrap();
+
+        assert!(!template.has_vars());
+        assert_eq! {
+            template.interpolate(&facts(), str::to_owned).unwrap(),
+            vec![Interpolation {
+                raw: String::from("/foo/%bar%/*"),
+                path: String::from("/foo/%bar%/*"),
+                bindings: BTreeMap::new(),
+            }],
+        };
+    }
+
+    #[test]
+    fn interpolate_environ_temp() {
+        assert_eq!(paths("%%environ_temp%%/foo"), vec!["/tmp/foo"]);
+    }
+
+    #[test]
+    fn interpolate_os_hostname() {
+        assert_eq!(paths("/etc/%%os.hostname%%.conf"), vec!["/etc/foohost.conf"]);
+    }
+
+    #[test]
+    fn interpolate_users_homedir() {
+        assert_eq!(paths("%%users.homedir%%/.bashrc"), vec![
+            "/home/foo/.bashrc",
+            "/home/bar/.bashrc",
+        ]);
+    }
+
+    #[test]
+    fn interpolate_users_username() {
+        // Unlike home folders, every user has a name, so none of them should
+        // be skipped.
+        assert_eq!(paths("/var/mail/%%users.username%%"), vec![
+            "/var/mail/foo",
+            "/var/mail/bar",
+            "/var/mail/nobody",
+        ]);
+    }
+
+    #[test]
+    fn interpolate_many_vars() {
+        let template = "%%users.homedir%%/%%os.hostname%%/%%users.username%%"
+            .parse::<Template>()
+            .unwrap();
+
+        let interpolations = template.interpolate(&facts(), str::to_owned)
+            .unwrap();
+        assert_eq!(interpolations.len(), 2);
+
+        assert_eq!(interpolations[0].path, "/home/foo/foohost/foo");
+        assert_eq!(interpolations[0].bindings, BTreeMap::from([
+            (Var::UsersHomedir, String::from("/home/foo")),
+            (Var::UsersUsername, String::from("foo")),
+            (Var::OsHostname, String::from("foohost")),
+        ]));
+
+        assert_eq!(interpolations[1].path, "/home/bar/foohost/bar");
+        assert_eq!(interpolations[1].bindings, BTreeMap::from([
+            (Var::UsersHomedir, String::from("/home/bar")),
+            (Var::UsersUsername, String::from("bar")),
+            (Var::OsHostname, String::from("foohost")),
+        ]));
+    }
+
+    #[test]
+    fn interpolate_unknown_fact() {
+        let template = "/%%os.hostname%%/%%users.homedir%%"
+            .parse::<Template>()
+            .unwrap();
+
+        let facts = Facts {
+            hostname: None,
+            ..facts()
+        };
+
+        assert!(template.interpolate(&facts, str::to_owned).unwrap().is_empty());
+    }
+
+    #[test]
+    fn interpolate_no_users() {
+        let template = "%%users.homedir%%/foo"
+            .parse::<Template>()
+            .unwrap();
+
+        let facts = Facts {
+            users: Some(vec![]),
+            ..Facts::default()
+        };
+
+        assert!(template.interpolate(&facts, str::to_owned).unwrap().is_empty());
+    }
+
+    #[test]
+    fn interpolate_users_unavailable() {
+        let template = "%%users.homedir%%/foo"
+            .parse::<Template>()
+            .unwrap();
+
+        let facts = Facts {
+            users: None,
+            ..facts()
+        };
+
+        assert_eq! {
+            template.interpolate(&facts, str::to_owned),
+            Err(UsersUnavailableError),
+        };
+    }
+
+    #[test]
+    fn interpolate_users_unavailable_no_user_vars() {
+        let template = "%%os.hostname%%/foo"
+            .parse::<Template>()
+            .unwrap();
+
+        let facts = Facts {
+            users: None,
+            ..facts()
+        };
+
+        assert_eq!(template.interpolate(&facts, str::to_owned).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn interpolate_escape() {
+        let template = "%%users.homedir%%/*"
+            .parse::<Template>()
+            .unwrap();
+
+        let facts = Facts {
+            users: Some(vec![
+                UserFacts {
+                    username: String::from("foo"),
+                    homedir: Some(String::from("/home/f*o")),
+                },
+            ]),
+            ..Facts::default()
+        };
+
+        let interpolations = template.interpolate(&facts, |value| value.replace('*', "[*]"))
+            .unwrap();
+        assert_eq!(interpolations.len(), 1);
+
+        assert_eq!(interpolations[0].path, "/home/f[*]o/*");
+        assert_eq!(interpolations[0].bindings, BTreeMap::from([
+            (Var::UsersHomedir, String::from("/home/f*o")),
+        ]));
+    }
+}
@@ -278,6 +278,255 @@ where
     }
 }
 
+/// Maximum length of regex matches that [`RegexSearch`] reports accurately.
+///
+/// Matches longer than this might be truncated or missed.
+pub const MAX_REGEX_MATCH_LEN: usize = 1024 * 1024;
+
+/// Streaming search for matches of a byte regex in a reader.
+///
+/// Unlike the [`LineReader`], this object does not split the input into lines,
+/// so it can search arbitrary (e.g. binary) content without any limits on the
+/// line length. The input is read using a sliding buffer: at each step only
+/// matches starting within the next [`MAX_REGEX_MATCH_LEN`] bytes are looked
+/// for, but the buffer is filled with enough data to also contain their whole
+/// content and the requested context. Thus, matches that fit in the limit are
+/// reported exactly as if the whole input was searched at once.
+///
+/// The search can also look for many regexes at once (see [`new_many`]), in
+/// which case the input is read only once and matches of every regex are
+/// reported exactly as if the regex was searched for alone.
+///
+/// [`new_many`]: RegexSearch::new_many
+///
+/// # Examples
+///
+/// ```
+/// use rrg::io::RegexSearchStep;
+///
+/// let regex = regex::bytes::Regex::new("ba[rz]").unwrap();
+/// let mut search = rrg::io::RegexSearch::new(&b"foo\xffbar baz"[..], &regex);
+///
+/// let mut offsets = vec!();
+/// loop {
+///     match search.step().unwrap() {
+///         RegexSearchStep::Match(matcz) => offsets.push(matcz.offset),
+///         RegexSearchStep::Progress => continue,
+///         RegexSearchStep::Done => break,
+///     }
+/// }
+///
+/// assert_eq!(offsets, vec![4, 8]);
+/// ```
+pub struct RegexSearch<'r, R: Read> {
+    /// Content source to search.
+    inner: R,
+    /// Regexes to search for.
+    regexes: Vec<&'r regex::bytes::Regex>,
+    /// Buffer with the part of the input that is currently searched.
+    buf: Vec<u8>,
+    /// Offset within the input at which the buffer starts.
+    buf_offset: u64,
+    /// Position within the buffer at which the currently searched part starts.
+    window_start: usize,
+    /// Positions within the buffer from which to search for the next match of
+    /// every regex.
+    positions: Vec<usize>,
+    /// Index of the regex that is currently searched for.
+    regex_index: usize,
+    /// Whether the whole input has already been read into the buffer.
+    eof: bool,
+    /// Number of bytes preceding each match to include in it.
+    context_before_len: usize,
+    /// Number of bytes following each match to include in it.
+    context_after_len: usize,
+}
+
+/// Single step of the [`RegexSearch`].
+pub enum RegexSearchStep<'a> {
+    /// A match was found.
+    Match(RegexMatch<'a>),
+    /// A part of the input was searched without any matches.
+    Progress,
+    /// The whole input has been searched.
+    Done,
+}
+
+/// Match found by the [`RegexSearch`].
+pub struct RegexMatch<'a> {
+    /// Index of the regex that matched (in the order given to the search).
+    pub regex_index: usize,
+    /// Offset within the input at which the match starts.
+    pub offset: u64,
+    /// Bytes that matched the regex.
+    pub content: &'a [u8],
+    /// Bytes preceding the match (up to the requested context length).
+    pub context_before: &'a [u8],
+    /// Bytes following the match (up to the requested context length).
+    pub context_after: &'a [u8],
+}
+
+impl<'r, R: Read> RegexSearch<'r, R> {
+
+    /// Creates a new search for the given regex in the given input.
+    pub fn new(inner: R, regex: &'r regex::bytes::Regex) -> RegexSearch<'r, R> {
+        RegexSearch::new_many(inner, vec![regex])
+    }
+
+    /// Creates a new search for all the given regexes in the given input.
+    pub fn new_many(inner: R, regexes: Vec<&'r regex::bytes::Regex>) -> RegexSearch<'r, R> {
+        RegexSearch {
+            inner,
+            positions: vec![0; regexes.len()],
+            regexes,
+            buf: Vec::new(),
+            buf_offset: 0,
+            window_start: 0,
+            regex_index: 0,
+            eof: false,
+            context_before_len: 0,
+            context_after_len: 0,
+        }
+    }
+
+    /// Sets the number of bytes around matches to include in them.
+    pub fn with_context(mut self, before_len: usize, after_len: usize) -> RegexSearch<'r, R> {
+        self.context_before_len = before_len;
+        self.context_after_len = after_len;
+        self
+    }
+
+    /// Performs a single step of the search.
+    ///
+    /// Every step either yields a match or searches a bounded part of the input
+    /// (returning [`RegexSearchStep::Progress`]), so the caller can regularly
+    /// do other work (e.g. send heartbeat signals) while searching big inputs.
+    ///
+    /// # Errors
+    ///
+    /// This function will fail if an I/O error is raised when reading data.
+    pub fn step(&mut self) -> std::io::Result<RegexSearchStep<'_>> {
+        // All the regexes have been searched for in the last part of the input.
+        if self.eof && self.regex_index >= self.regexes.len() {
+            return Ok(RegexSearchStep::Done);
+        }
+
+        self.discard();
+        self.fill()?;
+
+        // Matches have to start before this limit. If we reached the end of
+        // the input, there is no more data to come and all matches are valid.
+        let limit = self.window_start + MAX_REGEX_MATCH_LEN;
+
+        while self.regex_index < self.regexes.len() {
+            let index = self.regex_index;
+            let pos = self.positions[index];
+
+            // Empty matches at the very end of the input can move the position
+            // past the buffer (which is only possible once we reached the end
+            // of input).
+            let matcz = if pos > self.buf.len() {
+                None
+            } else {
+                self.regexes[index].find_at(&self.buf, pos)
+            };
+
+            match matcz {
+                Some(matcz) if self.eof || matcz.start() < limit => {
+                    let start = matcz.start();
+                    let end = matcz.end();
+
+                    // Empty matches do not advance the position by themselves,
+                    // so we need to skip a byte to avoid matching them again.
+                    self.positions[index] = if start == end { end + 1 } else { end };
+
+                    let context_before_start = start.saturating_sub(self.context_before_len);
+                    let context_after_end = std::cmp::min(
+                        end.saturating_add(self.context_after_len),
+                        self.buf.len(),
+                    );
+
+                    return Ok(RegexSearchStep::Match(RegexMatch {
+                        regex_index: index,
+                        offset: self.buf_offset + start as u64,
+                        content: &self.buf[start..end],
+                        context_before: &self.buf[context_before_start..start],
+                        context_after: &self.buf[end..context_after_end],
+                    }));
+                }
+                _ => {
+                    self.positions[index] = std::cmp::max(pos, limit);
+                    self.regex_index += 1;
+                }
+            }
+        }
+
+        if self.eof {
+            return Ok(RegexSearchStep::Done);
+        }
+
+        // All the regexes have been searched for in the current part of the
+        // input, so we can move on to the next one.
+        self.window_start = limit;
+        self.regex_index = 0;
+
+        Ok(RegexSearchStep::Progress)
+    }
+
+    /// Fills the buffer with enough data to search for the next match.
+    fn fill(&mut self) -> std::io::Result<()> {
+        let fill_len = self.window_start
+            .saturating_add(2 * MAX_REGEX_MATCH_LEN)
+            .saturating_add(self.context_after_len);
+
+        while !self.eof && self.buf.len() < fill_len {
+            let len = self.buf.len();
+            self.buf.resize(fill_len, 0);
+
+            match self.inner.read(&mut self.buf[len..]) {
+                Ok(len_read) => {
+                    self.buf.truncate(len + len_read);
+                    if len_read == 0 {
+                        self.eof = true;
+                    }
+                }
+                Err(error) => {
+                    self.buf.truncate(len);
+                    if error.kind() != std::io::ErrorKind::Interrupted {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Discards the part of the buffer that is no longer needed.
+    fn discard(&mut self) {
+        // We keep a few bytes before the current part even if no context was
+        // requested so that look-behind assertions (like `\b`) work at the
+        // buffer boundary. 4 bytes are enough for any UTF-8 encoded character.
+        const LOOKBEHIND_LEN: usize = 4;
+
+        // Positions of all the regexes are never before the start of the part
+        // that is currently searched.
+        let keep_start = self.window_start
+            .saturating_sub(self.context_before_len.saturating_add(LOOKBEHIND_LEN));
+
+        // To avoid moving the data too often, we do it only once there is a lot
+        // of bytes to discard.
+        if keep_start >= MAX_REGEX_MATCH_LEN {
+            self.buf.drain(..keep_start);
+            self.buf_offset += keep_start as u64;
+            self.window_start -= keep_start;
+            for pos in &mut self.positions {
+                *pos -= keep_start;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -562,4 +811,93 @@ mod tests {
 
         assert_eq!(buf, b"foobarbaz");
     }
+
+    /// Collects offsets of all matches of the regex in the given input.
+    fn regex_search_offsets(input: &[u8], regex: &str) -> Vec<u64> {
+        let regex = regex::bytes::Regex::new(regex).unwrap();
+        let mut search = RegexSearch::new(input, &regex);
+
+        let mut offsets = vec!();
+        loop {
+            match search.step().unwrap() {
+                RegexSearchStep::Match(matcz) => offsets.push(matcz.offset),
+                RegexSearchStep::Progress => continue,
+                RegexSearchStep::Done => return offsets,
+            }
+        }
+    }
+
+    #[test]
+    fn test_regex_search_with_empty_input() {
+        assert_eq!(regex_search_offsets(b"", "foo"), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn test_regex_search_with_multiple_matches() {
+        assert_eq!(regex_search_offsets(b"foo bar foo", "foo"), vec![0, 8]);
+    }
+
+    #[test]
+    fn test_regex_search_with_word_boundary_after_discard() {
+        let mut input = vec![b'x'; 3 * MAX_REGEX_MATCH_LEN];
+        input.extend_from_slice(b"foo bar");
+
+        assert_eq!(regex_search_offsets(&input, r"\bfoo"), Vec::<u64>::new());
+        assert_eq! {
+            regex_search_offsets(&input, r"\bbar"),
+            vec![3 * MAX_REGEX_MATCH_LEN as u64 + 4]
+        };
+    }
+
+    #[test]
+    fn test_regex_search_many() {
+        let mut input = Vec::new();
+        input.extend_from_slice(b"foo bar");
+        input.extend(std::iter::repeat_n(b'x', 3 * MAX_REGEX_MATCH_LEN));
+        input.extend_from_slice(b"bar foo");
+        input.extend(std::iter::repeat_n(b'x', MAX_REGEX_MATCH_LEN / 2));
+        input.extend_from_slice(b"foo");
+
+        let foo = regex::bytes::Regex::new("foo").unwrap();
+        let bar = regex::bytes::Regex::new("bar").unwrap();
+        let x = regex::bytes::Regex::new("x+").unwrap();
+        let mut search = RegexSearch::new_many(&input[..], vec![&foo, &bar, &x]);
+
+        let mut offsets = vec![Vec::new(); 3];
+        loop {
+            match search.step().unwrap() {
+                RegexSearchStep::Match(matcz) => {
+                    offsets[matcz.regex_index].push(matcz.offset);
+                }
+                RegexSearchStep::Progress => continue,
+                RegexSearchStep::Done => break,
+            }
+        }
+
+        assert_eq!(offsets[0], regex_search_offsets(&input, "foo"));
+        assert_eq!(offsets[1], regex_search_offsets(&input, "bar"));
+        assert_eq!(offsets[2], regex_search_offsets(&input, "x+"));
+        assert_eq!(offsets[0].len(), 3);
+        assert_eq!(offsets[1].len(), 2);
+    }
+
+    #[test]
+    fn test_regex_search_with_context() {
+        let regex = regex::bytes::Regex::new("bar").unwrap();
+        let mut search = RegexSearch::new(&b"foobarbaz"[..], &regex)
+            .with_context(2, 4);
+
+        match search.step().unwrap() {
+            RegexSearchStep::Match(matcz) => {
+                assert_eq!(matcz.offset, 3);
+                assert_eq!(matcz.content, b"bar");
+                assert_eq!(matcz.context_before, b"oo");
+                assert_eq!(matcz.context_after, b"baz");
+            }
+            _ => panic!("no match"),
+        }
+
+        assert!(matches!(search.step().unwrap(), RegexSearchStep::Done));
+        assert!(matches!(search.step().unwrap(), RegexSearchStep::Done));
+    }
 }
@@ -10,12 +10,28 @@ pub mod fs;
 pub mod io;
 pub mod log;
 pub mod args;
+pub mod offline;
 pub mod session;
 
 mod blob;
 mod filter;
+#[cfg(any(
+    feature = "action-find_files",
+    feature = "action-get_file_metadata",
+    feature = "action-get_file_contents",
+    feature = "action-get_file_hash",
+    feature = "action-get_filesystem_timeline",
+))]
+mod interpolate;
 mod request;
 mod response;
+#[cfg(feature = "action-scan_files")]
+mod scan;
+#[cfg(any(
+    feature = "action-list_raw_directory",
+    feature = "action-get_raw_file_contents",
+))]
+mod raw;
 
 pub mod startup; // TODO(@panhania): Hide this module.
 
@@ -51,10 +67,23 @@ pub fn init(args: &crate::args::Args) {
 pub fn listen(args: &crate::args::Args) {
     loop {
         let request = Request::receive(args.heartbeat_rate);
-        session::FleetspeakSession::dispatch(args.heartbeat_rate, request);
+        session::FleetspeakSession::dispatch(args, request);
     }
 }
 
+/// Processes requests without a Fleetspeak connection.
+///
+/// Requests are read from the offline input specified in the agent `args` and
+/// all the messages that would normally be sent to the GRR server (including
+/// the startup information) are written to the specified offline output. See
+/// the [`offline`] module for more details.
+///
+/// Unlike [`listen`], this function terminates once all the requests from the
+/// input are processed.
+pub fn listen_offline(args: &crate::args::Args) {
+    offline::listen(args)
+}
+
 /// Sends a system message with startup information to the GRR server.
 ///
 /// This function should be called only once at the beginning of RRG's process
@@ -230,6 +230,8 @@ pub struct ResponseLogger {
     log_builder: crate::LogBuilder,
     /// Minimum level at which messages are sent to the server.
     log_level: log::LevelFilter,
+    /// Offline output to write the messages to instead of sending them.
+    output: Option<std::sync::Arc<crate::offline::Output>>,
 }
 
 impl ResponseLogger {
@@ -239,9 +241,19 @@ impl ResponseLogger {
         ResponseLogger {
             log_builder: crate::LogBuilder::new(request.id()),
             log_level: request.log_level(),
+            output: None,
         }
     }
 
+    /// Makes the logger write messages to the given offline output.
+    pub fn with_output(
+        mut self,
+        output: std::sync::Arc<crate::offline::Output>,
+    ) -> ResponseLogger {
+        self.output = Some(output);
+        self
+    }
+
     /// Runs the specified function in a context with this logger enabled.
     ///
     /// # Panics
@@ -280,7 +292,15 @@ impl Log for ResponseLogger {
         }
 
         let log = self.log_builder.log(record);
-        log.send_unaccounted();
+        match &self.output {
+            // Similarly to other loggers, we consider failures to write logs
+            // critical and panic on them.
+            Some(output) => {
+                output.write_response(log.into())
+                    .expect("failed to write a log response");
+            }
+            None => log.send_unaccounted(),
+        }
     }
 
     fn flush(&self) {
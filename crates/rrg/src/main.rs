@@ -9,6 +9,12 @@ fn main() {
     let args = rrg::args::from_env_args();
     rrg::init(&args);
 
+    if args.offline_input.is_some() {
+        info!("processing requests offline");
+        rrg::listen_offline(&args);
+        return;
+    }
+
     info!("sending Fleetspeak startup information");
     fleetspeak::startup(env!("CARGO_PKG_VERSION"));
 
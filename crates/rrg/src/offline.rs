@@ -0,0 +1,567 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for running the agent without a Fleetspeak connection.
+//!
+//! In the offline mode, requests are read from a directory or from standard
+//! input and all the messages that would normally be sent to the GRR server are
+//! written to a file or to a spool directory instead. This is useful e.g. for
+//! air-gapped hosts or for collecting data from disk images on analysis hosts.
+//!
+//! The output consists of `rrg.OfflineMessage` messages. In case of an output
+//! file, these messages are [length-delimited][1] (in the same way as when
+//! using [`protobuf::Message::write_length_delimited_to_writer`]). In case of a
+//! spool directory, each message is written to a separate file and files are
+//! named such that their lexicographical order corresponds to the order in
+//! which messages were written.
+//!
+//! [1]: https://protobuf.dev/programming-guides/techniques/#streaming
+
+use std::path::{Path, PathBuf};
+
+use log::{error, info};
+
+/// Processes requests in the offline mode as specified by the agent `args`.
+///
+/// This function returns once all the requests from the input are processed.
+///
+/// # Panics
+///
+/// This function will panic if the input or output are not specified correctly
+/// or if it is not possible to open them.
+pub fn listen(args: &crate::args::Args) {
+    let input = match &args.offline_input {
+        Some(path) => Input::from_path(path),
+        None => panic!("no offline input specified"),
+    };
+
+    let output = match (&args.offline_output_file, &args.offline_output_dir) {
+        (Some(path), None) => Output::file(path),
+        (None, Some(path)) => Output::spool_dir(path),
+        (None, None) => panic!("no offline output specified"),
+        (Some(_), Some(_)) => panic!("more than one offline output specified"),
+    }.expect("failed to open the offline output");
+    let output = std::sync::Arc::new(output);
+
+    let startup = crate::response::Parcel::new({
+        crate::Sink::Startup
+    }, crate::startup::Startup::now());
+    output.write_parcel(startup.into())
+        .expect("failed to write startup information");
+
+    let requests = input.requests()
+        .expect("failed to open the offline input");
+
+    for request in requests {
+        let request = match request {
+            Ok(request) => request,
+            Err(error) => {
+                error!("failed to read offline request: {error}");
+                continue;
+            }
+        };
+
+        let request = crate::Request::parse_from_bytes(&request);
+        crate::session::OfflineSession::dispatch(args, &output, request);
+    }
+
+    info!("finished processing offline requests");
+}
+
+/// Source of requests to process in the offline mode.
+pub enum Input {
+    /// Length-delimited `rrg.Request` messages read from standard input.
+    Stdin,
+    /// Directory with files, each containing one `rrg.Request` message.
+    ///
+    /// Files are processed in the lexicographical order of their names. Files
+    /// with names starting with a dot and non-regular files are ignored.
+    Dir(PathBuf),
+}
+
+impl Input {
+
+    /// Creates an input corresponding to the given path.
+    ///
+    /// The special path `-` corresponds to the standard input.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Input {
+        let path = path.as_ref();
+        if path == Path::new("-") {
+            Input::Stdin
+        } else {
+            Input::Dir(path.to_path_buf())
+        }
+    }
+
+    /// Returns an iterator over serialized requests from the input.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if it was not possible to open the
+    /// input. Errors of reading individual requests are reported through the
+    /// iterator.
+    pub fn requests(&self) -> std::io::Result<Box<dyn Iterator<Item = std::io::Result<Vec<u8>>>>> {
+        match self {
+            Input::Stdin => {
+                Ok(Box::new(LengthDelimited::new(std::io::stdin().lock())))
+            }
+            Input::Dir(path) => {
+                let paths = request_paths(path)?;
+                Ok(Box::new(paths.into_iter().map(std::fs::read)))
+            }
+        }
+    }
+}
+
+/// Returns sorted paths of all request files in the given directory.
+fn request_paths(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+
+        if entry.file_name().as_encoded_bytes().starts_with(b".") {
+            continue;
+        }
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+
+        paths.push(entry.path());
+    }
+
+    paths.sort_unstable();
+    Ok(paths)
+}
+
+/// Iterator over length-delimited messages read from the underlying reader.
+///
+/// The iterator stops after the first error as it is not possible to recover
+/// message boundaries after that.
+struct LengthDelimited<R: std::io::Read> {
+    /// Reader to read the messages from.
+    reader: R,
+    /// Whether the reader has been exhausted (or failed).
+    done: bool,
+}
+
+impl<R: std::io::Read> LengthDelimited<R> {
+
+    /// Creates a new iterator reading messages from the given reader.
+    fn new(reader: R) -> LengthDelimited<R> {
+        LengthDelimited {
+            reader,
+            done: false,
+        }
+    }
+}
+
+impl<R: std::io::Read> Iterator for LengthDelimited<R> {
+
+    type Item = std::io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<std::io::Result<Vec<u8>>> {
+        if self.done {
+            return None;
+        }
+
+        let result = read_length_delimited(&mut self.reader).transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+
+        result
+    }
+}
+
+/// Reads a single length-delimited message from the given reader.
+///
+/// Returns `None` if the reader is at its end.
+fn read_length_delimited<R>(reader: &mut R) -> std::io::Result<Option<Vec<u8>>>
+where
+    R: std::io::Read,
+{
+    use std::io::Read as _;
+
+    // The length is encoded as a varint [1]: each byte carries 7 bits of the
+    // value (starting from the least significant ones) and the most significant
+    // bit indicates whether there are more bytes to follow.
+    //
+    // [1]: https://protobuf.dev/programming-guides/encoding/#varints
+    let mut len = 0u64;
+    for i in 0.. {
+        let mut byte = [0; 1];
+        match reader.read(&mut byte) {
+            Ok(0) if i == 0 => return Ok(None),
+            Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(_) => (),
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {
+                continue;
+            }
+            Err(error) => return Err(error),
+        }
+
+        if i >= 10 {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, {
+                "varint length too long"
+            }));
+        }
+
+        len |= u64::from(byte[0] & 0x7F) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+
+    let mut buf = Vec::new();
+    reader.take(len).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+
+    Ok(Some(buf))
+}
+
+/// Destination of messages produced in the offline mode.
+///
+/// The output can be safely shared between threads, writes of individual
+/// messages are atomic.
+pub struct Output {
+    /// The underlying writer, guarded for concurrent use.
+    writer: std::sync::Mutex<Writer>,
+}
+
+/// Writer of messages produced in the offline mode.
+enum Writer {
+    /// Writer appending length-delimited messages to a single file.
+    File(std::fs::File),
+    /// Writer putting every message into a separate file in a directory.
+    SpoolDir {
+        /// Path to the spool directory.
+        path: PathBuf,
+        /// Identifier to assign to the next written message.
+        next_id: u64,
+    },
+}
+
+impl Output {
+
+    /// Opens an output appending messages to the file at the given path.
+    ///
+    /// The file is created if it does not exist.
+    pub fn file<P: AsRef<Path>>(path: P) -> std::io::Result<Output> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        Ok(Output {
+            writer: std::sync::Mutex::new(Writer::File(file)),
+        })
+    }
+
+    /// Opens an output writing messages to the spool directory at given path.
+    ///
+    /// The directory is created if it does not exist. If it already contains
+    /// messages, new messages are going to be written after them.
+    pub fn spool_dir<P: AsRef<Path>>(path: P) -> std::io::Result<Output> {
+        let path = path.as_ref().to_path_buf();
+        std::fs::create_dir_all(&path)?;
+
+        let mut next_id = 0;
+        for entry in std::fs::read_dir(&path)? {
+            if let Some(id) = spool_id(&entry?.file_name()) {
+                next_id = std::cmp::max(next_id, id + 1);
+            }
+        }
+
+        Ok(Output {
+            writer: std::sync::Mutex::new(Writer::SpoolDir {
+                path,
+                next_id,
+            }),
+        })
+    }
+
+    /// Writes the given response to the output.
+    ///
+    /// This function returns number of bytes in the serialized response (i.e.
+    /// the same amount that would be sent to Fleetspeak).
+    pub fn write_response(
+        &self,
+        response: rrg_proto::rrg::Response,
+    ) -> std::io::Result<usize> {
+        use protobuf::Message as _;
+
+        let len = response.compute_size() as usize;
+
+        let mut message = rrg_proto::rrg::OfflineMessage::new();
+        message.set_response(response);
+        self.write(message)?;
+
+        Ok(len)
+    }
+
+    /// Writes the given parcel to the output.
+    ///
+    /// This function returns number of bytes in the serialized parcel (i.e.
+    /// the same amount that would be sent to Fleetspeak).
+    pub fn write_parcel(
+        &self,
+        parcel: rrg_proto::rrg::Parcel,
+    ) -> std::io::Result<usize> {
+        use protobuf::Message as _;
+
+        let len = parcel.compute_size() as usize;
+
+        let mut message = rrg_proto::rrg::OfflineMessage::new();
+        message.set_parcel(parcel);
+        self.write(message)?;
+
+        Ok(len)
+    }
+
+    /// Writes the given message to the output.
+    fn write(&self, message: rrg_proto::rrg::OfflineMessage) -> std::io::Result<()> {
+        use std::io::Write as _;
+        use protobuf::Message as _;
+
+        // Note that we must not log anything while holding the lock: logs are
+        // written to the output as well and this would result in a deadlock.
+        let mut writer = self.writer.lock()
+            .expect("failed to acquire offline output lock");
+
+        match &mut *writer {
+            Writer::File(file) => {
+                // We serialize the message to a buffer first and write it with
+                // a single call so that a failure does not leave half-written
+                // messages in the file (at least in most cases).
+                let data = message.write_length_delimited_to_bytes()?;
+                file.write_all(&data)?;
+                file.flush()?;
+            }
+            Writer::SpoolDir { path, next_id } => {
+                let data = message.write_to_bytes()?;
+
+                // We write to a temporary file first and rename it only once it
+                // is complete so that readers never see partial messages.
+                let name = format!("{:020}.pb", next_id);
+                let temp_path = path.join(format!(".{name}.tmp"));
+                std::fs::write(&temp_path, data)?;
+                std::fs::rename(&temp_path, path.join(name))?;
+
+                *next_id += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the identifier of a message in a spool directory from its file name.
+fn spool_id(name: &std::ffi::OsStr) -> Option<u64> {
+    name.to_str()?
+        .strip_suffix(".pb")?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn read_length_delimited_empty() {
+        let mut reader: &[u8] = b"";
+        assert!(read_length_delimited(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_length_delimited_many() {
+        let mut reader: &[u8] = b"\x03foo\x00\x06quuxyz";
+
+        assert_eq!(read_length_delimited(&mut reader).unwrap().unwrap(), b"foo");
+        assert_eq!(read_length_delimited(&mut reader).unwrap().unwrap(), b"");
+        assert_eq!(read_length_delimited(&mut reader).unwrap().unwrap(), b"quuxyz");
+        assert!(read_length_delimited(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_length_delimited_multibyte_len() {
+        let data = vec![0xAB; 300];
+
+        let mut buf = vec![0xAC, 0x02];
+        buf.extend_from_slice(&data);
+
+        let mut reader = &buf[..];
+        assert_eq!(read_length_delimited(&mut reader).unwrap().unwrap(), data);
+    }
+
+    #[test]
+    fn read_length_delimited_truncated_len() {
+        let mut reader: &[u8] = b"\xAC";
+        assert!(read_length_delimited(&mut reader).is_err());
+    }
+
+    #[test]
+    fn read_length_delimited_truncated_data() {
+        let mut reader: &[u8] = b"\x06foo";
+        assert!(read_length_delimited(&mut reader).is_err());
+    }
+
+    #[test]
+    fn length_delimited_stops_on_error() {
+        let reader: &[u8] = b"\x03foo\x06bar";
+
+        let mut iter = LengthDelimited::new(reader);
+        assert_eq!(iter.next().unwrap().unwrap(), b"foo");
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn input_from_path_stdin() {
+        assert!(matches!(Input::from_path("-"), Input::Stdin));
+    }
+
+    #[test]
+    fn input_dir_requests_sorted() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        std::fs::write(tempdir.path().join("b"), b"bar")
+            .unwrap();
+        std::fs::write(tempdir.path().join("a"), b"foo")
+            .unwrap();
+        std::fs::write(tempdir.path().join("c"), b"baz")
+            .unwrap();
+        std::fs::write(tempdir.path().join(".d"), b"quux")
+            .unwrap();
+        std::fs::create_dir(tempdir.path().join("e"))
+            .unwrap();
+
+        let requests = Input::from_path(tempdir.path()).requests()
+            .unwrap()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(requests, [b"foo", b"bar", b"baz"]);
+    }
+
+    #[test]
+    fn input_dir_non_existent() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        assert!(Input::from_path(tempdir.path().join("foo")).requests().is_err());
+    }
+
+    #[test]
+    fn output_file() {
+        use protobuf::Message as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("output");
+
+        let output = Output::file(&path)
+            .unwrap();
+
+        let mut response = rrg_proto::rrg::Response::new();
+        response.set_flow_id(0xF00);
+        let response_len = output.write_response(response)
+            .unwrap();
+        assert!(response_len > 0);
+
+        let mut parcel = rrg_proto::rrg::Parcel::new();
+        parcel.set_sink(rrg_proto::rrg::Sink::BLOB);
+        output.write_parcel(parcel)
+            .unwrap();
+
+        let data = std::fs::read(&path)
+            .unwrap();
+        let messages = LengthDelimited::new(&data[..])
+            .map(|data| rrg_proto::rrg::OfflineMessage::parse_from_bytes(&data.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].response().flow_id(), 0xF00);
+        assert_eq!(messages[1].parcel().sink(), rrg_proto::rrg::Sink::BLOB);
+    }
+
+    #[test]
+    fn output_file_append() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("output");
+
+        Output::file(&path).unwrap()
+            .write_response(rrg_proto::rrg::Response::new())
+            .unwrap();
+        Output::file(&path).unwrap()
+            .write_response(rrg_proto::rrg::Response::new())
+            .unwrap();
+
+        let data = std::fs::read(&path)
+            .unwrap();
+        assert_eq!(LengthDelimited::new(&data[..]).count(), 2);
+    }
+
+    #[test]
+    fn output_spool_dir() {
+        use protobuf::Message as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let path = tempdir.path().join("spool");
+
+        let output = Output::spool_dir(&path)
+            .unwrap();
+        for flow_id in 0..3 {
+            let mut response = rrg_proto::rrg::Response::new();
+            response.set_flow_id(flow_id);
+            output.write_response(response)
+                .unwrap();
+        }
+        drop(output);
+
+        // Reopening the directory should not overwrite existing messages.
+        let output = Output::spool_dir(&path)
+            .unwrap();
+        let mut response = rrg_proto::rrg::Response::new();
+        response.set_flow_id(3);
+        output.write_response(response)
+            .unwrap();
+
+        let mut names = std::fs::read_dir(&path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+
+        assert_eq!(names.len(), 4);
+        for (flow_id, name) in names.iter().enumerate() {
+            let data = std::fs::read(path.join(name))
+                .unwrap();
+            let message = rrg_proto::rrg::OfflineMessage::parse_from_bytes(&data)
+                .unwrap();
+
+            assert_eq!(message.response().flow_id(), flow_id as u64);
+        }
+    }
+
+    #[test]
+    fn spool_id_valid() {
+        assert_eq!(spool_id("00000000000000000042.pb".as_ref()), Some(42));
+    }
+
+    #[test]
+    fn spool_id_invalid() {
+        assert_eq!(spool_id(".00000000000000000042.pb.tmp".as_ref()), None);
+        assert_eq!(spool_id("foo.pb".as_ref()), None);
+        assert_eq!(spool_id("42".as_ref()), None);
+    }
+}
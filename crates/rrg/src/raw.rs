@@ -0,0 +1,360 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Utilities for actions parsing raw filesystems.
+//!
+//! Raw actions read filesystems directly from the underlying devices instead
+//! of going through the operating system. To detect discrepancies (e.g. files
+//! hidden by a rootkit), their results are compared against the operating
+//! system view (if the filesystem is mounted).
+
+use std::path::{Path, PathBuf};
+
+/// Raw filesystem stored on a device.
+pub type Filesystem = ospect::fs::ext4::Filesystem<std::fs::File>;
+
+/// Opens the raw filesystem stored on the given device (or image file).
+pub fn open(device: &Path) -> std::io::Result<Filesystem> {
+    let file = std::fs::File::open(device)?;
+
+    ospect::fs::ext4::Filesystem::new(file)
+}
+
+/// Converts a raw file name (as stored on the disk) to an OS string.
+pub fn os_string(name: Vec<u8>) -> std::ffi::OsString {
+    #[cfg(target_family = "unix")]
+    {
+        use std::os::unix::ffi::OsStringExt as _;
+        std::ffi::OsString::from_vec(name)
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    {
+        String::from_utf8_lossy(&name).into_owned().into()
+    }
+}
+
+/// Result of comparing raw data against the operating system view.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VfsStatus {
+    /// No comparison was made.
+    Unverified,
+    /// Raw data matches the operating system view.
+    Consistent,
+    /// File exists on the disk but is not visible to the operating system.
+    Missing,
+    /// File is visible to the operating system but its data differ.
+    Mismatch,
+    /// File is visible to the operating system but does not exist on the disk.
+    VfsOnly,
+}
+
+/// Operating system view of a mounted raw filesystem.
+pub struct Vfs {
+    /// Path at which the filesystem is mounted.
+    root: PathBuf,
+    /// Identifier of the device as reported by the operating system.
+    #[cfg(target_family = "unix")]
+    dev: u64,
+}
+
+impl Vfs {
+
+    /// Finds the operating system view of the filesystem on the given device.
+    ///
+    /// `None` is returned if the device is not mounted. Devices are matched by
+    /// their numbers (rather than names), so it does not matter through which
+    /// path the device is accessed. Image files are never considered mounted.
+    pub fn find(device: &Path) -> Option<Vfs> {
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::{FileTypeExt as _, MetadataExt as _};
+
+            let metadata = std::fs::metadata(device).ok()?;
+            if !metadata.file_type().is_block_device() {
+                return None;
+            }
+
+            let mounts = match ospect::fs::mounts() {
+                Ok(mounts) => mounts,
+                Err(error) => {
+                    log::error!("failed to list mounts: {error}");
+                    return None;
+                }
+            };
+
+            for mount in mounts {
+                let mount = match mount {
+                    Ok(mount) => mount,
+                    Err(error) => {
+                        log::error!("failed to read mount entry: {error}");
+                        continue;
+                    }
+                };
+
+                let mount_metadata = match std::fs::metadata(&mount.path) {
+                    Ok(mount_metadata) => mount_metadata,
+                    Err(_) => continue,
+                };
+
+                // Bind mounts of subdirectories share the device, so we also
+                // make sure that the mount point is the filesystem root.
+                if mount_metadata.dev() == metadata.rdev() &&
+                    mount_metadata.ino() == u64::from(ospect::fs::ext4::ROOT_INO)
+                {
+                    return Some(Vfs {
+                        root: mount.path,
+                        dev: metadata.rdev(),
+                    });
+                }
+            }
+
+            None
+        }
+
+        #[cfg(not(target_family = "unix"))]
+        {
+            let _ = device;
+            None
+        }
+    }
+
+    /// Returns the operating system path of the given filesystem path.
+    pub fn path(&self, path: &Path) -> PathBuf {
+        self.root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    /// Compares metadata of the given inode against the operating system view.
+    ///
+    /// The inode is considered consistent if the operating system reports the
+    /// same inode number, file type and size for the given path.
+    pub fn metadata_status(
+        &self,
+        path: &Path,
+        inode: &ospect::fs::ext4::Inode,
+    ) -> VfsStatus {
+        let metadata = match self.path(path).symlink_metadata() {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return VfsStatus::Missing;
+            }
+            Err(_) => return VfsStatus::Unverified,
+        };
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::MetadataExt as _;
+
+            // Another filesystem might be mounted on top of the path, there is
+            // nothing to compare then.
+            if metadata.dev() != self.dev {
+                return VfsStatus::Unverified;
+            }
+
+            let is_consistent = metadata.ino() == u64::from(inode.ino) &&
+                metadata.file_type().is_file() == inode.is_file() &&
+                metadata.file_type().is_dir() == inode.is_dir() &&
+                metadata.file_type().is_symlink() == inode.is_symlink() &&
+                metadata.len() == inode.size;
+
+            if is_consistent {
+                VfsStatus::Consistent
+            } else {
+                VfsStatus::Mismatch
+            }
+        }
+
+        #[cfg(not(target_family = "unix"))]
+        {
+            let _ = (metadata, inode);
+            VfsStatus::Unverified
+        }
+    }
+
+    /// Compares the given file part against the operating system view.
+    ///
+    /// The part is considered consistent if reading the same range of the
+    /// file through the operating system yields the same bytes.
+    pub fn contents_status(&self, path: &Path, offset: u64, data: &[u8]) -> VfsStatus {
+        use std::io::{Read as _, Seek as _};
+
+        let mut file = match std::fs::File::open(self.path(path)) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return VfsStatus::Missing;
+            }
+            Err(_) => return VfsStatus::Unverified,
+        };
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::MetadataExt as _;
+
+            match file.metadata() {
+                Ok(metadata) if metadata.dev() == self.dev => (),
+                _ => return VfsStatus::Unverified,
+            }
+        }
+
+        let mut buf = Vec::with_capacity(data.len());
+        let result = file.seek(std::io::SeekFrom::Start(offset))
+            .and_then(|_| file.take(data.len() as u64).read_to_end(&mut buf));
+        if result.is_err() {
+            return VfsStatus::Unverified;
+        }
+
+        if buf == data {
+            VfsStatus::Consistent
+        } else {
+            VfsStatus::Mismatch
+        }
+    }
+}
+
+impl From<VfsStatus> for rrg_proto::fs::VfsStatus {
+
+    fn from(status: VfsStatus) -> rrg_proto::fs::VfsStatus {
+        match status {
+            VfsStatus::Unverified => rrg_proto::fs::VfsStatus::UNVERIFIED,
+            VfsStatus::Consistent => rrg_proto::fs::VfsStatus::CONSISTENT,
+            VfsStatus::Missing => rrg_proto::fs::VfsStatus::MISSING,
+            VfsStatus::Mismatch => rrg_proto::fs::VfsStatus::MISMATCH,
+            VfsStatus::VfsOnly => rrg_proto::fs::VfsStatus::VFS_ONLY,
+        }
+    }
+}
+
+/// Creates a temporary image file with the given filesystem test fixture.
+///
+/// See `crates/ospect/testdata/ext4` for the list of available fixtures.
+#[cfg(test)]
+pub fn fixture(name: &str) -> tempfile::NamedTempFile {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("ospect")
+        .join("testdata")
+        .join("ext4")
+        .join(format!("{name}.img.gz"));
+
+    let file = std::fs::File::open(path)
+        .unwrap();
+
+    let mut image = tempfile::NamedTempFile::new()
+        .unwrap();
+    std::io::copy(&mut flate2::read::GzDecoder::new(file), &mut image)
+        .unwrap();
+
+    image
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn open_image() {
+        let image = fixture("basic");
+
+        let mut fs = open(image.path())
+            .unwrap();
+
+        assert!(fs.lookup("/hello.txt").is_ok());
+    }
+
+    #[test]
+    fn open_not_ext4() {
+        let tempfile = tempfile::NamedTempFile::new()
+            .unwrap();
+        std::fs::write(tempfile.path(), vec![0; 4096])
+            .unwrap();
+
+        assert!(open(tempfile.path()).is_err());
+    }
+
+    #[test]
+    fn vfs_find_image() {
+        let image = fixture("basic");
+
+        assert!(Vfs::find(image.path()).is_none());
+    }
+
+    /// Creates a fake view of a filesystem rooted in the given directory.
+    fn fake_vfs(root: &Path) -> Vfs {
+        Vfs {
+            root: root.to_path_buf(),
+            #[cfg(target_family = "unix")]
+            dev: std::os::unix::fs::MetadataExt::dev(&root.metadata().unwrap()),
+        }
+    }
+
+    #[test]
+    fn vfs_path() {
+        let vfs = Vfs {
+            root: PathBuf::from("/mnt/foo"),
+            #[cfg(target_family = "unix")]
+            dev: 0,
+        };
+
+        assert_eq!(vfs.path(Path::new("/bar/baz")), Path::new("/mnt/foo/bar/baz"));
+    }
+
+    #[test]
+    fn vfs_contents_status() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"0123456789")
+            .unwrap();
+
+        let vfs = fake_vfs(tempdir.path());
+
+        let status = vfs.contents_status(Path::new("/foo"), 2, b"234");
+        assert_eq!(status, VfsStatus::Consistent);
+
+        let status = vfs.contents_status(Path::new("/foo"), 2, b"xyz");
+        assert_eq!(status, VfsStatus::Mismatch);
+
+        let status = vfs.contents_status(Path::new("/bar"), 0, b"xyz");
+        assert_eq!(status, VfsStatus::Missing);
+    }
+
+    #[test]
+    fn vfs_metadata_status_missing() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+
+        let image = fixture("basic");
+        let mut fs = open(image.path())
+            .unwrap();
+        let inode = fs.lookup("/hello.txt")
+            .unwrap();
+
+        let vfs = fake_vfs(tempdir.path());
+
+        let status = vfs.metadata_status(Path::new("/hello.txt"), &inode);
+        assert_eq!(status, VfsStatus::Missing);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn vfs_metadata_status_mismatch() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("hello.txt"), b"Hello, world!\n")
+            .unwrap();
+
+        let image = fixture("basic");
+        let mut fs = open(image.path())
+            .unwrap();
+        let inode = fs.lookup("/hello.txt")
+            .unwrap();
+
+        let vfs = fake_vfs(tempdir.path());
+
+        // The size and type match, but inode numbers are different.
+        let status = vfs.metadata_status(Path::new("/hello.txt"), &inode);
+        assert_eq!(status, VfsStatus::Mismatch);
+    }
+}
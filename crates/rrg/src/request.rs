@@ -49,6 +49,24 @@ pub enum Action {
     ListWinregKeys,
     /// Query WMI using WQL (Windows-only).
     QueryWmi,
+    /// Execute a signed command.
+    ExecuteSignedCommand,
+    /// Grep files in the specified directory tree for patterns.
+    GrepFiles,
+    /// Scan files in the specified directory tree against rules.
+    ScanFiles,
+    /// List memory regions of a process (Linux-only).
+    ListMemoryRegions,
+    /// Get contents of memory regions of a process (Linux-only).
+    GetMemoryContents,
+    /// Grep memory of a process for a pattern (Linux-only).
+    GrepMemory,
+    /// List a directory by parsing the raw filesystem on a device.
+    ListRawDirectory,
+    /// Get contents of a file by parsing the raw filesystem on a device.
+    GetRawFileContents,
+    /// Find files matching glob patterns and conditions and collect them.
+    FindFiles,
 }
 
 impl std::fmt::Display for Action {
@@ -72,6 +90,15 @@ impl std::fmt::Display for Action {
             Action::ListWinregValues => write!(fmt, "list_winreg_values"),
             Action::ListWinregKeys => write!(fmt, "list_winreg_keys"),
             Action::QueryWmi => write!(fmt, "query_wmi"),
+            Action::ExecuteSignedCommand => write!(fmt, "execute_signed_command"),
+            Action::GrepFiles => write!(fmt, "grep_files"),
+            Action::ScanFiles => write!(fmt, "scan_files"),
+            Action::ListMemoryRegions => write!(fmt, "list_memory_regions"),
+            Action::GetMemoryContents => write!(fmt, "get_memory_contents"),
+            Action::GrepMemory => write!(fmt, "grep_memory"),
+            Action::ListRawDirectory => write!(fmt, "list_raw_directory"),
+            Action::GetRawFileContents => write!(fmt, "get_raw_file_contents"),
+            Action::FindFiles => write!(fmt, "find_files"),
         }
     }
 }
@@ -121,6 +148,15 @@ impl TryFrom<rrg_proto::rrg::Action> for Action {
             LIST_WINREG_VALUES => Ok(Action::ListWinregValues),
             LIST_WINREG_KEYS => Ok(Action::ListWinregKeys),
             QUERY_WMI => Ok(Action::QueryWmi),
+            EXECUTE_SIGNED_COMMAND => Ok(Action::ExecuteSignedCommand),
+            GREP_FILES => Ok(Action::GrepFiles),
+            SCAN_FILES => Ok(Action::ScanFiles),
+            LIST_MEMORY_REGIONS => Ok(Action::ListMemoryRegions),
+            GET_MEMORY_CONTENTS => Ok(Action::GetMemoryContents),
+            GREP_MEMORY => Ok(Action::GrepMemory),
+            LIST_RAW_DIRECTORY => Ok(Action::ListRawDirectory),
+            GET_RAW_FILE_CONTENTS => Ok(Action::GetRawFileContents),
+            FIND_FILES => Ok(Action::FindFiles),
             _ => {
                 let value = protobuf::Enum::value(&proto);
                 Err(UnknownAction { value })
@@ -272,15 +308,25 @@ impl Request {
             }
         }
 
+        Request::parse_from_bytes(&message.data[..])
+    }
+
+    /// Parses a request from a serialized `rrg.Request` message.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in case the message is malformed or
+    /// the request it contains is invalid.
+    pub fn parse_from_bytes(data: &[u8]) -> Result<Request, ParseRequestError> {
         use protobuf::Message as _;
-        let proto = rrg_proto::rrg::Request::parse_from_bytes(&message.data[..])
+        let proto = rrg_proto::rrg::Request::parse_from_bytes(data)
             .map_err(|error| ParseRequestError {
                 request_id: None,
                 kind: ParseRequestErrorKind::MalformedBytes,
                 error: Some(Box::new(error)),
             })?;
 
-        Ok(Request::try_from(proto)?)
+        Request::try_from(proto)
     }
 }
 
@@ -117,12 +117,28 @@ pub struct Status {
     response_id: ResponseId,
     /// Number of items that have been rejected by filters.
     filtered_out_count: u32,
+    /// Total CPU time spent on executing the action (if known).
+    cpu_time: Option<std::time::Duration>,
+    /// Total real (wall) time spent on executing the action (if known).
+    real_time: Option<std::time::Duration>,
     /// The action execution status.
     result: Result<(), crate::session::Error>,
 }
 
 impl Status {
 
+    /// Sets the total CPU time spent on executing the action.
+    pub fn with_cpu_time(mut self, cpu_time: std::time::Duration) -> Status {
+        self.cpu_time = Some(cpu_time);
+        self
+    }
+
+    /// Sets the total real (wall) time spent on executing the action.
+    pub fn with_real_time(mut self, real_time: std::time::Duration) -> Status {
+        self.real_time = Some(real_time);
+        self
+    }
+
     /// Sends the status message through Fleetspeak to the GRR server.
     ///
     /// This function consumes the status to ensure that it is not sent twice.
@@ -258,6 +274,8 @@ impl ResponseBuilder {
             // increment the response id.
             response_id: self.next_response_id,
             filtered_out_count: self.filtered_out_count,
+            cpu_time: None,
+            real_time: None,
             result,
         }
     }
@@ -445,6 +463,12 @@ impl From<Status> for rrg_proto::rrg::Status {
         }
 
         proto.set_filtered_out_count(status.filtered_out_count);
+        if let Some(cpu_time) = status.cpu_time {
+            proto.set_cpu_time(cpu_time.into());
+        }
+        if let Some(real_time) = status.real_time {
+            proto.set_real_time(real_time.into());
+        }
 
         proto
     }
@@ -0,0 +1,1621 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+//! Matching of content against rules written in a YARA-like language.
+//!
+//! The language is a subset of the [YARA] rule language. A rule consists of a
+//! name, optional tags, optional metadata (both ignored), optional string
+//! definitions and a condition:
+//!
+//! ```text
+//! rule Example : tag {
+//!     meta:
+//!         author = "Jane Doe"
+//!     strings:
+//!         $text = "foo" nocase wide ascii
+//!         $hex = { 4D 5A ?? 0? [2-4] ( 00 | FF ) ~00 }
+//!         $regex = /ba[rz]/is
+//!     condition:
+//!         ($text or #hex > 2) and 1 of ($regex, $t*) and filesize < 10MB
+//! }
+//! ```
+//!
+//! Conditions support boolean operators (`and`, `or`, `not`), string matches
+//! (`$a`), string match counts (`#a > 2`), the size of the scanned content
+//! (`filesize`) and string set quantifiers (`all of`, `any of`, `none of` and
+//! `N of` either `them` or a parenthesized list of string identifiers that can
+//! end with `*` to refer to all strings with the given prefix).
+//!
+//! Unlike in YARA, every string is matched against the content independently
+//! and only non-overlapping matches are reported.
+//!
+//! [YARA]: https://yara.readthedocs.io
+
+/// Maximum number of matches of a single string that are recorded.
+///
+/// Matches beyond this limit are still counted (so conditions on the number of
+/// matches are evaluated correctly) but their offsets are not reported.
+pub const MAX_STRING_MATCH_COUNT: usize = 1024;
+
+/// Maximum number of bytes of a single string match that are recorded.
+pub const MAX_STRING_MATCH_DATA_LEN: usize = 256;
+
+/// Maximum nesting depth of conditions (negations and parentheses).
+///
+/// Conditions are parsed and evaluated recursively, so without a limit deeply
+/// nested conditions could overflow the stack.
+const MAX_EXPR_DEPTH: usize = 64;
+
+/// A set of compiled rules.
+pub struct Rules {
+    /// Strings of all the rules (in the order of their definition).
+    strings: Vec<StringDef>,
+    /// Rules of the set (in the order of their definition).
+    rules: Vec<Rule>,
+}
+
+/// A string definition of a rule.
+struct StringDef {
+    /// Name of the string (without the `$` sigil).
+    name: String,
+    /// Regex equivalent to the string.
+    regex: regex::bytes::Regex,
+}
+
+/// An individual rule.
+struct Rule {
+    /// Name of the rule.
+    name: String,
+    /// Indices of strings (within the whole rule set) defined by the rule.
+    strings: std::ops::Range<usize>,
+    /// Condition under which the rule matches.
+    cond: Expr,
+}
+
+/// A condition of a rule.
+enum Expr {
+    /// Literal boolean value.
+    Bool(bool),
+    /// Negation of the condition.
+    Not(Box<Expr>),
+    /// Conjunction of the conditions.
+    And(Vec<Expr>),
+    /// Disjunction of the conditions.
+    Or(Vec<Expr>),
+    /// String with the given index matched at least once.
+    Matched(usize),
+    /// Number of matches of the string with the given index.
+    Count(usize, CmpOp, u64),
+    /// Size of the scanned content.
+    Filesize(CmpOp, u64),
+    /// Number of matched strings with the given indices.
+    Of(Quant, Vec<usize>),
+}
+
+/// Comparison operator used in conditions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Quantifier over a set of strings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Quant {
+    /// All the strings have to match.
+    All,
+    /// At least one of the strings has to match.
+    Any,
+    /// None of the strings can match.
+    None,
+    /// At least the given number of strings has to match.
+    AtLeast(u64),
+}
+
+impl Rules {
+
+    /// Parses and compiles rules from the given source.
+    pub fn parse(source: &str) -> Result<Rules, ParseError> {
+        let mut parser = Parser {
+            input: source.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+
+        let mut rules = Rules {
+            strings: Vec::new(),
+            rules: Vec::new(),
+        };
+        while !parser.at_end()? {
+            parser.rule(&mut rules)?;
+        }
+
+        Ok(rules)
+    }
+
+    /// Returns `true` if the set contains no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Returns regexes of all the strings that have to be searched for.
+    ///
+    /// Matches of the regex found in the content have to be [recorded][1]
+    /// with the index of the regex in the iterator.
+    ///
+    /// [1]: Scan::record
+    pub fn regexes(&self) -> impl Iterator<Item = &regex::bytes::Regex> {
+        self.strings.iter().map(|string| &string.regex)
+    }
+
+    /// Starts a scan of a new piece of content against the rules.
+    pub fn scan(&self) -> Scan<'_> {
+        Scan {
+            rules: self,
+            counts: vec![0; self.strings.len()],
+            matches: std::iter::repeat_with(Vec::new)
+                .take(self.strings.len())
+                .collect(),
+        }
+    }
+}
+
+/// State of a scan of a single piece of content against a set of rules.
+pub struct Scan<'r> {
+    /// Rules the content is scanned against.
+    rules: &'r Rules,
+    /// Total number of matches of every string.
+    counts: Vec<u64>,
+    /// Recorded matches of every string.
+    matches: Vec<Vec<StringMatch>>,
+}
+
+/// A match of a rule against scanned content.
+pub struct RuleMatch {
+    /// Name of the rule that matched.
+    pub rule_name: String,
+    /// Recorded matches of the strings of the rule.
+    pub string_matches: Vec<StringMatch>,
+}
+
+/// A match of a rule string against scanned content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StringMatch {
+    /// Identifier of the string (e.g. `$foo`).
+    pub id: String,
+    /// Byte offset within the content at which the match starts.
+    pub offset: u64,
+    /// Bytes that matched (truncated to [`MAX_STRING_MATCH_DATA_LEN`]).
+    pub data: Vec<u8>,
+}
+
+impl Scan<'_> {
+
+    /// Records a match of the regex with the given index.
+    ///
+    /// See [`Rules::regexes`] for more details.
+    pub fn record(&mut self, index: usize, offset: u64, data: &[u8]) {
+        self.counts[index] += 1;
+
+        let matches = &mut self.matches[index];
+        if matches.len() < MAX_STRING_MATCH_COUNT {
+            let data_len = std::cmp::min(data.len(), MAX_STRING_MATCH_DATA_LEN);
+            matches.push(StringMatch {
+                id: format!("${}", self.rules.strings[index].name),
+                offset,
+                data: data[..data_len].to_vec(),
+            });
+        }
+    }
+
+    /// Finishes the scan, returning rules that matched the content.
+    ///
+    /// `filesize` is the total size of the scanned content.
+    pub fn finish(mut self, filesize: u64) -> Vec<RuleMatch> {
+        let mut rule_matches = Vec::new();
+
+        for rule in &self.rules.rules {
+            if !rule.cond.eval(&self.counts, filesize) {
+                continue;
+            }
+
+            let mut string_matches = Vec::new();
+            for index in rule.strings.clone() {
+                string_matches.extend(std::mem::take(&mut self.matches[index]));
+            }
+
+            rule_matches.push(RuleMatch {
+                rule_name: rule.name.clone(),
+                string_matches,
+            });
+        }
+
+        rule_matches
+    }
+}
+
+impl Expr {
+
+    /// Evaluates the condition given match counts of all strings.
+    fn eval(&self, counts: &[u64], filesize: u64) -> bool {
+        match self {
+            Expr::Bool(value) => *value,
+            Expr::Not(expr) => !expr.eval(counts, filesize),
+            Expr::And(exprs) => exprs.iter().all(|expr| expr.eval(counts, filesize)),
+            Expr::Or(exprs) => exprs.iter().any(|expr| expr.eval(counts, filesize)),
+            Expr::Matched(index) => counts[*index] > 0,
+            Expr::Count(index, op, value) => op.eval(counts[*index], *value),
+            Expr::Filesize(op, value) => op.eval(filesize, *value),
+            Expr::Of(quant, indices) => {
+                let matched = indices.iter()
+                    .filter(|index| counts[**index] > 0)
+                    .count();
+
+                match quant {
+                    Quant::All => matched == indices.len(),
+                    Quant::Any => matched > 0,
+                    Quant::None => matched == 0,
+                    Quant::AtLeast(count) => matched as u64 >= *count,
+                }
+            }
+        }
+    }
+}
+
+impl CmpOp {
+
+    /// Compares the given values using the operator.
+    fn eval(self, lhs: u64, rhs: u64) -> bool {
+        match self {
+            CmpOp::Eq => lhs == rhs,
+            CmpOp::Ne => lhs != rhs,
+            CmpOp::Lt => lhs < rhs,
+            CmpOp::Le => lhs <= rhs,
+            CmpOp::Gt => lhs > rhs,
+            CmpOp::Ge => lhs >= rhs,
+        }
+    }
+}
+
+/// Recursive descent parser of the rule language.
+struct Parser<'s> {
+    /// Source of the rules.
+    input: &'s [u8],
+    /// Current position of the parser within the input.
+    pos: usize,
+    /// Current nesting depth of the condition being parsed.
+    depth: usize,
+}
+
+/// Strings of the rule that is currently being parsed.
+struct Scope<'a> {
+    /// String definitions of the rule.
+    strings: &'a [StringDef],
+    /// Index of the first string of the rule within the whole rule set.
+    offset: usize,
+}
+
+impl Scope<'_> {
+
+    /// Returns the index of the string with the given name.
+    fn resolve(&self, name: &str) -> Option<usize> {
+        self.strings.iter()
+            .position(|string| string.name == name)
+            .map(|index| self.offset + index)
+    }
+
+    /// Returns indices of all strings with names starting with the prefix.
+    fn resolve_prefix(&self, prefix: &str) -> Vec<usize> {
+        self.strings.iter().enumerate()
+            .filter(|(_, string)| string.name.starts_with(prefix))
+            .map(|(index, _)| self.offset + index)
+            .collect()
+    }
+}
+
+impl<'s> Parser<'s> {
+
+    /// Returns an error of the given kind at the specified position.
+    fn error_at(&self, pos: usize, repr: ParseErrorRepr) -> ParseError {
+        let line = 1 + self.input[..pos].iter()
+            .filter(|byte| **byte == b'\n')
+            .count();
+
+        ParseError {
+            line,
+            repr,
+        }
+    }
+
+    /// Returns an error of the given kind at the current position.
+    fn error(&self, repr: ParseErrorRepr) -> ParseError {
+        self.error_at(self.pos, repr)
+    }
+
+    /// Returns the byte at the current position (if any).
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            let rest = &self.input[self.pos..];
+            if rest.first().is_some_and(u8::is_ascii_whitespace) {
+                self.pos += 1;
+            } else if rest.starts_with(b"//") {
+                while self.peek().is_some_and(|byte| byte != b'\n') {
+                    self.pos += 1;
+                }
+            } else if rest.starts_with(b"/*") {
+                match rest.windows(2).position(|window| window == b"*/") {
+                    Some(end) => self.pos += end + 2,
+                    None => return Err(self.error(ParseErrorRepr::UnterminatedComment)),
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Returns `true` if there is nothing but whitespace and comments left.
+    fn at_end(&mut self) -> Result<bool, ParseError> {
+        self.skip_trivia()?;
+        Ok(self.pos == self.input.len())
+    }
+
+    /// Returns `true` if the next token starts with the given punctuation.
+    fn check(&mut self, punct: &str) -> Result<bool, ParseError> {
+        self.skip_trivia()?;
+        Ok(self.input[self.pos..].starts_with(punct.as_bytes()))
+    }
+
+    /// Consumes the given punctuation if it is the next token.
+    fn eat(&mut self, punct: &str) -> Result<bool, ParseError> {
+        if self.check(punct)? {
+            self.pos += punct.len();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Consumes the given punctuation or fails if it is not the next token.
+    fn expect(&mut self, punct: &'static str) -> Result<(), ParseError> {
+        if self.eat(punct)? {
+            Ok(())
+        } else {
+            Err(self.error(ParseErrorRepr::Expected(punct)))
+        }
+    }
+
+    /// Returns `true` if the next token is the given keyword.
+    fn check_keyword(&mut self, keyword: &str) -> Result<bool, ParseError> {
+        self.skip_trivia()?;
+
+        let rest = &self.input[self.pos..];
+        if !rest.starts_with(keyword.as_bytes()) {
+            return Ok(false);
+        }
+
+        Ok(!rest.get(keyword.len()).is_some_and(|byte| is_ident_byte(*byte)))
+    }
+
+    /// Consumes the given keyword if it is the next token.
+    fn eat_keyword(&mut self, keyword: &str) -> Result<bool, ParseError> {
+        if self.check_keyword(keyword)? {
+            self.pos += keyword.len();
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Consumes the given keyword or fails if it is not the next token.
+    fn expect_keyword(&mut self, keyword: &'static str) -> Result<(), ParseError> {
+        if self.eat_keyword(keyword)? {
+            Ok(())
+        } else {
+            Err(self.error(ParseErrorRepr::Expected(keyword)))
+        }
+    }
+
+    /// Consumes bytes that can be a part of an identifier.
+    fn ident_bytes(&mut self) -> &'s str {
+        let start = self.pos;
+        while self.peek().is_some_and(is_ident_byte) {
+            self.pos += 1;
+        }
+
+        // Identifier bytes are all ASCII, so this cannot fail.
+        std::str::from_utf8(&self.input[start..self.pos])
+            .unwrap_or_default()
+    }
+
+    /// Parses an identifier.
+    fn ident(&mut self) -> Result<String, ParseError> {
+        self.skip_trivia()?;
+
+        if !self.peek().is_some_and(|byte| byte.is_ascii_alphabetic() || byte == b'_') {
+            return Err(self.error(ParseErrorRepr::Expected("identifier")));
+        }
+
+        Ok(self.ident_bytes().to_string())
+    }
+
+    /// Parses a reference to a string with the given sigil (e.g. `$foo`).
+    ///
+    /// If `wildcard` is set, the reference can end with `*`, in which case the
+    /// returned boolean is set.
+    fn string_ref(&mut self, sigil: u8, wildcard: bool) -> Result<(String, bool), ParseError> {
+        self.skip_trivia()?;
+
+        if self.peek() != Some(sigil) {
+            return Err(self.error(ParseErrorRepr::Expected("string identifier")));
+        }
+        self.pos += 1;
+
+        let name = self.ident_bytes().to_string();
+        if wildcard && self.peek() == Some(b'*') {
+            self.pos += 1;
+            return Ok((name, true));
+        }
+
+        if name.is_empty() {
+            return Err(self.error(ParseErrorRepr::Expected("string identifier")));
+        }
+
+        Ok((name, false))
+    }
+
+    /// Parses an unsigned integer with an optional `KB` or `MB` suffix.
+    fn int(&mut self) -> Result<u64, ParseError> {
+        self.skip_trivia()?;
+        let start = self.pos;
+
+        let radix = if self.input[self.pos..].starts_with(b"0x") {
+            self.pos += 2;
+            16
+        } else {
+            10
+        };
+
+        let digits_start = self.pos;
+        while self.peek().is_some_and(|byte| (byte as char).is_digit(radix)) {
+            self.pos += 1;
+        }
+        if self.pos == digits_start {
+            return Err(self.error_at(start, ParseErrorRepr::Expected("integer")));
+        }
+
+        // Digits are all ASCII, so this cannot fail.
+        let digits = std::str::from_utf8(&self.input[digits_start..self.pos])
+            .unwrap_or_default();
+        let value = u64::from_str_radix(digits, radix)
+            .map_err(|_| self.error_at(start, ParseErrorRepr::IntegerOverflow))?;
+
+        let multiplier = if self.input[self.pos..].starts_with(b"KB") {
+            self.pos += 2;
+            1024
+        } else if self.input[self.pos..].starts_with(b"MB") {
+            self.pos += 2;
+            1024 * 1024
+        } else {
+            1
+        };
+
+        value.checked_mul(multiplier)
+            .ok_or_else(|| self.error_at(start, ParseErrorRepr::IntegerOverflow))
+    }
+
+    /// Parses a single rule and adds it to the rule set.
+    fn rule(&mut self, rules: &mut Rules) -> Result<(), ParseError> {
+        self.expect_keyword("rule")?;
+
+        self.skip_trivia()?;
+        let name_pos = self.pos;
+        let name = self.ident()?;
+        if rules.rules.iter().any(|rule| rule.name == name) {
+            return Err(self.error_at(name_pos, ParseErrorRepr::DuplicateRule(name)));
+        }
+
+        // Tags have no meaning for the scan results, so we just skip them.
+        if self.eat(":")? {
+            self.ident()?;
+            while !self.check("{")? {
+                self.ident()?;
+            }
+        }
+
+        self.expect("{")?;
+
+        // Similarly to tags, metadata is parsed but not used in any way.
+        if self.eat_keyword("meta")? {
+            self.expect(":")?;
+            while !self.check_keyword("strings")? && !self.check_keyword("condition")? {
+                self.meta()?;
+            }
+        }
+
+        let strings_start = rules.strings.len();
+        if self.eat_keyword("strings")? {
+            self.expect(":")?;
+            loop {
+                self.skip_trivia()?;
+                let string_pos = self.pos;
+
+                let (string_name, _) = self.string_ref(b'$', false)?;
+                if rules.strings[strings_start..].iter().any(|string| string.name == string_name) {
+                    let error = ParseErrorRepr::DuplicateString(string_name);
+                    return Err(self.error_at(string_pos, error));
+                }
+
+                self.expect("=")?;
+                rules.strings.push(StringDef {
+                    name: string_name,
+                    regex: self.string_def()?,
+                });
+
+                if !self.check("$")? {
+                    break;
+                }
+            }
+        }
+
+        self.expect_keyword("condition")?;
+        self.expect(":")?;
+
+        let scope = Scope {
+            strings: &rules.strings[strings_start..],
+            offset: strings_start,
+        };
+        let cond = self.expr_or(&scope)?;
+
+        self.expect("}")?;
+
+        rules.rules.push(Rule {
+            name,
+            strings: strings_start..rules.strings.len(),
+            cond,
+        });
+
+        Ok(())
+    }
+
+    /// Parses a single metadata entry.
+    fn meta(&mut self) -> Result<(), ParseError> {
+        self.ident()?;
+        self.expect("=")?;
+
+        if self.check("\"")? {
+            self.text()?;
+        } else if !self.eat_keyword("true")? && !self.eat_keyword("false")? {
+            self.eat("-")?;
+            self.int()?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses a string definition (including its modifiers) into a regex.
+    fn string_def(&mut self) -> Result<regex::bytes::Regex, ParseError> {
+        self.skip_trivia()?;
+        let start = self.pos;
+
+        enum Kind {
+            Text(Vec<u8>),
+            Hex(String),
+            Regex(String, String),
+        }
+
+        let kind = match self.peek() {
+            Some(b'"') => Kind::Text(self.text()?),
+            Some(b'{') => Kind::Hex(self.hex()?),
+            Some(b'/') => {
+                let (pattern, flags) = self.regex()?;
+                Kind::Regex(pattern, flags)
+            }
+            _ => return Err(self.error(ParseErrorRepr::Expected("string"))),
+        };
+
+        let mut nocase = false;
+        let mut wide = false;
+        let mut ascii = false;
+        loop {
+            self.skip_trivia()?;
+            let modifier_pos = self.pos;
+
+            if !self.peek().is_some_and(|byte| byte.is_ascii_alphabetic()) {
+                break;
+            }
+            if self.check_keyword("condition")? {
+                break;
+            }
+
+            let modifier = self.ident()?;
+            match (&kind, modifier.as_str()) {
+                (Kind::Text(_) | Kind::Regex(..), "nocase") => nocase = true,
+                (Kind::Text(_), "wide") => wide = true,
+                (Kind::Text(_), "ascii") => ascii = true,
+                _ => {
+                    let error = ParseErrorRepr::UnsupportedModifier(modifier);
+                    return Err(self.error_at(modifier_pos, error));
+                }
+            }
+        }
+
+        let pattern = match kind {
+            Kind::Text(text) => text_pattern(&text, nocase, wide, ascii),
+            Kind::Hex(pattern) => pattern,
+            Kind::Regex(pattern, mut flags) => {
+                // The regex might already be case-insensitive because of its
+                // own flag and `regex` rejects flags that are repeated.
+                if nocase && !flags.contains('i') {
+                    flags.push('i');
+                }
+                format!("(?{flags}-u){pattern}")
+            }
+        };
+
+        regex::bytes::Regex::new(&pattern)
+            .map_err(|error| self.error_at(start, ParseErrorRepr::InvalidRegex(error)))
+    }
+
+    /// Parses a double-quoted text string.
+    fn text(&mut self) -> Result<Vec<u8>, ParseError> {
+        self.expect("\"")?;
+
+        let mut text = Vec::new();
+        loop {
+            let byte = match self.peek() {
+                Some(b'\n') | None => {
+                    return Err(self.error(ParseErrorRepr::UnterminatedString));
+                }
+                Some(byte) => byte,
+            };
+            self.pos += 1;
+
+            match byte {
+                b'"' => return Ok(text),
+                b'\\' => {
+                    let escape_pos = self.pos - 1;
+                    let escaped = match self.peek() {
+                        Some(b'"') => b'"',
+                        Some(b'\\') => b'\\',
+                        Some(b'n') => b'\n',
+                        Some(b'r') => b'\r',
+                        Some(b't') => b'\t',
+                        Some(b'x') => {
+                            let hex = self.input.get(self.pos + 1..self.pos + 3)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                            match hex {
+                                Some(hex) => {
+                                    self.pos += 2;
+                                    hex
+                                }
+                                None => {
+                                    let error = ParseErrorRepr::InvalidEscape;
+                                    return Err(self.error_at(escape_pos, error));
+                                }
+                            }
+                        }
+                        _ => {
+                            return Err(self.error_at(escape_pos, ParseErrorRepr::InvalidEscape));
+                        }
+                    };
+                    self.pos += 1;
+
+                    text.push(escaped);
+                }
+                byte => text.push(byte),
+            }
+        }
+    }
+
+    /// Parses a hex string into an equivalent regex pattern.
+    fn hex(&mut self) -> Result<String, ParseError> {
+        self.skip_trivia()?;
+        let start = self.pos;
+        self.expect("{")?;
+
+        let mut pattern = String::from("(?s-u)");
+        let mut depth = 0;
+        let mut is_empty = true;
+
+        loop {
+            self.skip_trivia()?;
+            let token_pos = self.pos;
+
+            match self.peek() {
+                None => return Err(self.error(ParseErrorRepr::Expected("}"))),
+                Some(b'}') if depth == 0 => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'}') => return Err(self.error(ParseErrorRepr::Expected(")"))),
+                Some(b'(') => {
+                    self.pos += 1;
+                    depth += 1;
+                    pattern.push_str("(?:");
+                }
+                Some(b'|') if depth > 0 => {
+                    self.pos += 1;
+                    pattern.push('|');
+                }
+                Some(b')') if depth > 0 => {
+                    self.pos += 1;
+                    depth -= 1;
+                    pattern.push(')');
+                }
+                Some(b'[') => {
+                    self.pos += 1;
+
+                    let min = if self.check("-")? { 0 } else { self.int()? };
+                    let max = if self.eat("-")? {
+                        if self.check("]")? { None } else { Some(self.int()?) }
+                    } else {
+                        Some(min)
+                    };
+                    self.expect("]")?;
+
+                    match max {
+                        Some(max) if max < min => {
+                            return Err(self.error_at(token_pos, ParseErrorRepr::InvalidHexJump));
+                        }
+                        Some(max) if max == min => pattern.push_str(&format!(".{{{min}}}")),
+                        Some(max) => pattern.push_str(&format!(".{{{min},{max}}}?")),
+                        None => pattern.push_str(&format!(".{{{min},}}?")),
+                    }
+                }
+                Some(b'~') => {
+                    self.pos += 1;
+                    match self.hex_byte()? {
+                        Some(class) => pattern.push_str(&format!("[^{class}]")),
+                        None => {
+                            return Err(self.error_at(token_pos, ParseErrorRepr::Expected("hex byte")));
+                        }
+                    }
+                    is_empty = false;
+                }
+                Some(_) => {
+                    match self.hex_byte()? {
+                        Some(class) => pattern.push_str(&format!("[{class}]")),
+                        None => pattern.push('.'),
+                    }
+                    is_empty = false;
+                }
+            }
+        }
+
+        if is_empty {
+            return Err(self.error_at(start, ParseErrorRepr::EmptyHexString));
+        }
+
+        Ok(pattern)
+    }
+
+    /// Parses a hex byte with optional nibble wildcards into a regex class.
+    ///
+    /// The class is returned without the surrounding brackets. `None` is
+    /// returned if the byte is a full wildcard (`??`).
+    fn hex_byte(&mut self) -> Result<Option<String>, ParseError> {
+        fn nibble(byte: u8) -> Option<Option<u8>> {
+            match byte {
+                b'?' => Some(None),
+                // Hex digits are always less than 16, so the cast is safe.
+                _ => (byte as char).to_digit(16).map(|digit| Some(digit as u8)),
+            }
+        }
+
+        let high = self.input.get(self.pos).copied().and_then(nibble);
+        let low = self.input.get(self.pos + 1).copied().and_then(nibble);
+        let (Some(high), Some(low)) = (high, low) else {
+            return Err(self.error(ParseErrorRepr::Expected("hex byte")));
+        };
+        self.pos += 2;
+
+        let class = match (high, low) {
+            (Some(high), Some(low)) => format!("\\x{:02X}", (high << 4) | low),
+            (Some(high), None) => format!("\\x{:02X}-\\x{:02X}", high << 4, (high << 4) | 0xF),
+            (None, Some(low)) => {
+                (0..16).map(|high| format!("\\x{:02X}", (high << 4) | low)).collect()
+            }
+            (None, None) => return Ok(None),
+        };
+
+        Ok(Some(class))
+    }
+
+    /// Parses a slash-delimited regex and its flags.
+    fn regex(&mut self) -> Result<(String, String), ParseError> {
+        self.skip_trivia()?;
+        self.expect("/")?;
+
+        let mut pattern = Vec::new();
+        loop {
+            let byte = match self.peek() {
+                Some(b'\n') | None => {
+                    return Err(self.error(ParseErrorRepr::UnterminatedRegex));
+                }
+                Some(byte) => byte,
+            };
+            self.pos += 1;
+
+            match byte {
+                b'/' => break,
+                // Slashes have to be escaped in the source but they are not
+                // special in the regex syntax.
+                b'\\' if self.peek() == Some(b'/') => {
+                    self.pos += 1;
+                    pattern.push(b'/');
+                }
+                b'\\' => {
+                    pattern.push(b'\\');
+                    if let Some(escaped) = self.peek() {
+                        self.pos += 1;
+                        pattern.push(escaped);
+                    }
+                }
+                byte => pattern.push(byte),
+            }
+        }
+
+        let mut flags = String::new();
+        while let Some(flag @ (b'i' | b's')) = self.peek() {
+            self.pos += 1;
+            if !flags.contains(flag as char) {
+                flags.push(flag as char);
+            }
+        }
+
+        // The pattern consists of whole UTF-8 sequences of the input (as it is
+        // split only at ASCII bytes), so this cannot fail.
+        let pattern = String::from_utf8(pattern)
+            .unwrap_or_default();
+
+        Ok((pattern, flags))
+    }
+
+    /// Parses a disjunction of conditions.
+    fn expr_or(&mut self, scope: &Scope) -> Result<Expr, ParseError> {
+        let mut exprs = vec![self.expr_and(scope)?];
+        while self.eat_keyword("or")? {
+            exprs.push(self.expr_and(scope)?);
+        }
+
+        if exprs.len() == 1 {
+            Ok(exprs.remove(0))
+        } else {
+            Ok(Expr::Or(exprs))
+        }
+    }
+
+    /// Parses a conjunction of conditions.
+    fn expr_and(&mut self, scope: &Scope) -> Result<Expr, ParseError> {
+        let mut exprs = vec![self.expr_not(scope)?];
+        while self.eat_keyword("and")? {
+            exprs.push(self.expr_not(scope)?);
+        }
+
+        if exprs.len() == 1 {
+            Ok(exprs.remove(0))
+        } else {
+            Ok(Expr::And(exprs))
+        }
+    }
+
+    /// Parses a possibly negated condition.
+    fn expr_not(&mut self, scope: &Scope) -> Result<Expr, ParseError> {
+        if self.eat_keyword("not")? {
+            let expr = self.nested(|parser| parser.expr_not(scope))?;
+            Ok(Expr::Not(Box::new(expr)))
+        } else {
+            self.expr_primary(scope)
+        }
+    }
+
+    /// Parses a condition one nesting level deeper using the given function.
+    fn nested<F>(&mut self, parse: F) -> Result<Expr, ParseError>
+    where
+        F: FnOnce(&mut Self) -> Result<Expr, ParseError>,
+    {
+        if self.depth >= MAX_EXPR_DEPTH {
+            return Err(self.error(ParseErrorRepr::TooDeep));
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+
+        result
+    }
+
+    /// Parses an atomic or parenthesized condition.
+    fn expr_primary(&mut self, scope: &Scope) -> Result<Expr, ParseError> {
+        self.skip_trivia()?;
+        let start = self.pos;
+
+        if self.eat("(")? {
+            let expr = self.nested(|parser| parser.expr_or(scope))?;
+            self.expect(")")?;
+            return Ok(expr);
+        }
+
+        if self.eat_keyword("true")? {
+            return Ok(Expr::Bool(true));
+        }
+        if self.eat_keyword("false")? {
+            return Ok(Expr::Bool(false));
+        }
+        if self.eat_keyword("filesize")? {
+            let op = self.cmp_op()?;
+            return Ok(Expr::Filesize(op, self.int()?));
+        }
+
+        if self.eat_keyword("all")? {
+            return self.expr_of(scope, Quant::All);
+        }
+        if self.eat_keyword("any")? {
+            return self.expr_of(scope, Quant::Any);
+        }
+        if self.eat_keyword("none")? {
+            return self.expr_of(scope, Quant::None);
+        }
+
+        match self.peek() {
+            Some(b'$') => {
+                let (name, _) = self.string_ref(b'$', false)?;
+                let index = scope.resolve(&name)
+                    .ok_or_else(|| self.error_at(start, ParseErrorRepr::UndefinedString(name)))?;
+
+                Ok(Expr::Matched(index))
+            }
+            Some(b'#') => {
+                let (name, _) = self.string_ref(b'#', false)?;
+                let index = scope.resolve(&name)
+                    .ok_or_else(|| self.error_at(start, ParseErrorRepr::UndefinedString(name)))?;
+
+                let op = self.cmp_op()?;
+                Ok(Expr::Count(index, op, self.int()?))
+            }
+            Some(byte) if byte.is_ascii_digit() => {
+                let count = self.int()?;
+                self.expr_of(scope, Quant::AtLeast(count))
+            }
+            _ => Err(self.error(ParseErrorRepr::Expected("condition"))),
+        }
+    }
+
+    /// Parses the string set part of a quantified condition.
+    fn expr_of(&mut self, scope: &Scope, quant: Quant) -> Result<Expr, ParseError> {
+        self.expect_keyword("of")?;
+
+        if self.eat_keyword("them")? {
+            let indices = (0..scope.strings.len())
+                .map(|index| scope.offset + index)
+                .collect();
+
+            return Ok(Expr::Of(quant, indices));
+        }
+
+        self.expect("(")?;
+
+        let mut indices = Vec::new();
+        loop {
+            self.skip_trivia()?;
+            let ref_pos = self.pos;
+
+            let (name, is_prefix) = self.string_ref(b'$', true)?;
+            let resolved = if is_prefix {
+                scope.resolve_prefix(&name)
+            } else {
+                scope.resolve(&name).into_iter().collect()
+            };
+
+            if resolved.is_empty() {
+                let name = if is_prefix { format!("{name}*") } else { name };
+                return Err(self.error_at(ref_pos, ParseErrorRepr::UndefinedString(name)));
+            }
+
+            for index in resolved {
+                if !indices.contains(&index) {
+                    indices.push(index);
+                }
+            }
+
+            if !self.eat(",")? {
+                break;
+            }
+        }
+
+        self.expect(")")?;
+
+        Ok(Expr::Of(quant, indices))
+    }
+
+    /// Parses a comparison operator.
+    fn cmp_op(&mut self) -> Result<CmpOp, ParseError> {
+        const OPS: [(&str, CmpOp); 6] = [
+            ("==", CmpOp::Eq),
+            ("!=", CmpOp::Ne),
+            ("<=", CmpOp::Le),
+            (">=", CmpOp::Ge),
+            ("<", CmpOp::Lt),
+            (">", CmpOp::Gt),
+        ];
+
+        for (punct, op) in OPS {
+            if self.eat(punct)? {
+                return Ok(op);
+            }
+        }
+
+        Err(self.error(ParseErrorRepr::Expected("comparison operator")))
+    }
+}
+
+/// Determines whether the given byte can be a part of an identifier.
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Builds a regex pattern matching the given text with the given modifiers.
+fn text_pattern(text: &[u8], nocase: bool, wide: bool, ascii: bool) -> String {
+    fn push_escaped(pattern: &mut String, byte: u8) {
+        // Alphanumeric characters are never special, so we keep them as they
+        // are (which also makes it easier for case-insensitive matching).
+        if byte.is_ascii_alphanumeric() {
+            pattern.push(byte as char);
+        } else {
+            pattern.push_str(&format!("\\x{byte:02X}"));
+        }
+    }
+
+    let mut narrow_pattern = String::new();
+    let mut wide_pattern = String::new();
+    for byte in text {
+        push_escaped(&mut narrow_pattern, *byte);
+        push_escaped(&mut wide_pattern, *byte);
+        push_escaped(&mut wide_pattern, 0);
+    }
+
+    let flags = if nocase { "(?i-u)" } else { "(?-u)" };
+    match (ascii || !wide, wide) {
+        (true, true) => format!("{flags}(?:{narrow_pattern}|{wide_pattern})"),
+        (false, true) => format!("{flags}{wide_pattern}"),
+        (_, false) => format!("{flags}{narrow_pattern}"),
+    }
+}
+
+/// The error type for parsing rules.
+#[derive(Debug)]
+pub struct ParseError {
+    /// Line of the source (counted from 1) at which the error occurred.
+    line: usize,
+    repr: ParseErrorRepr,
+}
+
+/// Internal representation of the error type for parsing rules.
+#[derive(Debug)]
+enum ParseErrorRepr {
+    /// A specific token was expected.
+    Expected(&'static str),
+    /// Block comment is not terminated.
+    UnterminatedComment,
+    /// Text string is not terminated.
+    UnterminatedString,
+    /// Regex string is not terminated.
+    UnterminatedRegex,
+    /// Text string contains an invalid escape sequence.
+    InvalidEscape,
+    /// Integer literal does not fit in 64 bits.
+    IntegerOverflow,
+    /// Rule with the given name is already defined.
+    DuplicateRule(String),
+    /// String with the given name is already defined in the rule.
+    DuplicateString(String),
+    /// String with the given name is not defined in the rule.
+    UndefinedString(String),
+    /// Modifier is unknown or not supported for the given kind of string.
+    UnsupportedModifier(String),
+    /// Hex string has no bytes.
+    EmptyHexString,
+    /// Hex string jump has its lower bound greater than the upper bound.
+    InvalidHexJump,
+    /// String compiles to an invalid regex.
+    InvalidRegex(regex::Error),
+    /// Condition is nested too deeply.
+    TooDeep,
+}
+
+impl std::fmt::Display for ParseError {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.repr)
+    }
+}
+
+impl std::error::Error for ParseError {
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.repr {
+            ParseErrorRepr::InvalidRegex(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ParseErrorRepr {
+
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorRepr::Expected(token) => {
+                write!(f, "expected {token}")
+            }
+            ParseErrorRepr::UnterminatedComment => {
+                write!(f, "unterminated comment")
+            }
+            ParseErrorRepr::UnterminatedString => {
+                write!(f, "unterminated string")
+            }
+            ParseErrorRepr::UnterminatedRegex => {
+                write!(f, "unterminated regex")
+            }
+            ParseErrorRepr::InvalidEscape => {
+                write!(f, "invalid escape sequence")
+            }
+            ParseErrorRepr::IntegerOverflow => {
+                write!(f, "integer overflow")
+            }
+            ParseErrorRepr::DuplicateRule(name) => {
+                write!(f, "duplicate rule: {name:?}")
+            }
+            ParseErrorRepr::DuplicateString(name) => {
+                write!(f, "duplicate string: ${name}")
+            }
+            ParseErrorRepr::UndefinedString(name) => {
+                write!(f, "undefined string: ${name}")
+            }
+            ParseErrorRepr::UnsupportedModifier(name) => {
+                write!(f, "unsupported modifier: {name:?}")
+            }
+            ParseErrorRepr::EmptyHexString => {
+                write!(f, "empty hex string")
+            }
+            ParseErrorRepr::InvalidHexJump => {
+                write!(f, "invalid hex string jump")
+            }
+            ParseErrorRepr::InvalidRegex(error) => {
+                write!(f, "invalid regex: {error}")
+            }
+            ParseErrorRepr::TooDeep => {
+                write!(f, "condition nested too deeply")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Scans the given content against the given rules.
+    ///
+    /// Returns names of the rules that matched.
+    fn scan(rules: &str, content: &[u8]) -> Vec<String> {
+        scan_matches(rules, content).into_iter()
+            .map(|rule_match| rule_match.rule_name)
+            .collect()
+    }
+
+    /// Scans the given content against the given rules.
+    fn scan_matches(rules: &str, content: &[u8]) -> Vec<RuleMatch> {
+        let rules = Rules::parse(rules)
+            .unwrap();
+
+        let mut scan = rules.scan();
+        for (index, regex) in rules.regexes().enumerate() {
+            for matcz in regex.find_iter(content) {
+                scan.record(index, matcz.start() as u64, matcz.as_bytes());
+            }
+        }
+
+        scan.finish(content.len() as u64)
+    }
+
+    #[test]
+    fn parse_empty() {
+        let rules = Rules::parse("// Nothing to see here.\n/* Really. */")
+            .unwrap();
+
+        assert!(rules.is_empty());
+    }
+
+    #[test]
+    fn parse_tags_and_meta() {
+        let rules = Rules::parse(r#"
+            rule foo : bar baz {
+                meta:
+                    author = "quux"
+                    version = 42
+                    offset = -1
+                    draft = false
+                condition:
+                    true
+            }
+        "#).unwrap();
+
+        assert!(!rules.is_empty());
+    }
+
+    #[test]
+    fn parse_error_line() {
+        let error = Rules::parse("rule foo {\n    condition:\n        $bar\n}")
+            .err()
+            .unwrap();
+
+        assert_eq!(error.line, 3);
+        assert!(matches!(error.repr, ParseErrorRepr::UndefinedString(ref name) if name == "bar"));
+    }
+
+    #[test]
+    fn parse_duplicate_rule() {
+        let error = Rules::parse(r#"
+            rule foo { condition: true }
+            rule foo { condition: false }
+        "#).err().unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::DuplicateRule(_)));
+    }
+
+    #[test]
+    fn parse_duplicate_string() {
+        let error = Rules::parse(r#"
+            rule foo {
+                strings: $a = "bar" $a = "baz"
+                condition: $a
+            }
+        "#).err().unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::DuplicateString(_)));
+    }
+
+    #[test]
+    fn parse_unsupported_modifier() {
+        let error = Rules::parse(r#"
+            rule foo {
+                strings: $a = { 00 } nocase
+                condition: $a
+            }
+        "#).err().unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::UnsupportedModifier(_)));
+    }
+
+    #[test]
+    fn parse_unterminated_string() {
+        let error = Rules::parse("rule foo { strings: $a = \"bar\n condition: $a }")
+            .err()
+            .unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::UnterminatedString));
+    }
+
+    #[test]
+    fn parse_empty_hex_string() {
+        let error = Rules::parse("rule foo { strings: $a = { } condition: $a }")
+            .err()
+            .unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::EmptyHexString));
+    }
+
+    #[test]
+    fn parse_invalid_hex_jump() {
+        let error = Rules::parse("rule foo { strings: $a = { 00 [4-2] 00 } condition: $a }")
+            .err()
+            .unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::InvalidHexJump));
+    }
+
+    #[test]
+    fn parse_invalid_regex() {
+        let error = Rules::parse("rule foo { strings: $a = /(/ condition: $a }")
+            .err()
+            .unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::InvalidRegex(_)));
+    }
+
+    #[test]
+    fn parse_integer_overflow() {
+        let error = Rules::parse("rule foo { condition: filesize > 99999999999999999999 }")
+            .err()
+            .unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::IntegerOverflow));
+    }
+
+    #[test]
+    fn parse_too_deep_parens() {
+        let condition = format!("{}true{}", "(".repeat(100_000), ")".repeat(100_000));
+
+        let error = Rules::parse(&format!("rule foo {{ condition: {condition} }}"))
+            .err()
+            .unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::TooDeep));
+    }
+
+    #[test]
+    fn parse_too_deep_not() {
+        let condition = format!("{}true", "not ".repeat(100_000));
+
+        let error = Rules::parse(&format!("rule foo {{ condition: {condition} }}"))
+            .err()
+            .unwrap();
+
+        assert!(matches!(error.repr, ParseErrorRepr::TooDeep));
+    }
+
+    #[test]
+    fn scan_long_and_chain() {
+        let condition = vec!["$a"; 100_000].join(" and ");
+        let rules = format!(r#"
+            rule foo {{
+                strings: $a = "foo"
+                condition: {condition}
+            }}
+        "#);
+
+        assert_eq!(scan(&rules, b"foo"), vec!["foo"]);
+    }
+
+    #[test]
+    fn scan_nested() {
+        let rules = r#"
+            rule foo {
+                condition: ((not (not true) or false) or (true and not false))
+            }
+        "#;
+
+        assert_eq!(scan(rules, b""), vec!["foo"]);
+    }
+
+    #[test]
+    fn scan_text() {
+        let rules = r#"
+            rule foo {
+                strings: $a = "foo\x00\"bar\""
+                condition: $a
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"xx foo\x00\"bar\" xx"), vec!["foo"]);
+        assert!(scan(rules, b"foo\"bar\"").is_empty());
+    }
+
+    #[test]
+    fn scan_text_nocase() {
+        let rules = r#"
+            rule foo {
+                strings: $a = "FoO.bar" nocase
+                condition: $a
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"xx fOo.BAR xx"), vec!["foo"]);
+        assert!(scan(rules, b"foo_bar").is_empty());
+    }
+
+    #[test]
+    fn scan_text_wide() {
+        let rules = r#"
+            rule foo {
+                strings: $a = "ab" wide
+                condition: $a
+            }
+
+            rule bar {
+                strings: $a = "ab" wide ascii
+                condition: #a == 2
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"a\x00b\x00"), vec!["foo"]);
+        assert_eq!(scan(rules, b"ab a\x00b\x00"), vec!["foo", "bar"]);
+        assert!(scan(rules, b"ab").is_empty());
+    }
+
+    #[test]
+    fn scan_hex() {
+        let rules = r#"
+            rule foo {
+                strings: $a = { 4D 5A ?? 0? ?F [1-2] ( 00 | FF 00 ) ~00 }
+                condition: $a
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"\x4D\x5A\x12\x03\x1F\xAA\xFF\x00\x01"), vec!["foo"]);
+        assert_eq!(scan(rules, b"\x4D\x5A\x12\x03\x1F\xAA\xBB\x00\x01"), vec!["foo"]);
+        assert!(scan(rules, b"\x4D\x5A\x12\x13\x1F\xAA\x00\x01").is_empty());
+        assert!(scan(rules, b"\x4D\x5A\x12\x03\x1E\xAA\x00\x01").is_empty());
+        assert!(scan(rules, b"\x4D\x5A\x12\x03\x1F\xAA\x00\x00").is_empty());
+        assert!(scan(rules, b"\x4D\x5A\x12\x03\x1F\x00").is_empty());
+    }
+
+    #[test]
+    fn scan_hex_unbounded_jump() {
+        let rules = r#"
+            rule foo {
+                strings: $a = { AA [2-] BB }
+                condition: $a
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"\xAA\x00\x00\x00\x00\xBB"), vec!["foo"]);
+        assert!(scan(rules, b"\xAA\x00\xBB").is_empty());
+    }
+
+    #[test]
+    fn scan_regex() {
+        let rules = r#"
+            rule foo {
+                strings: $a = /ba[rz]\/qu+x/i
+                condition: $a
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"BAZ/quuux"), vec!["foo"]);
+        assert!(scan(rules, b"bat/quux").is_empty());
+    }
+
+    #[test]
+    fn scan_regex_nocase() {
+        let rules = r#"
+            rule foo {
+                strings:
+                    $a = /foo/ nocase
+                    $b = /bar/i nocase
+                    $c = /baz/ii
+                condition: $a and $b and $c
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"FOO BAR BAZ"), vec!["foo"]);
+        assert!(scan(rules, b"FOO BAR").is_empty());
+    }
+
+    #[test]
+    fn scan_regex_invalid_utf8() {
+        let rules = r#"
+            rule foo {
+                strings: $a = /\xFF.\xFE/s
+                condition: $a
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"\xFF\n\xFE"), vec!["foo"]);
+    }
+
+    #[test]
+    fn scan_bool_ops() {
+        let rules = r#"
+            rule foo {
+                strings:
+                    $a = "a"
+                    $b = "b"
+                    $c = "c"
+                condition:
+                    $a and not ($b or $c)
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"a"), vec!["foo"]);
+        assert!(scan(rules, b"ab").is_empty());
+        assert!(scan(rules, b"ac").is_empty());
+        assert!(scan(rules, b"c").is_empty());
+    }
+
+    #[test]
+    fn scan_count() {
+        let rules = r#"
+            rule foo {
+                strings: $a = "a"
+                condition: #a >= 2 and #a != 3
+            }
+        "#;
+
+        assert!(scan(rules, b"a").is_empty());
+        assert_eq!(scan(rules, b"aa"), vec!["foo"]);
+        assert!(scan(rules, b"aaa").is_empty());
+        assert_eq!(scan(rules, b"aaaa"), vec!["foo"]);
+    }
+
+    #[test]
+    fn scan_filesize() {
+        let rules = r#"
+            rule small { condition: filesize < 1KB }
+            rule exact { condition: filesize == 0x400 }
+            rule big { condition: filesize > 1MB }
+        "#;
+
+        assert_eq!(scan(rules, &[0; 1023]), vec!["small"]);
+        assert_eq!(scan(rules, &[0; 1024]), vec!["exact"]);
+        assert_eq!(scan(rules, &vec![0; 1024 * 1024 + 1]), vec!["big"]);
+    }
+
+    #[test]
+    fn scan_of() {
+        let rules = r#"
+            rule all_them {
+                strings: $a = "a" $b = "b" $c = "c"
+                condition: all of them
+            }
+
+            rule any_prefix {
+                strings: $foo1 = "a" $foo2 = "b" $bar = "c"
+                condition: any of ($foo*)
+            }
+
+            rule none_list {
+                strings: $a = "a" $b = "b" $c = "c"
+                condition: none of ($a, $b)
+            }
+
+            rule two_them {
+                strings: $a = "a" $b = "b" $c = "c"
+                condition: 2 of them
+            }
+        "#;
+
+        assert_eq!(scan(rules, b"abc"), vec!["all_them", "any_prefix", "two_them"]);
+        assert_eq!(scan(rules, b"bc"), vec!["any_prefix", "two_them"]);
+        assert_eq!(scan(rules, b"c"), vec!["none_list"]);
+    }
+
+    #[test]
+    fn scan_string_matches() {
+        let rule_matches = scan_matches(r#"
+            rule foo {
+                strings: $a = "foo" $b = "bar"
+                condition: $a
+            }
+        "#, b"foo bar foo");
+
+        assert_eq!(rule_matches.len(), 1);
+        assert_eq!(rule_matches[0].string_matches, vec![
+            StringMatch { id: String::from("$a"), offset: 0, data: b"foo".to_vec() },
+            StringMatch { id: String::from("$a"), offset: 8, data: b"foo".to_vec() },
+            StringMatch { id: String::from("$b"), offset: 4, data: b"bar".to_vec() },
+        ]);
+    }
+
+    #[test]
+    fn scan_string_matches_limits() {
+        let content = "a".repeat(MAX_STRING_MATCH_COUNT + 1);
+
+        let rule_matches = scan_matches(&format!(r#"
+            rule foo {{
+                strings: $a = "a" $b = /a+/
+                condition: #a == {}
+            }}
+        "#, MAX_STRING_MATCH_COUNT + 1), content.as_bytes());
+
+        assert_eq!(rule_matches.len(), 1);
+
+        let string_matches = &rule_matches[0].string_matches;
+        assert_eq!(string_matches.len(), MAX_STRING_MATCH_COUNT + 1);
+        assert_eq!(string_matches[MAX_STRING_MATCH_COUNT].id, "$b");
+        assert_eq!(string_matches[MAX_STRING_MATCH_COUNT].data.len(), MAX_STRING_MATCH_DATA_LEN);
+    }
+}
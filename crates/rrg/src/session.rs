@@ -15,22 +15,29 @@
 //! for a particular request.
 
 mod error;
+mod usage;
 
 #[cfg(test)]
 mod fake;
 mod fleetspeak;
+mod offline;
 
 #[cfg(test)]
 pub use crate::session::fake::FakeSession;
 pub use crate::session::fleetspeak::FleetspeakSession;
+pub use crate::session::offline::OfflineSession;
 
-pub use self::error::{Error};
+pub use self::error::{Error, ErrorKind};
+pub use self::usage::HelperCpuTime;
 
 /// A specialized `Result` type for sessions.
 pub type Result<T> = std::result::Result<T, Error>;
 
 /// Abstraction for various kinds of sessions.
 pub trait Session {
+    /// Provides the command-line arguments the agent was started with.
+    fn args(&self) -> &crate::args::Args;
+
     /// Sends a reply to the flow that call the action.
     fn reply<I>(&mut self, item: I) -> Result<()>
     where I: crate::response::Item + 'static;
@@ -41,6 +48,13 @@ pub trait Session {
 
     /// Sends a heartbeat signal to the Fleetspeak process.
     fn heartbeat(&mut self);
+
+    /// Verifies that the session has not exceeded its time limits.
+    ///
+    /// Limits are checked whenever a reply is sent, so long-running actions
+    /// that can go without replying for a while should call this regularly to
+    /// stop once the limits are exceeded.
+    fn check_time_limits(&mut self) -> Result<()>;
 }
 
 #[cfg(test)]
@@ -27,8 +27,12 @@ pub enum ErrorKind {
     FilterFailure,
     /// Action execution crossed the allowed network bytes limit.
     NetworkBytesLimitExceeded,
+    /// Action execution crossed the allowed CPU time limit.
+    CpuTimeLimitExceeded,
     /// Action execution crossed the allowed real (wall) time limit.
     RealTimeLimitExceeded,
+    /// Command requested for execution was not properly signed.
+    InvalidCommandSignature,
 }
 
 impl Error {
@@ -47,6 +51,20 @@ impl Error {
         }
     }
 
+    /// Converts a command signature verification error to a session error.
+    ///
+    /// This function should be used by actions executing signed commands to
+    /// report commands that are unsigned or whose signature does not match.
+    pub fn invalid_command_signature<E>(error: E) -> Error
+    where
+        E: std::error::Error + 'static,
+    {
+        Error {
+            kind: ErrorKind::InvalidCommandSignature,
+            error: Box::new(error),
+        }
+    }
+
     /// Converts an action that is not supported to a session error.
     pub fn unsupported_action(action: crate::request::Action) -> Error {
         Error {
@@ -54,6 +72,11 @@ impl Error {
             error: Box::new(UnsupportedActionError { action }),
         }
     }
+
+    /// Returns the corresponding [`ErrorKind`] of this error.
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -85,9 +108,15 @@ impl std::fmt::Display for Error {
             NetworkBytesLimitExceeded => {
                 write!(fmt, "network bytes limit exceeded: {}", self.error)
             }
+            CpuTimeLimitExceeded => {
+                write!(fmt, "CPU time limit exceeded: {}", self.error)
+            }
             RealTimeLimitExceeded => {
                 write!(fmt, "real time limit exceeded: {}", self.error)
             }
+            InvalidCommandSignature => {
+                write!(fmt, "invalid command signature: {}", self.error)
+            }
         }
     }
 }
@@ -152,7 +181,9 @@ impl From<ErrorKind> for rrg_proto::rrg::status::error::Type {
             ActionFailure => Self::ACTION_FAILURE,
             FilterFailure => Self::FILTER_FAILURE,
             NetworkBytesLimitExceeded => Self::NETWORK_BYTES_SENT_LIMIT_EXCEEDED,
+            CpuTimeLimitExceeded => Self::CPU_TIME_LIMIT_EXCEEDED,
             RealTimeLimitExceeded => Self::REAL_TIME_LIMIT_EXCEEDED,
+            InvalidCommandSignature => Self::INVALID_COMMAND_SIGNATURE,
         }
     }
 }
@@ -207,6 +238,40 @@ impl From<NetworkBytesLimitExceededError> for Error {
     }
 }
 
+/// An error type raised when the CPU time limit has been exceeded.
+#[derive(Debug)]
+pub struct CpuTimeLimitExceededError {
+    /// Amount of CPU time we actually spent on executing the action.
+    pub cpu_time_spent: std::time::Duration,
+    /// Amount of CPU time we were allowed to spend on executing the action.
+    pub cpu_time_limit: std::time::Duration,
+}
+
+impl std::fmt::Display for CpuTimeLimitExceededError {
+
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write! {
+            fmt,
+            "spent CPU time {} out of allowed {}",
+            humantime::format_duration(self.cpu_time_spent),
+            humantime::format_duration(self.cpu_time_limit),
+        }
+    }
+}
+
+impl std::error::Error for CpuTimeLimitExceededError {
+}
+
+impl From<CpuTimeLimitExceededError> for Error {
+
+    fn from(error: CpuTimeLimitExceededError) -> Error {
+        Error {
+            kind: ErrorKind::CpuTimeLimitExceeded,
+            error: Box::new(error),
+        }
+    }
+}
+
 /// An error type raised when the real (wall) time limit has been exceeded.
 #[derive(Debug)]
 pub struct RealTimeLimitExceededError {
@@ -12,20 +12,51 @@ use crate::Sink;
 /// Instead, one can use a `Fake` session. It simply accumulates responses
 /// that the action sends and lets the creator inspect them later.
 pub struct FakeSession {
+    args: crate::args::Args,
     replies: Vec<Box<dyn Any>>,
     parcels: std::collections::HashMap<Sink, Vec<Box<dyn Any>>>,
+    real_time_start: std::time::Instant,
+    real_time_limit: Option<std::time::Duration>,
 }
 
 impl FakeSession {
 
-    /// Constructs a new fake session.
+    /// Constructs a new fake session with test default agent arguments.
     pub fn new() -> FakeSession {
+        FakeSession::with_args(crate::args::Args {
+            heartbeat_rate: std::time::Duration::from_secs(0),
+            verbosity: log::LevelFilter::Debug,
+            log_to_stdout: false,
+            log_to_file: None,
+            offline_input: None,
+            offline_output_file: None,
+            offline_output_dir: None,
+            #[cfg(feature = "action-execute_signed_command")]
+            command_verification_key: None,
+            #[cfg(feature = "action-get_filesystem_timeline")]
+            timeline_snapshot_dir: None,
+        })
+    }
+
+    /// Constructs a new fake session with the given agent arguments.
+    pub fn with_args(args: crate::args::Args) -> FakeSession {
         FakeSession {
+            args,
             replies: Vec::new(),
             parcels: std::collections::HashMap::new(),
+            real_time_start: std::time::Instant::now(),
+            real_time_limit: None,
         }
     }
 
+    /// Sets the real time limit enforced by [`Session::check_time_limits`].
+    ///
+    /// [`Session::check_time_limits`]: crate::session::Session::check_time_limits
+    pub fn with_real_time_limit(mut self, limit: std::time::Duration) -> FakeSession {
+        self.real_time_limit = Some(limit);
+        self
+    }
+
     /// Yields the number of replies that this session sent so far.
     pub fn reply_count(&self) -> usize {
         self.replies.len()
@@ -111,6 +142,10 @@ impl FakeSession {
 
 impl crate::session::Session for FakeSession {
 
+    fn args(&self) -> &crate::args::Args {
+        &self.args
+    }
+
     fn reply<I>(&mut self, item: I) -> crate::session::Result<()>
     where
         I: crate::response::Item + 'static,
@@ -132,4 +167,20 @@ impl crate::session::Session for FakeSession {
 
     fn heartbeat(&mut self) {
     }
+
+    fn check_time_limits(&mut self) -> crate::session::Result<()> {
+        use crate::session::error::RealTimeLimitExceededError;
+
+        if let Some(real_time_limit) = self.real_time_limit {
+            let real_time_spent = self.real_time_start.elapsed();
+            if real_time_spent > real_time_limit {
+                return Err(RealTimeLimitExceededError {
+                    real_time_spent,
+                    real_time_limit,
+                }.into());
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -5,22 +5,16 @@ use log::{error, info};
 /// This is a normal session type that that is associated with some flow on the
 /// server. It keeps track of the responses it sends and collects statistics
 /// about network and runtime utilization to kill the action if it is needed.
-pub struct FleetspeakSession {
+pub struct FleetspeakSession<'a> {
+    /// Arguments passed to the agent.
+    args: &'a crate::args::Args,
     /// A builder for responses sent through Fleetspeak to the GRR server.
     response_builder: crate::ResponseBuilder,
-    /// Maximum frequency of heartbeat messages to send to Fleetspeak.
-    heartbeat_rate: std::time::Duration,
-    /// Number of bytes sent since the session was created.
-    network_bytes_sent: u64,
-    /// Number of bytes we are allowed to send within the session.
-    network_bytes_limit: Option<u64>,
-    /// Time at which the session was created.
-    real_time_start: std::time::Instant,
-    /// Time which we are allowed to spend within the session.
-    real_time_limit: Option<std::time::Duration>,
+    /// Resource usage of the session and its limits.
+    usage: crate::session::usage::Usage,
 }
 
-impl FleetspeakSession {
+impl<'a> FleetspeakSession<'a> {
 
     /// Dispatches the given `request` to an appropriate action handler.
     ///
@@ -34,9 +28,9 @@ impl FleetspeakSession {
     ///
     /// Long-running actions spawned by requests that need to send heartbeat
     /// signal to Fleetspeak will do so with frequency not greater than the one
-    /// specified `heartbeat_rate`.
+    /// specified in the `heartbeat_rate` field of the agent `args`.
     pub fn dispatch(
-        heartbeat_rate: std::time::Duration,
+        args: &'a crate::args::Args,
         request: Result<crate::Request, crate::ParseRequestError>,
     ) {
         let request_id = match &request {
@@ -58,18 +52,16 @@ impl FleetspeakSession {
             Ok(mut request) => {
                 let filters = request.take_filters();
                 let mut session = FleetspeakSession {
+                    args,
                     response_builder: response_builder.with_filters(filters),
-                    heartbeat_rate,
-                    network_bytes_sent: 0,
-                    network_bytes_limit: request.network_bytes_limit(),
-                    real_time_start: std::time::Instant::now(),
-                    real_time_limit: request.real_time_limit(),
+                    usage: crate::session::usage::Usage::start(&request),
                 };
 
                 let result = crate::log::ResponseLogger::new(&request)
                     .context(|| crate::action::dispatch(&mut session, request));
 
-                session.response_builder.status(result)
+                let status = session.response_builder.status(result);
+                session.usage.finalize(status)
             },
             Err(error) => {
                 error!("invalid request '{request_id}': {error}");
@@ -81,47 +73,11 @@ impl FleetspeakSession {
     }
 }
 
-impl FleetspeakSession {
+impl<'a> crate::session::Session for FleetspeakSession<'a> {
 
-    /// Checks whether the network bytes limit was crossed.
-    ///
-    /// This function will return an error if it was.
-    fn check_network_bytes_limit(&self) -> crate::session::Result<()> {
-        use crate::session::error::NetworkBytesLimitExceededError;
-
-        if let Some(network_bytes_limit) = self.network_bytes_limit {
-            if self.network_bytes_sent > network_bytes_limit {
-                return Err(NetworkBytesLimitExceededError {
-                    network_bytes_sent: self.network_bytes_sent,
-                    network_bytes_limit,
-                }.into());
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Checks whether the real (wall) time limit was crossed.
-    ///
-    /// This function will return an error if it was.
-    fn check_real_time_limit(&self) -> crate::session::Result<()> {
-        use crate::session::error::RealTimeLimitExceededError;
-
-        if let Some(real_time_limit) = self.real_time_limit {
-            let real_time_spent = self.real_time_start.elapsed();
-            if real_time_spent > real_time_limit {
-                return Err(RealTimeLimitExceededError {
-                    real_time_spent,
-                    real_time_limit,
-                }.into());
-            }
-        }
-
-        Ok(())
+    fn args(&self) -> &crate::args::Args {
+        self.args
     }
-}
-
-impl crate::session::Session for FleetspeakSession {
 
     fn reply<I>(&mut self, item: I) -> crate::session::Result<()>
     where
@@ -136,13 +92,7 @@ impl crate::session::Session for FleetspeakSession {
             Error(error) => return Err(error.into()),
         };
 
-        self.network_bytes_sent += reply.send_unaccounted() as u64;
-        self.check_network_bytes_limit()?;
-
-        // TODO(@panhania): Enforce CPU time limits.
-        self.check_real_time_limit()?;
-
-        Ok(())
+        self.usage.record_sent(reply.send_unaccounted())
     }
 
     fn send<I>(&mut self, sink: crate::Sink, item: I) -> crate::session::Result<()>
@@ -151,16 +101,14 @@ impl crate::session::Session for FleetspeakSession {
     {
         let parcel = crate::response::Parcel::new(sink, item);
 
-        self.network_bytes_sent += parcel.send_unaccounted() as u64;
-        self.check_network_bytes_limit()?;
-
-        // TODO(@panhania): Enforce CPU time limits.
-        self.check_real_time_limit()?;
-
-        Ok(())
+        self.usage.record_sent(parcel.send_unaccounted())
     }
 
     fn heartbeat(&mut self) {
-        fleetspeak::heartbeat_with_throttle(self.heartbeat_rate);
+        fleetspeak::heartbeat_with_throttle(self.args.heartbeat_rate);
+    }
+
+    fn check_time_limits(&mut self) -> crate::session::Result<()> {
+        self.usage.check_time_limits()
     }
 }
@@ -0,0 +1,290 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use log::{error, info};
+
+/// A session implementation that writes all the messages to an offline output.
+///
+/// This session type is used when the agent runs without Fleetspeak (e.g. on
+/// air-gapped hosts). It behaves just like [`FleetspeakSession`] (i.e. applies
+/// filters and enforces limits in the same way) but instead of sending messages
+/// to the GRR server it writes them to an [`Output`].
+///
+/// [`FleetspeakSession`]: crate::session::FleetspeakSession
+/// [`Output`]: crate::offline::Output
+pub struct OfflineSession<'a> {
+    /// Arguments passed to the agent.
+    args: &'a crate::args::Args,
+    /// Output to write the messages to.
+    output: &'a std::sync::Arc<crate::offline::Output>,
+    /// A builder for responses written to the output.
+    response_builder: crate::ResponseBuilder,
+    /// Resource usage of the session and its limits.
+    usage: crate::session::usage::Usage,
+}
+
+impl<'a> OfflineSession<'a> {
+
+    /// Dispatches the given `request` to an appropriate action handler.
+    ///
+    /// This is the main entry point of the session. It processes the request
+    /// and writes the execution status to the output.
+    ///
+    /// See [`FleetspeakSession::dispatch`] for more details.
+    ///
+    /// [`FleetspeakSession::dispatch`]: crate::session::FleetspeakSession::dispatch
+    pub fn dispatch(
+        args: &'a crate::args::Args,
+        output: &'a std::sync::Arc<crate::offline::Output>,
+        request: Result<crate::Request, crate::ParseRequestError>,
+    ) {
+        let request_id = match &request {
+            Ok(request) => request.id(),
+            Err(error) => match error.request_id() {
+                Some(request_id) => request_id,
+                None => {
+                    error!("invalid request: {}", error);
+                    return;
+                }
+            }
+        };
+
+        info!("received request '{request_id}'");
+
+        let response_builder = crate::ResponseBuilder::new(request_id);
+
+        let status = match request {
+            Ok(mut request) => {
+                let filters = request.take_filters();
+                let mut session = OfflineSession {
+                    args,
+                    output,
+                    response_builder: response_builder.with_filters(filters),
+                    usage: crate::session::usage::Usage::start(&request),
+                };
+
+                let result = crate::log::ResponseLogger::new(&request)
+                    .with_output(output.clone())
+                    .context(|| crate::action::dispatch(&mut session, request));
+
+                let status = session.response_builder.status(result);
+                session.usage.finalize(status)
+            },
+            Err(error) => {
+                error!("invalid request '{request_id}': {error}");
+                response_builder.status(Err(error.into()))
+            }
+        };
+
+        if let Err(error) = output.write_response(status.into()) {
+            error!("failed to write status of request '{request_id}': {error}");
+        }
+    }
+}
+
+impl<'a> crate::session::Session for OfflineSession<'a> {
+
+    fn args(&self) -> &crate::args::Args {
+        self.args
+    }
+
+    fn reply<I>(&mut self, item: I) -> crate::session::Result<()>
+    where
+        I: crate::response::Item,
+    {
+        let item = crate::response::PreparedItem::from(item);
+
+        use crate::response::FilteredReply::*;
+        let reply = match self.response_builder.reply(item) {
+            Accepted(reply) => reply,
+            Rejected => return Ok(()),
+            Error(error) => return Err(error.into()),
+        };
+
+        let len = self.output.write_response(reply.into())
+            .map_err(crate::session::Error::action)?;
+
+        self.usage.record_sent(len)
+    }
+
+    fn send<I>(&mut self, sink: crate::Sink, item: I) -> crate::session::Result<()>
+    where
+        I: crate::response::Item,
+    {
+        let parcel = crate::response::Parcel::new(sink, item);
+
+        let len = self.output.write_parcel(parcel.into())
+            .map_err(crate::session::Error::action)?;
+
+        self.usage.record_sent(len)
+    }
+
+    fn heartbeat(&mut self) {
+        // There is no Fleetspeak process that could kill us for being
+        // unresponsive, so there is nothing to do here.
+    }
+
+    fn check_time_limits(&mut self) -> crate::session::Result<()> {
+        self.usage.check_time_limits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Dispatches the given request and returns all messages written.
+    fn dispatch(request: rrg_proto::rrg::Request) -> Vec<rrg_proto::rrg::OfflineMessage> {
+        use protobuf::Message as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        let spool_path = tempdir.path().join("spool");
+
+        let output = std::sync::Arc::new({
+            crate::offline::Output::spool_dir(&spool_path).unwrap()
+        });
+
+        let request = crate::Request::try_from(request);
+        OfflineSession::dispatch(&args(), &output, request);
+
+        let mut names = std::fs::read_dir(&spool_path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+
+        names.into_iter().map(|name| {
+            let data = std::fs::read(spool_path.join(name))
+                .unwrap();
+
+            rrg_proto::rrg::OfflineMessage::parse_from_bytes(&data)
+                .unwrap()
+        }).collect()
+    }
+
+    /// Returns default agent arguments used in tests.
+    fn args() -> crate::args::Args {
+        argh::FromArgs::from_args(&["rrg"], &[])
+            .unwrap()
+    }
+
+    #[test]
+    fn dispatch_unknown_action() {
+        let mut request = rrg_proto::rrg::Request::new();
+        request.set_flow_id(0xF00);
+        request.set_request_id(0xB42);
+        request.action = protobuf::EnumOrUnknown::from_i32(0xDEAD);
+
+        let messages = dispatch(request);
+        assert_eq!(messages.len(), 1);
+
+        let response = messages[0].response();
+        assert_eq!(response.flow_id(), 0xF00);
+        assert_eq!(response.request_id(), 0xB42);
+        assert_eq! {
+            response.status().error().type_(),
+            rrg_proto::rrg::status::error::Type::UNKNOWN_ACTION
+        };
+    }
+
+    #[cfg(feature = "action-list_directory")]
+    fn list_directory_request(path: &std::path::Path) -> rrg_proto::rrg::Request {
+        let mut args = rrg_proto::list_directory::Args::new();
+        args.set_path(path.to_path_buf().into());
+
+        let mut request = rrg_proto::rrg::Request::new();
+        request.set_flow_id(0xF00);
+        request.set_request_id(0xB42);
+        request.set_action(rrg_proto::rrg::Action::LIST_DIRECTORY);
+        request.set_args(protobuf::well_known_types::any::Any::pack(&args).unwrap());
+
+        request
+    }
+
+    #[cfg(feature = "action-list_directory")]
+    #[test]
+    fn dispatch_replies_and_status() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar"), b"")
+            .unwrap();
+
+        let messages = dispatch(list_directory_request(tempdir.path()));
+        assert_eq!(messages.len(), 2 + 1);
+
+        for (i, message) in messages.iter().enumerate() {
+            assert_eq!(message.response().response_id(), i as u64 + 1);
+        }
+
+        let status = messages[2].response().status();
+        assert!(!status.has_error());
+        assert!(status.has_real_time());
+    }
+
+    #[cfg(feature = "action-list_directory")]
+    #[test]
+    fn dispatch_filters() {
+        use protobuf::Message as _;
+
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar"), b"")
+            .unwrap();
+
+        let mut condition = rrg_proto::rrg::Condition::new();
+        condition.set_field(vec![1, 1]);
+        condition.set_bytes_match(String::from("foo$"));
+
+        let mut filter = rrg_proto::rrg::Filter::new();
+        filter.mut_conditions().push(condition);
+
+        let mut request = list_directory_request(tempdir.path());
+        request.mut_filters().push(filter);
+
+        let messages = dispatch(request);
+        assert_eq!(messages.len(), 1 + 1);
+
+        let result = rrg_proto::list_directory::Result::parse_from_bytes({
+            &messages[0].response().result().value
+        }).unwrap();
+        assert_eq! {
+            result.path().raw_bytes(),
+            rrg_proto::path::into_bytes(tempdir.path().join("foo"))
+        };
+
+        let status = messages[1].response().status();
+        assert!(!status.has_error());
+        assert_eq!(status.filtered_out_count(), 1);
+    }
+
+    #[cfg(feature = "action-list_directory")]
+    #[test]
+    fn dispatch_network_bytes_limit() {
+        let tempdir = tempfile::tempdir()
+            .unwrap();
+        std::fs::write(tempdir.path().join("foo"), b"")
+            .unwrap();
+        std::fs::write(tempdir.path().join("bar"), b"")
+            .unwrap();
+
+        let mut request = list_directory_request(tempdir.path());
+        request.set_network_bytes_limit(1);
+
+        let messages = dispatch(request);
+        assert_eq!(messages.len(), 1 + 1);
+
+        let status = messages[1].response().status();
+        assert_eq! {
+            status.error().type_(),
+            rrg_proto::rrg::status::error::Type::NETWORK_BYTES_SENT_LIMIT_EXCEEDED
+        };
+    }
+}
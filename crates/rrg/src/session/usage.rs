@@ -0,0 +1,239 @@
+// Copyright 2024 Google LLC
+//
+// Use of this source code is governed by an MIT-style license that can be found
+// in the LICENSE file or at https://opensource.org/licenses/MIT.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use log::error;
+
+/// CPU time (in nanoseconds) spent by helper threads on behalf of sessions.
+///
+/// Sessions measure CPU time of their own thread only, so threads spawned by
+/// actions (e.g. workers of a parallel filesystem traversal) have to charge the
+/// time they spend explicitly (see [`HelperCpuTime`]).
+static HELPER_CPU_TIME_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// Resource usage of a session along with the limits imposed by the request.
+///
+/// This type is shared by all non-fake session implementations to ensure that
+/// limits are enforced in the same way regardless of where the responses end
+/// up being delivered.
+pub struct Usage {
+    /// Number of bytes sent since the session was created.
+    network_bytes_sent: u64,
+    /// Number of bytes we are allowed to send within the session.
+    network_bytes_limit: Option<u64>,
+    /// CPU time of the session thread at the time the session was created.
+    ///
+    /// This is `None` if the CPU time could not be measured, in which case the
+    /// CPU time limit is not enforced.
+    cpu_time_start: Option<std::time::Duration>,
+    /// CPU time charged by helper threads at the time the session was created.
+    helper_cpu_time_start: u64,
+    /// CPU time which we are allowed to spend within the session.
+    cpu_time_limit: Option<std::time::Duration>,
+    /// Time at which the session was created.
+    real_time_start: std::time::Instant,
+    /// Time which we are allowed to spend within the session.
+    real_time_limit: Option<std::time::Duration>,
+}
+
+impl Usage {
+
+    /// Starts tracking resource usage for the given request.
+    ///
+    /// Note that the session is bound to the thread this function is called on
+    /// and only CPU time of that thread (and of helper threads that charge it
+    /// explicitly) is accounted for.
+    pub fn start(request: &crate::Request) -> Usage {
+        Usage {
+            network_bytes_sent: 0,
+            network_bytes_limit: request.network_bytes_limit(),
+            cpu_time_start: thread_cpu_time(),
+            helper_cpu_time_start: HELPER_CPU_TIME_NANOS.load(Ordering::Relaxed),
+            cpu_time_limit: request.cpu_time_limit(),
+            real_time_start: std::time::Instant::now(),
+            real_time_limit: request.real_time_limit(),
+        }
+    }
+
+    /// Records that the given number of bytes has been sent.
+    ///
+    /// This function will return an error if any of the limits was crossed.
+    pub fn record_sent(&mut self, bytes: usize) -> crate::session::Result<()> {
+        self.network_bytes_sent += bytes as u64;
+
+        self.check_network_bytes_limit()?;
+        self.check_time_limits()
+    }
+
+    /// Checks whether any of the time limits was crossed.
+    ///
+    /// This function will return an error if it was.
+    pub fn check_time_limits(&self) -> crate::session::Result<()> {
+        self.check_cpu_time_limit()?;
+        self.check_real_time_limit()?;
+
+        Ok(())
+    }
+
+    /// Finalizes the status with information about the resource usage.
+    pub fn finalize(&self, status: crate::response::Status) -> crate::response::Status {
+        let mut status = status.with_real_time(self.real_time_spent());
+        if let Some(cpu_time_spent) = self.cpu_time_spent() {
+            status = status.with_cpu_time(cpu_time_spent);
+        }
+
+        status
+    }
+
+    /// Returns the CPU time spent since the session was created.
+    fn cpu_time_spent(&self) -> Option<std::time::Duration> {
+        let cpu_time_start = self.cpu_time_start?;
+        let cpu_time_now = thread_cpu_time()?;
+
+        let helper_cpu_time_now = HELPER_CPU_TIME_NANOS.load(Ordering::Relaxed);
+        let helper_cpu_time_nanos = helper_cpu_time_now
+            .saturating_sub(self.helper_cpu_time_start);
+        let helper_cpu_time = std::time::Duration::from_nanos(helper_cpu_time_nanos);
+
+        Some(cpu_time_now.saturating_sub(cpu_time_start) + helper_cpu_time)
+    }
+
+    /// Returns the real (wall) time spent since the session was created.
+    fn real_time_spent(&self) -> std::time::Duration {
+        self.real_time_start.elapsed()
+    }
+
+    /// Checks whether the network bytes limit was crossed.
+    ///
+    /// This function will return an error if it was.
+    fn check_network_bytes_limit(&self) -> crate::session::Result<()> {
+        use crate::session::error::NetworkBytesLimitExceededError;
+
+        if let Some(network_bytes_limit) = self.network_bytes_limit {
+            if self.network_bytes_sent > network_bytes_limit {
+                return Err(NetworkBytesLimitExceededError {
+                    network_bytes_sent: self.network_bytes_sent,
+                    network_bytes_limit,
+                }.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the CPU time limit was crossed.
+    ///
+    /// This function will return an error if it was.
+    fn check_cpu_time_limit(&self) -> crate::session::Result<()> {
+        use crate::session::error::CpuTimeLimitExceededError;
+
+        if let Some(cpu_time_limit) = self.cpu_time_limit {
+            if let Some(cpu_time_spent) = self.cpu_time_spent() {
+                if cpu_time_spent > cpu_time_limit {
+                    return Err(CpuTimeLimitExceededError {
+                        cpu_time_spent,
+                        cpu_time_limit,
+                    }.into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the real (wall) time limit was crossed.
+    ///
+    /// This function will return an error if it was.
+    fn check_real_time_limit(&self) -> crate::session::Result<()> {
+        use crate::session::error::RealTimeLimitExceededError;
+
+        if let Some(real_time_limit) = self.real_time_limit {
+            let real_time_spent = self.real_time_spent();
+            if real_time_spent > real_time_limit {
+                return Err(RealTimeLimitExceededError {
+                    real_time_spent,
+                    real_time_limit,
+                }.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Tracker of CPU time spent by a helper thread on behalf of the session.
+///
+/// Requests are handled one at a time, so the time is charged to whichever
+/// session is currently active.
+pub struct HelperCpuTime {
+    /// CPU time of the thread at the time it was last charged.
+    last: Option<std::time::Duration>,
+}
+
+impl HelperCpuTime {
+
+    /// Starts tracking CPU time of the calling thread.
+    pub fn start() -> HelperCpuTime {
+        HelperCpuTime {
+            last: thread_cpu_time(),
+        }
+    }
+
+    /// Charges CPU time spent by the calling thread since it was last charged.
+    pub fn charge(&mut self) {
+        let Some(last) = self.last else {
+            return;
+        };
+        let Some(now) = thread_cpu_time() else {
+            return;
+        };
+
+        let spent = u64::try_from(now.saturating_sub(last).as_nanos())
+            .unwrap_or(u64::MAX);
+        HELPER_CPU_TIME_NANOS.fetch_add(spent, Ordering::Relaxed);
+
+        self.last = Some(now);
+    }
+}
+
+/// Returns the CPU time consumed so far by the calling thread.
+///
+/// Failures are logged and reported as `None` as there is not much we can do
+/// about them other than not enforcing the CPU time limit.
+fn thread_cpu_time() -> Option<std::time::Duration> {
+    match ospect::proc::thread_cpu_time() {
+        Ok(cpu_time) => Some(cpu_time),
+        Err(error) => {
+            error!("failed to obtain thread CPU time: {error}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn helper_cpu_time_charge() {
+        let start = HELPER_CPU_TIME_NANOS.load(Ordering::Relaxed);
+
+        std::thread::spawn(|| {
+            let mut cpu_time = HelperCpuTime::start();
+
+            let cpu_time_start = thread_cpu_time().unwrap();
+            while thread_cpu_time().unwrap() - cpu_time_start < std::time::Duration::from_millis(50) {
+                std::hint::spin_loop();
+            }
+
+            cpu_time.charge();
+        }).join().unwrap();
+
+        let spent = HELPER_CPU_TIME_NANOS.load(Ordering::Relaxed) - start;
+        assert!(std::time::Duration::from_nanos(spent) >= std::time::Duration::from_millis(50));
+    }
+}